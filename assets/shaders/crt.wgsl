@@ -0,0 +1,24 @@
+// Darkens the picture with scanlines and a vignette, like an old CRT screen. Drawn as a
+// transparent quad over the whole game, so it only ever takes light away.
+#import bevy_sprite::mesh2d_vertex_output MeshVertexOutput
+
+// x: strength of the effect from 0 to 1, y: number of scanlines across the screen
+@group(1) @binding(0)
+var<uniform> params: vec4<f32>;
+
+@fragment
+fn fragment(mesh: MeshVertexOutput) -> @location(0) vec4<f32> {
+    let strength = params.x;
+    let lines = params.y;
+
+    // Dark gap between every pair of scanlines
+    let scan = 0.5 + 0.5 * sin(mesh.uv.y * lines * 3.14159265);
+    let scanline = (1.0 - scan) * 0.4;
+
+    // Darker towards the corners, as if the screen were curved
+    let from_centre = (mesh.uv - vec2<f32>(0.5, 0.5)) * vec2<f32>(1.0, 0.9);
+    let vignette = smoothstep(0.35, 0.75, length(from_centre)) * 0.7;
+
+    let alpha = clamp((scanline + vignette) * strength, 0.0, 1.0);
+    return vec4<f32>(0.0, 0.0, 0.0, alpha);
+}
@@ -0,0 +1,24 @@
+use crate::GameOptions;
+use bevy::prelude::*;
+
+pub struct GameAudioPlugin;
+
+impl Plugin for GameAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, apply_volume);
+    }
+}
+
+// Keep the global volume of all audio in line with the game options
+// and whether the game window is focused or not.
+fn apply_volume(
+    options: Res<GameOptions>,
+    windows: Query<&Window>,
+    mut global_volume: ResMut<GlobalVolume>,
+) {
+    let focused = windows.iter().any(|window| window.focused);
+    let volume = options.output_volume(focused);
+    if global_volume.volume.get() != volume {
+        *global_volume = GlobalVolume::new(volume);
+    }
+}
@@ -1,7 +1,8 @@
 mod menu;
 
-use crate::GameState;
-use bevy::app::AppExit;
+use crate::{DespawnOnExitExt, GameState};
+use crate::gameplay::run_reset::RetryRun;
+use crate::shutdown::ExitRequest;
 use bevy::prelude::*;
 use rand::Rng;
 
@@ -41,23 +42,27 @@ impl Plugin for GameOverPlugin {
                 )
                     .run_if(in_state(GameState::GameOver)),
             )
-            .add_systems(OnExit(GameState::GameOver), crate::despawn_component::<InGameOverMenu>);
+            .despawn_on_exit::<InGameOverMenu>(GameState::GameOver);
     }
 }
 
 // Handle all the button interactions in the game over screen
 #[allow(clippy::type_complexity)]
 fn button_interactions(
+    mut commands: Commands,
     interaction: Query<(&Interaction, &Action), (Changed<Interaction>, With<Button>)>,
-    mut exit: EventWriter<AppExit>,
+    mut exit: EventWriter<ExitRequest>,
     mut game_state: ResMut<NextState<GameState>>,
 ) {
     for (interaction, action) in interaction.iter() {
         if *interaction == Interaction::Pressed {
             match action {
-                Action::Retry => game_state.set(GameState::Gameplay),
+                Action::Retry => {
+                    commands.insert_resource(RetryRun);
+                    game_state.set(GameState::Gameplay);
+                }
                 Action::ToMainMenu => game_state.set(GameState::Menu),
-                Action::Exit => exit.send(AppExit),
+                Action::Exit => exit.send_default(),
             }
         }
     }
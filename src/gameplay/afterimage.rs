@@ -0,0 +1,74 @@
+// Fading copies of the player left behind while focusing, to make small dodges through dense
+// bullet patterns easier to follow.
+use super::input::PlayerInput;
+use super::player::{Focused, Player};
+use crate::GameOptions;
+use super::GameTime;
+use bevy::prelude::*;
+
+// Time between copies, and how long each copy takes to fade out. Together these keep three
+// to four copies on screen at once.
+const AFTERIMAGE_INTERVAL: f32 = 0.04;
+const AFTERIMAGE_LIFETIME: f32 = 0.15;
+// Opacity of a copy when it is left behind
+const AFTERIMAGE_ALPHA: f32 = 0.45;
+
+#[derive(Component, Debug)]
+pub struct Afterimage(Timer);
+
+// Leave a copy of the player sprite behind every so often while the player is focused
+// and moving
+#[allow(clippy::type_complexity)]
+pub fn spawn_afterimages(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    mut timer: Local<Option<Timer>>,
+    input: Res<PlayerInput>,
+    options: Res<GameOptions>,
+    player: Query<(&Focused, &Transform, &TextureAtlasSprite, &Handle<TextureAtlas>), With<Player>>,
+) {
+    let timer = timer
+        .get_or_insert_with(|| Timer::from_seconds(AFTERIMAGE_INTERVAL, TimerMode::Repeating));
+    let Ok((focused, transform, sprite, atlas)) = player.get_single() else { return; };
+    if !options.get_afterimages() || !focused.0 || input.movement == Vec2::ZERO {
+        // Start a fresh trail straight away next time
+        timer.reset();
+        return;
+    }
+
+    timer.tick(time.delta());
+    if timer.times_finished_this_tick() == 0 {
+        return;
+    }
+
+    commands.spawn((
+        Afterimage(Timer::from_seconds(AFTERIMAGE_LIFETIME, TimerMode::Once)),
+        SpriteSheetBundle {
+            texture_atlas: atlas.clone(),
+            sprite: TextureAtlasSprite {
+                index: sprite.index,
+                custom_size: sprite.custom_size,
+                color: Color::rgba(0.6, 0.8, 1.0, AFTERIMAGE_ALPHA),
+                ..default()
+            },
+            // Just behind the player
+            transform: Transform::from_translation(transform.translation - Vec3::Z * 0.01),
+            ..default()
+        },
+    ));
+}
+
+pub fn fade_afterimages(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    mut afterimages: Query<(Entity, &mut Afterimage, &mut TextureAtlasSprite)>,
+) {
+    for (entity, mut afterimage, mut sprite) in afterimages.iter_mut() {
+        afterimage.0.tick(time.delta());
+        if afterimage.0.finished() {
+            commands.entity(entity).despawn();
+        } else {
+            sprite.color.set_a(AFTERIMAGE_ALPHA * afterimage.0.percent_left());
+        }
+    }
+}
@@ -0,0 +1,196 @@
+// Every kind of enemy in the game, and what the player has seen and beaten of them across
+// all of their runs. The records are kept between launches, and shown in the bestiary screen
+// of the main menu.
+use super::{event::EnemyKilledEvent, loading::SPRITE_SHEETS};
+use crate::persistence::{load_or_default, SaveLoad};
+use crate::storage::Storage;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const BESTIARY_FILE: &str = "bestiary.ron";
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum EnemyKind {
+    Drone,
+    // Drone which blows up when it is destroyed
+    Volatile,
+    // Dives at the player
+    Diver,
+    // Stops and fires aimed streams
+    Turret,
+    // Lets out divers when destroyed
+    Carrier,
+    ScoutLeader,
+    Gunship,
+    Warden,
+    BigBoss,
+    BiggerBoss,
+    BiggestBoss,
+    MiniBoss,
+}
+
+impl EnemyKind {
+    // In the order they are listed in the bestiary
+    pub const ALL: [EnemyKind; 12] = [
+        EnemyKind::Drone,
+        EnemyKind::Volatile,
+        EnemyKind::Diver,
+        EnemyKind::ScoutLeader,
+        EnemyKind::BigBoss,
+        EnemyKind::Turret,
+        EnemyKind::Gunship,
+        EnemyKind::BiggerBoss,
+        EnemyKind::Carrier,
+        EnemyKind::Warden,
+        EnemyKind::BiggestBoss,
+        EnemyKind::MiniBoss,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EnemyKind::Drone => "Drone",
+            EnemyKind::Volatile => "Volatile",
+            EnemyKind::Diver => "Diver",
+            EnemyKind::Turret => "Turret",
+            EnemyKind::Carrier => "Carrier",
+            EnemyKind::ScoutLeader => "Scout Leader",
+            EnemyKind::Gunship => "Gunship",
+            EnemyKind::Warden => "Warden",
+            EnemyKind::BigBoss => "Big Boss",
+            EnemyKind::BiggerBoss => "Bigger Boss",
+            EnemyKind::BiggestBoss => "Biggest Boss",
+            EnemyKind::MiniBoss => "Mini Boss",
+        }
+    }
+
+    // Sprite sheet and colour the enemy is drawn with
+    pub fn sprite(&self) -> (&'static str, Color) {
+        match self {
+            EnemyKind::Drone => ("sprites/enemy-small.png", Color::rgb(1.0, 1.0, 1.0)),
+            EnemyKind::Volatile => ("sprites/enemy-small.png", Color::rgb(1.0, 0.55, 0.2)),
+            EnemyKind::Diver => ("sprites/enemy-small.png", Color::rgb(1.0, 0.3, 0.3)),
+            EnemyKind::Turret => ("sprites/enemy-medium.png", Color::rgb(0.7, 0.9, 0.6)),
+            EnemyKind::Carrier => ("sprites/enemy-medium.png", Color::rgb(0.6, 0.5, 1.0)),
+            EnemyKind::ScoutLeader => ("sprites/enemy-small.png", Color::rgb(0.9, 0.9, 0.4)),
+            EnemyKind::Gunship => ("sprites/enemy-medium.png", Color::rgb(0.5, 0.8, 1.0)),
+            EnemyKind::Warden => ("sprites/enemy-medium.png", Color::rgb(1.0, 0.5, 0.9)),
+            EnemyKind::BigBoss => ("sprites/enemy-medium.png", Color::rgb(0.1, 0.6, 0.3)),
+            EnemyKind::BiggerBoss => ("sprites/enemy-medium.png", Color::rgb(0.8, 0.1, 0.3)),
+            EnemyKind::BiggestBoss => ("sprites/enemy-big.png", Color::rgb(1.0, 1.0, 1.0)),
+            EnemyKind::MiniBoss => ("sprites/enemy-medium.png", Color::rgb(0.8, 0.3, 0.3)),
+        }
+    }
+
+    // Health on Normal difficulty
+    pub fn health(&self) -> f32 {
+        match self {
+            EnemyKind::Drone | EnemyKind::Volatile | EnemyKind::Diver => 20.0,
+            EnemyKind::Turret => 60.0,
+            EnemyKind::Carrier => 80.0,
+            EnemyKind::ScoutLeader | EnemyKind::Gunship | EnemyKind::Warden => 120.0,
+            EnemyKind::BigBoss
+            | EnemyKind::BiggerBoss
+            | EnemyKind::BiggestBoss
+            | EnemyKind::MiniBoss => 300.0,
+        }
+    }
+
+    pub fn attacks(&self) -> &'static str {
+        match self {
+            EnemyKind::Drone => "Flies down the screen firing small rings",
+            EnemyKind::Volatile => "Blows up when destroyed, taking nearby enemies with it",
+            EnemyKind::Diver => "Dives at you, speeding up as it closes in",
+            EnemyKind::Turret => "Stops in place and fires aimed streams",
+            EnemyKind::Carrier => "Slow rings, and lets out divers when destroyed",
+            EnemyKind::ScoutLeader => "Slow rings of bullets",
+            EnemyKind::Gunship => "Waves and aimed bursts",
+            EnemyKind::Warden => "Dense spinning spirals",
+            EnemyKind::BigBoss => "Waves, rings and aimed lines. Faster below half health.",
+            EnemyKind::BiggerBoss => "Bullet streams and waves. Faster below 40% health.",
+            EnemyKind::BiggestBoss => "Spirals and walls over three phases, enrages on time out",
+            EnemyKind::MiniBoss => "Spinning rings and aimed lines, stronger the longer you survive",
+        }
+    }
+
+    // Tags every enemy of this kind is given as it is spawned
+    pub fn tags(&self) -> &'static [&'static str] {
+        match self {
+            EnemyKind::Drone => &["popcorn"],
+            EnemyKind::Volatile => &["popcorn", "explosive"],
+            EnemyKind::Diver => &["popcorn", "diver"],
+            EnemyKind::Turret => &["turret"],
+            EnemyKind::Carrier => &["carrier"],
+            EnemyKind::ScoutLeader | EnemyKind::Gunship | EnemyKind::Warden => &["mid boss"],
+            EnemyKind::BigBoss | EnemyKind::BiggerBoss | EnemyKind::BiggestBoss => &["boss"],
+            EnemyKind::MiniBoss => &["boss", "endless"],
+        }
+    }
+
+    // Texture atlas for showing the enemy outside of gameplay, where the gameplay atlases
+    // aren't loaded
+    pub fn texture_atlas(
+        &self,
+        asset_server: &AssetServer,
+        texture_atlases: &mut Assets<TextureAtlas>,
+    ) -> Handle<TextureAtlas> {
+        let (path, _) = self.sprite();
+        let (_, size, columns, rows) = SPRITE_SHEETS
+            .into_iter()
+            .find(|(sheet, ..)| *sheet == path)
+            .expect("Enemy sprite is missing from the sprite sheets.");
+        texture_atlases.add(TextureAtlas::from_grid(
+            asset_server.load(path),
+            size,
+            columns,
+            rows,
+            None,
+            None,
+        ))
+    }
+}
+
+// Kills of every kind of enemy that the player has come across. Kinds which were never
+// encountered have no entry at all.
+#[derive(Resource, Debug, Default, Serialize, Deserialize)]
+pub struct Bestiary {
+    kills: BTreeMap<EnemyKind, u32>,
+}
+
+impl Bestiary {
+    pub fn load() -> Self {
+        load_or_default(Storage::Saves, BESTIARY_FILE)
+    }
+
+    pub fn encountered(&self, kind: EnemyKind) -> bool {
+        self.kills.contains_key(&kind)
+    }
+
+    pub fn kills(&self, kind: EnemyKind) -> u32 {
+        self.kills.get(&kind).copied().unwrap_or(0)
+    }
+}
+
+pub fn record_encounters(
+    enemies: Query<&EnemyKind, Added<EnemyKind>>,
+    mut bestiary: ResMut<Bestiary>,
+) {
+    for kind in enemies.iter() {
+        if !bestiary.encountered(*kind) {
+            bestiary.kills.insert(*kind, 0);
+        }
+    }
+}
+
+pub fn record_kills(mut killed_ev: EventReader<EnemyKilledEvent>, mut bestiary: ResMut<Bestiary>) {
+    for event in killed_ev.iter() {
+        let Some(kind) = event.kind else { continue; };
+        *bestiary.kills.entry(kind).or_insert(0) += 1;
+    }
+}
+
+pub fn save_bestiary(bestiary: Res<Bestiary>, mut save_load: ResMut<SaveLoad>) {
+    if bestiary.is_changed() && !bestiary.is_added() {
+        save_load.save(Storage::Saves, BESTIARY_FILE, &*bestiary);
+    }
+}
@@ -1,8 +1,18 @@
 use super::{
+    bullet_grid::BulletRadius,
     collisions::ColliderType,
-    shared::{physics::*, ExtraSpriteInfo, Formation, Movement},
+    death_sequence::DeathSequence,
+    difficulty::Difficulty,
+    enemy::Enemy,
+    event::DespawnEvent,
+    levels::Playfield,
+    player::Player,
+    shared::{physics::*, ExtraSpriteInfo, Formation, Movement, MovementScript, Tags, METRE},
+    GameTime,
 };
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashSet};
+use rand::Rng;
+use std::f32::consts::TAU;
 use std::time::Duration;
 
 // Marker component. This is what makes an entity a bullet
@@ -56,6 +66,285 @@ impl Bullet {
     }
 }
 
+// How far above the top of the playfield player bullets are removed
+const CULL_MARGIN: f32 = 2.0 * METRE;
+
+// Despawn player bullets which have flown off the top of the playfield. They would normally
+// hit the top wall, but this doesn't depend on the wall being in the right place.
+pub fn cull_player_bullets(
+    playfield: Res<Playfield>,
+    bullets: Query<(Entity, &ColliderType, &Transform), With<Bullet>>,
+    mut despawn_ev: EventWriter<DespawnEvent>,
+) {
+    let cull_line = playfield.max.y + CULL_MARGIN;
+    for (entity, collider_type, transform) in bullets.iter() {
+        if *collider_type == ColliderType::PlayerBullet && transform.translation.y > cull_line {
+            despawn_ev.send(DespawnEvent::new(entity, true));
+        }
+    }
+}
+
+// Bullet that steers towards the nearest enemy. It turns by at most `turn_rate` radians a
+// second, and only for `time_left` seconds, so that a bullet which misses doesn't circle
+// around its target forever.
+#[derive(Component, Debug, Clone)]
+pub struct Homing {
+    pub turn_rate: f32,
+    pub time_left: f32,
+}
+
+// Turn homing bullets towards the nearest enemy, keeping their speed the same. Runs before
+// the bullets are moved, and changes only the absolute velocity of their Movement.
+#[allow(clippy::type_complexity)]
+pub fn steer_homing(
+    time: Res<GameTime>,
+    mut bullets: Query<(&mut Homing, &mut Movement, &Transform), With<Bullet>>,
+    // Bosses which are already going down aren't worth chasing
+    enemies: Query<&Transform, (With<Enemy>, Without<DeathSequence>)>,
+) {
+    let dt = time.delta_seconds();
+    for (mut homing, mut movement, transform) in bullets.iter_mut() {
+        if homing.time_left <= 0.0 {
+            continue;
+        }
+        homing.time_left -= dt;
+
+        let position = transform.translation.truncate();
+        let Some(target) = enemies
+            .iter()
+            .map(|enemy| enemy.translation.truncate())
+            .min_by(|a, b| a.distance_squared(position).total_cmp(&b.distance_squared(position)))
+        else {
+            continue;
+        };
+
+        let speed = movement.velocity.length();
+        let wanted = (target - position).normalize_or_zero();
+        if speed == 0.0 || wanted == Vec2::ZERO {
+            continue;
+        }
+        let angle = movement.velocity.angle_between(wanted);
+        let max_turn = homing.turn_rate * dt;
+        let turn = angle.clamp(-max_turn, max_turn);
+        movement.velocity = Vec2::from_angle(turn).rotate(movement.velocity).normalize() * speed;
+    }
+}
+
+// Which enemy bullets a RetargetBullets event applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BulletFilter {
+    All,
+    Tagged(&'static str),
+}
+
+// Change the Movement of every live enemy bullet matching `filter`, for moments like all the
+// bullets on screen suddenly turning on the player. With `aim`, the bullets are also turned to
+// face the player, so a relative `new_movement` sends them straight at them.
+#[derive(Debug, Clone, Event)]
+pub struct RetargetBullets {
+    pub filter: BulletFilter,
+    pub new_movement: Movement,
+    pub aim: bool,
+}
+
+#[allow(clippy::type_complexity)]
+pub fn retarget_bullets(
+    mut commands: Commands,
+    mut retarget_ev: EventReader<RetargetBullets>,
+    mut bullets: Query<
+        (
+            Entity,
+            &ColliderType,
+            Option<&Tags>,
+            &mut Movement,
+            &mut Transform,
+        ),
+        (With<Bullet>, Without<Player>),
+    >,
+    player: Query<&Transform, With<Player>>,
+) {
+    let player = player.get_single().ok();
+    for event in retarget_ev.iter() {
+        for (entity, collider_type, tags, mut movement, mut transform) in bullets.iter_mut() {
+            if *collider_type != ColliderType::EnemyBullet {
+                continue;
+            }
+            if let BulletFilter::Tagged(wanted) = event.filter {
+                if !tags.is_some_and(|tags| tags.has(wanted)) {
+                    continue;
+                }
+            }
+
+            *movement = event.new_movement.clone();
+            if let (true, Some(player)) = (event.aim, player) {
+                let direction = (player.translation - transform.translation)
+                    .truncate()
+                    .normalize_or_zero();
+                if direction != Vec2::ZERO {
+                    transform.rotation = Quat::from_rotation_z(Vec2::Y.angle_between(direction));
+                }
+            }
+            // A bullet part way through its script would otherwise replace the new movement
+            commands.entity(entity).remove::<MovementScript>();
+        }
+    }
+}
+
+// Copies of a bullet group made around its origin. Every bullet of the group is repeated
+// `folds` times, turned by an equal share of a full circle each time, and with `mirror` each
+// of those is also reflected left to right. This turns a single authored arc or stream into a
+// symmetric flower without needing a formation shape for it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Symmetry {
+    pub mirror: bool,
+    pub folds: u16,
+}
+
+// No copies, only the authored bullets
+impl Default for Symmetry {
+    fn default() -> Self {
+        Self {
+            mirror: false,
+            folds: 1,
+        }
+    }
+}
+
+impl Symmetry {
+    // The group and its mirror image
+    pub fn mirror() -> Self {
+        Self {
+            mirror: true,
+            ..default()
+        }
+    }
+
+    // The group repeated `folds` times around the origin
+    pub fn rotational(folds: u16) -> Self {
+        Self {
+            folds: folds.max(1),
+            ..default()
+        }
+    }
+
+    pub fn with_mirror(mut self) -> Self {
+        self.mirror = true;
+        self
+    }
+
+    // Every copy of a bullet spawned at `spawn_point` with `movement`, the original first.
+    // Relative velocities follow the bullet's rotation, so only absolute ones are turned,
+    // while mirroring flips the sideways part of both.
+    fn apply(
+        &self,
+        spawn_point: Transform,
+        movement: &Movement,
+        origin: Vec3,
+    ) -> Vec<(Transform, Movement)> {
+        let folds = self.folds.max(1);
+        let mut copies = Vec::with_capacity(folds as usize * (1 + self.mirror as usize));
+
+        for k in 0..folds {
+            let angle = TAU * k as f32 / folds as f32;
+            let turn = Quat::from_rotation_z(angle);
+            let rotate = |v: Vec2| Vec2::from_angle(angle).rotate(v);
+
+            let mut transform = spawn_point;
+            transform.translation = origin + turn * (spawn_point.translation - origin);
+            transform.rotation = turn * spawn_point.rotation;
+            let mut turned = movement.clone();
+            turned.velocity = rotate(movement.velocity);
+            turned.acceleration = rotate(movement.acceleration);
+
+            if self.mirror {
+                let mut reflected = transform;
+                reflected.translation.x = 2.0 * origin.x - transform.translation.x;
+                // Reflecting across the y axis turns a rotation about z the other way
+                reflected.rotation.z = -transform.rotation.z;
+                reflected.rotation.y = -transform.rotation.y;
+                let mut flipped = turned.clone();
+                flipped.velocity.x = -flipped.velocity.x;
+                flipped.acceleration.x = -flipped.acceleration.x;
+                flipped.v_local.x = -flipped.v_local.x;
+                flipped.a_local.x = -flipped.a_local.x;
+                copies.push((transform, turned));
+                copies.push((reflected, flipped));
+            } else {
+                copies.push((transform, turned));
+            }
+        }
+        copies
+    }
+}
+
+// Bullets of bullet groups which have been taken out of play, ready to be used again. Attacks
+// spawn hundreds of bullets at a time, so rather than despawning and spawning them over and
+// over, bullets are hidden and have their collider disabled until they are needed again.
+#[derive(Resource, Debug, Default)]
+pub struct BulletPool {
+    free: Vec<Entity>,
+    // Bullets released this frame. Hiding them is done by commands which may not have been
+    // applied yet, so they only join `free` on the next frame.
+    pending: Vec<Entity>,
+    // The same bullets as `free` and `pending`, for checking whether one is already in the pool
+    pooled: HashSet<Entity>,
+}
+
+// Marker for bullets which go back to the BulletPool instead of being despawned
+#[derive(Component, Debug)]
+pub struct Pooled;
+
+impl BulletPool {
+    // Take a bullet out of play and keep it for later. Releasing a bullet twice, e.g. when it
+    // hits something and leaves the playfield on the same frame, only pools it once.
+    pub fn release(&mut self, commands: &mut Commands, entity: Entity) {
+        let Some(mut bullet) = commands.get_entity(entity) else { return; };
+        if !self.pooled.insert(entity) {
+            return;
+        }
+        // Without a collider type, pooled bullets are left alone by everything that looks for
+        // enemy or player bullets
+        bullet
+            .insert((
+                ColliderType::None,
+                Visibility::Hidden,
+                ColliderDisabled,
+                Velocity::zero(),
+                Movement::ZERO,
+            ))
+            .remove::<(MovementScript, Tags)>();
+        self.pending.push(entity);
+    }
+
+    // Make the bullets released last frame available again
+    fn settle(&mut self) {
+        self.free.append(&mut self.pending);
+    }
+
+    // A bullet to reuse, if there are any. Bullets which have been despawned since they were
+    // pooled are skipped.
+    fn take(&mut self, commands: &mut Commands) -> Option<Entity> {
+        while let Some(entity) = self.free.pop() {
+            self.pooled.remove(&entity);
+            if commands.get_entity(entity).is_some() {
+                return Some(entity);
+            }
+        }
+        None
+    }
+}
+
+// Run at the start of the frame, once the commands releasing last frame's bullets have been
+// applied
+pub fn settle_bullet_pool(mut pool: ResMut<BulletPool>) {
+    pool.settle();
+}
+
+// Every bullet is despawned when leaving the game, pooled ones included
+pub fn clear_bullet_pool(mut pool: ResMut<BulletPool>) {
+    *pool = BulletPool::default();
+}
+
 // Struct for defining a set of bullets that are similar and have a formation
 #[derive(Debug, Clone)]
 pub struct BulletGroup {
@@ -64,6 +353,12 @@ pub struct BulletGroup {
     pub origin: Transform,
     pub formation: Formation,
     pub bullet: Bullet,
+    pub symmetry: Symmetry,
+    // Stages every bullet of the group goes through after it is spawned, e.g. stopping and
+    // re-aiming at the player
+    pub script: Option<MovementScript>,
+    // Given to every bullet of the group, unless there are none
+    pub tags: Tags,
 }
 
 // A "default" BulletGroup value consists of one bullet at world spawn with default
@@ -76,49 +371,152 @@ impl Default for BulletGroup {
             origin: Transform::default(),
             formation: Formation::default(),
             bullet: Bullet::new(1.0, 1.0),
+            symmetry: Symmetry::default(),
+            script: None,
+            tags: Tags::default(),
         }
     }
 }
 
 impl BulletGroup {
     // Procedure for spawning a single bullet of a given bullet group into the world.
-    // The bullet to spawn out of the group is given as `i`
+    // The bullet to spawn out of the group is given as `i`. Bullets left in the pool are
+    // reused before any new ones are spawned.
     pub fn spawn_single<T: ExtraSpriteInfo>(
         &self,
         commands: &mut Commands,
+        pool: &mut BulletPool,
         movement: Movement,
         i: u16,
         sprite: T,
+        rng: &mut impl Rng,
     ) {
-        let spawn_point = self.formation.transform(i, self.number, self.origin);
+        let spawn_point = self.formation.transform(i, self.number, self.origin, rng);
 
-        let mut binding = commands.spawn((
-            sprite.bundle(spawn_point),
-            self.bullet,
-            RigidBody::Dynamic,
-            Velocity::zero(),
-            movement,
-            sprite.collider(),
-            self.collider_type,
-            self.collider_type.collision_group(),
-            Sensor,
-        ));
-        if let Some(collider) = sprite.grazing_collider() {
-            binding.with_children(|parent| {
-                parent.spawn((collider, ColliderType::Graze, ColliderType::Graze.collision_group(), Sensor));
-            });
+        for (transform, movement) in
+            self.symmetry
+                .apply(spawn_point, &movement, self.origin.translation)
+        {
+            let components = (
+                sprite.bundle(transform),
+                self.bullet,
+                movement,
+                self.collider_type,
+            );
+            let taken = pool.take(commands);
+            // Enemy bullets are left out of the physics simulation, see bullet_grid
+            let mut bullet = if self.collider_type == ColliderType::EnemyBullet {
+                let radius = BulletRadius::around(&sprite.collider(), &transform);
+                match taken {
+                    Some(entity) => {
+                        let mut bullet = commands.entity(entity);
+                        bullet.insert((components, radius)).remove::<(
+                            RigidBody,
+                            Sensor,
+                            Collider,
+                            CollisionGroups,
+                            Velocity,
+                            ColliderDisabled,
+                        )>();
+                        bullet
+                    }
+                    None => commands.spawn((components, radius, Pooled)),
+                }
+            } else {
+                let physics = (
+                    RigidBody::Dynamic,
+                    Sensor,
+                    Velocity::zero(),
+                    sprite.collider(),
+                    self.collider_type.collision_group(),
+                );
+                match taken {
+                    Some(entity) => {
+                        let mut bullet = commands.entity(entity);
+                        bullet
+                            .insert((components, physics))
+                            .remove::<(ColliderDisabled, BulletRadius)>();
+                        bullet
+                    }
+                    None => commands.spawn((components, physics, Pooled)),
+                }
+            };
+            if let Some(script) = &self.script {
+                bullet.insert(script.clone());
+            }
+            if !self.tags.is_empty() {
+                bullet.insert(self.tags.clone());
+            }
         }
     }
     pub fn spawn_all<T: ExtraSpriteInfo + Clone>(
         &self,
         commands: &mut Commands,
+        pool: &mut BulletPool,
         movement: Movement,
         sprite: T,
+        rng: &mut impl Rng,
     ) {
         for i in 0..self.number {
-            self.spawn_single(commands, movement.clone(), i, sprite.clone());
+            self.spawn_single(commands, pool, movement.clone(), i, sprite.clone(), rng);
+        }
+    }
+
+    // Spawn points of every authored bullet in the group, in spawn order, before symmetry
+    // copies are made. Randomised formations use `rng`, so a seeded generator always gives the
+    // same points. Only used by the tests.
+    #[cfg(test)]
+    pub fn transforms<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<Transform> {
+        (0..self.number)
+            .map(|i| self.formation.transform(i, self.number, self.origin, rng))
+            .collect()
+    }
+
+    // Create a headless simulation of this group, as if it had been spawned with spawn_all.
+    #[cfg(test)]
+    pub fn simulate<R: Rng + ?Sized>(&self, movement: &Movement, rng: &mut R) -> BulletGroupSim {
+        BulletGroupSim {
+            bullets: self
+                .transforms(rng)
+                .into_iter()
+                .flat_map(|transform| {
+                    self.symmetry
+                        .apply(transform, movement, self.origin.translation)
+                })
+                .collect(),
+        }
+    }
+}
+
+// A BulletGroup stepped without rendering, physics or a World. Each step does what
+// move_object and the physics simulation would do to the live bullets over `dt` seconds,
+// which makes it possible to check pattern maths outside of the game.
+#[cfg(test)]
+#[derive(Debug, Clone)]
+pub struct BulletGroupSim {
+    pub bullets: Vec<(Transform, Movement)>,
+}
+
+#[cfg(test)]
+impl BulletGroupSim {
+    pub fn step(&mut self, dt: f32) {
+        for (transform, movement) in self.bullets.iter_mut() {
+            // move_object hands the physics simulation a velocity in metres, which then
+            // moves the transform by that velocity every second.
+            let velocity = movement.step(transform.rotation, dt) * METRE;
+            transform.translation += velocity.extend(0.0) * dt;
+        }
+    }
+
+    pub fn step_n(&mut self, dt: f32, steps: usize) {
+        for _ in 0..steps {
+            self.step(dt);
         }
     }
+
+    pub fn positions(&self) -> Vec<Vec3> {
+        self.bullets.iter().map(|(t, _)| t.translation).collect()
+    }
 }
 
 #[derive(Debug, Clone, Component)]
@@ -133,6 +531,10 @@ pub struct AttackPattern {
     pub icd: Option<Timer>,
     // For using with the ICD as an iterator
     pub current_bullet: u16,
+    // Lowest difficulty the attack is used at
+    pub min_difficulty: Difficulty,
+    // More bullet groups fired as part of the same attack
+    pub composite: Option<CompositePattern>,
 }
 
 impl AttackPattern {
@@ -148,8 +550,22 @@ impl AttackPattern {
             cd: cooldown,
             icd: internal_cooldown,
             current_bullet: 0,
+            min_difficulty: Difficulty::Easy,
+            composite: None,
         }
     }
+
+    // Fire more bullet groups with every volley of the attack
+    pub fn with_composite(mut self, composite: CompositePattern) -> Self {
+        self.composite = Some(composite);
+        self
+    }
+
+    // Only use the attack at `difficulty` and above
+    pub fn with_min_difficulty(mut self, difficulty: Difficulty) -> Self {
+        self.min_difficulty = difficulty;
+        self
+    }
 }
 
 impl Default for AttackPattern {
@@ -160,6 +576,200 @@ impl Default for AttackPattern {
             cd: Timer::new(Duration::from_millis(10000), TimerMode::Once),
             icd: Some(Timer::new(Duration::from_millis(100), TimerMode::Once)),
             current_bullet: 0,
+            min_difficulty: Difficulty::Easy,
+            composite: None,
+        }
+    }
+}
+
+// How the parts of a composite attack follow on from the attack's own bullet group
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sequencing {
+    // Every part starts its delay as the volley starts
+    Together,
+    // Every part starts its delay once the part before it has fired all of its bullets
+    InTurn,
+}
+
+// Bullet group fired as part of a composite attack. It has no cooldown of its own, and fires
+// once with every volley of the attack it is part of, so it has to fit inside the attack's
+// cooldown.
+#[derive(Debug, Clone)]
+pub struct PatternPart {
+    pub bullet_group: BulletGroup,
+    pub movement: Movement,
+    pub delay: Timer,
+    pub icd: Option<Timer>,
+    pub current_bullet: u16,
+    // Waiting to fire, or firing, in the current volley
+    pub armed: bool,
+}
+
+impl PatternPart {
+    pub fn new(
+        bullet_group: BulletGroup,
+        movement: Movement,
+        delay_secs: f32,
+        internal_cooldown: Option<Timer>,
+    ) -> Self {
+        Self {
+            bullet_group,
+            movement,
+            delay: Timer::from_seconds(delay_secs, TimerMode::Once),
+            icd: internal_cooldown,
+            current_bullet: 0,
+            armed: false,
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        !self.armed
+    }
+
+    // Get ready to fire again with a new volley
+    pub fn arm(&mut self) {
+        self.delay.reset();
+        if let Some(icd) = &mut self.icd {
+            icd.reset();
+        }
+        self.current_bullet = 0;
+        self.armed = true;
+    }
+}
+
+// Several bullet groups fired as a single attack, sharing its cooldown, e.g. an aimed stream
+// with a ring of bullets around it
+#[derive(Debug, Clone)]
+pub struct CompositePattern {
+    pub sequencing: Sequencing,
+    pub parts: Vec<PatternPart>,
+}
+
+impl CompositePattern {
+    pub fn together(parts: Vec<PatternPart>) -> Self {
+        Self {
+            sequencing: Sequencing::Together,
+            parts,
+        }
+    }
+
+    pub fn in_turn(parts: Vec<PatternPart>) -> Self {
+        Self {
+            sequencing: Sequencing::InTurn,
+            parts,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    const EPSILON: f32 = 1e-3;
+
+    fn group(formation: Formation, number: u16, symmetry: Symmetry) -> BulletGroup {
+        BulletGroup {
+            formation,
+            number,
+            symmetry,
+            origin: Transform::from_xyz(40.0, -25.0, 0.0),
+            collider_type: ColliderType::EnemyBullet,
+            ..default()
+        }
+    }
+
+    fn origin(group: &BulletGroup) -> Vec2 {
+        group.origin.translation.truncate()
+    }
+
+    #[test]
+    fn bullet_counts_include_symmetry_copies() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let movement = Movement::relative(Vec2::new(0.0, 5.0), Vec2::ZERO);
+        let cases = [
+            (Symmetry::default(), 12),
+            (Symmetry::mirror(), 24),
+            (Symmetry::rotational(4), 48),
+            (Symmetry::rotational(3).with_mirror(), 72),
+            // No folds is taken as one
+            (Symmetry::rotational(0), 12),
+        ];
+        for (symmetry, expected) in cases {
+            let group = group(Formation::circular(false, 10.0), 12, symmetry);
+            assert_eq!(group.transforms(&mut rng).len(), 12);
+            assert_eq!(group.simulate(&movement, &mut rng).bullets.len(), expected);
+        }
+    }
+
+    #[test]
+    fn circle_stays_centred_on_its_origin() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let group = group(Formation::circular(false, 20.0), 16, Symmetry::default());
+        let movement = Movement::relative(Vec2::new(3.0, 4.0), Vec2::new(0.5, 0.0));
+        let mut sim = group.simulate(&movement, &mut rng);
+
+        for steps in [0, 1, 30] {
+            sim.step_n(1.0 / 60.0, steps);
+            let positions = sim.positions();
+            let distances: Vec<f32> = positions
+                .iter()
+                .map(|position| position.truncate().distance(origin(&group)))
+                .collect();
+            // Every bullet moves the same way relative to its own heading, so the ring only
+            // grows and keeps its centre
+            for distance in distances.iter() {
+                assert!((distance - distances[0]).abs() < EPSILON, "{:?}", distances);
+            }
+            let centre = positions.iter().map(|p| p.truncate()).sum::<Vec2>() / 16.0;
+            assert!(centre.distance(origin(&group)) < EPSILON, "{:?}", centre);
         }
     }
+
+    #[test]
+    fn rotational_copies_are_evenly_turned() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let arc = Formation::arc(15.0, 0.5);
+        let group = group(arc, 5, Symmetry::rotational(4));
+        let sim = group.simulate(&Movement::ONE_FWD, &mut rng);
+        let offsets: Vec<Vec2> =
+            sim.positions().iter().map(|p| p.truncate() - origin(&group)).collect();
+
+        // Copies of each authored bullet come one after the other, a quarter turn apart
+        for bullet in offsets.chunks(4) {
+            for pair in bullet.windows(2) {
+                let turned = Vec2::from_angle(TAU / 4.0).rotate(pair[0]);
+                assert!(turned.distance(pair[1]) < EPSILON, "{:?}", bullet);
+            }
+        }
+    }
+
+    #[test]
+    fn harmonic_stays_within_its_amplitude() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let (radius, amplitude) = (50.0, 12.0);
+        let group = group(
+            Formation::harmonic(false, radius, amplitude, 1.5),
+            40,
+            Symmetry::default(),
+        );
+        for transform in group.transforms(&mut rng) {
+            let offset = transform.translation.truncate() - origin(&group);
+            assert!(offset.x.abs() <= amplitude + EPSILON, "{:?}", offset);
+            assert!((offset.length() - radius).abs() < EPSILON, "{:?}", offset);
+            // A pendulum hanging below its origin
+            assert!(offset.y < 0.0, "{:?}", offset);
+        }
+    }
+
+    #[test]
+    fn seeded_random_formations_repeat() {
+        let group = group(Formation::circular(true, 30.0), 20, Symmetry::mirror());
+        let movement = Movement::absolute(Vec2::new(0.0, -2.0), Vec2::ZERO);
+        let mut first = group.simulate(&movement, &mut StdRng::seed_from_u64(5));
+        let mut second = group.simulate(&movement, &mut StdRng::seed_from_u64(5));
+        first.step(0.1);
+        second.step(0.1);
+        assert_eq!(first.positions(), second.positions());
+    }
 }
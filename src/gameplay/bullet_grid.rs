@@ -0,0 +1,164 @@
+// Collision detection for enemy bullets, done without the physics simulation. Attacks put
+// hundreds of enemy bullets on screen at once, and giving each of them a rigid body and a
+// sensor costs far more than they need, as they are only ever checked against the few things
+// around the player. Instead, an enemy bullet is only a position and a radius. Every frame the
+// bullets are sorted into a grid, and each target only checks the cells around it.
+//
+// Overlaps are sent as the same collision events the physics simulation sends, so that
+// handle_collisions and the collision handlers deal with them like any other collision.
+use super::{
+    bullet::Bullet,
+    collisions::ColliderType,
+    event::DespawnEvent,
+    levels::Playfield,
+    shared::{physics::*, Movement, METRE},
+    GameTime,
+};
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use bevy_rapier2d::prelude::CollisionEventFlags;
+
+// Size of the grid cells. A few bullets across, so that most bullets are in a single cell.
+const CELL_SIZE: f32 = 2.0 * METRE;
+// How far out of the playfield bullets are removed, so that bullets fired from just outside
+// of it have time to come in
+const OFFSCREEN_MARGIN: f32 = 2.0 * METRE;
+
+// Radius of a bullet which is left out of the physics simulation
+#[derive(Component, Debug, Clone, Copy)]
+pub struct BulletRadius(pub f32);
+
+impl BulletRadius {
+    // Radius of the circle around `collider`, scaled like the bullet
+    pub fn around(collider: &Collider, transform: &Transform) -> Self {
+        Self(bounding_radius(collider) * transform.scale.truncate().max_element())
+    }
+}
+
+// Radius of the smallest circle around a collider
+fn bounding_radius(collider: &Collider) -> f32 {
+    collider.raw.compute_local_bounding_sphere().radius()
+}
+
+// Enemy bullets, sorted by the grid cell they are in
+#[derive(Resource, Debug, Default)]
+pub struct BulletGrid {
+    cells: HashMap<IVec2, Vec<(Entity, Vec2, f32)>>,
+    // Radius of the biggest bullet in the grid, for how far around a circle to look
+    max_radius: f32,
+    // Bullets and the targets they were overlapping at the last check, so that events are
+    // only sent when an overlap starts or stops
+    contacts: HashSet<(Entity, Entity)>,
+}
+
+impl BulletGrid {
+    fn cell(position: Vec2) -> IVec2 {
+        (position / CELL_SIZE).floor().as_ivec2()
+    }
+
+    // Empty every cell, keeping them around for the next frame
+    fn clear(&mut self) {
+        self.cells.values_mut().for_each(Vec::clear);
+        self.max_radius = 0.0;
+    }
+
+    fn insert(&mut self, entity: Entity, position: Vec2, radius: f32) {
+        self.cells
+            .entry(Self::cell(position))
+            .or_default()
+            .push((entity, position, radius));
+        self.max_radius = self.max_radius.max(radius);
+    }
+
+    // Every bullet overlapping a circle, as of the last time the grid was filled
+    pub fn bullets_in_radius(&self, centre: Vec2, radius: f32) -> Vec<Entity> {
+        let reach = Vec2::splat(radius + self.max_radius);
+        let (min, max) = (Self::cell(centre - reach), Self::cell(centre + reach));
+        let mut found = Vec::new();
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                let Some(cell) = self.cells.get(&IVec2::new(x, y)) else { continue; };
+                found.extend(
+                    cell.iter()
+                        .filter(|(_, position, bullet_radius)| {
+                            position.distance_squared(centre) <= (radius + bullet_radius).powi(2)
+                        })
+                        .map(|(entity, ..)| *entity),
+                );
+            }
+        }
+        found
+    }
+}
+
+// Move bullets which are left out of the physics simulation, the same way it moves the ones
+// in it
+pub fn move_bullets(
+    time: Res<GameTime>,
+    mut bullets: Query<(&mut Movement, &mut Transform), (With<Bullet>, With<BulletRadius>)>,
+) {
+    let dt = time.delta_seconds();
+    for (mut movement, mut transform) in bullets.iter_mut() {
+        let velocity = movement.step(transform.rotation, dt) * METRE;
+        transform.translation += velocity.extend(0.0) * dt;
+    }
+}
+
+// Fill the grid with this frame's enemy bullets and check them against everything they
+// interact with in the collision matrix. Bullets which have left the playfield are removed
+// here too, as there are no walls for them to pass through.
+#[allow(clippy::type_complexity)]
+pub fn detect_bullet_collisions(
+    mut grid: ResMut<BulletGrid>,
+    playfield: Res<Playfield>,
+    bullets: Query<(Entity, &ColliderType, &BulletRadius, &Transform), With<Bullet>>,
+    targets: Query<
+        (Entity, &ColliderType, &Collider, &GlobalTransform),
+        (Without<BulletRadius>, Without<ColliderDisabled>),
+    >,
+    mut collision_ev: EventWriter<CollisionEvent>,
+    mut despawn_ev: EventWriter<DespawnEvent>,
+) {
+    grid.clear();
+    for (entity, collider_type, radius, transform) in bullets.iter() {
+        // Pooled bullets have no collider type, and are left out
+        if *collider_type != ColliderType::EnemyBullet {
+            continue;
+        }
+        let position = transform.translation.truncate();
+        let bounds = playfield.half_size() + Vec2::splat(OFFSCREEN_MARGIN + radius.0);
+        if (position - playfield.center()).abs().cmpgt(bounds).any() {
+            despawn_ev.send(DespawnEvent::new(entity, true));
+            continue;
+        }
+        grid.insert(entity, position, radius.0);
+    }
+
+    let mut contacts = HashSet::new();
+    for (target, target_type, collider, transform) in targets.iter() {
+        if *target_type == ColliderType::Wall
+            || !ColliderType::EnemyBullet.interacts_with(target_type)
+        {
+            continue;
+        }
+        let centre = transform.translation().truncate();
+        for bullet in grid.bullets_in_radius(centre, bounding_radius(collider)) {
+            contacts.insert((bullet, target));
+        }
+    }
+
+    for &(bullet, target) in contacts.difference(&grid.contacts) {
+        collision_ev.send(CollisionEvent::Started(bullet, target, CollisionEventFlags::SENSOR));
+    }
+    for &(bullet, target) in grid.contacts.difference(&contacts) {
+        collision_ev.send(CollisionEvent::Stopped(bullet, target, CollisionEventFlags::SENSOR));
+    }
+    grid.contacts = contacts;
+}
+
+// Forget about the bullets of the game that was left
+pub fn clear_bullet_grid(mut grid: ResMut<BulletGrid>) {
+    *grid = BulletGrid::default();
+}
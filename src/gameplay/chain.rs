@@ -0,0 +1,176 @@
+// Kills and grazes in quick succession chain together, and the longer the chain, the higher
+// the score multiplier. Every link tops the chain gauge back up, and the gauge drains over
+// time. Once it runs dry the chain is broken and the multiplier drops back to where it started.
+use super::{
+    event::EnemyKilledEvent,
+    player::{Player, Score},
+    shared::Counter,
+    timeline::{RunTimeline, TimelineEventKind},
+    ui::{StatsList, UpdatingText},
+    GameTime, GameplayTime,
+};
+use bevy::prelude::*;
+
+// Multiplier with no chain going
+pub const BASE_MULTIPLIER: f32 = 1.5;
+const MULTIPLIER_PER_LINK: f32 = 0.01;
+const MAX_MULTIPLIER: f32 = 4.0;
+// Links added for every enemy killed and every bullet grazed
+const LINKS_PER_KILL: u32 = 5;
+pub const LINKS_PER_GRAZE: u32 = 1;
+// How long a full gauge takes to drain
+const GAUGE_SECS: f32 = 2.5;
+// Broken chains at least this long are marked on the run's timeline
+const NOTABLE_CHAIN: u32 = 50;
+
+#[derive(Component, Debug, Default)]
+pub struct Chain {
+    links: u32,
+    // From 0 to 1
+    gauge: f32,
+}
+
+impl Chain {
+    pub fn extend(&mut self, links: u32) {
+        self.links += links;
+        self.gauge = 1.0;
+    }
+
+    pub fn multiplier(&self) -> f32 {
+        (BASE_MULTIPLIER + self.links as f32 * MULTIPLIER_PER_LINK).min(MAX_MULTIPLIER)
+    }
+}
+
+impl Counter for Chain {
+    type Data = u32;
+
+    fn set(&mut self, links: Self::Data) {
+        self.links = links;
+    }
+
+    fn get(&self) -> Self::Data {
+        self.links
+    }
+
+    fn add(&mut self, links: Self::Data) {
+        self.extend(links);
+    }
+
+    fn subtract(&mut self, links: Self::Data) {
+        self.links = self.links.saturating_sub(links);
+    }
+}
+
+#[derive(Component)]
+pub struct ChainText {
+    pub entity: Entity,
+}
+
+impl UpdatingText for ChainText {
+    type DataHolder = Chain;
+
+    fn original(&self) -> String {
+        String::from("Chain:")
+    }
+
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+}
+
+// Inner part of the chain gauge, which shrinks as the gauge drains
+#[derive(Component)]
+pub struct ChainGauge;
+
+// Sent when the chain gauge runs dry with a chain going
+#[derive(Debug, Event)]
+pub struct ChainBroken {
+    // How long the chain was
+    pub links: u32,
+}
+
+// Add the chain gauge to the stats list, under the chain counter
+pub fn spawn_chain_gauge(commands: &mut Commands, list: &mut Query<(Entity, &mut StatsList)>) {
+    let (list_entity, _) = list
+        .get_single_mut()
+        .expect("None or more than 1 stats list was found.");
+    commands.entity(list_entity).with_children(|parent| {
+        parent
+            .spawn(NodeBundle {
+                style: Style {
+                    width: Val::Px(100.0),
+                    height: Val::Px(6.0),
+                    margin: UiRect::top(Val::Px(4.0)),
+                    ..default()
+                },
+                background_color: Color::rgb(0.3, 0.3, 0.3).into(),
+                ..default()
+            })
+            .with_children(|parent| {
+                parent.spawn((
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Percent(0.0),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        background_color: Color::GOLD.into(),
+                        ..default()
+                    },
+                    ChainGauge,
+                ));
+            });
+    });
+}
+
+pub fn chain_kills(
+    mut killed_ev: EventReader<EnemyKilledEvent>,
+    mut chain: Query<&mut Chain, With<Player>>,
+) {
+    let kills = killed_ev.iter().count() as u32;
+    if kills == 0 {
+        return;
+    }
+    let Ok(mut chain) = chain.get_single_mut() else { return; };
+    chain.extend(kills * LINKS_PER_KILL);
+}
+
+// Drain the gauge, break the chain once it's empty and keep the score multiplier in line
+// with the chain
+pub fn drain_chain(
+    time: Res<GameTime>,
+    mut player: Query<(&mut Chain, &mut Score), With<Player>>,
+    mut broken_ev: EventWriter<ChainBroken>,
+) {
+    let Ok((mut chain, mut score)) = player.get_single_mut() else { return; };
+    chain.gauge = (chain.gauge - time.delta_seconds() / GAUGE_SECS).max(0.0);
+    if chain.gauge <= 0.0 && chain.links > 0 {
+        broken_ev.send(ChainBroken { links: chain.links });
+        chain.links = 0;
+    }
+    if score.get_multiplier() != chain.multiplier() {
+        score.set_multiplier(chain.multiplier());
+    }
+}
+
+pub fn update_chain_gauge(
+    chain: Query<&Chain, (With<Player>, Changed<Chain>)>,
+    mut gauge: Query<&mut Style, With<ChainGauge>>,
+) {
+    let Ok(chain) = chain.get_single() else { return; };
+    for mut style in gauge.iter_mut() {
+        style.width = Val::Percent(chain.gauge * 100.0);
+    }
+}
+
+pub fn record_broken_chains(
+    mut broken_ev: EventReader<ChainBroken>,
+    mut timeline: ResMut<RunTimeline>,
+    g_time: Res<GameplayTime>,
+) {
+    for event in broken_ev.iter() {
+        if event.links >= NOTABLE_CHAIN {
+            timeline.record(&g_time, TimelineEventKind::ChainBroken);
+        }
+    }
+}
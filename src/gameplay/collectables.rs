@@ -1,7 +1,9 @@
-use crate::gameplay::{player::Player, shared::magnetise_to};
+use crate::gameplay::{player::Player, shared::magnetise_to, tuning::Tuning};
+use super::bestiary::EnemyKind;
 use super::collisions::ColliderType;
 use super::shared::physics::*;
 use super::shared::Movement;
+use super::GameTime;
 use bevy::prelude::*;
 use rand::Rng;
 use std::time::Duration;
@@ -10,6 +12,8 @@ use std::time::Duration;
 pub enum CollectableType {
     Power,
     Score,
+    // Dropped by a boss, and kept between runs to be spent in the shop
+    Token(EnemyKind),
 }
 
 #[derive(Component)]
@@ -17,6 +21,20 @@ pub struct Collectable {
     pub kind: CollectableType,
 }
 
+// Collectables dropped by an enemy when it is killed, for enemies which drop something other
+// than the usual amount
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Drops {
+    pub score: u8,
+    pub power: u8,
+}
+
+impl Default for Drops {
+    fn default() -> Self {
+        Self { score: 5, power: 3 }
+    }
+}
+
 // Lifetime for despawning and updating visuals of Collectables after
 // some time has passed since the creation of a Collectable.
 #[derive(Component, Debug)]
@@ -52,7 +70,7 @@ impl CollectableLifetime {
 // They are despawned once their lifetime is over
 pub fn manage_lifetimes(
     mut commands: Commands,
-    time: Res<Time>,
+    time: Res<GameTime>,
     mut collectables: Query<(Entity, &mut Sprite, &mut CollectableLifetime), With<Collectable>>,
 ) {
     for (entity, mut sprite, mut lifetime) in collectables.iter_mut() {
@@ -137,7 +155,11 @@ pub fn spawn_collectable_around(
     };
 
     // Spawn collectible with a 15-second lifetime and 2 Hz flashing update rate.
-    // The sprite is chosen based on the kind of Collectable it is
+    // The sprite is chosen based on the kind of Collectable it is, with tokens drawn larger
+    let size = match kind {
+        CollectableType::Token(_) => 28.0,
+        _ => 16.0,
+    };
     commands.spawn((
         Collectable { kind },
         CollectableLifetime::new(
@@ -146,17 +168,19 @@ pub fn spawn_collectable_around(
         ),
         SpriteBundle {
             sprite: Sprite {
-                custom_size: Some(Vec2::new(16.0, 16.0)),
+                custom_size: Some(Vec2::splat(size)),
                 ..default()
             },
             texture: match kind {
-                CollectableType::Score => assets.load("sprites/energy-pickup.png"),
+                CollectableType::Score | CollectableType::Token(_) => {
+                    assets.load("sprites/energy-pickup.png")
+                }
                 CollectableType::Power => assets.load("sprites/power-pickup.png"),
             },
             transform: r_transform,
             ..default()
         },
-        Collider::ball(8.0),
+        Collider::ball(size / 2.0),
         RigidBody::Dynamic,
         Velocity::zero(),
         ColliderType::Collectable,
@@ -170,15 +194,13 @@ pub fn spawn_collectable_around(
 pub fn magnetise_to_player(
     mut collectables: Query<(&mut Movement, &Transform), With<Collectable>>,
     player_t: Query<&Transform, With<Player>>,
+    tuning: Res<Tuning>,
 ) {
-    const STRENGTH: f32 = 15.0;
-    const MINIMUM_DISTANCE: f32 = 30.0;
-
     let Ok(player_t) = player_t.get_single() else { return };
 
     for (mut movement, transform) in collectables.iter_mut() {
-        if (player_t.translation - transform.translation).length() <= MINIMUM_DISTANCE {
-            magnetise_to(&mut movement, transform, player_t, STRENGTH, false);
+        if (player_t.translation - transform.translation).length() <= tuning.magnet_distance {
+            magnetise_to(&mut movement, transform, player_t, tuning.magnet_strength, false);
         }
     }
 }
@@ -186,12 +208,11 @@ pub fn magnetise_to_player(
 pub fn magnetise_all(
     mut collectables: Query<(&mut Movement, &Transform), With<Collectable>>,
     player_t: Query<&Transform, With<Player>>,
+    tuning: Res<Tuning>,
 ) {
-    const STRENGTH: f32 = 40.0;
-
     let Ok(player_t) = player_t.get_single() else { return };
 
     for (mut movement, transform) in collectables.iter_mut() {
-        magnetise_to(&mut movement, transform, player_t, STRENGTH, false);
+        magnetise_to(&mut movement, transform, player_t, tuning.magnet_all_strength, false);
     }
 }
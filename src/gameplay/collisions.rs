@@ -1,19 +1,26 @@
 use crate::gameplay::player::Graze;
 use super::{
     bullet::Bullet,
+    chain::{Chain, LINKS_PER_GRAZE},
     collectables::{Collectable, CollectableType},
     enemy::Enemy,
     event::{DespawnEvent, TakeDamageEvent},
-    levels::Wall,
-    player::{Player, Power, Score},
+    levels::{Playfield, Wall},
+    player::{Invulnerable, Player, PlayerGraze, Power, Score},
+    scrape::ScoringMode,
     shared::{physics::*, Counter, Movement},
+    shockwave::Shockwave,
+    spark::{HitSparkEvent, SparkKind},
+    tokens::Tokens,
+    tuning::Tuning,
+    wave::FlightPath,
 };
 use bevy::{prelude::*, utils::hashbrown::HashMap};
-use bevy_rapier2d::rapier::geometry::CollisionEventFlags;
+use bevy_rapier2d::prelude::{QueryFilter, RapierContext};
 use rand::Rng;
 
-// Define all Collision Groups and Collision Filters so that
-// all game objects interact as intended.
+// Define all Collision Groups so that all game objects interact as intended.
+// The Collision Filters are generated from the collision matrix below.
 pub const PLAYER_COL: Group = Group::GROUP_1;
 pub const WALL_COL: Group = Group::GROUP_2;
 pub const ENEMY_COL: Group = Group::GROUP_3;
@@ -21,22 +28,7 @@ pub const ENEMY_BULLET_COL: Group = Group::GROUP_4;
 pub const PLAYER_BULLET_COL: Group = Group::GROUP_5;
 pub const COLLECTABLE_COL: Group = Group::GROUP_6;
 pub const GRAZE_COL: Group = Group::GROUP_7;
-
-pub const PLAYER_FILTER: Group = ENEMY_COL
-    .union(ENEMY_BULLET_COL)
-    .union(WALL_COL)
-    .union(COLLECTABLE_COL)
-    .union(GRAZE_COL);
-pub const ENEMY_FILTER: Group = PLAYER_COL.union(PLAYER_BULLET_COL).union(WALL_COL);
-pub const WALL_FILTER: Group = ENEMY_COL
-    .union(PLAYER_COL)
-    .union(PLAYER_BULLET_COL)
-    .union(ENEMY_BULLET_COL)
-    .union(COLLECTABLE_COL);
-pub const PLAYER_BULLET_FILTER: Group = ENEMY_COL.union(WALL_COL);
-pub const ENEMY_BULLET_FILTER: Group = PLAYER_COL.union(WALL_COL);
-pub const COLLECTABLE_FILTER: Group = PLAYER_COL.union(WALL_COL);
-pub const GRAZE_FILTER: Group = PLAYER_COL;
+pub const SHOCKWAVE_COL: Group = Group::GROUP_8;
 
 // Used for filtering collision handling by object type.
 #[derive(Clone, Component, Copy, PartialEq, Eq, Debug)]
@@ -48,24 +40,159 @@ pub enum ColliderType {
     Wall,
     Collectable,
     Graze,
+    Shockwave,
     None,
 }
+
+// Every ColliderType that takes part in collisions, in the same order as the rows and columns
+// of the collision matrix.
+pub const COLLIDER_TYPES: [ColliderType; 8] = [
+    ColliderType::Player,
+    ColliderType::PlayerBullet,
+    ColliderType::Enemy,
+    ColliderType::EnemyBullet,
+    ColliderType::Wall,
+    ColliderType::Collectable,
+    ColliderType::Graze,
+    ColliderType::Shockwave,
+];
+
+// When a collider type reacts to a collision with another collider type.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CollisionPhase {
+    // The two types do not interact at all
+    Never,
+    // The two types interact, but this type does not react itself. Either the other type
+    // handles the collision or it is left to the physics simulation.
+    Passive,
+    // React as soon as the two colliders start overlapping
+    Enter,
+    // React once the two colliders stop overlapping
+    Exit,
+}
+
+// Declarative table of which ColliderTypes interact, and when. Each row lists when the row's
+// type reacts to a collision with each column's type. The physics simulation only reports a
+// collision when both colliders have each other in their filters, so an entry can only be
+// `Never` if its mirror across the diagonal is `Never` as well. This is checked when the game
+// starts by `validate_collision_matrix`.
+//
+// Leaving the playfield is handled with `Exit` against walls, so that an object is only
+// despawned after it has gone all the way through a wall.
+//
+// Enemy bullets are left out of the physics simulation, and bullet_grid checks them against
+// the same table instead. They have no walls to pass through, and are removed once they are
+// far enough out of the playfield.
+#[rustfmt::skip]
+pub const COLLISION_MATRIX: [[CollisionPhase; COLLIDER_TYPES.len()]; COLLIDER_TYPES.len()] = {
+    use CollisionPhase::{Enter as E, Exit as X, Never as N, Passive as P};
+    [
+        //               Player PlayerBullet Enemy EnemyBullet Wall Collectable Graze Shockwave
+        /* Player */      [N,    N,           E,    P,          P,   P,          N,    N],
+        /* PlayerBullet */[N,    N,           E,    N,          X,   N,          N,    N],
+        /* Enemy */       [E,    P,           N,    N,          X,   N,          N,    P],
+        /* EnemyBullet */ [E,    N,           N,    N,          X,   N,          P,    P],
+        /* Wall */        [P,    P,           P,    P,          N,   P,          N,    N],
+        /* Collectable */ [E,    N,           N,    N,          E,   N,          N,    N],
+        /* Graze */       [N,    N,           N,    E,          N,   N,          N,    N],
+        /* Shockwave */   [N,    N,           E,    E,          N,   N,          N,    N],
+    ]
+};
+
 impl ColliderType {
-    // Function for automatically generating collision groups for each collider type.
-    pub fn collision_group(&self) -> CollisionGroups {
+    // Position of the collider type in COLLIDER_TYPES and the collision matrix.
+    pub const fn index(&self) -> Option<usize> {
+        use ColliderType::*;
+        match self {
+            Player => Some(0),
+            PlayerBullet => Some(1),
+            Enemy => Some(2),
+            EnemyBullet => Some(3),
+            Wall => Some(4),
+            Collectable => Some(5),
+            Graze => Some(6),
+            Shockwave => Some(7),
+            None => Option::None,
+        }
+    }
+
+    // Collision group that this collider type is a member of.
+    pub const fn membership(&self) -> Group {
         use ColliderType::*;
         match self {
-            Player => CollisionGroups::new(PLAYER_COL, PLAYER_FILTER),
-            PlayerBullet => CollisionGroups::new(PLAYER_BULLET_COL, PLAYER_BULLET_FILTER),
-            Enemy => CollisionGroups::new(ENEMY_COL, ENEMY_FILTER),
-            EnemyBullet => CollisionGroups::new(ENEMY_BULLET_COL, ENEMY_BULLET_FILTER),
-            Wall => CollisionGroups::new(WALL_COL, WALL_FILTER),
-            Collectable => CollisionGroups::new(COLLECTABLE_COL, COLLECTABLE_FILTER),
-            Graze => CollisionGroups::new(GRAZE_COL, GRAZE_FILTER),
-            _ => {
-                warn!("default collision group on ColliderType reached.");
-                CollisionGroups::new(Group::NONE, Group::NONE)
+            Player => PLAYER_COL,
+            PlayerBullet => PLAYER_BULLET_COL,
+            Enemy => ENEMY_COL,
+            EnemyBullet => ENEMY_BULLET_COL,
+            Wall => WALL_COL,
+            Collectable => COLLECTABLE_COL,
+            Graze => GRAZE_COL,
+            Shockwave => SHOCKWAVE_COL,
+            None => Group::NONE,
+        }
+    }
+
+    // Collision filter of this collider type, built from its row of the collision matrix.
+    pub const fn filter(&self) -> Group {
+        let Some(row) = self.index() else {
+            return Group::NONE;
+        };
+        let mut filter = Group::NONE;
+        let mut column = 0;
+        while column < COLLIDER_TYPES.len() {
+            if !matches!(COLLISION_MATRIX[row][column], CollisionPhase::Never) {
+                filter = filter.union(COLLIDER_TYPES[column].membership());
             }
+            column += 1;
+        }
+        filter
+    }
+
+    // When this collider type reacts to a collision with another, according to the
+    // collision matrix.
+    pub const fn phase_with(&self, other: &ColliderType) -> CollisionPhase {
+        match (self.index(), other.index()) {
+            (Some(row), Some(column)) => COLLISION_MATRIX[row][column],
+            _ => CollisionPhase::Never,
+        }
+    }
+
+    // Check whether two collider types interact according to the collision matrix.
+    pub const fn interacts_with(&self, other: &ColliderType) -> bool {
+        !matches!(self.phase_with(other), CollisionPhase::Never)
+    }
+
+    // Check whether this collider type should react to a collision, depending on whether
+    // the collision has just started or stopped.
+    pub const fn reacts_to(&self, collision: &CollisionData) -> bool {
+        match self.phase_with(&collision.other_type) {
+            CollisionPhase::Enter => collision.started,
+            CollisionPhase::Exit => !collision.started,
+            CollisionPhase::Never | CollisionPhase::Passive => false,
+        }
+    }
+
+    // Function for automatically generating collision groups for each collider type.
+    pub fn collision_group(&self) -> CollisionGroups {
+        if *self == ColliderType::None {
+            warn!("default collision group on ColliderType reached.");
+        }
+        CollisionGroups::new(self.membership(), self.filter())
+    }
+}
+
+// Make sure that the collision matrix is symmetric, as a one-sided entry would silently never
+// produce any collision events.
+pub fn validate_collision_matrix() {
+    for a in COLLIDER_TYPES {
+        for b in COLLIDER_TYPES {
+            assert_eq!(
+                a.interacts_with(&b),
+                b.interacts_with(&a),
+                "Collision matrix is not symmetric for {:?} and {:?}.",
+                a,
+                b
+            );
         }
     }
 }
@@ -80,7 +207,6 @@ pub struct CollisionMarker;
 pub struct CollisionData {
     pub other_type: ColliderType,
     pub other_entity: Entity,
-    pub flags: CollisionEventFlags,
     pub started: bool,
 }
 
@@ -101,9 +227,9 @@ pub fn handle_collisions(
     mut collision_events: EventReader<CollisionEvent>,
 ) {
     for event in collision_events.iter() {
-        let (entity1, entity2, flags, started) = match event {
-            CollisionEvent::Started(e1, e2, flags) => (*e1, *e2, *flags, true),
-            CollisionEvent::Stopped(e1, e2, flags) => (*e1, *e2, *flags, false),
+        let (entity1, entity2, started) = match event {
+            CollisionEvent::Started(e1, e2, _) => (*e1, *e2, true),
+            CollisionEvent::Stopped(e1, e2, _) => (*e1, *e2, false),
         };
 
         // Retrieve the ColliderType of the entities that have collided.
@@ -115,7 +241,6 @@ pub fn handle_collisions(
         let entity1_data = CollisionData {
             other_type: *entity2_type,
             other_entity: entity2,
-            flags,
             started,
         };
 
@@ -123,7 +248,6 @@ pub fn handle_collisions(
         let entity2_data = CollisionData {
             other_type: *entity1_type,
             other_entity: entity1,
-            flags,
             started,
         };
 
@@ -149,62 +273,133 @@ pub fn handle_collisions(
     }
 }
 
+// Every entity of one of the `targets` collider types whose collider overlaps a circle, for
+// dealing damage over an area. Asks the physics simulation directly, so nothing has to be
+// spawned for it and the result is ready straight away.
+pub fn entities_in_radius(
+    rapier: &RapierContext,
+    centre: Vec2,
+    radius: f32,
+    targets: &[ColliderType],
+) -> Vec<Entity> {
+    let filter = targets
+        .iter()
+        .fold(Group::NONE, |filter, target| filter.union(target.membership()));
+    let mut found = Vec::new();
+    rapier.intersections_with_shape(
+        centre,
+        0.0,
+        &Collider::ball(radius),
+        QueryFilter::new().groups(CollisionGroups::new(Group::ALL, filter)),
+        |entity| {
+            found.push(entity);
+            // Keep looking
+            true
+        },
+    );
+    found
+}
+
+// Damage every entity of the `targets` collider types caught in a circle. This is how
+// explosions and other blasts deal their damage, rather than each of them querying for what
+// they hit in their own way.
+pub fn damage_in_radius(
+    rapier: &RapierContext,
+    damage_ev: &mut EventWriter<TakeDamageEvent>,
+    centre: Vec2,
+    radius: f32,
+    damage: f32,
+    targets: &[ColliderType],
+) {
+    // One type at a time, so that the type of every entity that was hit is known
+    for target in targets {
+        for entity in entities_in_radius(rapier, centre, radius, &[*target]) {
+            damage_ev.send(TakeDamageEvent::new(entity, Some(*target), damage));
+        }
+    }
+}
+
+// Whether an object has left the playfield. Objects which stop touching a wall while still
+// inside the playfield, e.g. because they were spawned overlapping it, have not left.
+fn left_playfield(playfield: &Playfield, transform: &Transform) -> bool {
+    !playfield.contains(transform.translation.truncate())
+}
+
+// Point where a bullet hit something. Sensors don't report contact points, so this is the
+// point on the other object's collider that is closest to the bullet.
+fn impact_point(bullet: &Transform, target: Option<(&Collider, &Transform)>) -> Vec2 {
+    let bullet_position = bullet.translation.truncate();
+    let Some((collider, transform)) = target else { return bullet_position; };
+    let (_, rotation, _) = transform.to_scale_rotation_translation();
+    collider
+        .project_point(
+            transform.translation.truncate(),
+            rotation.to_euler(EulerRot::ZYX).0,
+            bullet_position,
+            false,
+        )
+        .point
+}
+
 // Handles collisions for Bullet entities.
-#[allow(clippy::type_complexity)]
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
 pub fn handle_bullet_col(
     collisions: Res<Collisions>,
+    playfield: Res<Playfield>,
     mut despawn_ev: EventWriter<DespawnEvent>,
     mut damage_ev: EventWriter<TakeDamageEvent>,
+    mut spark_ev: EventWriter<HitSparkEvent>,
     player_power: Query<&Power, With<Player>>,
-    bullets: Query<(Entity, &ColliderType, &Bullet), With<CollisionMarker>>,
+    bullets: Query<(Entity, &ColliderType, &Bullet, &Transform), With<CollisionMarker>>,
+    targets: Query<(&Collider, &Transform), Without<Bullet>>,
+    invulnerable: Query<(), With<Invulnerable>>,
 ) {
-    for (entity, bullet_type, bullet) in bullets.iter() {
+    for (entity, bullet_type, bullet, transform) in bullets.iter() {
         let Some(collisions) = collisions.get(&entity) else { continue; };
         for collision in collisions {
-            let damage_dealt = if *bullet_type == ColliderType::PlayerBullet && collision.started {
-                // Create variables needed for mapping the values
-                let Ok(power) = player_power.get_single() else { continue; };
-                let upper_damage = bullet.get_max();
-                let lower_damage = bullet.get_damage();
-                let upper_power = power.max() as f32;
-                let input = power.get() as f32;
-
-                // This is mapping a range, a_1..a_2, to another range, b_1..b_2
-                // for an input value i. The formula is the following:
-                // scaled_value = b_1 + ((i - a_1) * (b_2 - b_1) / (a_2 - a_1))
-                // Since my a_1 is 0, I have simplified into the following.
-                lower_damage + input * (upper_damage - lower_damage) / upper_power
-            } else {
-                bullet.get_damage()
-            };
+            if !bullet_type.reacts_to(collision) {
+                continue;
+            }
+            // Bullets pass straight through a player who can't be hurt
+            if invulnerable.contains(collision.other_entity) {
+                continue;
+            }
             match collision.other_type {
-                ColliderType::Player => {
-                    if !collision.started {
-                        continue;
-                    }
-                    damage_ev.send(TakeDamageEvent::new(
-                        collision.other_entity,
-                        Some(collision.other_type),
-                        damage_dealt,
-                    ));
-                    despawn_ev.send(DespawnEvent::new(entity, true));
-                }
-                ColliderType::Enemy => {
-                    if !collision.started {
-                        continue;
-                    }
+                ColliderType::Player | ColliderType::Enemy => {
+                    let damage_dealt = if *bullet_type == ColliderType::PlayerBullet {
+                        // Create variables needed for mapping the values
+                        let Ok(power) = player_power.get_single() else { continue; };
+                        let upper_damage = bullet.get_max();
+                        let lower_damage = bullet.get_damage();
+                        let upper_power = power.max() as f32;
+                        let input = power.get() as f32;
+
+                        // This is mapping a range, a_1..a_2, to another range, b_1..b_2
+                        // for an input value i. The formula is the following:
+                        // scaled_value = b_1 + ((i - a_1) * (b_2 - b_1) / (a_2 - a_1))
+                        // Since my a_1 is 0, I have simplified into the following.
+                        lower_damage + input * (upper_damage - lower_damage) / upper_power
+                    } else {
+                        bullet.get_damage()
+                    };
                     damage_ev.send(TakeDamageEvent::new(
                         collision.other_entity,
                         Some(collision.other_type),
                         damage_dealt,
                     ));
+                    spark_ev.send(HitSparkEvent {
+                        position: impact_point(transform, targets.get(collision.other_entity).ok()),
+                        kind: match collision.other_type {
+                            ColliderType::Player => SparkKind::Player,
+                            _ => SparkKind::Enemy,
+                        },
+                    });
                     despawn_ev.send(DespawnEvent::new(entity, true));
                 }
                 ColliderType::Wall => {
-                    if collision.started {
-                        continue;
+                    if left_playfield(&playfield, transform) {
+                        despawn_ev.send(DespawnEvent::new(entity, true));
                     }
-                    despawn_ev.send(DespawnEvent::new(entity, true));
                 }
                 _ => continue,
             }
@@ -220,12 +415,12 @@ pub fn handle_collectable_col(
     mut player_score: Query<&mut Score, With<Player>>,
     mut player_power: Query<&mut Power, With<Player>>,
     walls: Query<&Wall>,
+    mut tokens: ResMut<Tokens>,
 ) {
     for (entity, mut movement, collectable) in collectables.iter_mut() {
         let Some(collisions) = collisions.get(&entity) else { continue; };
         for collision in collisions {
-            // Only act on the beginning of a collision event, ignoring the end.
-            if collision.started {
+            if ColliderType::Collectable.reacts_to(collision) {
                 // If the collectable has been picked up by the player, make appropriate changes
                 if collision.other_type == ColliderType::Player {
                     match collectable.kind {
@@ -235,6 +430,7 @@ pub fn handle_collectable_col(
                         CollectableType::Power => {
                             player_power.iter_mut().for_each(|mut p| p.add(1))
                         }
+                        CollectableType::Token(kind) => tokens.collect(kind),
                     }
                     // Despawn the entity
                     despawn_ev.send(DespawnEvent::new(entity, false));
@@ -277,48 +473,114 @@ pub fn handle_collectable_col(
 pub fn handle_player_col(
     collisions: Res<Collisions>,
     mut damage_ev: EventWriter<TakeDamageEvent>,
-    player: Query<Entity, (With<Player>, With<CollisionMarker>)>,
-    mut graze: Query<&mut Graze, With<Player>>,
-    mut score: Query<&mut Score, With<Player>>,
+    player: Query<Entity, (With<Player>, With<CollisionMarker>, Without<Invulnerable>)>,
+    tuning: Res<Tuning>,
 ) {
     // There is only one player in the game so we can get_single()
     let Ok(player) = player.get_single() else { return; };
     let Some(collisions) = collisions.get(&player) else { return; };
     for collision in collisions {
-        // The player takes 10 health points damage when collising with any enemy
-        if collision.other_type == ColliderType::Enemy && collision.started {
+        if !ColliderType::Player.reacts_to(collision) {
+            continue;
+        }
+        // The player takes damage when collising with any enemy
+        if collision.other_type == ColliderType::Enemy {
             damage_ev.send(TakeDamageEvent::new(
                 player,
                 Some(ColliderType::Player),
-                10.0,
+                tuning.player_contact_damage,
             ));
         }
-        if collision.other_type == ColliderType::Graze && collision.started {
-            graze.iter_mut().for_each(|mut g| g.add(1));
-            score.iter_mut().for_each(|mut s| s.increase_multiplier_by(0.01))
+    }
+}
+
+// Handles enemy bullets passing through the player's graze sensor
+pub fn handle_graze_col(
+    collisions: Res<Collisions>,
+    sensor: Query<Entity, (With<PlayerGraze>, With<CollisionMarker>)>,
+    mut player: Query<(&mut Graze, &mut Chain), With<Player>>,
+    scoring: Res<ScoringMode>,
+) {
+    let Ok(sensor) = sensor.get_single() else { return; };
+    let Some(collisions) = collisions.get(&sensor) else { return; };
+    let Ok((mut graze, mut chain)) = player.get_single_mut() else { return; };
+    for collision in collisions {
+        // Every bullet counts once, when it first comes close to the player
+        if ColliderType::Graze.reacts_to(collision) {
+            graze.add(1);
+            // With scrape scoring, being close to bullets pays out over time instead
+            if *scoring == ScoringMode::Graze {
+                chain.extend(LINKS_PER_GRAZE);
+            }
         }
     }
 }
 
 // Handles enemy collisions
+#[allow(clippy::type_complexity)]
 pub fn handle_enemy_col(
     collisions: Res<Collisions>,
     mut damage_ev: EventWriter<TakeDamageEvent>,
     mut despawn_ev: EventWriter<DespawnEvent>,
-    enemies: Query<Entity, (With<Enemy>, With<CollisionMarker>)>,
+    playfield: Res<Playfield>,
+    tuning: Res<Tuning>,
+    enemies: Query<
+        (Entity, &Transform, Option<&FlightPath>),
+        (With<Enemy>, With<CollisionMarker>),
+    >,
 ) {
-    for enemy in enemies.into_iter() {
+    for (enemy, transform, flight_path) in enemies.iter() {
         let Some(collisions) = collisions.get(&enemy) else { continue; };
         for collision in collisions {
-            if collision.other_type == ColliderType::Player && collision.started {
+            if !ColliderType::Enemy.reacts_to(collision) {
+                continue;
+            }
+            match collision.other_type {
                 // Enemy should also take damage by collisiding with the player
-                damage_ev.send(TakeDamageEvent::new(enemy, None, 15.0));
+                ColliderType::Player => damage_ev.send(TakeDamageEvent::new(
+                    enemy,
+                    None,
+                    tuning.enemy_contact_damage,
+                )),
+                // Bosses are kept in by the walls, so only normal enemies can ever leave.
+                // Enemies coming in from above in formation haven't left yet.
+                ColliderType::Wall => {
+                    let entering = flight_path.is_some_and(FlightPath::entering);
+                    if !entering && left_playfield(&playfield, transform) {
+                        despawn_ev.send(DespawnEvent::new(enemy, false));
+                    }
+                }
+                _ => continue,
             }
-            if collision.flags.contains(CollisionEventFlags::SENSOR)
-                && collision.other_type == ColliderType::Wall
-                && !collision.started
-            {
-                despawn_ev.send(DespawnEvent::new(enemy, false));
+        }
+    }
+}
+
+// Handles the shockwave of a special passing over enemies and their bullets
+pub fn handle_shockwave_col(
+    collisions: Res<Collisions>,
+    mut despawn_ev: EventWriter<DespawnEvent>,
+    mut damage_ev: EventWriter<TakeDamageEvent>,
+    shockwaves: Query<Entity, (With<Shockwave>, With<CollisionMarker>)>,
+    tuning: Res<Tuning>,
+) {
+    for shockwave in shockwaves.iter() {
+        let Some(collisions) = collisions.get(&shockwave) else { continue; };
+        for collision in collisions {
+            if !ColliderType::Shockwave.reacts_to(collision) {
+                continue;
+            }
+            match collision.other_type {
+                // Enemy bullets are turned into score collectables
+                ColliderType::EnemyBullet => despawn_ev.send(
+                    DespawnEvent::new(collision.other_entity, true).with_score(1),
+                ),
+                ColliderType::Enemy => damage_ev.send(TakeDamageEvent::new(
+                    collision.other_entity,
+                    Some(ColliderType::Enemy),
+                    tuning.bomb_damage,
+                )),
+                _ => continue,
             }
         }
     }
@@ -338,3 +600,99 @@ pub fn cleanup_collisions(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collision(other_type: ColliderType, started: bool) -> CollisionData {
+        CollisionData {
+            other_type,
+            other_entity: Entity::from_raw(0),
+            started,
+        }
+    }
+
+    #[test]
+    fn matrix_is_symmetric() {
+        validate_collision_matrix();
+    }
+
+    #[test]
+    fn matrix_pairs_react_in_their_phase() {
+        use ColliderType::*;
+        // (row, column, reacts as they start overlapping, reacts as they stop)
+        let cases = [
+            (Player, Enemy, true, false),
+            (Enemy, Player, true, false),
+            (Player, EnemyBullet, false, false),
+            (EnemyBullet, Player, true, false),
+            (Player, Collectable, false, false),
+            (Collectable, Player, true, false),
+            (PlayerBullet, Enemy, true, false),
+            (Enemy, PlayerBullet, false, false),
+            (PlayerBullet, Wall, false, true),
+            (EnemyBullet, Wall, false, true),
+            (Enemy, Wall, false, true),
+            (Collectable, Wall, true, false),
+            (Wall, Player, false, false),
+            (Graze, EnemyBullet, true, false),
+            (Shockwave, Enemy, true, false),
+            (Shockwave, EnemyBullet, true, false),
+            (PlayerBullet, EnemyBullet, false, false),
+            (Player, PlayerBullet, false, false),
+        ];
+        for (row, column, on_enter, on_exit) in cases {
+            assert_eq!(row.reacts_to(&collision(column, true)), on_enter, "{:?} {:?}", row, column);
+            assert_eq!(row.reacts_to(&collision(column, false)), on_exit, "{:?} {:?}", row, column);
+        }
+    }
+
+    #[test]
+    fn filters_follow_the_matrix() {
+        use ColliderType::*;
+        for a in COLLIDER_TYPES {
+            for b in COLLIDER_TYPES {
+                assert_eq!(
+                    a.filter().contains(b.membership()),
+                    a.interacts_with(&b),
+                    "{:?} {:?}",
+                    a,
+                    b
+                );
+            }
+        }
+        assert!(!Player.interacts_with(&PlayerBullet));
+        assert!(!Graze.interacts_with(&Enemy));
+    }
+
+    #[test]
+    fn untyped_colliders_never_interact() {
+        for other in COLLIDER_TYPES {
+            assert!(!ColliderType::None.interacts_with(&other));
+            assert!(!other.interacts_with(&ColliderType::None));
+            assert!(!other.reacts_to(&collision(ColliderType::None, true)));
+        }
+        assert_eq!(ColliderType::None.filter(), Group::NONE);
+    }
+
+    #[test]
+    fn left_playfield_edges() {
+        let playfield = Playfield(Rect::new(-200.0, -300.0, 200.0, 300.0));
+        let at = |x: f32, y: f32| left_playfield(&playfield, &Transform::from_xyz(x, y, 5.0));
+
+        assert!(!at(0.0, 0.0));
+        // The edges themselves still count as inside
+        assert!(!at(200.0, 0.0));
+        assert!(!at(-200.0, 300.0));
+        assert!(!at(200.0, -300.0));
+        // Only just past each edge
+        assert!(at(200.01, 0.0));
+        assert!(at(-200.01, 0.0));
+        assert!(at(0.0, 300.01));
+        assert!(at(0.0, -300.01));
+        assert!(at(250.0, 350.0));
+        // Depth doesn't matter
+        assert!(!left_playfield(&playfield, &Transform::from_xyz(0.0, 0.0, -99.0)));
+    }
+}
@@ -0,0 +1,200 @@
+// Send-off for a defeated boss. Instead of disappearing straight away, the boss flashes while
+// explosions go off across it, then blows up in one big blast that clears the screen of
+// bullets. Only after that is it despawned and drops its collectables.
+use super::{
+    bullet::Bullet,
+    collisions::{damage_in_radius, ColliderType},
+    event::{DespawnEvent, TakeDamageEvent},
+    hit_stop::{HitStop, BOSS_HIT_STOP},
+    shake::ScreenShake,
+    GameTime,
+};
+use bevy::prelude::*;
+use bevy::sprite::MaterialMesh2dBundle;
+use bevy_rapier2d::prelude::RapierContext;
+use rand::Rng;
+
+// Length of the whole sequence, and when the final blast goes off
+const SEQUENCE_SECS: f32 = 2.0;
+const FINAL_BLAST_SECS: f32 = 1.5;
+// Size of the final blast, and the damage it does to other enemies
+const FINAL_BLAST_RADIUS: f32 = 160.0;
+const FINAL_BLAST_DAMAGE: f32 = 100.0;
+// Time between the small explosions before the final blast
+const EXPLOSION_INTERVAL: f32 = 0.15;
+// How long the boss spends on each colour while flashing
+const FLASH_SECS: f32 = 0.08;
+// Collectables dropped by the boss once the sequence is over
+const BOSS_SCORE_DROP: u8 = 20;
+const BOSS_POWER_DROP: u8 = 10;
+
+// Added to a boss when it runs out of health. The boss no longer attacks or collides with
+// anything while this plays out.
+#[derive(Component, Debug)]
+pub struct DeathSequence {
+    timer: Timer,
+    explosion_timer: Timer,
+    blasted: bool,
+}
+
+impl Default for DeathSequence {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(SEQUENCE_SECS, TimerMode::Once),
+            explosion_timer: Timer::from_seconds(EXPLOSION_INTERVAL, TimerMode::Repeating),
+            blasted: false,
+        }
+    }
+}
+
+// Circle which quickly grows and fades away. The mesh has a radius of 1 and the entity is
+// scaled up to the current radius.
+#[derive(Component, Debug)]
+pub struct Explosion {
+    timer: Timer,
+    radius: f32,
+}
+
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+pub fn run_death_sequences(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    mut dying: Query<(
+        Entity,
+        &mut DeathSequence,
+        &Transform,
+        Option<&mut Sprite>,
+        Option<&mut TextureAtlasSprite>,
+    )>,
+    bullets: Query<(Entity, &ColliderType), With<Bullet>>,
+    mut despawn_ev: EventWriter<DespawnEvent>,
+    mut damage_ev: EventWriter<TakeDamageEvent>,
+    rapier: Res<RapierContext>,
+    mut shake: ResMut<ScreenShake>,
+    mut hit_stop: ResMut<HitStop>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let mut rng = rand::thread_rng();
+    for (entity, mut sequence, transform, sprite, atlas_sprite) in dying.iter_mut() {
+        sequence.timer.tick(time.delta());
+        let elapsed = sequence.timer.elapsed_secs();
+        let origin = transform.translation.truncate();
+
+        if sequence.timer.finished() {
+            despawn_ev.send(
+                DespawnEvent::new(entity, true)
+                    .with_score(BOSS_SCORE_DROP)
+                    .with_power(BOSS_POWER_DROP),
+            );
+            continue;
+        }
+
+        if !sequence.blasted {
+            // Flash between white and red until the final blast
+            let colour = if (elapsed / FLASH_SECS) as u32 % 2 == 0 {
+                Color::WHITE
+            } else {
+                Color::rgb(1.0, 0.3, 0.2)
+            };
+            let size = match (sprite, atlas_sprite) {
+                (Some(mut sprite), _) => {
+                    sprite.color = colour;
+                    sprite.custom_size
+                }
+                (None, Some(mut sprite)) => {
+                    sprite.color = colour;
+                    sprite.custom_size
+                }
+                (None, None) => None,
+            }
+            .unwrap_or(Vec2::splat(40.0));
+
+            // Small explosions all over the boss' sprite
+            sequence.explosion_timer.tick(time.delta());
+            for _ in 0..sequence.explosion_timer.times_finished_this_tick() {
+                let offset = Vec2::new(
+                    rng.gen_range(-0.5..0.5) * size.x,
+                    rng.gen_range(-0.5..0.5) * size.y,
+                );
+                let radius = rng.gen_range(10.0..20.0);
+                spawn_explosion(&mut commands, origin + offset, radius, &mut meshes, &mut materials);
+            }
+        }
+
+        if !sequence.blasted && elapsed >= FINAL_BLAST_SECS {
+            sequence.blasted = true;
+            spawn_explosion(&mut commands, origin, FINAL_BLAST_RADIUS, &mut meshes, &mut materials);
+            // Enemies caught in the blast are hurt by it, which is usually enough for the
+            // smaller ones escorting the boss
+            damage_in_radius(
+                &rapier,
+                &mut damage_ev,
+                origin,
+                FINAL_BLAST_RADIUS,
+                FINAL_BLAST_DAMAGE,
+                &[ColliderType::Enemy],
+            );
+            shake.start(14.0, 0.6);
+            hit_stop.start(BOSS_HIT_STOP.0, BOSS_HIT_STOP.1);
+            // Every enemy bullet left on screen is turned into a score collectable
+            for (bullet, kind) in bullets.iter() {
+                if *kind == ColliderType::EnemyBullet {
+                    despawn_ev.send(DespawnEvent::new(bullet, true).with_score(1));
+                }
+            }
+            // Hide the boss behind the blast for the rest of the sequence
+            commands.entity(entity).insert(Visibility::Hidden);
+        }
+    }
+}
+
+pub fn spawn_explosion(
+    commands: &mut Commands,
+    position: Vec2,
+    radius: f32,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+) {
+    commands.spawn((
+        Explosion {
+            // Bigger explosions last longer
+            timer: Timer::from_seconds(0.25 + radius / 400.0, TimerMode::Once),
+            radius,
+        },
+        MaterialMesh2dBundle {
+            mesh: meshes.add(shape::Circle::new(1.0).into()).into(),
+            material: materials.add(ColorMaterial::from(Color::rgba(1.0, 0.8, 0.4, 0.9))),
+            // In front of the boss, but under the UI
+            transform: Transform::from_translation(position.extend(0.4)),
+            ..default()
+        },
+    ));
+}
+
+// Grow every explosion to its full size while it fades from yellow to red
+pub fn update_explosions(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    mut explosions: Query<(Entity, &mut Explosion, &mut Transform, &Handle<ColorMaterial>)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (entity, mut explosion, mut transform, material) in explosions.iter_mut() {
+        explosion.timer.tick(time.delta());
+        if explosion.timer.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let progress = explosion.timer.percent();
+        let radius = explosion.radius * (0.3 + 0.7 * progress.sqrt());
+        transform.scale = Vec3::new(radius, radius, 1.0);
+        if let Some(material) = materials.get_mut(material) {
+            material.color = Color::rgba(
+                1.0,
+                0.8 - 0.6 * progress,
+                0.4 - 0.3 * progress,
+                0.9 * (1.0 - progress),
+            );
+        }
+    }
+}
@@ -0,0 +1,172 @@
+// Second window for debug information on native debug builds with the `inspector` feature, so
+// that the game window stays uncluttered while playtesting. F10 opens and closes it. While it
+// is open it shows the debug overlay, a log of gameplay events and the entity inspector, which
+// are otherwise drawn over the game.
+use super::{
+    bullet::Bullet,
+    collisions::ColliderType,
+    enemy::PhaseChangeEvent,
+    event::{EnemyKilledEvent, GameOverEvent},
+    player::LifeLostEvent,
+    shared::format_duration,
+    ui::{self, DebugOverlayText},
+    GameplayState, GameplayTime,
+};
+use crate::GameOptions;
+use bevy::prelude::*;
+use bevy::window::WindowCloseRequested;
+use bevy_inspector_egui::bevy_egui::{egui, EguiContexts};
+use std::collections::VecDeque;
+
+const TOGGLE_KEY: KeyCode = KeyCode::F10;
+// The oldest lines of the event log are dropped past this many
+const LOG_LINES: usize = 200;
+
+#[derive(Component)]
+pub struct DebugWindow;
+
+// Gameplay events of the current run, with the run time they happened at
+#[derive(Resource, Debug, Default)]
+struct EventLog(VecDeque<String>);
+
+impl EventLog {
+    fn push(&mut self, time: &GameplayTime, line: String) {
+        if self.0.len() >= LOG_LINES {
+            self.0.pop_front();
+        }
+        self.0
+            .push_back(format!("{}  {}", format_duration(time.elapsed()), line));
+    }
+}
+
+pub struct DebugWindowPlugin;
+
+impl Plugin for DebugWindowPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EventLog>()
+            .add_systems(OnEnter(GameplayState::Loading), clear_log)
+            .add_systems(
+                Update,
+                (
+                    toggle_debug_window,
+                    close_debug_window,
+                    hide_game_overlay,
+                    debug_window_panel,
+                ),
+            )
+            .add_systems(
+                Update,
+                log_events
+                    .before(debug_window_panel)
+                    .run_if(in_state(GameplayState::Playing)),
+            );
+    }
+}
+
+fn toggle_debug_window(
+    mut commands: Commands,
+    input: Res<Input<KeyCode>>,
+    windows: Query<Entity, With<DebugWindow>>,
+) {
+    if !input.just_pressed(TOGGLE_KEY) {
+        return;
+    }
+    match windows.get_single() {
+        Ok(window) => commands.entity(window).despawn(),
+        Err(_) => {
+            commands.spawn((
+                Window {
+                    title: "Debug".to_string(),
+                    resolution: (480., 800.).into(),
+                    ..default()
+                },
+                DebugWindow,
+            ));
+        }
+    }
+}
+
+fn close_debug_window(
+    mut commands: Commands,
+    mut close_ev: EventReader<WindowCloseRequested>,
+    windows: Query<(), With<DebugWindow>>,
+) {
+    for event in close_ev.iter() {
+        if windows.contains(event.window) {
+            commands.entity(event.window).despawn();
+        }
+    }
+}
+
+// The overlay over the game is left out while the debug window shows the same thing
+fn hide_game_overlay(
+    windows: Query<(), With<DebugWindow>>,
+    mut overlay: Query<&mut Visibility, With<DebugOverlayText>>,
+) {
+    let visibility = if windows.is_empty() {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    for mut overlay_visibility in overlay.iter_mut() {
+        if *overlay_visibility != visibility {
+            *overlay_visibility = visibility;
+        }
+    }
+}
+
+fn clear_log(mut log: ResMut<EventLog>) {
+    log.0.clear();
+}
+
+fn log_events(
+    time: Res<GameplayTime>,
+    mut log: ResMut<EventLog>,
+    mut killed_ev: EventReader<EnemyKilledEvent>,
+    mut phase_ev: EventReader<PhaseChangeEvent>,
+    mut life_lost_ev: EventReader<LifeLostEvent>,
+    mut game_over_ev: EventReader<GameOverEvent>,
+) {
+    for event in killed_ev.iter() {
+        let name = event.kind.map_or("Enemy", |kind| kind.name());
+        let line = format!(
+            "{} killed at ({:.0}, {:.0})",
+            name, event.position.x, event.position.y
+        );
+        log.push(&time, line);
+    }
+    for event in phase_ev.iter() {
+        log.push(&time, format!("Boss {:?} moved on to phase {}", event.boss, event.phase));
+    }
+    for _ in life_lost_ev.iter() {
+        log.push(&time, "Player lost a life".to_string());
+    }
+    for _ in game_over_ev.iter() {
+        log.push(&time, "Game over".to_string());
+    }
+}
+
+fn debug_window_panel(
+    mut contexts: EguiContexts,
+    windows: Query<Entity, With<DebugWindow>>,
+    log: Res<EventLog>,
+    bullets: Query<&ColliderType, With<Bullet>>,
+    options: Res<GameOptions>,
+) {
+    let Ok(window) = windows.get_single() else { return; };
+    // The window only gets its egui context the frame after it is opened
+    let Some(ctx) = contexts.try_ctx_for_window_mut(window) else { return; };
+    egui::CentralPanel::default().show(ctx, |ui| {
+        ui.heading("Overlay");
+        ui.label(ui::debug_overlay_text(&bullets, options.get_frame_cap()));
+        ui.separator();
+        ui.heading("Events");
+        egui::ScrollArea::vertical()
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                for line in log.0.iter() {
+                    ui.label(line);
+                }
+            });
+    });
+}
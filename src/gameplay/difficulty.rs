@@ -0,0 +1,101 @@
+// How hard the story levels are. Attack patterns say which difficulty they start appearing
+// at, so that a single boss definition can be used for every difficulty. On top of that, the
+// difficulty scales enemy health, the bullets they fire and, through
+// ScoringConfig in rank.rs, what they drop.
+use super::{enemy::Enemy, shared::Health, ui::GameplayUI};
+use crate::ui::fonts::Fonts;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Lunatic,
+}
+
+impl Difficulty {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Lunatic => "Lunatic",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Lunatic,
+            Difficulty::Lunatic => Difficulty::Easy,
+        }
+    }
+
+    // Multiplies the health of every enemy
+    pub fn health_scale(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.7,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.3,
+            Difficulty::Lunatic => 1.6,
+        }
+    }
+
+    // Multiplies the number of bullets in every attack
+    pub fn bullet_scale(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.6,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.3,
+            Difficulty::Lunatic => 1.6,
+        }
+    }
+
+    // Multiplies the time between attacks
+    pub fn cooldown_scale(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 1.4,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 0.85,
+            Difficulty::Lunatic => 0.7,
+        }
+    }
+}
+
+// Scale the health of enemies as they spawn. Runs after commands are applied, so it also
+// covers health set after spawning, like the mid-boss'.
+pub fn scale_enemy_health(
+    difficulty: Res<Difficulty>,
+    mut enemies: Query<&mut Health, Added<Enemy>>,
+) {
+    let scale = difficulty.health_scale();
+    for mut health in enemies.iter_mut() {
+        health.total *= scale;
+        health.current *= scale;
+    }
+}
+
+// Show the difficulty being played in the bottom right corner, above the player's health bar
+pub fn spawn_difficulty_text(mut commands: Commands, fonts: Res<Fonts>, difficulty: Res<Difficulty>) {
+    commands.spawn((
+        TextBundle::from_section(
+            difficulty.name(),
+            TextStyle {
+                font: fonts.main(),
+                font_size: 22.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(100.0),
+            right: Val::Px(30.0),
+            ..default()
+        }),
+        GameplayUI,
+    ));
+}
@@ -0,0 +1,151 @@
+// Damage per second dealt to bosses in practice runs, so that shot types and routes can be
+// compared. The rolling value is shown on the HUD, and the peak and average on the results.
+use super::{
+    enemy::Boss,
+    event::TakeDamageEvent,
+    levels::GameMode,
+    ui::GameplayUI,
+    GameplayTime,
+};
+use crate::ui::fonts::Fonts;
+use bevy::prelude::*;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+// How far back the rolling value looks
+const DPS_WINDOW: Duration = Duration::from_secs(3);
+
+#[derive(Resource, Debug, Default)]
+pub struct DpsMeter {
+    // Damage dealt in the last DPS_WINDOW, with the run time it was dealt at
+    recent: VecDeque<(Duration, f32)>,
+    total: f32,
+    // When the first and the latest hit on a boss landed
+    first_hit: Option<Duration>,
+    last_hit: Duration,
+    peak: f32,
+}
+
+impl DpsMeter {
+    fn record(&mut self, now: Duration, damage: f32) {
+        self.first_hit.get_or_insert(now);
+        self.last_hit = now;
+        self.total += damage;
+        self.recent.push_back((now, damage));
+    }
+
+    // Forget damage which has fallen out of the window and update the peak
+    fn update(&mut self, now: Duration) {
+        while self.recent.front().map_or(false, |(at, _)| now.saturating_sub(*at) > DPS_WINDOW) {
+            self.recent.pop_front();
+        }
+        self.peak = self.peak.max(self.rolling(now));
+    }
+
+    // Damage per second over the last DPS_WINDOW, or since the first hit if that was sooner
+    pub fn rolling(&self, now: Duration) -> f32 {
+        let Some(first_hit) = self.first_hit else { return 0.0; };
+        let window = now.saturating_sub(first_hit).min(DPS_WINDOW).as_secs_f32().max(1.0);
+        self.recent.iter().map(|(_, damage)| damage).sum::<f32>() / window
+    }
+
+    pub fn peak(&self) -> f32 {
+        self.peak
+    }
+
+    // Damage per second from the first hit on a boss to the last one
+    pub fn average(&self) -> f32 {
+        let Some(first_hit) = self.first_hit else { return 0.0; };
+        self.total / self.last_hit.saturating_sub(first_hit).as_secs_f32().max(1.0)
+    }
+}
+
+// Marker for the DPS text on the HUD
+#[derive(Component)]
+pub struct DpsText;
+
+// Add up the damage dealt to bosses. Only run in practice.
+pub fn track_boss_damage(
+    mut damage_ev: EventReader<TakeDamageEvent>,
+    bosses: Query<(), With<Boss>>,
+    g_time: Res<GameplayTime>,
+    mut meter: ResMut<DpsMeter>,
+) {
+    let now = g_time.elapsed();
+    for event in damage_ev.iter() {
+        if bosses.contains(event.entity()) {
+            meter.record(now, event.damage());
+        }
+    }
+    meter.update(now);
+}
+
+// Show the meter in the bottom right corner, above the difficulty, in practice runs
+pub fn spawn_dps_text(mut commands: Commands, fonts: Res<Fonts>, mode: Res<GameMode>) {
+    if *mode != GameMode::Practice {
+        return;
+    }
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: fonts.main(),
+                font_size: 22.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(130.0),
+            right: Val::Px(30.0),
+            ..default()
+        }),
+        DpsText,
+        GameplayUI,
+    ));
+}
+
+pub fn update_dps_text(
+    mut texts: Query<&mut Text, With<DpsText>>,
+    meter: Res<DpsMeter>,
+    g_time: Res<GameplayTime>,
+) {
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = format!(
+            "DPS: {:.0} (peak {:.0})",
+            meter.rolling(g_time.elapsed()),
+            meter.peak()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: f32) -> Duration {
+        Duration::from_secs_f32(secs)
+    }
+
+    #[test]
+    fn rolling_only_counts_the_window() {
+        let mut meter = DpsMeter::default();
+        meter.record(secs(0.0), 90.0);
+        meter.record(secs(5.0), 30.0);
+        meter.update(secs(5.0));
+        assert_eq!(meter.rolling(secs(5.0)), 10.0);
+        assert_eq!(meter.average(), 24.0);
+    }
+
+    #[test]
+    fn peak_is_kept_after_the_window() {
+        let mut meter = DpsMeter::default();
+        meter.record(secs(0.0), 20.0);
+        meter.record(secs(2.0), 40.0);
+        meter.update(secs(3.0));
+        assert_eq!(meter.peak(), 20.0);
+        meter.update(secs(10.0));
+        assert_eq!(meter.rolling(secs(10.0)), 0.0);
+        assert_eq!(meter.peak(), 20.0);
+    }
+}
@@ -1,16 +1,26 @@
 use super::{
-    bullet::AttackPattern,
+    bestiary::EnemyKind,
+    bullet::{AttackPattern, BulletGroup, BulletPool, Sequencing},
+    bullet::RetargetBullets,
     collisions::ColliderType,
+    death_sequence::DeathSequence,
+    difficulty::Difficulty,
+    event::{DespawnEvent, EnemyKilledEvent},
+    levels::palette::tinted,
     loading::Atlases,
     player::Player,
+    rng::RunRng,
     shared::{
         physics::*, ExtraSpriteInfo, Formation, FormationShape, Health, MetaSpriteAtlas, Movement,
-        Name, METRE, METRE_SQUARED,
+        Name, Tags, METRE, METRE_SQUARED,
     },
-    ui::{create_health_bar, ObjectType},
+    ui::{create_health_bar, Link, ObjectType},
+    GameTime,
+    GameplayTime,
 };
-use crate::GameState;
+use crate::ui::fonts::Fonts;
 use bevy::prelude::*;
+use std::time::Duration;
 
 #[derive(Component)]
 pub struct Enemy;
@@ -18,6 +28,17 @@ pub struct Enemy;
 #[derive(Component)]
 pub struct Boss;
 
+// Glow around a boss. It pulses slowly and brightens as the boss charges up its next attack,
+// so that attacks can be seen coming.
+#[derive(Component)]
+pub struct BossAura;
+
+// Size of the aura compared to the boss
+const AURA_SCALE: f32 = 1.4;
+// How far and how often bosses bob up and down while hovering
+const HOVER_AMPLITUDE: f32 = 6.0;
+const HOVER_FREQUENCY: f32 = 0.6;
+
 // Health Bar UI element for Boss-type enemies
 #[derive(Component)]
 pub struct BossHealthBar;
@@ -32,6 +53,10 @@ pub struct Attacks {
     current_attack: usize,
     // Timer for switching between attacks
     switch_timer: Timer,
+    // Extra patterns fired on their own cooldowns on top of whichever attack is current
+    overlays: Vec<AttackPattern>,
+    // Difficulty the patterns have been picked for, None until the enemy first attacks
+    difficulty: Option<Difficulty>,
 }
 
 impl Attacks {
@@ -40,6 +65,49 @@ impl Attacks {
             attacks,
             current_attack: 0,
             switch_timer,
+            overlays: Vec::new(),
+            difficulty: None,
+        }
+    }
+
+    // For enemies which never fire, such as divers
+    pub fn none() -> Self {
+        Self::new(Vec::new(), Timer::default())
+    }
+
+    pub fn with_overlays(mut self, overlays: Vec<AttackPattern>) -> Self {
+        self.overlays = overlays;
+        self
+    }
+
+    // Drop the patterns meant for harder difficulties than the one being played, and scale
+    // the rest to it. The first attack is always kept, so that an enemy never ends up with
+    // nothing to fire.
+    fn pick_for_difficulty(&mut self, difficulty: Difficulty) {
+        if self.difficulty == Some(difficulty) {
+            return;
+        }
+        self.difficulty = Some(difficulty);
+
+        let first = self.attacks.first().cloned();
+        self.attacks.retain(|attack| attack.min_difficulty <= difficulty);
+        if self.attacks.is_empty() {
+            self.attacks.extend(first);
+        }
+        self.overlays.retain(|attack| attack.min_difficulty <= difficulty);
+        self.current_attack = 0;
+
+        for attack in self.attacks.iter_mut().chain(self.overlays.iter_mut()) {
+            let parts = attack.composite.iter_mut().flat_map(|composite| &mut composite.parts);
+            for group in parts
+                .map(|part| &mut part.bullet_group)
+                .chain([&mut attack.bullet_group])
+            {
+                let number = group.number as f32 * difficulty.bullet_scale();
+                group.number = (number.round() as u16).max(1);
+            }
+            let cooldown = attack.cd.duration().mul_f32(difficulty.cooldown_scale());
+            attack.cd.set_duration(cooldown);
         }
     }
     pub fn get_attacks(&self) -> Vec<AttackPattern> {
@@ -62,6 +130,17 @@ impl Attacks {
         &mut self.current_attack
     }
 
+    // How far along the current attack is with charging up, from 0 to 1. An attack is fully
+    // charged while it is firing, and charges up again during its cooldown.
+    pub fn charge(&self) -> f32 {
+        let attack = &self.attacks[self.current_attack];
+        if attack.current_bullet < attack.bullet_group.number {
+            1.0
+        } else {
+            attack.cd.percent()
+        }
+    }
+
     pub fn get_all_mut(&mut self) -> (&mut Vec<AttackPattern>, &mut usize, &mut Timer) {
         (
             &mut self.attacks,
@@ -71,84 +150,389 @@ impl Attacks {
     }
 }
 
+// Give enemies the tags of their kind. The kind is inserted in many places as enemies are
+// spawned, so the tags are added here once instead of next to each of them.
+pub fn tag_enemies(mut commands: Commands, enemies: Query<(Entity, &EnemyKind), Added<EnemyKind>>) {
+    for (entity, kind) in enemies.iter() {
+        commands.entity(entity).insert(Tags::new(kind.tags()));
+    }
+}
+
+// One phase of a boss fight. The phase starts once the boss' health drops to `threshold`,
+// given as a fraction of its total health.
+#[derive(Debug)]
+pub struct BossPhase {
+    pub threshold: f32,
+    // Attacks used for the rest of the fight, replacing the ones from the previous phase
+    pub attacks: Attacks,
+    // Multiplies the colour of the boss' sprite
+    pub tint: Color,
+    // How fast the boss moves compared to the first phase
+    pub speed: f32,
+    // Sent as the phase starts, to change what the bullets already on screen are doing
+    pub retarget: Option<RetargetBullets>,
+}
+
+// Phases a boss goes through as it takes damage. The first phase is whatever the boss was
+// spawned with, so only the later phases are listed here.
+#[derive(Component, Debug)]
+pub struct BossPhases {
+    // Phases which haven't started yet, in order
+    upcoming: Vec<BossPhase>,
+    // 0 for the first phase
+    current: usize,
+    speed: f32,
+}
+
+impl BossPhases {
+    pub fn new(mut phases: Vec<BossPhase>) -> Self {
+        phases.sort_by(|a, b| b.threshold.total_cmp(&a.threshold));
+        Self {
+            upcoming: phases,
+            current: 0,
+            speed: 1.0,
+        }
+    }
+
+    // Movement speed of the current phase, for the levels' boss movement systems
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+}
+
+// Sent when a boss moves on to its next phase
+#[derive(Debug, Event)]
+pub struct PhaseChangeEvent {
+    pub boss: Entity,
+    pub phase: usize,
+}
+
+// Start the next phase of every boss whose health has dropped far enough
+#[allow(clippy::type_complexity)]
+pub fn advance_boss_phases(
+    mut commands: Commands,
+    mut bosses: Query<
+        (
+            Entity,
+            &Health,
+            &mut BossPhases,
+            Option<&mut TextureAtlasSprite>,
+            Option<&mut Sprite>,
+        ),
+        (With<Boss>, Without<DeathSequence>),
+    >,
+    mut phase_ev: EventWriter<PhaseChangeEvent>,
+    mut retarget_ev: EventWriter<RetargetBullets>,
+) {
+    for (entity, health, mut phases, atlas_sprite, sprite) in bosses.iter_mut() {
+        let fraction = health.current / health.total;
+        // A big enough hit can skip a phase, in which case only the last one reached is used
+        let mut next = None;
+        while phases
+            .upcoming
+            .first()
+            .is_some_and(|phase| fraction <= phase.threshold)
+        {
+            next = Some(phases.upcoming.remove(0));
+            phases.current += 1;
+        }
+        let Some(phase) = next else { continue; };
+
+        phases.speed = phase.speed;
+        commands.entity(entity).insert(phase.attacks);
+        if let Some(retarget) = phase.retarget {
+            retarget_ev.send(retarget);
+        }
+        if let Some(mut atlas_sprite) = atlas_sprite {
+            atlas_sprite.color = tinted(atlas_sprite.color, phase.tint);
+        }
+        if let Some(mut sprite) = sprite {
+            sprite.color = tinted(sprite.color, phase.tint);
+        }
+        phase_ev.send(PhaseChangeEvent {
+            boss: entity,
+            phase: phases.current,
+        });
+    }
+}
+
+// What a boss does when the player runs out of time to beat it
+#[derive(Debug)]
+pub enum TimeOut {
+    // The boss leaves, dropping only a few collectables
+    Escape,
+    // The boss switches to these attacks for the rest of the fight
+    Enrage(Attacks),
+}
+
+// Collectables dropped by a boss that escapes, instead of the full amount for beating it
+const ESCAPE_SCORE_DROP: u8 = 5;
+// Multiplies the colour of an enraged boss' sprite
+const ENRAGE_TINT: Color = Color::rgb(1.0, 0.35, 0.35);
+
+// Time limit for beating a boss. The time left is shown next to the boss' health bar.
+#[derive(Component, Debug)]
+pub struct BossTimer {
+    timer: Timer,
+    // Taken once the time runs out
+    time_out: Option<TimeOut>,
+}
+
+impl BossTimer {
+    pub fn new(secs: f32, time_out: TimeOut) -> Self {
+        Self {
+            timer: Timer::from_seconds(secs, TimerMode::Once),
+            time_out: Some(time_out),
+        }
+    }
+
+    // Whole seconds left, or None once the time is up
+    pub fn seconds_left(&self) -> Option<u32> {
+        if self.timer.finished() {
+            return None;
+        }
+        Some(self.timer.remaining_secs().ceil() as u32)
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn tick_boss_timers(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    mut bosses: Query<
+        (
+            Entity,
+            &mut BossTimer,
+            Option<&Link>,
+            Option<&mut TextureAtlasSprite>,
+            Option<&mut Sprite>,
+        ),
+        (With<Boss>, Without<DeathSequence>),
+    >,
+    mut despawn_ev: EventWriter<DespawnEvent>,
+) {
+    for (entity, mut boss_timer, health_bar, atlas_sprite, sprite) in bosses.iter_mut() {
+        boss_timer.timer.tick(time.delta());
+        if !boss_timer.timer.just_finished() {
+            continue;
+        }
+        match boss_timer.time_out.take() {
+            Some(TimeOut::Escape) => {
+                if let Some(health_bar) = health_bar {
+                    despawn_ev.send(DespawnEvent::new(health_bar.0, true));
+                }
+                despawn_ev.send(DespawnEvent::new(entity, true).with_score(ESCAPE_SCORE_DROP));
+            }
+            Some(TimeOut::Enrage(attacks)) => {
+                // Enraged for good, so later phases don't replace the attacks
+                commands
+                    .entity(entity)
+                    .insert(attacks)
+                    .remove::<BossPhases>();
+                if let Some(mut atlas_sprite) = atlas_sprite {
+                    atlas_sprite.color = tinted(atlas_sprite.color, ENRAGE_TINT);
+                }
+                if let Some(mut sprite) = sprite {
+                    sprite.color = tinted(sprite.color, ENRAGE_TINT);
+                }
+            }
+            None => {}
+        }
+    }
+}
+
+// Spawns a boss of the given kind, with its name and health from the bestiary
 pub fn spawn_boss<T: ExtraSpriteInfo>(
     commands: &mut Commands,
-    name: Name<'static>,
+    kind: EnemyKind,
     spawn_point: Transform,
     attacks: Attacks,
-    asset_server: Res<AssetServer>,
+    fonts: &Fonts,
     sprite: T,
-) {
+) -> Entity {
+    let name = Name::from(kind.name());
     let health_bar = create_health_bar::<BossHealthBar>(
         commands,
-        &asset_server,
+        fonts,
         name.clone(),
         ObjectType::Enemy,
         BossHealthBar,
     );
 
-    commands.spawn((
-        sprite.bundle(spawn_point),
-        sprite.collider(),
-        attacks,
-        name,
-        Enemy,
-        Boss,
-        Health::new(300.0, None),
-        RigidBody::Dynamic,
-        ColliderType::Enemy,
-        ColliderType::Enemy.collision_group(),
-        ActiveEvents::COLLISION_EVENTS,
-        Velocity::zero(),
-        Movement::ZERO,
-        super::ui::Link(health_bar),
-    ));
+    commands
+        .spawn((
+            sprite.bundle(spawn_point),
+            sprite.collider(),
+            attacks,
+            name,
+            Enemy,
+            Boss,
+            kind,
+            Health::new(kind.health(), None),
+            RigidBody::Dynamic,
+            ColliderType::Enemy,
+            ColliderType::Enemy.collision_group(),
+            ActiveEvents::COLLISION_EVENTS,
+            Velocity::zero(),
+            Movement::ZERO,
+            super::ui::Link(health_bar),
+        ))
+        .with_children(|parent| {
+            // A faint, larger copy of the boss sprite just in front of it
+            parent.spawn((
+                BossAura,
+                sprite.bundle(
+                    Transform::from_xyz(0.0, 0.0, 0.05).with_scale(Vec3::splat(AURA_SCALE)),
+                ),
+            ));
+        })
+        .id()
+}
+
+// Pulse the aura of every boss, and light it up according to how charged the boss' attack is
+#[allow(clippy::type_complexity)]
+pub fn animate_boss_aura(
+    time: Res<GameplayTime>,
+    bosses: Query<&Attacks, With<Boss>>,
+    mut auras: Query<
+        (
+            &Parent,
+            &mut Transform,
+            Option<&mut TextureAtlasSprite>,
+            Option<&mut Sprite>,
+        ),
+        With<BossAura>,
+    >,
+) {
+    use std::f32::consts::TAU;
+    let pulse = (TAU * 1.5 * time.level_elapsed_secs()).sin();
+
+    for (parent, mut transform, atlas_sprite, sprite) in auras.iter_mut() {
+        let Ok(attacks) = bosses.get(parent.get()) else { continue; };
+        let charge = attacks.charge();
+
+        // Pulse a little, and a little more when charged
+        transform.scale = Vec3::splat(AURA_SCALE * (1.0 + (0.04 + 0.06 * charge) * pulse));
+        // Fade in and go from a cold blue to a hot white as the attack charges up
+        let colour = Color::rgba(
+            0.4 + 0.6 * charge,
+            0.6 + 0.4 * charge,
+            1.0,
+            0.1 + 0.35 * charge * charge,
+        );
+        if let Some(mut atlas_sprite) = atlas_sprite {
+            atlas_sprite.color = colour;
+        }
+        if let Some(mut sprite) = sprite {
+            sprite.color = colour;
+        }
+    }
 }
 
+// Make bosses bob up and down gently on top of their normal movement
+pub fn hover_bosses(
+    time: Res<GameplayTime>,
+    dt: Res<GameTime>,
+    mut bosses: Query<&mut Transform, With<Boss>>,
+) {
+    use std::f32::consts::TAU;
+    let bob = |t: f32| HOVER_AMPLITUDE * (TAU * HOVER_FREQUENCY * t).sin();
+    let now = time.level_elapsed_secs();
+    // Only move by the change since the last frame, so the physics simulation keeps control
+    // of where the boss actually is.
+    let offset = bob(now) - bob(now - dt.delta_seconds());
+
+    for mut transform in bosses.iter_mut() {
+        transform.translation.y += offset;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn enemy_attack(
     mut commands: Commands,
-    mut enemy: Query<(&Transform, &mut Attacks), With<Enemy>>,
-    player_t: Query<&Transform, With<Player>>,
-    dt: Res<Time>,
+    mut enemy: Query<(&Transform, &mut Attacks, Option<&Turret>), With<Enemy>>,
+    player_t: Query<(&Transform, &Velocity), With<Player>>,
+    dt: Res<GameTime>,
     atlases: Res<Atlases<'static>>,
-    state: Res<State<GameState>>,
+    difficulty: Res<Difficulty>,
+    mut rng: ResMut<RunRng>,
+    mut pool: ResMut<BulletPool>,
 ) {
-    for (transform, mut attacks) in enemy.iter_mut() {
+    let player = match player_t.get_single() {
+        Ok((transform, velocity)) => AimTarget {
+            transform: *transform,
+            // The player's velocity isn't slowed down with gameplay, unlike the bullets
+            velocity: if dt.scale() > 0.0 {
+                velocity.linvel / dt.scale()
+            } else {
+                Vec2::ZERO
+            },
+        },
+        Err(_) => AimTarget {
+            transform: Transform::default(),
+            velocity: Vec2::ZERO,
+        },
+    };
+
+    for (transform, mut attacks, turret) in enemy.iter_mut() {
+        attacks.pick_for_difficulty(*difficulty);
+        if attacks.attacks.is_empty() || turret.is_some_and(|turret| !turret.firing()) {
+            continue;
+        }
+
+        // Overlays don't take part in the switching between attacks, they keep firing
+        // whenever their own cooldown allows
+        for overlay in attacks.overlays.iter_mut() {
+            if charge_attack(overlay, dt.delta()) {
+                fire_attack(
+                    &mut commands,
+                    &mut pool,
+                    overlay,
+                    transform,
+                    &player,
+                    &atlases,
+                    &mut rng,
+                );
+            }
+            fire_parts(
+                &mut commands,
+                &mut pool,
+                overlay,
+                transform,
+                &player,
+                &atlases,
+                &mut rng,
+                dt.delta(),
+            );
+        }
+
         // Get number of attacks that the enemy can cycle through
         let attacks_number = attacks.attacks.len();
 
         // Retrieve the current attack
         let (attacks, current_attack_number, switch_timer) = attacks.get_all_mut();
-        let mut attack = &mut attacks[*current_attack_number];
+        let attack = &mut attacks[*current_attack_number];
 
-        // Whenever the game end or starts, reset the variables and timers
-        if state.is_changed() {
-            attack.cd.reset();
-            if let Some(icd) = &mut attack.icd {
-                icd.reset();
-            };
-            switch_timer.reset();
-            *current_attack_number = 0;
-            attack.current_bullet = 0;
-        }
+        // Parts of composite attacks carry on with their volley while the attack cools down
+        fire_parts(
+            &mut commands,
+            &mut pool,
+            attack,
+            transform,
+            &player,
+            &atlases,
+            &mut rng,
+            dt.delta(),
+        );
 
-        // Tick attack timers.
-        attack.cd.tick(dt.delta());
-
-        // If the current bullet number is equal to or has gone over the total
-        // number of bullets in the bullet_group, check if the attack cooldown is finished
-        // so that current_bullet can be reset to 0 and the attack cooldown can be reset.
-        if attack.current_bullet >= attack.bullet_group.number {
-            if attack.cd.finished() {
-                attack.current_bullet = 0u16;
-                attack.cd.reset();
-            }
+        // Attacks which have fired all of their bullets only wait for their cooldown, and
+        // don't count towards switching to the next attack
+        let finished_firing = attack.current_bullet >= attack.bullet_group.number;
+        let ready = charge_attack(attack, dt.delta());
+        if finished_firing {
             continue;
         }
 
-        if let Some(icd) = &mut attack.icd {
-            icd.tick(dt.delta());
-        };
         switch_timer.tick(dt.delta());
 
         // Cycle through attacks by increasing current_attack by one until
@@ -162,87 +546,298 @@ pub fn enemy_attack(
             switch_timer.reset();
         }
 
-        // If there is an ICD and it is not yet finished, do not continue running
-        // the function and return early.
-        let timer = match &attack.icd {
-            Some(icd) => icd,
-            None => &attack.cd,
-        };
-        if !timer.finished() {
-            continue;
+        if ready {
+            fire_attack(
+                &mut commands,
+                &mut pool,
+                attack,
+                transform,
+                &player,
+                &atlases,
+                &mut rng,
+            );
         }
+    }
+}
 
-        // Actually spawn the attacks
-        // Load bullet sprite
-        let bullet_texture = atlases
-            .get("sprites/enemy-projectile.png")
-            .expect("Texture atlas not found!")
-            .clone();
+// Whenever a run starts, reset the variables and timers of the attacks. Coming back from the
+// pause menu carries on with the attacks where they were left.
+pub fn reset_attacks(mut enemies: Query<&mut Attacks>) {
+    for mut attacks in enemies.iter_mut() {
+        for overlay in attacks.overlays.iter_mut() {
+            reset_attack(overlay);
+        }
+        let (attacks, current_attack_number, switch_timer) = attacks.get_all_mut();
+        for attack in attacks.iter_mut() {
+            reset_attack(attack);
+        }
+        switch_timer.reset();
+        *current_attack_number = 0;
+    }
+}
 
-        // Create Meta Sprite for the bullet entity
-        let meta_sprite = MetaSpriteAtlas {
-            sprite: TextureAtlasSprite {
-                custom_size: Some(METRE_SQUARED * 2.0),
-                ..default()
-            },
-            texture_atlas: Some(bullet_texture),
-            collider: Collider::ball(METRE / 2.5),
-            // Value of 1.3 found through experimentation and what looks ok to me
-            grazing_collider: Some(Collider::ball(METRE / 1.3)),
-        };
-
-        // Set the bullet_group origin transform to the enemy's position
-        attack.bullet_group.origin = *transform;
-
-        // If an attack is of Linear Formation, then it means that it was not
-        // fully initialised when declared as it would require a target transform,
-        // which can only be retrieved at runtime, when spawning the bullet, so
-        // update the bullet_group formation so that the target transform is initialised
-        // correctly.
-        if attack.bullet_group.formation.kind == FormationShape::Linear {
-            let player_transform = *player_t.get_single().unwrap_or(&Transform::default());
-            attack.bullet_group.formation =
-                Formation::linear(player_transform, meta_sprite.sprite.custom_size.unwrap());
-        }
-
-        // If there is an ICD in the attack pattern, create a custom loop that runs accross frames
-        // by using current_bullet as an iterator, and manually increment it every time ICD finishes.
-        if let Some(icd) = &mut attack.icd {
-            attack.bullet_group.spawn_single(
-                &mut commands,
-                attack.movement.clone(),
-                attack.current_bullet,
-                meta_sprite,
-            );
+fn reset_attack(attack: &mut AttackPattern) {
+    attack.cd.reset();
+    if let Some(icd) = &mut attack.icd {
+        icd.reset();
+    };
+    attack.current_bullet = 0;
+    for part in attack.composite.iter_mut().flat_map(|composite| &mut composite.parts) {
+        part.armed = false;
+    }
+}
+
+// Tick the timers of an attack, and return whether it should fire this frame
+fn charge_attack(attack: &mut AttackPattern, delta: Duration) -> bool {
+    // Tick attack timers.
+    attack.cd.tick(delta);
+
+    // If the current bullet number is equal to or has gone over the total
+    // number of bullets in the bullet_group, check if the attack cooldown is finished
+    // so that current_bullet can be reset to 0 and the attack cooldown can be reset.
+    if attack.current_bullet >= attack.bullet_group.number {
+        if attack.cd.finished() {
+            attack.current_bullet = 0u16;
+            attack.cd.reset();
+        }
+        return false;
+    }
+
+    if let Some(icd) = &mut attack.icd {
+        icd.tick(delta);
+    };
+
+    // If there is an ICD, the attack has to wait for it instead of the CD
+    let timer = match &attack.icd {
+        Some(icd) => icd,
+        None => &attack.cd,
+    };
+    timer.finished()
+}
+
+// Where the player is for attacks aimed at them, with their velocity in pixels per second of
+// gameplay
+struct AimTarget {
+    transform: Transform,
+    velocity: Vec2,
+}
+
+// Point to fire at so that a bullet going at `speed` meets a target which keeps moving in a
+// straight line. The target itself if the bullet is too slow to ever catch up with it.
+fn lead_target(origin: Vec2, target: Vec2, velocity: Vec2, speed: f32) -> Vec2 {
+    // Solve |offset + velocity * t| = speed * t for the earliest time t in the future
+    let offset = target - origin;
+    let a = velocity.length_squared() - speed * speed;
+    let b = 2.0 * offset.dot(velocity);
+    let c = offset.length_squared();
+    let time = if a.abs() < 1e-6 {
+        (b < 0.0).then(|| -c / b)
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        (discriminant >= 0.0)
+            .then(|| {
+                let root = discriminant.sqrt();
+                [(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)]
+            })
+            .and_then(|times| times.into_iter().filter(|t| *t > 0.0).reduce(f32::min))
+    };
+    match time {
+        Some(time) => target + velocity * time,
+        None => target,
+    }
+}
+
+// Actually spawn the bullets of an attack
+fn fire_attack(
+    commands: &mut Commands,
+    pool: &mut BulletPool,
+    attack: &mut AttackPattern,
+    transform: &Transform,
+    player: &AimTarget,
+    atlases: &Atlases<'static>,
+    rng: &mut RunRng,
+) {
+    let meta_sprite = enemy_bullet_sprite(atlases);
+    aim_group(&mut attack.bullet_group, &attack.movement, transform, player, &meta_sprite);
+
+    // A new volley starts with the first bullet of the attack's own group
+    if attack.current_bullet == 0 {
+        for part in attack.composite.iter_mut().flat_map(|composite| &mut composite.parts) {
+            part.arm();
+        }
+    }
 
-            if icd.finished() {
-                attack.current_bullet += 1;
+    // If there is an ICD in the attack pattern, create a custom loop that runs accross frames
+    // by using current_bullet as an iterator, and manually increment it every time ICD finishes.
+    if let Some(icd) = &mut attack.icd {
+        attack.bullet_group.spawn_single(
+            commands,
+            pool,
+            attack.movement.clone(),
+            attack.current_bullet,
+            meta_sprite,
+            &mut **rng,
+        );
+
+        if icd.finished() {
+            attack.current_bullet += 1;
+            icd.reset();
+            if attack.current_bullet >= attack.bullet_group.number {
+                attack.bullet_group.formation.advance();
+            }
+        }
+    } else {
+        // When there is no ICD, spawn all the bullets in the group at once, using just the CD
+        // for timing attacks.
+        attack
+            .bullet_group
+            .spawn_all(commands, pool, attack.movement.clone(), meta_sprite, &mut **rng);
+        attack.bullet_group.formation.advance();
+        attack.cd.reset();
+    }
+}
+
+// Fire whichever parts of a composite attack are due this frame
+#[allow(clippy::too_many_arguments)]
+fn fire_parts(
+    commands: &mut Commands,
+    pool: &mut BulletPool,
+    attack: &mut AttackPattern,
+    transform: &Transform,
+    player: &AimTarget,
+    atlases: &Atlases<'static>,
+    rng: &mut RunRng,
+    delta: Duration,
+) {
+    let Some(composite) = &mut attack.composite else { return; };
+    // Fired in turn, the first part waits for the attack's own group to finish
+    let mut previous_finished =
+        attack.icd.is_none() || attack.current_bullet >= attack.bullet_group.number;
+
+    for part in composite.parts.iter_mut() {
+        if part.finished() {
+            previous_finished = true;
+            continue;
+        }
+        if composite.sequencing == Sequencing::InTurn && !previous_finished {
+            return;
+        }
+        previous_finished = false;
+        if !part.delay.tick(delta).finished() {
+            continue;
+        }
+
+        let meta_sprite = enemy_bullet_sprite(atlases);
+        aim_group(&mut part.bullet_group, &part.movement, transform, player, &meta_sprite);
+        // Same as the attack's own group: one bullet every ICD, or all of them at once
+        match &mut part.icd {
+            Some(icd) => {
+                if !icd.tick(delta).finished() {
+                    continue;
+                }
                 icd.reset();
+                part.bullet_group.spawn_single(
+                    commands,
+                    pool,
+                    part.movement.clone(),
+                    part.current_bullet,
+                    meta_sprite,
+                    &mut **rng,
+                );
+                part.current_bullet += 1;
+            }
+            None => {
+                part.bullet_group.spawn_all(
+                    commands,
+                    pool,
+                    part.movement.clone(),
+                    meta_sprite,
+                    &mut **rng,
+                );
+                part.current_bullet = part.bullet_group.number;
             }
-        } else {
-            // When there is no ICD, spawn all the bullets in the group at once, using just the CD
-            // for timing attacks.
-            attack
-                .bullet_group
-                .spawn_all(&mut commands, attack.movement.clone(), meta_sprite);
-            attack.cd.reset();
+        }
+        if part.current_bullet >= part.bullet_group.number {
+            part.bullet_group.formation.advance();
+            part.armed = false;
         }
     }
 }
 
-// Spawns normal enemies
+fn enemy_bullet_sprite(atlases: &Atlases<'static>) -> MetaSpriteAtlas {
+    // Load bullet sprite
+    let bullet_texture = atlases
+        .get("sprites/enemy-projectile.png")
+        .expect("Texture atlas not found!")
+        .clone();
+
+    // Create Meta Sprite for the bullet entity
+    MetaSpriteAtlas {
+        sprite: TextureAtlasSprite {
+            custom_size: Some(METRE_SQUARED * 2.0),
+            ..default()
+        },
+        texture_atlas: Some(bullet_texture),
+        collider: Collider::ball(METRE / 2.5),
+    }
+}
+
+// Move a bullet group to the enemy firing it, and point it at the player if it is aimed
+fn aim_group(
+    group: &mut BulletGroup,
+    movement: &Movement,
+    transform: &Transform,
+    player: &AimTarget,
+    meta_sprite: &MetaSpriteAtlas,
+) {
+    // Set the bullet_group origin transform to the enemy's position
+    group.origin = *transform;
+
+    // If an attack is of Linear Formation, then it means that it was not
+    // fully initialised when declared as it would require a target transform,
+    // which can only be retrieved at runtime, when spawning the bullet, so
+    // update the bullet_group formation so that the target transform is initialised
+    // correctly. Aimed and Predictive formations are the same, and only need their target.
+    let formation = &mut group.formation;
+    match formation.kind {
+        FormationShape::Linear => {
+            *formation =
+                Formation::linear(player.transform, meta_sprite.sprite.custom_size.unwrap());
+        }
+        FormationShape::Aimed => formation.target = Some(player.transform),
+        FormationShape::Predictive => {
+            let speed = if movement.local {
+                movement.v_local.length()
+            } else {
+                movement.velocity.length()
+            } * METRE;
+            let target = lead_target(
+                transform.translation.truncate(),
+                player.transform.translation.truncate(),
+                player.velocity,
+                speed,
+            );
+            formation.target = Some(Transform::from_translation(target.extend(0.0)));
+        }
+        _ => {}
+    }
+}
+
+// Spawns normal enemies, with their health from the bestiary
 pub fn spawn_enemy<T: ExtraSpriteInfo>(
     commands: &mut Commands,
+    kind: EnemyKind,
     spawn_point: Transform,
     attacks: Attacks,
     sprite: T,
-) {
+) -> Entity {
     commands.spawn((
         sprite.bundle(spawn_point),
         sprite.collider(),
         attacks,
         Enemy,
-        Health::new(20.0, Some(20.0)),
+        kind,
+        Health::new(kind.health(), None),
         RigidBody::Dynamic,
         ColliderType::Enemy,
         ColliderType::Enemy.collision_group(),
@@ -250,5 +845,161 @@ pub fn spawn_enemy<T: ExtraSpriteInfo>(
         Sensor,
         Velocity::zero(),
         Movement::relative(Vec2::ZERO, Vec2::new(0.0, -3.0)),
-    ));
+    )).id()
+}
+
+// Drawing of a kind of enemy from the gameplay atlases, `size` pixels across
+pub fn kind_sprite(atlases: &Atlases<'static>, kind: EnemyKind, size: f32) -> MetaSpriteAtlas {
+    let (path, color) = kind.sprite();
+    MetaSpriteAtlas {
+        sprite: TextureAtlasSprite {
+            color,
+            custom_size: Some(Vec2::splat(size)),
+            ..default()
+        },
+        texture_atlas: Some(
+            atlases
+                .get(path)
+                .expect("Couldn't get enemy texture atlas.")
+                .clone(),
+        ),
+        collider: Collider::cuboid(size / 2.0, size / 2.0),
+    }
+}
+
+// Enemies which hang back for a moment after coming in, then dive at the player and speed up
+// as they close in. They don't fire, and only hurt the player by flying into them.
+#[derive(Component, Debug, Clone)]
+pub struct Diver {
+    // Time before the dive starts
+    windup: Timer,
+    acceleration: f32,
+    max_speed: f32,
+}
+
+impl Diver {
+    pub fn new(windup_secs: f32, acceleration: f32, max_speed: f32) -> Self {
+        Self {
+            windup: Timer::from_seconds(windup_secs, TimerMode::Once),
+            acceleration,
+            max_speed,
+        }
+    }
+}
+
+pub fn dive_at_player(
+    time: Res<GameTime>,
+    player: Query<&Transform, With<Player>>,
+    mut divers: Query<(&mut Diver, &mut Movement, &Transform)>,
+) {
+    let player = player.get_single().ok();
+    for (mut diver, mut movement, transform) in divers.iter_mut() {
+        diver.windup.tick(time.delta());
+        if !diver.windup.finished() {
+            continue;
+        }
+        if diver.windup.just_finished() {
+            // Carry on from the drift the diver came in with
+            let drift = (transform.rotation * movement.v_local.extend(0.0)).truncate();
+            movement.velocity += drift;
+            movement.v_local = Vec2::ZERO;
+        }
+        // Steering stops once past the player, so that a diver which misses carries on off the
+        // screen instead of turning back around
+        if let Some(player) = player {
+            let offset = (player.translation - transform.translation).truncate();
+            if offset.y < 0.0 {
+                movement.acceleration = offset.normalize_or_zero() * diver.acceleration;
+            }
+        }
+        movement.velocity = movement.velocity.clamp_length_max(diver.max_speed);
+    }
+}
+
+// Enemies which fly in, stop at a set height and fire aimed streams at the player from there
+// for a while before leaving. They only fire while they are stopped.
+#[derive(Component, Debug, Clone)]
+pub struct Turret {
+    park_y: f32,
+    parked: bool,
+    // How long the turret stays once it has stopped
+    stay: Timer,
+}
+
+// Speed of a turret as it leaves, in metres per second
+const TURRET_LEAVE_SPEED: f32 = 4.0;
+
+impl Turret {
+    pub fn new(park_y: f32, stay_secs: f32) -> Self {
+        Self {
+            park_y,
+            parked: false,
+            stay: Timer::from_seconds(stay_secs, TimerMode::Once),
+        }
+    }
+
+    fn firing(&self) -> bool {
+        self.parked && !self.stay.finished()
+    }
+}
+
+pub fn park_turrets(
+    time: Res<GameTime>,
+    mut turrets: Query<(&mut Turret, &mut Movement, &Transform)>,
+) {
+    for (mut turret, mut movement, transform) in turrets.iter_mut() {
+        if !turret.parked {
+            if transform.translation.y <= turret.park_y {
+                turret.parked = true;
+                *movement = Movement::ZERO;
+            }
+            continue;
+        }
+        turret.stay.tick(time.delta());
+        if turret.stay.just_finished() {
+            *movement = Movement::relative(Vec2::new(0.0, -TURRET_LEAVE_SPEED), Vec2::ZERO);
+        }
+    }
+}
+
+// Enemies which let out a group of divers when they are destroyed
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Carrier {
+    pub minions: u8,
+}
+
+pub const CARRIER: Carrier = Carrier { minions: 4 };
+
+// How far from a destroyed carrier its minions come out
+const MINION_SPREAD: f32 = 20.0;
+// Speed the minions burst out of the carrier with, in metres per second
+const MINION_BURST_SPEED: f32 = 3.0;
+const MINION_SIZE: f32 = 14.0;
+// Minions are half as tough as a diver spawned on its own
+const MINION_HEALTH_SCALE: f32 = 0.5;
+
+// Spawn the minions of every carrier that was destroyed, spread out around it
+pub fn release_minions(
+    mut commands: Commands,
+    mut killed_ev: EventReader<EnemyKilledEvent>,
+    atlases: Res<Atlases<'static>>,
+) {
+    use std::f32::consts::TAU;
+    for event in killed_ev.iter() {
+        let Some(carrier) = event.carrier else { continue; };
+        for i in 0..carrier.minions {
+            let direction = Vec2::from_angle(TAU * i as f32 / carrier.minions as f32);
+            let spawn_point = Transform::from_translation(
+                (event.position + direction * MINION_SPREAD).extend(0.2),
+            );
+            let sprite = kind_sprite(&atlases, EnemyKind::Diver, MINION_SIZE);
+            let minion =
+                spawn_enemy(&mut commands, EnemyKind::Diver, spawn_point, Attacks::none(), sprite);
+            commands.entity(minion).insert((
+                Diver::new(0.5, 14.0, 16.0),
+                Health::new(EnemyKind::Diver.health() * MINION_HEALTH_SCALE, None),
+                Movement::absolute(direction * MINION_BURST_SPEED, Vec2::ZERO),
+            ));
+        }
+    }
 }
@@ -1,12 +1,25 @@
 use super::{
-    collectables::spawn_collectables,
+    bestiary::EnemyKind,
+    bullet::{BulletPool, Pooled},
+    collectables::{spawn_collectables, Drops},
     collisions::ColliderType,
-    player::{Player, Score},
-    shared::{Counter, Health, Movement},
+    death_sequence::DeathSequence,
+    difficulty::Difficulty,
+    enemy::{Attacks, Boss, Carrier},
+    explosive::Explosive,
+    levels::LevelConduct,
+    player::{
+        Graze, Invulnerable, LifeLostEvent, Lives, Player, Power, RunEnd, Score,
+        HIT_INVULNERABILITY_SECS,
+    },
+    rank::{Rank, ScoringConfig},
+    timeline::{RunTimeline, TimelineEventKind},
+    shared::{physics::ColliderDisabled, Counter, Health, Movement},
     ui::Link,
-    GameplayState,
+    wave::WaveMember,
+    GameplayState, GameplayTime,
 };
-use crate::{GameState, gameplay::player::EnemiesKilled};
+use crate::{GameState, gameplay::player::EnemiesKilled, versus::Versus};
 use bevy::prelude::*;
 
 #[derive(Debug, Event)]
@@ -23,40 +36,118 @@ impl TakeDamageEvent {
             damage,
         }
     }
+
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    pub fn damage(&self) -> f32 {
+        self.damage
+    }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn take_damage(
+    mut commands: Commands,
     mut damage_ev: EventReader<TakeDamageEvent>,
     mut game_over_ev: EventWriter<GameOverEvent>,
+    mut life_lost_ev: EventWriter<LifeLostEvent>,
     mut health: Query<(&mut Health, Option<&Link>)>,
+    mut lives: Query<&mut Lives, Without<Invulnerable>>,
     mut enemies_killed: Query<&mut EnemiesKilled, With<Player>>,
+    bosses: Query<(), With<Boss>>,
+    // What killing an enemy drops, which kind of enemy it was for the bestiary, whether it
+    // blows up or lets out minions, and which wave it was part of
+    enemy_info: Query<(
+        Option<&Drops>,
+        Option<&EnemyKind>,
+        Option<&Explosive>,
+        Option<&Carrier>,
+        Option<&Transform>,
+        Option<&WaveMember>,
+    )>,
+    mut killed_ev: EventWriter<EnemyKilledEvent>,
     mut despawn_ev: EventWriter<DespawnEvent>,
+    mut conduct: ResMut<LevelConduct>,
+    mut timeline: ResMut<RunTimeline>,
+    g_time: Res<GameplayTime>,
+    // What killed enemies drop is scaled by these
+    (difficulty, scoring, rank): (Res<Difficulty>, Res<ScoringConfig>, Res<Rank>),
 ) {
+    // Invulnerability is only added once commands are applied, so any other hits in the same
+    // frame as the first one are ignored here instead
+    let mut player_hit = false;
     for event in damage_ev.iter() {
         let Ok((mut hp, health_bar)) = health.get_mut(event.entity) else { continue; };
+        if event.entity_type == Some(ColliderType::Player) {
+            // Only a player who is not invulnerable has their lives in the query
+            if player_hit || lives.get(event.entity).is_err() {
+                continue;
+            }
+            player_hit = true;
+            commands
+                .entity(event.entity)
+                .insert(Invulnerable::new(HIT_INVULNERABILITY_SECS));
+            conduct.missed = true;
+            timeline.record(&g_time, TimelineEventKind::Hit);
+        }
         // Update the affected entity by taking away the damage value from its health component.
         if hp.current > event.damage {
             hp.current -= event.damage;
+        } else if event.entity_type == Some(ColliderType::Player) {
+            // The player only dies for good once they are out of lives. Otherwise a life is
+            // taken and they are respawned.
+            let Ok(mut lives) = lives.get_mut(event.entity) else { continue; };
+            lives.subtract(1);
+            if lives.get() > 0 {
+                hp.current = 0.0;
+                life_lost_ev.send_default();
+                continue;
+            }
+            if let Some(health_bar) = health_bar {
+                despawn_ev.send(DespawnEvent::new(health_bar.0, true));
+            }
+            game_over_ev.send(GameOverEvent);
+            timeline.record(&g_time, TimelineEventKind::Death);
+            despawn_ev.send(DespawnEvent::new(event.entity, true));
         } else {
+            // A boss which is already going down is left at 0 health, and can't be killed again
+            if bosses.contains(event.entity) && hp.current <= 0.0 {
+                continue;
+            }
             // If the damage is >= health, then this event would kill the entity, so we despawn the
             // entity and its health bar UI element if it exists.
-            // Furthermore, if the receiving entity is a Player, this results in a Game Over event.
-
             if let Some(health_bar) = health_bar {
                 despawn_ev.send(DespawnEvent::new(health_bar.0, true));
             }
-            if let Some(entity_type) = event.entity_type {
-                if entity_type == ColliderType::Player {
-                    game_over_ev.send(GameOverEvent);
-                }
-                else if entity_type == ColliderType::Enemy {
-                    enemies_killed.iter_mut().for_each(|mut k| k.increment());
-                }
+            let (drops, kind, explosive, carrier, transform, wave) =
+                enemy_info.get(event.entity).unwrap_or_default();
+            if event.entity_type == Some(ColliderType::Enemy) {
+                enemies_killed.iter_mut().for_each(|mut k| k.increment());
+                timeline.record(&g_time, TimelineEventKind::Kill);
+                killed_ev.send(EnemyKilledEvent {
+                    kind: kind.copied(),
+                    position: transform.map_or(Vec2::ZERO, |t| t.translation.truncate()),
+                    explosive: explosive.copied(),
+                    carrier: carrier.copied(),
+                    wave: wave.copied(),
+                });
             }
+            // Bosses go out with a death sequence, which despawns them once it's over
+            if bosses.contains(event.entity) {
+                hp.current = 0.0;
+                commands
+                    .entity(event.entity)
+                    .insert((DeathSequence::default(), ColliderDisabled))
+                    .remove::<Attacks>();
+                continue;
+            }
+            // Recursive, so that anything attached to the entity (e.g. a boss' aura) goes too
+            let drops = scoring.scale(drops.copied().unwrap_or_default(), *difficulty, *rank);
             despawn_ev.send(
-                DespawnEvent::new(event.entity, false)
-                    .with_score(5)
-                    .with_power(3),
+                DespawnEvent::new(event.entity, true)
+                    .with_score(drops.score)
+                    .with_power(drops.power),
             );
         }
     }
@@ -103,8 +194,18 @@ impl DespawnEvent {
     }
 }
 
-pub fn despawn_entity(mut despawn_ev: EventReader<DespawnEvent>, mut commands: Commands) {
+pub fn despawn_entity(
+    mut despawn_ev: EventReader<DespawnEvent>,
+    mut commands: Commands,
+    mut pool: ResMut<BulletPool>,
+    pooled: Query<(), With<Pooled>>,
+) {
     for event in despawn_ev.iter() {
+        // Bullets from bullet groups are put back in the pool to be used again
+        if pooled.contains(event.entity) {
+            pool.release(&mut commands, event.entity);
+            continue;
+        }
         let Some(mut entity_commands) = commands.get_entity(event.entity) else { continue };
         // Recursive despawning removes the entity as well as its children
         // from the world.
@@ -143,19 +244,46 @@ pub fn create_collectables_on_despawn(
     }
 }
 
+// Sent whenever the player kills an enemy, including bosses as their death sequence starts
+#[derive(Debug, Event)]
+pub struct EnemyKilledEvent {
+    // None for enemies which aren't in the bestiary
+    pub kind: Option<EnemyKind>,
+    pub position: Vec2,
+    // Blast the enemy goes off with, for explosive enemies
+    pub explosive: Option<Explosive>,
+    // Minions the enemy lets out, for carriers
+    pub carrier: Option<Carrier>,
+    // Wave the enemy was part of, for normal enemies
+    pub wave: Option<WaveMember>,
+}
+
 #[derive(Default, Event)]
 pub struct GameOverEvent;
 
 pub fn game_over(
+    mut commands: Commands,
     mut game_over_ev: EventReader<GameOverEvent>,
     mut game_state: ResMut<NextState<GameState>>,
     mut gameplay_state: ResMut<NextState<GameplayState>>,
+    versus: Option<Res<Versus>>,
+    player: Query<(&Score, &Power, &EnemiesKilled, &Graze), With<Player>>,
 ) {
     // Here .iter().next() is used as there may be a case where more than one GameOverEvent is
     // received due to how the systems are being scheduled. Only one event is needed to be handled,
     // so the rest are ignored.
     if game_over_ev.iter().next().is_some() {
+        // Runs after take_damage, so the player's despawn hasn't been applied yet
+        match player.get_single() {
+            Ok(player) => commands.insert_resource(RunEnd::new(false, player)),
+            Err(_) => commands.remove_resource::<RunEnd>(),
+        }
         gameplay_state.set(GameplayState::None);
-        game_state.set(GameState::GameOver);
+        // Versus runs end on the comparison between the players instead
+        if versus.is_some() {
+            game_state.set(GameState::VersusResults);
+        } else {
+            game_state.set(GameState::GameOver);
+        }
     }
 }
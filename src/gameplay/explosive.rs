@@ -0,0 +1,58 @@
+// Enemies which blow up when they are destroyed. The blast damages the other enemies around
+// them and clears nearby enemy bullets, so one kill in a crowd can set off a chain reaction
+// that keeps the kill chain going.
+use super::{
+    bullet_grid::BulletGrid,
+    collisions::{damage_in_radius, ColliderType},
+    death_sequence::spawn_explosion,
+    event::{DespawnEvent, EnemyKilledEvent, TakeDamageEvent},
+};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::RapierContext;
+
+// Blast of an explosive enemy
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Explosive {
+    pub radius: f32,
+    pub damage: f32,
+}
+
+// Blast of the Volatile enemies
+pub const VOLATILE: Explosive = Explosive {
+    radius: 70.0,
+    damage: 30.0,
+};
+
+// Damage everything caught in the blast of an explosive enemy that was killed. Enemies killed
+// by it are only handled by take_damage a frame later, so chain reactions spread out instead
+// of going off all at once.
+pub fn detonate(
+    mut commands: Commands,
+    mut killed_ev: EventReader<EnemyKilledEvent>,
+    rapier: Res<RapierContext>,
+    grid: Res<BulletGrid>,
+    mut damage_ev: EventWriter<TakeDamageEvent>,
+    mut despawn_ev: EventWriter<DespawnEvent>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for event in killed_ev.iter() {
+        let Some(Explosive { radius, damage }) = event.explosive else { continue; };
+        // Shows how far the blast reaches
+        spawn_explosion(&mut commands, event.position, radius, &mut meshes, &mut materials);
+
+        // The enemy that blew up is still around until the end of the frame, but it has already
+        // been killed, so more damage to it is ignored
+        damage_in_radius(
+            &rapier,
+            &mut damage_ev,
+            event.position,
+            radius,
+            damage,
+            &[ColliderType::Enemy],
+        );
+        for bullet in grid.bullets_in_radius(event.position, radius) {
+            despawn_ev.send(DespawnEvent::new(bullet, true).with_score(1));
+        }
+    }
+}
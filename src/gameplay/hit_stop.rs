@@ -0,0 +1,78 @@
+// Brief slow motion for dramatic moments, such as a boss blowing up or the player losing a
+// life. Gameplay drops to a fraction of its normal speed and then eases back up, by changing
+// the scale of the GameTime clock that everything in gameplay runs on.
+use super::{player::LifeLostEvent, GameTime};
+use bevy::prelude::*;
+
+// Slow motion as the player loses a life
+const LIFE_LOST_HIT_STOP: (f32, f32) = (0.2, 0.3);
+// Slow motion as a boss' death sequence ends in its final blast
+pub const BOSS_HIT_STOP: (f32, f32) = (0.2, 0.4);
+
+// Slow motion currently playing. The timer runs on real time, as it would otherwise be slowed
+// down by itself.
+#[derive(Resource, Debug)]
+pub struct HitStop {
+    timer: Timer,
+    // Speed of gameplay at the start of the slow motion
+    scale: f32,
+}
+
+impl Default for HitStop {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(0.0, TimerMode::Once),
+            scale: 1.0,
+        }
+    }
+}
+
+impl HitStop {
+    // Slow gameplay down to `scale` and bring it back to normal speed over `secs` seconds.
+    // A milder slow motion doesn't cut a stronger one short.
+    pub fn start(&mut self, scale: f32, secs: f32) {
+        if self.current_scale() < scale {
+            return;
+        }
+        self.scale = scale;
+        self.timer = Timer::from_seconds(secs, TimerMode::Once);
+    }
+
+    fn current_scale(&self) -> f32 {
+        if self.timer.finished() {
+            return 1.0;
+        }
+        // Easing in keeps gameplay slow for most of the time, then speeds it up quickly
+        let t = self.timer.percent();
+        self.scale + (1.0 - self.scale) * t * t
+    }
+}
+
+// Runs before the GameTime delta is worked out for the frame
+pub fn update_hit_stop(
+    time: Res<Time>,
+    mut hit_stop: ResMut<HitStop>,
+    mut game_time: ResMut<GameTime>,
+) {
+    if game_time.paused() || hit_stop.timer.finished() {
+        return;
+    }
+    hit_stop.timer.tick(time.delta());
+    game_time.set_scale(hit_stop.current_scale());
+}
+
+pub fn hit_stop_on_life_lost(
+    mut life_lost_ev: EventReader<LifeLostEvent>,
+    mut hit_stop: ResMut<HitStop>,
+) {
+    if life_lost_ev.iter().count() > 0 {
+        hit_stop.start(LIFE_LOST_HIT_STOP.0, LIFE_LOST_HIT_STOP.1);
+    }
+}
+
+// Put gameplay back to normal speed when leaving the game, so that the next run doesn't start
+// in slow motion
+pub fn reset_hit_stop(mut hit_stop: ResMut<HitStop>, mut game_time: ResMut<GameTime>) {
+    *hit_stop = HitStop::default();
+    game_time.set_scale(1.0);
+}
@@ -0,0 +1,228 @@
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Sticks have to be pushed at least this far before the player starts moving
+const STICK_DEADZONE: f32 = 0.2;
+
+// Everything the player can do with the keyboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Control {
+    Up,
+    Down,
+    Left,
+    Right,
+    Fire,
+    Special,
+    Focus,
+    Pause,
+    // Adds a credit in arcade mode
+    Coin,
+}
+
+impl Control {
+    pub const ALL: [Control; 9] = [
+        Control::Up,
+        Control::Down,
+        Control::Left,
+        Control::Right,
+        Control::Fire,
+        Control::Special,
+        Control::Focus,
+        Control::Pause,
+        Control::Coin,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Control::Up => "Up",
+            Control::Down => "Down",
+            Control::Left => "Left",
+            Control::Right => "Right",
+            Control::Fire => "Fire",
+            Control::Special => "Special",
+            Control::Focus => "Focus",
+            Control::Pause => "Pause",
+            Control::Coin => "Coin",
+        }
+    }
+}
+
+// Keyboard key for every control. Missing fields take their default keys when loading
+// bindings saved by an older version of the game.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    up: KeyCode,
+    down: KeyCode,
+    left: KeyCode,
+    right: KeyCode,
+    fire: KeyCode,
+    special: KeyCode,
+    focus: KeyCode,
+    pause: KeyCode,
+    coin: KeyCode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            up: KeyCode::Up,
+            down: KeyCode::Down,
+            left: KeyCode::Left,
+            right: KeyCode::Right,
+            fire: KeyCode::Z,
+            special: KeyCode::X,
+            focus: KeyCode::ShiftLeft,
+            pause: KeyCode::P,
+            // The usual coin key of arcade emulators
+            coin: KeyCode::Key5,
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn get(&self, control: Control) -> KeyCode {
+        match control {
+            Control::Up => self.up,
+            Control::Down => self.down,
+            Control::Left => self.left,
+            Control::Right => self.right,
+            Control::Fire => self.fire,
+            Control::Special => self.special,
+            Control::Focus => self.focus,
+            Control::Pause => self.pause,
+            Control::Coin => self.coin,
+        }
+    }
+
+    fn get_mut(&mut self, control: Control) -> &mut KeyCode {
+        match control {
+            Control::Up => &mut self.up,
+            Control::Down => &mut self.down,
+            Control::Left => &mut self.left,
+            Control::Right => &mut self.right,
+            Control::Fire => &mut self.fire,
+            Control::Special => &mut self.special,
+            Control::Focus => &mut self.focus,
+            Control::Pause => &mut self.pause,
+            Control::Coin => &mut self.coin,
+        }
+    }
+
+    // Bind a control to a key. A control which already used the key gets the old key of
+    // this control instead, so that a key never does two things at once.
+    pub fn set(&mut self, control: Control, key: KeyCode) {
+        let old = self.get(control);
+        for other in Control::ALL {
+            if other != control && self.get(other) == key {
+                *self.get_mut(other) = old;
+            }
+        }
+        *self.get_mut(control) = key;
+    }
+
+    pub fn pressed(&self, keys: &Input<KeyCode>, control: Control) -> bool {
+        keys.pressed(self.get(control))
+    }
+
+    pub fn just_pressed(&self, keys: &Input<KeyCode>, control: Control) -> bool {
+        keys.just_pressed(self.get(control))
+    }
+}
+
+// What the player is asking for this frame, from the keyboard and any connected gamepads.
+// The player systems only read from here, so they don't care where the input came from.
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct PlayerInput {
+    // Direction to move in. Up to unit length, shorter when a stick is only pushed part way.
+    pub movement: Vec2,
+    // The focus key or a trigger is held down
+    pub focus: bool,
+    // The fire key or button is held down
+    pub fire: bool,
+    // The special key or button was pressed this frame
+    pub special: bool,
+}
+
+pub struct PlayerInputPlugin;
+
+impl Plugin for PlayerInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerInput>()
+            .init_resource::<KeyBindings>()
+            .add_systems(PreUpdate, read_player_input.after(InputSystem));
+    }
+}
+
+fn read_player_input(
+    mut player_input: ResMut<PlayerInput>,
+    keys: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+) {
+    // input.pressed() returns a boolean value, which can be converted into an integer,
+    // as false = 0 and true = 1
+    // Therefore, the Right key can be set as +1 when true and the Left key
+    // as -1 when true (by adding a coefficient of -1). This can be represented on a number line
+    // as -1 = left, 0 = none, 1 = right. By adding the two values we get the overall
+    // movement desired. Holding down only the right key gives +1, holding the left key gives
+    // -1, holding both gives 0 and not pressing either gives 0.
+    // The same thing is implemented for vertical movement with the Up and Down keys.
+    let x = bindings.pressed(&keys, Control::Right) as i8
+        - bindings.pressed(&keys, Control::Left) as i8;
+    let y = bindings.pressed(&keys, Control::Up) as i8
+        - bindings.pressed(&keys, Control::Down) as i8;
+    let mut movement = Vec2::new(x as f32, y as f32).normalize_or_zero();
+
+    let mut focus = bindings.pressed(&keys, Control::Focus);
+    let mut fire = bindings.pressed(&keys, Control::Fire);
+    let mut special = bindings.just_pressed(&keys, Control::Special);
+
+    for gamepad in gamepads.iter() {
+        let button = |kind| GamepadButton::new(gamepad, kind);
+        let axis = |kind| axes.get(GamepadAxis::new(gamepad, kind)).unwrap_or(0.0);
+
+        // The keyboard wins if both are used at once
+        if movement == Vec2::ZERO {
+            movement = stick_movement(Vec2::new(
+                axis(GamepadAxisType::LeftStickX),
+                axis(GamepadAxisType::LeftStickY),
+            ));
+        }
+        if movement == Vec2::ZERO {
+            let x = buttons.pressed(button(GamepadButtonType::DPadRight)) as i8
+                - buttons.pressed(button(GamepadButtonType::DPadLeft)) as i8;
+            let y = buttons.pressed(button(GamepadButtonType::DPadUp)) as i8
+                - buttons.pressed(button(GamepadButtonType::DPadDown)) as i8;
+            movement = Vec2::new(x as f32, y as f32).normalize_or_zero();
+        }
+
+        focus |= buttons.any_pressed([
+            button(GamepadButtonType::LeftTrigger2),
+            button(GamepadButtonType::RightTrigger2),
+        ]);
+        fire |= buttons.pressed(button(GamepadButtonType::South));
+        special |= buttons.just_pressed(button(GamepadButtonType::East));
+    }
+
+    *player_input = PlayerInput {
+        movement,
+        focus,
+        fire,
+        special,
+    };
+}
+
+// Ignore small stick movements, and scale the rest so that movement still starts from zero
+// at the edge of the deadzone.
+fn stick_movement(stick: Vec2) -> Vec2 {
+    let length = stick.length();
+    if length <= STICK_DEADZONE {
+        return Vec2::ZERO;
+    }
+    let scaled = ((length - STICK_DEADZONE) / (1.0 - STICK_DEADZONE)).min(1.0);
+    stick / length * scaled
+}
@@ -0,0 +1,189 @@
+// Panel for looking at and editing the player and enemies while playing. Only built with the
+// `inspector` feature, and only added in debug builds.
+use super::{
+    bestiary::EnemyKind,
+    collisions::{ColliderType, COLLIDER_TYPES},
+    enemy::{Attacks, Enemy},
+    player::Player,
+    shared::{Health, Movement, Name},
+    GameplayState,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use super::debug_window::DebugWindow;
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::{egui, EguiContexts, EguiPlugin};
+
+pub struct InspectorPlugin;
+
+impl Plugin for InspectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(EguiPlugin).add_systems(
+            Update,
+            inspector_panel.run_if(in_state(GameplayState::Playing)),
+        );
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn inspector_panel(
+    mut contexts: EguiContexts,
+    mut entities: Query<
+        (
+            Entity,
+            Option<&EnemyKind>,
+            Option<&Name<'static>>,
+            Option<&mut Health>,
+            Option<&mut Movement>,
+            Option<&mut Attacks>,
+            Option<&mut ColliderType>,
+        ),
+        Or<(With<Player>, With<Enemy>)>,
+    >,
+    // The panel is shown in the debug window instead of over the game while it is open
+    #[cfg(not(target_arch = "wasm32"))] debug_window: Query<Entity, With<DebugWindow>>,
+) {
+    #[cfg(not(target_arch = "wasm32"))]
+    let ctx = match debug_window.get_single() {
+        Ok(window) => {
+            let Some(ctx) = contexts.try_ctx_for_window_mut(window) else { return; };
+            ctx
+        }
+        Err(_) => contexts.ctx_mut(),
+    };
+    #[cfg(target_arch = "wasm32")]
+    let ctx = contexts.ctx_mut();
+
+    egui::Window::new("Entities")
+        .default_width(280.0)
+        .show(ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (entity, kind, name, health, movement, attacks, collider_type) in
+                    entities.iter_mut()
+                {
+                    let title = kind
+                        .map(EnemyKind::name)
+                        .or_else(|| name.and_then(|name| name.0))
+                        .unwrap_or("Entity");
+                    egui::CollapsingHeader::new(format!("{} ({:?})", title, entity))
+                        .id_source(entity)
+                        .show(ui, |ui| {
+                            // Only edits mark the components as changed, so that just having the
+                            // panel open doesn't set off change detection every frame
+                            if let Some(mut health) = health {
+                                let changed = edit_health(ui, health.bypass_change_detection());
+                                if changed {
+                                    health.set_changed();
+                                }
+                            }
+                            if let Some(mut movement) = movement {
+                                let changed = edit_movement(ui, movement.bypass_change_detection());
+                                if changed {
+                                    movement.set_changed();
+                                }
+                            }
+                            if let Some(mut attacks) = attacks {
+                                let changed = edit_attacks(ui, attacks.bypass_change_detection());
+                                if changed {
+                                    attacks.set_changed();
+                                }
+                            }
+                            if let Some(mut collider_type) = collider_type {
+                                let changed = edit_collider_type(
+                                    ui,
+                                    entity,
+                                    collider_type.bypass_change_detection(),
+                                );
+                                if changed {
+                                    collider_type.set_changed();
+                                }
+                            }
+                        });
+                }
+            });
+        });
+}
+
+fn edit_f32(ui: &mut egui::Ui, label: &str, value: &mut f32) -> bool {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        ui.add(egui::DragValue::new(value).speed(0.1)).changed()
+    })
+    .inner
+}
+
+fn edit_vec2(ui: &mut egui::Ui, label: &str, value: &mut Vec2) -> bool {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        let x = ui.add(egui::DragValue::new(&mut value.x).speed(0.1)).changed();
+        let y = ui.add(egui::DragValue::new(&mut value.y).speed(0.1)).changed();
+        x || y
+    })
+    .inner
+}
+
+fn edit_health(ui: &mut egui::Ui, health: &mut Health) -> bool {
+    ui.label("Health");
+    let current = edit_f32(ui, "Current", &mut health.current);
+    let total = edit_f32(ui, "Total", &mut health.total);
+    current || total
+}
+
+fn edit_movement(ui: &mut egui::Ui, movement: &mut Movement) -> bool {
+    ui.label("Movement");
+    let mut changed = edit_vec2(ui, "Velocity", &mut movement.velocity);
+    changed |= edit_vec2(ui, "Acceleration", &mut movement.acceleration);
+    changed |= ui.checkbox(&mut movement.local, "Local").changed();
+    changed |= edit_vec2(ui, "Local velocity", &mut movement.v_local);
+    changed |= edit_vec2(ui, "Local acceleration", &mut movement.a_local);
+    changed
+}
+
+fn edit_attacks(ui: &mut egui::Ui, attacks: &mut Attacks) -> bool {
+    ui.label("Attacks");
+    let (attacks, current, switch_timer) = attacks.get_all_mut();
+    if attacks.is_empty() {
+        ui.label("None");
+        return false;
+    }
+
+    let mut changed = ui
+        .horizontal(|ui| {
+            ui.label("Current");
+            ui.add(egui::DragValue::new(current).clamp_range(0..=attacks.len() - 1))
+                .changed()
+        })
+        .inner;
+    let mut switch_secs = switch_timer.duration().as_secs_f32();
+    if edit_f32(ui, "Switch after", &mut switch_secs) {
+        switch_timer.set_duration(std::time::Duration::from_secs_f32(switch_secs.max(0.0)));
+        changed = true;
+    }
+    for (i, attack) in attacks.iter_mut().enumerate() {
+        ui.label(format!("Attack {}", i));
+        changed |= ui
+            .horizontal(|ui| {
+                ui.label("Bullets");
+                ui.add(egui::DragValue::new(&mut attack.bullet_group.number))
+                    .changed()
+            })
+            .inner;
+        let mut cooldown = attack.cd.duration().as_secs_f32();
+        if edit_f32(ui, "Cooldown", &mut cooldown) {
+            attack.cd.set_duration(std::time::Duration::from_secs_f32(cooldown.max(0.0)));
+            changed = true;
+        }
+    }
+    changed
+}
+
+fn edit_collider_type(ui: &mut egui::Ui, entity: Entity, collider_type: &mut ColliderType) -> bool {
+    let before = *collider_type;
+    egui::ComboBox::from_id_source((entity, "collider type"))
+        .selected_text(format!("{:?}", collider_type))
+        .show_ui(ui, |ui| {
+            for kind in COLLIDER_TYPES.into_iter().chain([ColliderType::None]) {
+                ui.selectable_value(collider_type, kind, format!("{:?}", kind));
+            }
+        });
+    *collider_type != before
+}
@@ -0,0 +1,307 @@
+// Weather and other ambient effects, drawn above the level background but below everything
+// that takes part in the gameplay. Which effects a level has is part of the level's data, see
+// AMBIENCE in the level modules.
+use super::{endless, level1, level2, level3, CurrentLevel, Playfield};
+use crate::gameplay::loading::ParticleEffects;
+use crate::gameplay::GameTime;
+use bevy::prelude::*;
+use bevy_hanabi::prelude::*;
+use rand::Rng;
+
+// Between the background (0.0) and the player (0.1)
+const AMBIENT_Z: f32 = 0.05;
+// How far outside the playfield ambient particles can go before they are removed
+const OFFSCREEN_MARGIN: f32 = 120.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ambience {
+    // Fast streaks falling at a slight angle
+    Rain,
+    // Glowing specks rising up from the bottom, swaying side to side
+    Embers,
+    // Large faint clouds drifting past a little faster than the background
+    Clouds,
+}
+
+impl Ambience {
+    // Time between CPU particles
+    fn spawn_interval(&self) -> f32 {
+        match self {
+            Ambience::Rain => 0.01,
+            Ambience::Embers => 0.08,
+            Ambience::Clouds => 3.0,
+        }
+    }
+
+    // CPU particles placed straight away, so that the layer doesn't start empty
+    fn initial_particles(&self) -> u32 {
+        match self {
+            Ambience::Rain => 60,
+            Ambience::Embers => 20,
+            Ambience::Clouds => 3,
+        }
+    }
+
+    // Name of the GPU particle effect, for the effects that have one. Compute shaders aren't
+    // available with WebGL2, so on the web everything is drawn with sprites moved on the CPU.
+    fn effect_name(&self) -> Option<&'static str> {
+        if cfg!(target_arch = "wasm32") {
+            return None;
+        }
+        match self {
+            Ambience::Rain => Some("ambient_rain"),
+            Ambience::Embers => Some("ambient_embers"),
+            Ambience::Clouds => None,
+        }
+    }
+}
+
+impl CurrentLevel {
+    pub fn ambience(&self) -> &'static [Ambience] {
+        match self {
+            CurrentLevel::One => level1::AMBIENCE,
+            CurrentLevel::Two => level2::AMBIENCE,
+            CurrentLevel::Three => level3::AMBIENCE,
+            CurrentLevel::Endless => endless::AMBIENCE,
+            CurrentLevel::None => &[],
+        }
+    }
+}
+
+// Root of one ambient effect. Everything belonging to the effect is despawned with it.
+#[derive(Component)]
+pub struct AmbientLayer;
+
+// Spawns CPU particles for an ambient effect
+#[derive(Component)]
+pub struct AmbientEmitter {
+    kind: Ambience,
+    timer: Timer,
+}
+
+// Particle of an ambient effect drawn with a sprite
+#[derive(Component)]
+pub struct AmbientParticle {
+    velocity: Vec2,
+    // Side to side movement, in pixels either way
+    sway: f32,
+    age: f32,
+}
+
+// Replace the ambient effects whenever the level changes
+pub fn change_ambience(
+    mut commands: Commands,
+    current_level: Res<State<CurrentLevel>>,
+    layers: Query<Entity, With<AmbientLayer>>,
+    particles: Query<Entity, With<AmbientParticle>>,
+    playfield: Res<Playfield>,
+    effects: Res<ParticleEffects<'static>>,
+) {
+    for entity in layers.iter().chain(particles.iter()) {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    for &kind in current_level.get().ambience() {
+        if let Some(name) = kind.effect_name() {
+            // Rain falls in from above the playfield, embers rise up from below it
+            let y = match kind {
+                Ambience::Embers => playfield.min.y - OFFSCREEN_MARGIN,
+                _ => playfield.max.y + OFFSCREEN_MARGIN,
+            };
+            commands.spawn((
+                AmbientLayer,
+                ParticleEffectBundle {
+                    effect: ParticleEffect::new(effects.get(name).unwrap().clone())
+                        .with_z_layer_2d(Some(AMBIENT_Z)),
+                    transform: Transform::from_xyz(playfield.center().x, y, AMBIENT_Z),
+                    ..default()
+                },
+            ));
+            continue;
+        }
+
+        commands.spawn((
+            AmbientLayer,
+            AmbientEmitter {
+                kind,
+                timer: Timer::from_seconds(kind.spawn_interval(), TimerMode::Repeating),
+            },
+        ));
+        // Fill the screen up front, spread over the whole height of the playfield
+        let mut rng = rand::thread_rng();
+        for _ in 0..kind.initial_particles() {
+            let y = rng.gen_range(playfield.min.y..playfield.max.y);
+            spawn_particle(&mut commands, kind, &playfield, Some(y));
+        }
+    }
+}
+
+pub fn emit_ambient_particles(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    mut emitters: Query<&mut AmbientEmitter>,
+    playfield: Res<Playfield>,
+) {
+    for mut emitter in emitters.iter_mut() {
+        emitter.timer.tick(time.delta());
+        for _ in 0..emitter.timer.times_finished_this_tick() {
+            spawn_particle(&mut commands, emitter.kind, &playfield, None);
+        }
+    }
+}
+
+pub fn move_ambient_particles(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    mut particles: Query<(Entity, &mut AmbientParticle, &mut Transform)>,
+    playfield: Res<Playfield>,
+) {
+    let dt = time.delta_seconds();
+    for (entity, mut particle, mut transform) in particles.iter_mut() {
+        let before = (particle.age * 2.0).sin() * particle.sway;
+        particle.age += dt;
+        let after = (particle.age * 2.0).sin() * particle.sway;
+
+        transform.translation.x += particle.velocity.x * dt + after - before;
+        transform.translation.y += particle.velocity.y * dt;
+
+        let position = transform.translation.truncate();
+        if position.y < playfield.min.y - OFFSCREEN_MARGIN
+            || position.y > playfield.max.y + OFFSCREEN_MARGIN
+            || position.x.abs() > playfield.half_size().x + OFFSCREEN_MARGIN
+        {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+// Spawn one CPU particle just outside the playfield, or at `y` if given
+fn spawn_particle(commands: &mut Commands, kind: Ambience, playfield: &Playfield, y: Option<f32>) {
+    let mut rng = rand::thread_rng();
+    let x = rng.gen_range(playfield.min.x..playfield.max.x);
+
+    let (colour, size, velocity, sway, start_y) = match kind {
+        Ambience::Rain => (
+            Color::rgba(0.7, 0.8, 1.0, 0.35),
+            Vec2::new(1.5, rng.gen_range(14.0..24.0)),
+            Vec2::new(-40.0, rng.gen_range(-700.0..-550.0)),
+            0.0,
+            playfield.max.y + 20.0,
+        ),
+        Ambience::Embers => (
+            Color::rgba(1.0, rng.gen_range(0.3..0.6), 0.1, 0.8),
+            Vec2::splat(rng.gen_range(2.0..4.0)),
+            Vec2::new(0.0, rng.gen_range(40.0..90.0)),
+            rng.gen_range(5.0..15.0),
+            playfield.min.y - 10.0,
+        ),
+        Ambience::Clouds => (
+            Color::rgba(1.0, 1.0, 1.0, rng.gen_range(0.05..0.12)),
+            Vec2::new(rng.gen_range(180.0..300.0), rng.gen_range(60.0..110.0)),
+            Vec2::new(rng.gen_range(-8.0..8.0), rng.gen_range(-160.0..-130.0)),
+            0.0,
+            playfield.max.y + OFFSCREEN_MARGIN / 2.0,
+        ),
+    };
+
+    // Rain leans the same way it falls
+    let rotation = match kind {
+        Ambience::Rain => Quat::from_rotation_z(velocity.x.atan2(-velocity.y)),
+        _ => Quat::IDENTITY,
+    };
+
+    commands.spawn((
+        AmbientParticle {
+            velocity,
+            sway,
+            age: rng.gen_range(0.0..10.0),
+        },
+        SpriteBundle {
+            sprite: Sprite {
+                color: colour,
+                custom_size: Some(size),
+                ..default()
+            },
+            transform: Transform::from_xyz(x, y.unwrap_or(start_y), AMBIENT_Z)
+                .with_rotation(rotation),
+            ..default()
+        },
+    ));
+}
+
+// GPU versions of the rain and ember effects
+pub fn load_ambient_effects(
+    mut effects: ResMut<Assets<EffectAsset>>,
+    mut effect_handles: ResMut<ParticleEffects<'static>>,
+) {
+    // Rain is spawned over a disc above the playfield, so drops start at different heights
+    // and fall into view at different times.
+    let mut module = Module::default();
+    let init_pos = SetPositionCircleModifier {
+        center: module.lit(Vec3::ZERO),
+        axis: module.lit(Vec3::Z),
+        radius: module.lit(320.0),
+        dimension: ShapeDimension::Volume,
+    };
+    let init_vel = SetAttributeModifier::new(
+        Attribute::VELOCITY,
+        module.lit(Vec3::new(-40.0, -650.0, 0.0)),
+    );
+    let init_lifetime = SetAttributeModifier::new(Attribute::LIFETIME, module.lit(2.0));
+    let rain = effects.add(
+        EffectAsset::new(4096, Spawner::rate(CpuValue::Single(250.0)), module)
+            .with_name("ambient_rain")
+            .init(init_pos)
+            .init(init_vel)
+            .init(init_lifetime)
+            .render(ColorOverLifetimeModifier {
+                gradient: Gradient::constant(Vec4::new(0.7, 0.8, 1.0, 0.35)),
+            })
+            .render(SetSizeModifier {
+                size: CpuValue::Single(Vec2::new(1.5, 18.0)),
+                screen_space_size: false,
+            })
+            .render(OrientAlongVelocityModifier),
+    );
+
+    // Embers rise from below the playfield and slow down as they cool off
+    let mut module = Module::default();
+    let init_pos = SetPositionCircleModifier {
+        center: module.lit(Vec3::ZERO),
+        axis: module.lit(Vec3::Z),
+        radius: module.lit(320.0),
+        dimension: ShapeDimension::Volume,
+    };
+    let init_vel = SetVelocityCircleModifier {
+        center: module.lit(Vec3::new(0.0, -200.0, 0.0)),
+        axis: module.lit(Vec3::Z),
+        speed: module.lit(80.0),
+    };
+    let init_lifetime = SetAttributeModifier::new(Attribute::LIFETIME, module.lit(9.0));
+    let update_drag = LinearDragModifier::new(module.lit(0.1));
+    let embers = effects.add(
+        EffectAsset::new(1024, Spawner::rate(CpuValue::Single(12.0)), module)
+            .with_name("ambient_embers")
+            .init(init_pos)
+            .init(init_vel)
+            .init(init_lifetime)
+            .update(update_drag)
+            .render(ColorOverLifetimeModifier {
+                gradient: {
+                    let mut gradient = Gradient::new();
+                    gradient.add_key(0.0, Vec4::new(1.0, 0.6, 0.1, 0.0));
+                    gradient.add_key(0.1, Vec4::new(1.0, 0.5, 0.1, 0.8));
+                    gradient.add_key(0.7, Vec4::new(1.0, 0.2, 0.05, 0.6));
+                    gradient.add_key(1.0, Vec4::new(0.5, 0.1, 0.0, 0.0));
+                    gradient
+                },
+            })
+            .render(SetSizeModifier {
+                size: CpuValue::Single(Vec2::splat(3.0)),
+                screen_space_size: false,
+            }),
+    );
+
+    effect_handles.insert("ambient_rain", rain);
+    effect_handles.insert("ambient_embers", embers);
+}
@@ -0,0 +1,348 @@
+use crate::gameplay::{
+    bestiary::EnemyKind,
+    bullet::AttackPattern,
+    bullet::Bullet,
+    bullet::BulletGroup,
+    collisions::ColliderType,
+    enemy,
+    enemy::{Attacks, Boss, BossTimer, Enemy, TimeOut},
+    loading::Atlases,
+    player::{Player, Specials},
+    rng::RunRng,
+    shared::Counter,
+    shared::Formation,
+    shared::Movement,
+    shared::MetaSpriteAtlas,
+    wave::Waves,
+    GameTime,
+    GameplayTime,
+    levels::{ambient::Ambience, level1, Playfield, SpawnEnemyTimer},
+    ui::GameplayUI,
+};
+use crate::ui::fonts::Fonts;
+use bevy::prelude::*;
+use bevy::utils::Duration;
+use bevy_rapier2d::prelude::*;
+
+// Weather and other ambient effects shown over the background of the level
+pub const AMBIENCE: &[Ambience] = &[Ambience::Clouds];
+
+// Difficulty gained for every minute survived, and the most it can ever reach
+const DIFFICULTY_PER_MINUTE: f32 = 0.5;
+const MAX_DIFFICULTY: f32 = 5.0;
+// Time between normal enemy spawns at a difficulty of 1
+const BASE_SPAWN_INTERVAL: f32 = 1.6;
+// Seconds the player has to beat a mini-boss before it escapes
+const MINI_BOSS_TIME_LIMIT: f32 = 40.0;
+// Time between mini-boss waves. Only counts down while no mini-boss is alive.
+const MINI_BOSS_INTERVAL: Duration = Duration::from_secs(45);
+// Normal enemies spawned in every wave
+const ENEMIES_PER_WAVE: u16 = 12;
+// Pause before each wave, while its banner is shown
+const WAVE_BREAK: Duration = Duration::from_secs(3);
+// A special is given every time this many waves have been cleared
+const WAVES_PER_SPECIAL: u32 = 10;
+
+// Scales how hard endless mode is. Starts at 1 and ramps up the longer the player survives.
+#[derive(Resource, Debug, Deref)]
+pub struct EndlessDifficulty(f32);
+impl Default for EndlessDifficulty {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+// Timer for spawning mini-bosses
+#[derive(Resource, Debug, Deref, DerefMut)]
+pub struct MiniBossTimer(Timer);
+impl Default for MiniBossTimer {
+    fn default() -> Self {
+        Self(Timer::new(MINI_BOSS_INTERVAL, TimerMode::Repeating))
+    }
+}
+
+// Normal enemies come in waves. A wave is over once all of its enemies have been spawned and
+// none of them are left, and the next one starts after a short break.
+#[derive(Resource, Debug)]
+pub struct EndlessWave {
+    number: u32,
+    // Enemies of the current wave spawned so far
+    spawned: u16,
+    // Counts down the break before the current wave, None once it has started
+    break_timer: Option<Timer>,
+}
+
+impl Default for EndlessWave {
+    fn default() -> Self {
+        Self {
+            number: 1,
+            spawned: 0,
+            break_timer: Some(Timer::new(WAVE_BREAK, TimerMode::Once)),
+        }
+    }
+}
+
+impl EndlessWave {
+    // Wave the player has reached
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    fn spawning(&self) -> bool {
+        self.break_timer.is_none() && self.spawned < ENEMIES_PER_WAVE
+    }
+}
+
+// "Wave N" text shown in the middle of the screen during the break before a wave
+#[derive(Component, Debug, Deref, DerefMut)]
+pub struct WaveBanner(Timer);
+
+pub fn setup_level(mut commands: Commands, fonts: Res<Fonts>) {
+    commands.insert_resource(EndlessDifficulty::default());
+    commands.insert_resource(MiniBossTimer::default());
+    commands.insert_resource(EndlessWave::default());
+    spawn_wave_banner(&mut commands, &fonts, 1);
+}
+
+fn spawn_wave_banner(commands: &mut Commands, fonts: &Fonts, number: u32) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    top: Val::Percent(40.0),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            WaveBanner(Timer::new(WAVE_BREAK, TimerMode::Once)),
+            GameplayUI,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                format!("Wave {}", number),
+                TextStyle {
+                    font: fonts.main(),
+                    font_size: 60.0,
+                    color: Color::WHITE,
+                },
+            ));
+        });
+}
+
+// End the break before a wave, or the wave itself once it has been dealt with. Every
+// WAVES_PER_SPECIAL waves cleared earn the player a special.
+pub fn advance_wave(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    mut wave: ResMut<EndlessWave>,
+    enemies: Query<(), (With<Enemy>, Without<Boss>)>,
+    mut player: Query<&mut Specials, With<Player>>,
+    fonts: Res<Fonts>,
+) {
+    if let Some(timer) = wave.break_timer.as_mut() {
+        if timer.tick(time.delta()).finished() {
+            wave.break_timer = None;
+        }
+        return;
+    }
+    if wave.spawned < ENEMIES_PER_WAVE || !enemies.is_empty() {
+        return;
+    }
+    if wave.number % WAVES_PER_SPECIAL == 0 {
+        for mut specials in player.iter_mut() {
+            specials.add(1);
+        }
+    }
+    wave.number += 1;
+    wave.spawned = 0;
+    wave.break_timer = Some(Timer::new(WAVE_BREAK, TimerMode::Once));
+    spawn_wave_banner(&mut commands, &fonts, wave.number);
+}
+
+pub fn expire_wave_banners(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    mut banners: Query<(Entity, &mut WaveBanner)>,
+) {
+    for (entity, mut banner) in banners.iter_mut() {
+        if banner.tick(time.delta()).just_finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+pub fn update_difficulty(time: Res<GameplayTime>, mut difficulty: ResMut<EndlessDifficulty>) {
+    let minutes = time.level_elapsed_secs() / 60.0;
+    difficulty.0 = (1.0 + minutes * DIFFICULTY_PER_MINUTE).min(MAX_DIFFICULTY);
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_enemies(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    mut timer: ResMut<SpawnEnemyTimer>,
+    mut waves: ResMut<Waves>,
+    atlases: Res<Atlases<'static>>,
+    playfield: Res<Playfield>,
+    difficulty: Res<EndlessDifficulty>,
+    mut rng: ResMut<RunRng>,
+    mut wave: ResMut<EndlessWave>,
+) {
+    if !wave.spawning() {
+        return;
+    }
+    // Enemies come more often and fire more, faster bullets as the difficulty goes up
+    let interval = Duration::from_secs_f32(BASE_SPAWN_INTERVAL / difficulty.0);
+    if timer.duration() != interval {
+        timer.set_duration(interval);
+    }
+    timer.tick(time.delta());
+    if !timer.finished() {
+        return;
+    }
+    let attacks = Attacks::new(
+        vec![AttackPattern {
+            bullet_group: BulletGroup {
+                collider_type: ColliderType::EnemyBullet,
+                number: (4.0 + 2.0 * difficulty.0) as u16,
+                formation: Formation::circular(false, 20.0),
+                bullet: Bullet::new(5.0, 5.0),
+                ..default()
+            },
+            movement: Movement::new(
+                Vec2::ZERO,
+                Vec2::ZERO,
+                true,
+                Vec2::new(0.0, 2.0 + difficulty.0),
+                Vec2::ZERO,
+            ),
+            cd: Timer::from_seconds(1.6 - 0.2 * difficulty.0, TimerMode::Once),
+            icd: None,
+            current_bullet: 0,
+            ..default()
+        }],
+        Timer::new(Duration::from_secs(10), TimerMode::Once),
+    );
+
+    // Keep the whole enemy inside the walls
+    let half_width = 10.0;
+    let spawn_point = Transform {
+        translation: playfield.spawn_point(&mut rng, 1.0, half_width, 4.0 * half_width).extend(0.2),
+        ..default()
+    };
+    let sprite = MetaSpriteAtlas {
+        sprite: TextureAtlasSprite {
+            color: Color::rgb(1.0, 1.0, 1.0),
+            custom_size: Some(Vec2::new(20.0, 20.0)),
+            ..default()
+        },
+        texture_atlas: Some(
+            atlases
+                .get("sprites/enemy-small.png")
+                .expect("Couldn't get enemy texture atlas.")
+                .clone(),
+        ),
+        collider: Collider::cuboid(half_width, half_width),
+        ..default()
+    };
+
+    timer.reset();
+    wave.spawned += 1;
+    let enemy = enemy::spawn_enemy(&mut commands, EnemyKind::Drone, spawn_point, attacks, sprite);
+    commands.entity(enemy).insert(waves.join());
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_mini_boss(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    mut timer: ResMut<MiniBossTimer>,
+    bosses: Query<(), With<Boss>>,
+    fonts: Res<Fonts>,
+    atlases: Res<Atlases<'static>>,
+    playfield: Res<Playfield>,
+    difficulty: Res<EndlessDifficulty>,
+) {
+    // Only one mini-boss at a time, the next wave is held back until the current one is beaten
+    if !bosses.is_empty() {
+        return;
+    }
+    timer.tick(time.delta());
+    if !timer.just_finished() {
+        return;
+    }
+
+    let attacks = Attacks::new(
+        vec![
+            AttackPattern::new(
+                BulletGroup {
+                    formation: Formation::circular(true, 20.0),
+                    number: (12.0 + 4.0 * difficulty.0) as u16,
+                    collider_type: ColliderType::EnemyBullet,
+                    bullet: Bullet::new(5.0, 20.0),
+                    ..default()
+                },
+                Movement::new(
+                    Vec2::ZERO,
+                    Vec2::ZERO,
+                    true,
+                    Vec2::new(0.0, 4.0 + difficulty.0),
+                    Vec2::ZERO,
+                ),
+                Timer::new(Duration::from_millis(2500), TimerMode::Once),
+                Some(Timer::new(Duration::from_millis(50), TimerMode::Once)),
+            ),
+            AttackPattern::new(
+                BulletGroup {
+                    formation: Formation::linear(Transform::default(), Vec2::ZERO),
+                    number: (2.0 + difficulty.0) as u16,
+                    collider_type: ColliderType::EnemyBullet,
+                    bullet: Bullet::new(5.0, 20.0),
+                    ..default()
+                },
+                Movement::new(
+                    Vec2::ZERO,
+                    Vec2::ZERO,
+                    true,
+                    Vec2::new(0.0, 12.0),
+                    Vec2::ZERO,
+                ),
+                Timer::new(Duration::from_millis(1200), TimerMode::Once),
+                Some(Timer::new(Duration::from_millis(80), TimerMode::Once)),
+            ),
+        ],
+        Timer::new(Duration::from_secs(8), TimerMode::Once),
+    );
+
+    let spawn_point = Transform {
+        translation: Vec3::new(0.0, playfield.max.y - 150.0, 0.0),
+        ..default()
+    };
+
+    let sprite = MetaSpriteAtlas {
+        sprite: TextureAtlasSprite {
+            color: Color::rgb(0.8, 0.3, 0.3),
+            custom_size: Some(Vec2::new(40.0, 40.0)),
+            ..default()
+        },
+        texture_atlas: Some(atlases.get("sprites/enemy-medium.png").expect("Couldn't get enemy texture atlas.").clone()),
+        collider: Collider::cuboid(20.0, 20.0),
+        ..default()
+    };
+
+    let boss = enemy::spawn_boss(
+        &mut commands,
+        EnemyKind::MiniBoss,
+        spawn_point,
+        attacks,
+        &fonts,
+        sprite,
+    );
+    commands.entity(boss).insert((
+        Movement::along(level1::boss_path()),
+        BossTimer::new(MINI_BOSS_TIME_LIMIT, TimeOut::Escape),
+    ));
+}
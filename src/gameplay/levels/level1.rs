@@ -1,22 +1,67 @@
 use crate::gameplay::{
+    bestiary::EnemyKind,
     bullet::AttackPattern,
     bullet::Bullet,
     bullet::BulletGroup,
     collisions::ColliderType,
+    difficulty::Difficulty,
     enemy,
-    enemy::{Attacks, Boss, Enemy},
+    enemy::{Attacks, Boss, BossPhase, BossPhases, BossTimer, Diver, Enemy, TimeOut, Turret},
     loading::Atlases,
+    rng::RunRng,
     shared::Formation,
-    shared::Movement,
-    shared::Name,
+    shared::{Easing, Movement, MovePath},
     shared::MetaSpriteAtlas,
+    wave::{FlightPath, WaveShape, WaveSpec, Waves},
+    GameTime,
     GameplayTime,
-    levels::SpawnEnemyTimer,
+    levels::{ambient::Ambience, MidBoss, Playfield, StageEvent, StageTimeline, SpawnEnemyTimer},
 };
+use crate::ui::fonts::Fonts;
 use bevy::prelude::*;
 use bevy::utils::Duration;
 use bevy_rapier2d::prelude::*;
-use rand::Rng;
+
+// Target clear time for the level. Clearing the level faster than this awards a time bonus.
+pub const PAR_TIME: Duration = Duration::from_secs(90);
+
+// Seconds the player has to beat the boss before it escapes
+const BOSS_TIME_LIMIT: f32 = 90.0;
+
+// Drones come in until the mid-boss turns up, with a V of them sweeping across on the way.
+// Once it is beaten there is a moment to breathe, and then the drones keep coming until the
+// boss is down.
+pub const TIMELINE: &[StageEvent] = &[
+    StageEvent::Spawn(Duration::from_secs(15)),
+    StageEvent::Wave(&V_WING_SWEEP),
+    StageEvent::Spawn(Duration::from_secs(20)),
+    StageEvent::MidBoss,
+    StageEvent::WaitForClear,
+    StageEvent::Wait(Duration::from_secs(3)),
+];
+
+// Divers come in between the drones
+const ARCHETYPES: &[EnemyKind] = &[EnemyKind::Diver];
+
+// A V of drones which comes in from the top left, sweeps across and leaves on the right
+const V_WING_SWEEP: WaveSpec = WaveSpec {
+    kind: EnemyKind::Drone,
+    count: 5,
+    shape: WaveShape::VWing { spacing: 30.0 },
+    path: [
+        Vec2::new(-0.6, 0.9),
+        Vec2::new(-0.4, 0.0),
+        Vec2::new(0.4, 0.2),
+        Vec2::new(1.4, 0.6),
+    ],
+    secs: 7.0,
+    // Slow to come in, then speeding up into the sweep
+    easing: Easing::In,
+    attacks: super::formation_attacks,
+};
+
+// Weather and other ambient effects shown over the background of the level
+pub const AMBIENCE: &[Ambience] = &[Ambience::Clouds];
 
 #[allow(unused)]
 fn setup_level() {
@@ -25,9 +70,12 @@ fn setup_level() {
 
 pub fn spawn_enemies(
     mut commands: Commands,
-    time: Res<Time>,
+    time: Res<GameTime>,
     mut timer: ResMut<SpawnEnemyTimer>,
+    mut waves: ResMut<Waves>,
     atlases: Res<Atlases<'static>>,
+    playfield: Res<Playfield>,
+    mut rng: ResMut<RunRng>,
 ) {
     timer.tick(time.delta());
     if !timer.finished() {
@@ -52,14 +100,28 @@ pub fn spawn_enemies(
             cd: Timer::from_seconds(0.8, TimerMode::Once),
             icd: None,
             current_bullet: 0,
+            ..default()
         }],
         Timer::new(Duration::from_secs(10), TimerMode::Once),
     );
 
     let spawn_point = Transform {
-        translation: Vec3::new(rand::thread_rng().gen_range(-150..150) as f32, 300.0, 0.2),
+        translation: playfield.spawn_point(&mut rng, 0.5, 10.0, 100.0).extend(0.2),
         ..default()
     };
+    if let Some(kind) = super::roll_archetype(&mut rng, ARCHETYPES) {
+        timer.reset();
+        let enemy = super::spawn_archetype(
+            &mut commands,
+            &atlases,
+            &playfield,
+            &mut rng,
+            kind,
+            spawn_point,
+        );
+        commands.entity(enemy).insert(waves.join());
+        return;
+    }
     let sprite = MetaSpriteAtlas {
         sprite: TextureAtlasSprite {
             color: Color::rgb(1.0, 1.0, 1.0),
@@ -77,10 +139,66 @@ pub fn spawn_enemies(
     };
 
     timer.reset();
-    enemy::spawn_enemy(&mut commands, spawn_point, attacks, sprite);
+    let enemy = enemy::spawn_enemy(&mut commands, EnemyKind::Drone, spawn_point, attacks, sprite);
+    commands.entity(enemy).insert(waves.join());
+}
+
+// The mid-boss fires a slow ring of bullets every so often
+pub fn spawn_mid_boss(
+    mut commands: Commands,
+    mut timeline: ResMut<StageTimeline>,
+    fonts: Res<Fonts>,
+    atlases: Res<Atlases<'static>>,
+    playfield: Res<Playfield>,
+) {
+    if !timeline.take_mid_boss() {
+        return;
+    }
+    let attacks = Attacks::new(
+        vec![
+            AttackPattern::new(
+                BulletGroup {
+                    formation: Formation::circular(false, 20.0),
+                    number: 16,
+                    collider_type: ColliderType::EnemyBullet,
+                    bullet: Bullet::new(5.0, 20.0),
+                    ..default()
+                },
+                Movement::new(
+                    Vec2::ZERO,
+                    Vec2::ZERO,
+                    true,
+                    Vec2::new(0.0, 5.0),
+                    Vec2::ZERO,
+                ),
+                Timer::new(Duration::from_millis(1500), TimerMode::Once),
+                None,
+            ),
+        ],
+        Timer::new(Duration::from_secs(10), TimerMode::Once),
+    );
+
+    let spawn_point = Transform {
+        translation: Vec3::new(playfield.center().x, playfield.max.y - 120.0, 0.2),
+        ..default()
+    };
+    let sprite = MetaSpriteAtlas {
+        sprite: TextureAtlasSprite {
+            color: Color::rgb(0.9, 0.9, 0.4),
+            custom_size: Some(Vec2::new(36.0, 36.0)),
+            ..default()
+        },
+        texture_atlas: Some(atlases.get("sprites/enemy-small.png").expect("Couldn't get enemy texture atlas.").clone()),
+        collider: Collider::cuboid(18.0, 18.0),
+        ..default()
+    };
+
+    super::spawn_mid_boss(&mut commands, &fonts, EnemyKind::ScoutLeader, spawn_point, attacks, sprite);
 }
 
-pub fn spawn_boss(mut commands: Commands, asset_server: Res<AssetServer>, atlases: Res<Atlases<'static>>) {
+pub fn spawn_boss(mut commands: Commands, fonts: Res<Fonts>, atlases: Res<Atlases<'static>>) {
+    // Hard adds a spinning ring to the rotation, and on Lunatic aimed shots keep coming in
+    // between attacks
     let attacks = Attacks::new(
         vec![
             AttackPattern::new(
@@ -137,9 +255,49 @@ pub fn spawn_boss(mut commands: Commands, asset_server: Res<AssetServer>, atlase
                 Timer::new(Duration::from_millis(1200), TimerMode::Once),
                 Some(Timer::new(Duration::from_millis(60), TimerMode::Once)),
             ),
+            AttackPattern::new(
+                BulletGroup {
+                    formation: Formation::rotating_ring(15.0, 1.0 / 48.0),
+                    number: 24,
+                    collider_type: ColliderType::EnemyBullet,
+                    bullet: Bullet::new(5.0, 20.0),
+                    ..default()
+                },
+                Movement::new(
+                    Vec2::ZERO,
+                    Vec2::ZERO,
+                    true,
+                    Vec2::new(0.0, 9.0),
+                    Vec2::ZERO,
+                ),
+                Timer::new(Duration::from_millis(2500), TimerMode::Once),
+                Some(Timer::new(Duration::from_millis(40), TimerMode::Once)),
+            )
+            .with_min_difficulty(Difficulty::Hard),
         ],
         Timer::new(Duration::from_secs(10), TimerMode::Once),
-    );
+    )
+    .with_overlays(vec![
+        AttackPattern::new(
+            BulletGroup {
+                formation: Formation::linear(Transform::default(), Vec2::ZERO),
+                number: 3,
+                collider_type: ColliderType::EnemyBullet,
+                bullet: Bullet::new(5.0, 20.0),
+                ..default()
+            },
+            Movement::new(
+                Vec2::ZERO,
+                Vec2::ZERO,
+                true,
+                Vec2::new(0.0, 14.0),
+                Vec2::ZERO,
+            ),
+            Timer::new(Duration::from_millis(1800), TimerMode::Once),
+            None,
+        )
+        .with_min_difficulty(Difficulty::Lunatic),
+    ]);
 
     let spawn_point = Transform {
         translation: Vec3::new(100.0, 100.0, 0.0),
@@ -157,19 +315,88 @@ pub fn spawn_boss(mut commands: Commands, asset_server: Res<AssetServer>, atlase
         ..default()
     };
 
-    enemy::spawn_boss(
+    let boss = enemy::spawn_boss(
         &mut commands,
-        Name::from("Big Boss"),
+        EnemyKind::BigBoss,
         spawn_point,
         attacks,
-        asset_server,
+        &fonts,
         sprite,
     );
+    commands.entity(boss).insert((
+        Movement::along(boss_path()),
+        BossPhases::new(vec![final_phase()]),
+        BossTimer::new(BOSS_TIME_LIMIT, TimeOut::Escape),
+    ));
 }
 
+// Below half health the boss speeds up, and sprays fast aimed lines between rings of bullets
+fn final_phase() -> BossPhase {
+    let attacks = Attacks::new(
+        vec![
+            AttackPattern::new(
+                BulletGroup {
+                    formation: Formation::circular(true, 20.0),
+                    number: 40,
+                    collider_type: ColliderType::EnemyBullet,
+                    bullet: Bullet::new(5.0, 20.0),
+                    ..default()
+                },
+                Movement::new(
+                    Vec2::ZERO,
+                    Vec2::ZERO,
+                    true,
+                    Vec2::new(0.0, 8.0),
+                    Vec2::ZERO,
+                ),
+                Timer::new(Duration::from_millis(2000), TimerMode::Once),
+                Some(Timer::new(Duration::from_millis(50), TimerMode::Once)),
+            ),
+            AttackPattern::new(
+                BulletGroup {
+                    formation: Formation::linear(Transform::default(), Vec2::ZERO),
+                    number: 8,
+                    collider_type: ColliderType::EnemyBullet,
+                    bullet: Bullet::new(5.0, 20.0),
+                    ..default()
+                },
+                Movement::new(
+                    Vec2::ZERO,
+                    Vec2::ZERO,
+                    true,
+                    Vec2::new(0.0, 18.0),
+                    Vec2::ZERO,
+                ),
+                Timer::new(Duration::from_millis(900), TimerMode::Once),
+                Some(Timer::new(Duration::from_millis(50), TimerMode::Once)),
+            ),
+        ],
+        Timer::new(Duration::from_secs(6), TimerMode::Once),
+    );
+    BossPhase {
+        threshold: 0.5,
+        attacks,
+        tint: Color::rgb(1.0, 0.6, 0.5),
+        speed: 1.6,
+        retarget: None,
+    }
+}
+
+// Divers, turrets and enemies flying in formation steer themselves, so they are left out
+#[allow(clippy::type_complexity)]
 pub fn enemy_movement(
     time: Res<GameplayTime>,
-    mut enemies: Query<&mut Movement, (With<Enemy>, Without<Boss>)>,
+    mut enemies: Query<
+        &mut Movement,
+        (
+            With<Enemy>,
+            Without<Boss>,
+            Without<MidBoss>,
+            Without<Diver>,
+            Without<Turret>,
+            Without<FlightPath>,
+        ),
+    >,
 ) {
     use std::f32::consts::TAU;
     let amplitude = 2.0;
@@ -179,15 +406,24 @@ pub fn enemy_movement(
     for mut mov in enemies.iter_mut() {
         phase_difference += 4.0;
         mov.v_local.x =
-            amplitude * (TAU * frequency * time.elapsed_secs() + phase_difference).sin();
+            amplitude * (TAU * frequency * time.level_elapsed_secs() + phase_difference).sin();
     }
 }
 
-pub fn boss_movement(mut boss: Query<&mut Movement, With<Boss>>, time: Res<GameplayTime>) {
-    let Ok(mut movement) = boss.get_single_mut() else { return; };
-    use std::f32::consts::TAU;
-    let amplitude = 5.0;
-    let frequency = 0.1;
-    let phase_difference = 10.0;
-    movement.v_local = Vec2::new(amplitude * (TAU * frequency * time.elapsed_secs() + phase_difference).sin(), 0.0);
+// Sweep from side to side, starting out to the right, and back to the middle every 10 seconds
+pub fn boss_path() -> MovePath {
+    let points = vec![
+        Vec2::ZERO,
+        Vec2::new(160.0, 0.0),
+        Vec2::new(-160.0, 0.0),
+        Vec2::ZERO,
+    ];
+    MovePath::waypoints(points, 10.0, Easing::Linear).looped()
+}
+
+// The boss goes around its path faster in later phases
+pub fn boss_movement(mut boss: Query<(&mut Movement, Option<&BossPhases>), With<Boss>>) {
+    let Ok((mut movement, phases)) = boss.get_single_mut() else { return; };
+    let Some(path) = &mut movement.path else { return; };
+    path.set_speed(phases.map_or(1.0, BossPhases::speed));
 }
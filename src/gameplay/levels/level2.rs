@@ -1,22 +1,70 @@
 use crate::gameplay::{
+    bestiary::EnemyKind,
     bullet::AttackPattern,
     bullet::Bullet,
     bullet::BulletGroup,
+    bullet::{CompositePattern, PatternPart},
     collisions::ColliderType,
+    difficulty::Difficulty,
     enemy,
-    enemy::{Attacks, Boss, Enemy},
+    explosive,
+    enemy::{Attacks, Boss, BossPhase, BossPhases, BossTimer, Diver, Enemy, TimeOut, Turret},
     loading::{Atlases, BackgroundHandle},
-    shared::Formation,
-    shared::Movement,
-    shared::Name,
+    rng::RunRng,
+    shared::{Easing, Formation, GOLDEN_ANGLE},
+    shared::{Movement, MovementScript},
     shared::MetaSpriteAtlas,
+    wave::{FlightPath, WaveShape, WaveSpec, Waves},
+    GameTime,
     GameplayTime,
-    levels::{SpawnEnemyTimer, LevelBackground},
+    levels::{ambient::Ambience, LevelBackground, MidBoss, Playfield, StageEvent, StageTimeline, SpawnEnemyTimer},
 };
+use crate::ui::fonts::Fonts;
 use bevy::prelude::*;
 use bevy::utils::Duration;
 use bevy_rapier2d::prelude::*;
-use rand::Rng;
+
+// Target clear time for the level. Clearing the level faster than this awards a time bonus.
+pub const PAR_TIME: Duration = Duration::from_secs(100);
+
+// Seconds the player has to beat the boss before it escapes
+const BOSS_TIME_LIMIT: f32 = 100.0;
+
+// A first wave of drones has to be cleared before the second comes in, which opens with a
+// column diving through and leads up to the mid-boss
+pub const TIMELINE: &[StageEvent] = &[
+    StageEvent::Spawn(Duration::from_secs(18)),
+    StageEvent::WaitForClear,
+    StageEvent::Wait(Duration::from_secs(2)),
+    StageEvent::Wave(&COLUMN_DIVE),
+    StageEvent::Spawn(Duration::from_secs(18)),
+    StageEvent::MidBoss,
+    StageEvent::WaitForClear,
+    StageEvent::Wait(Duration::from_secs(3)),
+];
+
+// Divers and turrets come in between the drones
+const ARCHETYPES: &[EnemyKind] = &[EnemyKind::Diver, EnemyKind::Turret];
+
+// A column of drones which dives down the middle and curves away to the left
+const COLUMN_DIVE: WaveSpec = WaveSpec {
+    kind: EnemyKind::Drone,
+    count: 6,
+    shape: WaveShape::Column { spacing: 28.0 },
+    path: [
+        Vec2::new(0.0, 0.9),
+        Vec2::new(0.0, -0.2),
+        Vec2::new(-0.6, -0.4),
+        Vec2::new(-1.4, 0.2),
+    ],
+    secs: 6.0,
+    // Picking up speed as it dives
+    easing: Easing::In,
+    attacks: super::formation_attacks,
+};
+
+// Weather and other ambient effects shown over the background of the level
+pub const AMBIENCE: &[Ambience] = &[Ambience::Rain, Ambience::Clouds];
 
 pub fn setup_level(
     asset_server: Res<AssetServer>,
@@ -33,9 +81,12 @@ pub fn setup_level(
 
 pub fn spawn_enemies(
     mut commands: Commands,
-    time: Res<Time>,
+    time: Res<GameTime>,
     mut timer: ResMut<SpawnEnemyTimer>,
+    mut waves: ResMut<Waves>,
     atlases: Res<Atlases<'static>>,
+    playfield: Res<Playfield>,
+    mut rng: ResMut<RunRng>,
 ) {
     if timer.duration() != Duration::from_millis(1100) {
         timer.set_duration(Duration::from_millis(1100));
@@ -60,17 +111,33 @@ pub fn spawn_enemies(
             cd: Timer::from_seconds(0.8, TimerMode::Once),
             icd: None,
             current_bullet: 0,
+            ..default()
         }],
         Timer::new(Duration::from_secs(10), TimerMode::Once),
     );
 
     let spawn_point = Transform {
-        translation: Vec3::new(rand::thread_rng().gen_range(-250..250) as f32, 330.0, 0.2),
+        translation: playfield.spawn_point(&mut rng, 0.85, 10.0, 70.0).extend(0.2),
         ..default()
     };
+    if let Some(kind) = super::roll_archetype(&mut rng, ARCHETYPES) {
+        timer.reset();
+        let enemy = super::spawn_archetype(
+            &mut commands,
+            &atlases,
+            &playfield,
+            &mut rng,
+            kind,
+            spawn_point,
+        );
+        commands.entity(enemy).insert(waves.join());
+        return;
+    }
+    // Some of the drones are volatile, and take their neighbours with them when destroyed
+    let kind = super::roll_drone_kind(&mut rng);
     let sprite = MetaSpriteAtlas {
         sprite: TextureAtlasSprite {
-            color: Color::rgb(1.0, 1.0, 1.0),
+            color: kind.sprite().1,
             custom_size: Some(Vec2::new(20.0, 20.0)),
             ..default()
         },
@@ -85,10 +152,70 @@ pub fn spawn_enemies(
     };
 
     timer.reset();
-    enemy::spawn_enemy(&mut commands, spawn_point, attacks, sprite);
+    let enemy = enemy::spawn_enemy(&mut commands, kind, spawn_point, attacks, sprite);
+    commands.entity(enemy).insert(waves.join());
+    if kind == EnemyKind::Volatile {
+        commands.entity(enemy).insert(explosive::VOLATILE);
+    }
+}
+
+// The mid-boss fires a wave of bullets every so often
+pub fn spawn_mid_boss(
+    mut commands: Commands,
+    mut timeline: ResMut<StageTimeline>,
+    fonts: Res<Fonts>,
+    atlases: Res<Atlases<'static>>,
+    playfield: Res<Playfield>,
+) {
+    if !timeline.take_mid_boss() {
+        return;
+    }
+    let attacks = Attacks::new(
+        vec![
+            AttackPattern::new(
+                BulletGroup {
+                    formation: Formation::harmonic(false, 20.0, 20.0, 3.0),
+                    number: 20,
+                    collider_type: ColliderType::EnemyBullet,
+                    bullet: Bullet::new(5.0, 20.0),
+                    ..default()
+                },
+                Movement::new(
+                    Vec2::ZERO,
+                    Vec2::ZERO,
+                    true,
+                    Vec2::new(0.0, 8.0),
+                    Vec2::ZERO,
+                ),
+                Timer::new(Duration::from_millis(2000), TimerMode::Once),
+                Some(Timer::new(Duration::from_millis(60), TimerMode::Once)),
+            ),
+        ],
+        Timer::new(Duration::from_secs(10), TimerMode::Once),
+    );
+
+    let spawn_point = Transform {
+        translation: Vec3::new(playfield.center().x, playfield.max.y - 120.0, 0.2),
+        ..default()
+    };
+    let sprite = MetaSpriteAtlas {
+        sprite: TextureAtlasSprite {
+            color: Color::rgb(0.5, 0.8, 1.0),
+            custom_size: Some(Vec2::new(36.0, 36.0)),
+            ..default()
+        },
+        texture_atlas: Some(atlases.get("sprites/enemy-medium.png").expect("Couldn't get enemy texture atlas.").clone()),
+        collider: Collider::cuboid(18.0, 18.0),
+        ..default()
+    };
+
+    super::spawn_mid_boss(&mut commands, &fonts, EnemyKind::Gunship, spawn_point, attacks, sprite);
 }
 
-pub fn spawn_boss(mut commands: Commands, asset_server: Res<AssetServer>, atlases: Res<Atlases<'static>>) {
+pub fn spawn_boss(mut commands: Commands, fonts: Res<Fonts>, atlases: Res<Atlases<'static>>) {
+    // Hard adds a spinning wave, followed in turn by an aimed fan and a ring, and a ring which
+    // stops and fires back at the player to the rotation. Lunatic adds a slow ring on top of
+    // every attack.
     let attacks = Attacks::new(
         vec![
             AttackPattern::new(
@@ -127,9 +254,97 @@ pub fn spawn_boss(mut commands: Commands, asset_server: Res<AssetServer>, atlase
                 Timer::new(Duration::from_millis(6000), TimerMode::Once),
                 Some(Timer::new(Duration::from_millis(100), TimerMode::Once)),
             ),
+            AttackPattern::new(
+                BulletGroup {
+                    formation: Formation::harmonic(true, 40.0, 20.0, 3.0),
+                    number: 30,
+                    collider_type: ColliderType::EnemyBullet,
+                    bullet: Bullet::new(5.0, 20.0),
+                    ..default()
+                },
+                Movement::new(
+                    Vec2::ZERO,
+                    Vec2::ZERO,
+                    true,
+                    Vec2::new(0.0, 9.0),
+                    Vec2::ZERO,
+                ),
+                Timer::new(Duration::from_millis(3000), TimerMode::Once),
+                Some(Timer::new(Duration::from_millis(60), TimerMode::Once)),
+            )
+            // Once the wave is out, a fan is fired at the player, and once that is out, a ring
+            // closes off the gaps left between them
+            .with_composite(CompositePattern::in_turn(vec![
+                PatternPart::new(
+                    BulletGroup {
+                        formation: Formation::aimed(0.6),
+                        number: 5,
+                        collider_type: ColliderType::EnemyBullet,
+                        bullet: Bullet::new(5.0, 20.0),
+                        ..default()
+                    },
+                    Movement::new(Vec2::ZERO, Vec2::ZERO, true, Vec2::new(0.0, 12.0), Vec2::ZERO),
+                    0.2,
+                    None,
+                ),
+                PatternPart::new(
+                    BulletGroup {
+                        formation: Formation::circular(false, 15.0),
+                        number: 16,
+                        collider_type: ColliderType::EnemyBullet,
+                        bullet: Bullet::new(5.0, 20.0),
+                        ..default()
+                    },
+                    Movement::new(Vec2::ZERO, Vec2::ZERO, true, Vec2::new(0.0, 7.0), Vec2::ZERO),
+                    0.3,
+                    None,
+                ),
+            ]))
+            .with_min_difficulty(Difficulty::Hard),
+            AttackPattern::new(
+                BulletGroup {
+                    formation: Formation::spiral(15.0, GOLDEN_ANGLE, 0.1),
+                    number: 20,
+                    collider_type: ColliderType::EnemyBullet,
+                    bullet: Bullet::new(5.0, 20.0),
+                    script: Some(MovementScript::delayed_fire(0.8, 0.5, 6.0, 8.0)),
+                    ..default()
+                },
+                Movement::new(
+                    Vec2::ZERO,
+                    Vec2::ZERO,
+                    true,
+                    Vec2::new(0.0, 10.0),
+                    Vec2::ZERO,
+                ),
+                Timer::new(Duration::from_millis(2500), TimerMode::Once),
+                None,
+            )
+            .with_min_difficulty(Difficulty::Hard),
         ],
         Timer::new(Duration::from_secs(10), TimerMode::Once),
-    );
+    )
+    .with_overlays(vec![
+        AttackPattern::new(
+            BulletGroup {
+                formation: Formation::circular(false, 20.0),
+                number: 16,
+                collider_type: ColliderType::EnemyBullet,
+                bullet: Bullet::new(5.0, 20.0),
+                ..default()
+            },
+            Movement::new(
+                Vec2::ZERO,
+                Vec2::ZERO,
+                true,
+                Vec2::new(0.0, 4.0),
+                Vec2::ZERO,
+            ),
+            Timer::new(Duration::from_millis(2000), TimerMode::Once),
+            None,
+        )
+        .with_min_difficulty(Difficulty::Lunatic),
+    ]);
 
     let spawn_point = Transform {
         translation: Vec3::new(0.0, 80.0, 0.0),
@@ -147,19 +362,87 @@ pub fn spawn_boss(mut commands: Commands, asset_server: Res<AssetServer>, atlase
         ..default()
     };
 
-    enemy::spawn_boss(
+    let boss = enemy::spawn_boss(
         &mut commands,
-        Name::from("Bigger Boss"),
+        EnemyKind::BiggerBoss,
         spawn_point,
         attacks,
-        asset_server,
+        &fonts,
         sprite,
     );
+    commands.entity(boss).insert((
+        BossPhases::new(vec![final_phase()]),
+        BossTimer::new(BOSS_TIME_LIMIT, TimeOut::Escape),
+    ));
+}
+
+// Below 40% health the boss spins faster and fires denser spirals
+fn final_phase() -> BossPhase {
+    let attacks = Attacks::new(
+        vec![
+            AttackPattern::new(
+                BulletGroup {
+                    formation: Formation::circular(true, 10.0),
+                    number: 60,
+                    collider_type: ColliderType::EnemyBullet,
+                    bullet: Bullet::new(5.0, 20.0),
+                    ..default()
+                },
+                Movement::new(
+                    Vec2::ZERO,
+                    Vec2::ZERO,
+                    true,
+                    Vec2::new(0.0, 11.0),
+                    Vec2::new(0.0, -3.0),
+                ),
+                Timer::new(Duration::from_millis(1500), TimerMode::Once),
+                Some(Timer::new(Duration::from_millis(10), TimerMode::Once)),
+            ),
+            AttackPattern::new(
+                BulletGroup {
+                    formation: Formation::harmonic(false, 20.0, 40.0, 5.0),
+                    number: 45,
+                    collider_type: ColliderType::EnemyBullet,
+                    bullet: Bullet::new(5.0, 20.0),
+                    ..default()
+                },
+                Movement::new(
+                    Vec2::ZERO,
+                    Vec2::ZERO,
+                    true,
+                    Vec2::new(0.0, 12.0),
+                    Vec2::ZERO,
+                ),
+                Timer::new(Duration::from_millis(4000), TimerMode::Once),
+                Some(Timer::new(Duration::from_millis(80), TimerMode::Once)),
+            ),
+        ],
+        Timer::new(Duration::from_secs(8), TimerMode::Once),
+    );
+    BossPhase {
+        threshold: 0.4,
+        attacks,
+        tint: Color::rgb(1.0, 0.7, 0.4),
+        speed: 1.5,
+        retarget: None,
+    }
 }
 
+// Divers, turrets and enemies flying in formation steer themselves, so they are left out
+#[allow(clippy::type_complexity)]
 pub fn enemy_movement(
     time: Res<GameplayTime>,
-    mut enemies: Query<&mut Movement, (With<Enemy>, Without<Boss>)>,
+    mut enemies: Query<
+        &mut Movement,
+        (
+            With<Enemy>,
+            Without<Boss>,
+            Without<MidBoss>,
+            Without<Diver>,
+            Without<Turret>,
+            Without<FlightPath>,
+        ),
+    >,
 ) {
     use std::f32::consts::TAU;
     let amplitude = 4.0;
@@ -169,13 +452,16 @@ pub fn enemy_movement(
     for mut mov in enemies.iter_mut() {
         phase_difference += 4.0;
         mov.v_local.x =
-            amplitude * (TAU * frequency * time.elapsed_secs() + phase_difference).sin();
+            amplitude * (TAU * frequency * time.level_elapsed_secs() + phase_difference).sin();
     }
 }
 
-pub fn boss_movement(mut boss: Query<(&mut Velocity, &mut Movement), With<Boss>>) {
-    let Ok((mut velocity, mut movement)) = boss.get_single_mut() else { return; };
-    movement.v_local = Vec2::new(0.0, 5.0);
-    velocity.angvel = 2.0;
+pub fn boss_movement(
+    mut boss: Query<(&mut Velocity, &mut Movement, Option<&BossPhases>), With<Boss>>,
+) {
+    let Ok((mut velocity, mut movement, phases)) = boss.get_single_mut() else { return; };
+    let speed = phases.map_or(1.0, BossPhases::speed);
+    movement.v_local = Vec2::new(0.0, 5.0 * speed);
+    velocity.angvel = 2.0 * speed;
 }
 
@@ -1,22 +1,77 @@
 use crate::gameplay::{
+    bestiary::EnemyKind,
     bullet::AttackPattern,
     bullet::Bullet,
     bullet::BulletGroup,
+    bullet::{BulletFilter, RetargetBullets},
+    bullet::Symmetry,
+    bullet::{CompositePattern, PatternPart},
     collisions::ColliderType,
+    difficulty::Difficulty,
     enemy,
-    enemy::{Attacks, Boss, Enemy},
+    explosive,
+    enemy::{Attacks, Boss, BossPhase, BossPhases, BossTimer, Diver, Enemy, TimeOut, Turret},
     loading::{Atlases, BackgroundHandle},
-    shared::Formation,
+    rng::RunRng,
+    shared::{Easing, Formation},
     shared::Movement,
-    shared::Name,
+    shared::{MovementScript, ScriptStage},
+    shared::Tags,
     shared::MetaSpriteAtlas,
+    wave::{FlightPath, WaveShape, WaveSpec, Waves},
+    GameTime,
     GameplayTime,
-    levels::{SpawnEnemyTimer, LevelBackground},
+    levels::{ambient::Ambience, LevelBackground, MidBoss, Playfield, StageEvent, StageTimeline, SpawnEnemyTimer},
 };
+use crate::ui::fonts::Fonts;
 use bevy::prelude::*;
 use bevy::utils::Duration;
 use bevy_rapier2d::prelude::*;
-use rand::Rng;
+
+// Target clear time for the level. Clearing the level faster than this awards a time bonus.
+pub const PAR_TIME: Duration = Duration::from_secs(120);
+
+// Seconds the player has to beat the boss before it enrages
+const BOSS_TIME_LIMIT: f32 = 120.0;
+
+// Three waves of drones, each of which has to be cleared before the next, then the mid-boss.
+// The second wave opens with an arc of them swinging through.
+pub const TIMELINE: &[StageEvent] = &[
+    StageEvent::Spawn(Duration::from_secs(14)),
+    StageEvent::WaitForClear,
+    StageEvent::Wave(&ARC_SWING),
+    StageEvent::Spawn(Duration::from_secs(14)),
+    StageEvent::WaitForClear,
+    StageEvent::Spawn(Duration::from_secs(14)),
+    StageEvent::MidBoss,
+    StageEvent::WaitForClear,
+    StageEvent::Wait(Duration::from_secs(3)),
+];
+
+// Divers, turrets and carriers come in between the drones
+const ARCHETYPES: &[EnemyKind] = &[EnemyKind::Diver, EnemyKind::Turret, EnemyKind::Carrier];
+
+// An arc of drones which drops in from the top and swings out to the right
+const ARC_SWING: WaveSpec = WaveSpec {
+    kind: EnemyKind::Drone,
+    count: 7,
+    shape: WaveShape::Arc {
+        radius: 120.0,
+        span: 1.6,
+    },
+    path: [
+        Vec2::new(0.0, 0.95),
+        Vec2::new(0.0, 0.1),
+        Vec2::new(0.8, 0.0),
+        Vec2::new(1.4, -0.3),
+    ],
+    secs: 6.0,
+    easing: Easing::Linear,
+    attacks: super::formation_attacks,
+};
+
+// Weather and other ambient effects shown over the background of the level
+pub const AMBIENCE: &[Ambience] = &[Ambience::Embers];
 
 pub fn setup_level(
     asset_server: Res<AssetServer>,
@@ -33,9 +88,12 @@ pub fn setup_level(
 
 pub fn spawn_enemies(
     mut commands: Commands,
-    time: Res<Time>,
+    time: Res<GameTime>,
     mut timer: ResMut<SpawnEnemyTimer>,
+    mut waves: ResMut<Waves>,
     atlases: Res<Atlases<'static>>,
+    playfield: Res<Playfield>,
+    mut rng: ResMut<RunRng>,
 ) {
     if timer.duration() != Duration::from_millis(800) {
         timer.set_duration(Duration::from_millis(800));
@@ -60,17 +118,33 @@ pub fn spawn_enemies(
             cd: Timer::from_seconds(2.8, TimerMode::Once),
             icd: Some(Timer::from_seconds(0.4, TimerMode::Once)),
             current_bullet: 0,
+            ..default()
         }],
         Timer::new(Duration::from_secs(10), TimerMode::Once),
     );
 
     let spawn_point = Transform {
-        translation: Vec3::new(rand::thread_rng().gen_range(-250..250) as f32, 330.0, 0.2),
+        translation: playfield.spawn_point(&mut rng, 0.85, 10.0, 70.0).extend(0.2),
         ..default()
     };
+    if let Some(kind) = super::roll_archetype(&mut rng, ARCHETYPES) {
+        timer.reset();
+        let enemy = super::spawn_archetype(
+            &mut commands,
+            &atlases,
+            &playfield,
+            &mut rng,
+            kind,
+            spawn_point,
+        );
+        commands.entity(enemy).insert(waves.join());
+        return;
+    }
+    // Some of the drones are volatile, and take their neighbours with them when destroyed
+    let kind = super::roll_drone_kind(&mut rng);
     let sprite = MetaSpriteAtlas {
         sprite: TextureAtlasSprite {
-            color: Color::rgb(1.0, 1.0, 1.0),
+            color: kind.sprite().1,
             custom_size: Some(Vec2::new(20.0, 20.0)),
             ..default()
         },
@@ -85,10 +159,69 @@ pub fn spawn_enemies(
     };
 
     timer.reset();
-    enemy::spawn_enemy(&mut commands, spawn_point, attacks, sprite);
+    let enemy = enemy::spawn_enemy(&mut commands, kind, spawn_point, attacks, sprite);
+    commands.entity(enemy).insert(waves.join());
+    if kind == EnemyKind::Volatile {
+        commands.entity(enemy).insert(explosive::VOLATILE);
+    }
 }
 
-pub fn spawn_boss(mut commands: Commands, asset_server: Res<AssetServer>, atlases: Res<Atlases<'static>>) {
+// The mid-boss fires a spiral of bullets every so often
+pub fn spawn_mid_boss(
+    mut commands: Commands,
+    mut timeline: ResMut<StageTimeline>,
+    fonts: Res<Fonts>,
+    atlases: Res<Atlases<'static>>,
+    playfield: Res<Playfield>,
+) {
+    if !timeline.take_mid_boss() {
+        return;
+    }
+    let attacks = Attacks::new(
+        vec![
+            AttackPattern::new(
+                BulletGroup {
+                    formation: Formation::circular(true, 20.0),
+                    number: 60,
+                    collider_type: ColliderType::EnemyBullet,
+                    bullet: Bullet::new(5.0, 20.0),
+                    ..default()
+                },
+                Movement::new(
+                    Vec2::ZERO,
+                    Vec2::ZERO,
+                    true,
+                    Vec2::new(0.0, 7.0),
+                    Vec2::ZERO,
+                ),
+                Timer::new(Duration::from_millis(2000), TimerMode::Once),
+                Some(Timer::new(Duration::from_millis(20), TimerMode::Once)),
+            ),
+        ],
+        Timer::new(Duration::from_secs(10), TimerMode::Once),
+    );
+
+    let spawn_point = Transform {
+        translation: Vec3::new(playfield.center().x, playfield.max.y - 120.0, 0.2),
+        ..default()
+    };
+    let sprite = MetaSpriteAtlas {
+        sprite: TextureAtlasSprite {
+            color: Color::rgb(1.0, 0.5, 0.9),
+            custom_size: Some(Vec2::new(40.0, 40.0)),
+            ..default()
+        },
+        texture_atlas: Some(atlases.get("sprites/enemy-medium.png").expect("Couldn't get enemy texture atlas.").clone()),
+        collider: Collider::cuboid(20.0, 20.0),
+        ..default()
+    };
+
+    super::spawn_mid_boss(&mut commands, &fonts, EnemyKind::Warden, spawn_point, attacks, sprite);
+}
+
+pub fn spawn_boss(mut commands: Commands, fonts: Res<Fonts>, atlases: Res<Atlases<'static>>) {
+    // Hard adds bursts of aimed bullets to the rotation, and Lunatic adds a ring of bullets on
+    // top of every attack
     let attacks = Attacks::new(
         vec![
             AttackPattern::new(
@@ -127,9 +260,91 @@ pub fn spawn_boss(mut commands: Commands, asset_server: Res<AssetServer>, atlase
                 Timer::new(Duration::from_millis(3000), TimerMode::Once),
                 Some(Timer::new(Duration::from_millis(20), TimerMode::Once)),
             ),
+            AttackPattern::new(
+                BulletGroup {
+                    formation: Formation::linear(Transform::default(), Vec2::ZERO),
+                    number: 10,
+                    collider_type: ColliderType::EnemyBullet,
+                    bullet: Bullet::new(5.0, 20.0),
+                    ..default()
+                },
+                Movement::new(
+                    Vec2::ZERO,
+                    Vec2::ZERO,
+                    true,
+                    Vec2::new(0.0, 16.0),
+                    Vec2::ZERO,
+                ),
+                Timer::new(Duration::from_millis(1000), TimerMode::Once),
+                Some(Timer::new(Duration::from_millis(40), TimerMode::Once)),
+            )
+            // A slow ring goes off halfway through the stream, so that it can't be dodged by
+            // just moving sideways. It freezes once it has spread out, then comes back at the
+            // player.
+            .with_composite(CompositePattern::together(vec![PatternPart::new(
+                BulletGroup {
+                    formation: Formation::circular(false, 15.0),
+                    number: 12,
+                    collider_type: ColliderType::EnemyBullet,
+                    bullet: Bullet::new(5.0, 20.0),
+                    script: Some(MovementScript::new(vec![
+                        ScriptStage::Travel(0.5),
+                        ScriptStage::Stop(0.3),
+                        ScriptStage::Aim(4.0),
+                        ScriptStage::Accelerate(6.0),
+                    ])),
+                    ..default()
+                },
+                Movement::new(Vec2::ZERO, Vec2::ZERO, true, Vec2::new(0.0, 6.0), Vec2::ZERO),
+                0.2,
+                None,
+            )]))
+            .with_min_difficulty(Difficulty::Hard),
         ],
         Timer::new(Duration::from_secs(15), TimerMode::Once),
-    );
+    )
+    .with_overlays(vec![
+        AttackPattern::new(
+            BulletGroup {
+                formation: Formation::circular(true, 15.0),
+                number: 24,
+                collider_type: ColliderType::EnemyBullet,
+                bullet: Bullet::new(5.0, 20.0),
+                ..default()
+            },
+            Movement::new(
+                Vec2::ZERO,
+                Vec2::ZERO,
+                true,
+                Vec2::new(0.0, 5.0),
+                Vec2::ZERO,
+            ),
+            Timer::new(Duration::from_millis(1500), TimerMode::Once),
+            None,
+        )
+        .with_min_difficulty(Difficulty::Lunatic),
+        // Aimed stream repeated in four directions, a cross which turns to follow the player
+        AttackPattern::new(
+            BulletGroup {
+                formation: Formation::linear(Transform::default(), Vec2::ZERO),
+                number: 6,
+                collider_type: ColliderType::EnemyBullet,
+                bullet: Bullet::new(5.0, 20.0),
+                symmetry: Symmetry::rotational(4),
+                ..default()
+            },
+            Movement::new(
+                Vec2::ZERO,
+                Vec2::ZERO,
+                true,
+                Vec2::new(0.0, 8.0),
+                Vec2::ZERO,
+            ),
+            Timer::new(Duration::from_millis(2500), TimerMode::Once),
+            Some(Timer::new(Duration::from_millis(60), TimerMode::Once)),
+        )
+        .with_min_difficulty(Difficulty::Lunatic),
+    ]);
 
     let spawn_point = Transform {
         translation: Vec3::new(0.0, 80.0, 0.0),
@@ -147,19 +362,135 @@ pub fn spawn_boss(mut commands: Commands, asset_server: Res<AssetServer>, atlase
         ..default()
     };
 
-    enemy::spawn_boss(
+    let boss = enemy::spawn_boss(
         &mut commands,
-        Name::from("Biggest Boss"),
+        EnemyKind::BiggestBoss,
         spawn_point,
         attacks,
-        asset_server,
+        &fonts,
         sprite,
     );
+    commands.entity(boss).insert((
+        BossPhases::new(vec![second_phase(), final_phase()]),
+        BossTimer::new(BOSS_TIME_LIMIT, TimeOut::Enrage(enraged_attacks())),
+    ));
+}
+
+// Bullets of the second phase's tight rings, which are sent at the player when the final
+// phase starts
+const SLOW_RINGS: &str = "slow rings";
+
+// Below two thirds of its health the boss alternates tight rings with wide spirals
+fn second_phase() -> BossPhase {
+    let attacks = Attacks::new(
+        vec![
+            AttackPattern::new(
+                BulletGroup {
+                    formation: Formation::circular(false, 10.0),
+                    number: 30,
+                    collider_type: ColliderType::EnemyBullet,
+                    bullet: Bullet::new(5.0, 20.0),
+                    tags: Tags::new(&[SLOW_RINGS]),
+                    ..default()
+                },
+                Movement::new(
+                    Vec2::ZERO,
+                    Vec2::ZERO,
+                    true,
+                    Vec2::new(0.0, 4.0),
+                    Vec2::ZERO,
+                ),
+                Timer::new(Duration::from_millis(250), TimerMode::Once),
+                Some(Timer::new(Duration::from_millis(8), TimerMode::Once)),
+            ),
+            AttackPattern::new(
+                BulletGroup {
+                    formation: Formation::circular(true, 30.0),
+                    number: 120,
+                    collider_type: ColliderType::EnemyBullet,
+                    bullet: Bullet::new(5.0, 20.0),
+                    ..default()
+                },
+                Movement::new(
+                    Vec2::ZERO,
+                    Vec2::ZERO,
+                    true,
+                    Vec2::new(0.0, 9.0),
+                    Vec2::new(0.0, 1.0),
+                ),
+                Timer::new(Duration::from_millis(2500), TimerMode::Once),
+                Some(Timer::new(Duration::from_millis(15), TimerMode::Once)),
+            ),
+        ],
+        Timer::new(Duration::from_secs(12), TimerMode::Once),
+    );
+    BossPhase {
+        threshold: 0.66,
+        attacks,
+        tint: Color::rgb(0.8, 0.8, 1.0),
+        speed: 1.3,
+        retarget: None,
+    }
 }
 
+// Below a third of its health the boss spins much faster and never lets up
+fn final_phase() -> BossPhase {
+    let attacks = Attacks::new(
+        vec![AttackPattern::new(
+            BulletGroup {
+                formation: Formation::circular(true, 20.0),
+                number: 200,
+                collider_type: ColliderType::EnemyBullet,
+                bullet: Bullet::new(5.0, 20.0),
+                ..default()
+            },
+            Movement::new(
+                Vec2::ZERO,
+                Vec2::ZERO,
+                true,
+                Vec2::new(0.0, 10.0),
+                Vec2::ZERO,
+            ),
+            Timer::new(Duration::from_millis(1500), TimerMode::Once),
+            Some(Timer::new(Duration::from_millis(15), TimerMode::Once)),
+        )],
+        Timer::new(Duration::from_secs(15), TimerMode::Once),
+    );
+    BossPhase {
+        threshold: 0.33,
+        attacks,
+        tint: Color::rgb(1.0, 0.45, 0.45),
+        speed: 2.0,
+        // Whatever is left of the slow rings turns on the player all at once
+        retarget: Some(RetargetBullets {
+            filter: BulletFilter::Tagged(SLOW_RINGS),
+            new_movement: Movement::new(
+                Vec2::ZERO,
+                Vec2::ZERO,
+                true,
+                Vec2::new(0.0, 3.0),
+                Vec2::new(0.0, 6.0),
+            ),
+            aim: true,
+        }),
+    }
+}
+
+// Divers, turrets and enemies flying in formation steer themselves, so they are left out
+#[allow(clippy::type_complexity)]
 pub fn enemy_movement(
     time: Res<GameplayTime>,
-    mut enemies: Query<&mut Movement, (With<Enemy>, Without<Boss>)>,
+    mut enemies: Query<
+        &mut Movement,
+        (
+            With<Enemy>,
+            Without<Boss>,
+            Without<MidBoss>,
+            Without<Diver>,
+            Without<Turret>,
+            Without<FlightPath>,
+        ),
+    >,
 ) {
     use std::f32::consts::TAU;
     let amplitude = 4.0;
@@ -169,13 +500,61 @@ pub fn enemy_movement(
     for mut mov in enemies.iter_mut() {
         phase_difference += 4.0;
         mov.v_local.x =
-            amplitude * (TAU * frequency * time.elapsed_secs() + phase_difference).sin();
+            amplitude * (TAU * frequency * time.level_elapsed_secs() + phase_difference).sin();
     }
 }
 
-pub fn boss_movement(mut boss: Query<(&mut Velocity, &mut Movement), With<Boss>>) {
-    let Ok((mut velocity, mut movement)) = boss.get_single_mut() else { return; };
-    movement.v_local = Vec2::new(0.0, 5.0);
-    velocity.angvel = 2.0;
+pub fn boss_movement(
+    mut boss: Query<(&mut Velocity, &mut Movement, Option<&BossPhases>), With<Boss>>,
+) {
+    let Ok((mut velocity, mut movement, phases)) = boss.get_single_mut() else { return; };
+    let speed = phases.map_or(1.0, BossPhases::speed);
+    movement.v_local = Vec2::new(0.0, 5.0 * speed);
+    velocity.angvel = 2.0 * speed;
 }
 
+// Attacks of the boss once the time limit runs out: fast rings on top of dense spirals, with
+// barely any break in between
+fn enraged_attacks() -> Attacks {
+    Attacks::new(
+        vec![
+            AttackPattern::new(
+                BulletGroup {
+                    formation: Formation::circular(true, 20.0),
+                    number: 240,
+                    collider_type: ColliderType::EnemyBullet,
+                    bullet: Bullet::new(5.0, 20.0),
+                    ..default()
+                },
+                Movement::new(
+                    Vec2::ZERO,
+                    Vec2::ZERO,
+                    true,
+                    Vec2::new(0.0, 12.0),
+                    Vec2::ZERO,
+                ),
+                Timer::new(Duration::from_millis(800), TimerMode::Once),
+                Some(Timer::new(Duration::from_millis(10), TimerMode::Once)),
+            ),
+            AttackPattern::new(
+                BulletGroup {
+                    formation: Formation::circular(false, 10.0),
+                    number: 40,
+                    collider_type: ColliderType::EnemyBullet,
+                    bullet: Bullet::new(5.0, 20.0),
+                    ..default()
+                },
+                Movement::new(
+                    Vec2::ZERO,
+                    Vec2::ZERO,
+                    true,
+                    Vec2::new(0.0, 16.0),
+                    Vec2::ZERO,
+                ),
+                Timer::new(Duration::from_millis(200), TimerMode::Once),
+                Some(Timer::new(Duration::from_millis(5), TimerMode::Once)),
+            ),
+        ],
+        Timer::new(Duration::from_secs(6), TimerMode::Once),
+    )
+}
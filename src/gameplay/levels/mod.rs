@@ -1,17 +1,31 @@
+pub mod ambient;
+pub mod endless;
 pub mod level1;
 pub mod level2;
 pub mod level3;
+pub mod palette;
+pub mod practice;
 use std::time::Duration;
 
-use crate::{gameplay::{bullet::Bullet, enemy::Boss, player::{EnemiesKilled, Player}, shared::Movement, collectables::{spawn_collectables, magnetise_all}, GameplayState}, GameState};
+use crate::{DespawnOnExitExt, gameplay::{bestiary::EnemyKind, bullet::Bullet, enemy::{self, Attacks, Boss, Enemy, PhaseChangeEvent}, player::{EnemiesKilled, Graze, Lives, Player, Power, RunEnd, Score}, shared::{Counter, Easing, ExtraSpriteInfo, Movement, MovePath, Name}, collectables::{spawn_collectables, magnetise_all, Drops}, GameplayState, GameTime, GameplayTime}, GameState};
+use crate::ui::fonts::Fonts;
+use crate::versus::Versus;
+use serde::{Deserialize, Serialize};
 
 use super::{
+    bullet::{AttackPattern, BulletGroup},
     collisions::{self, ColliderType},
-    loading::BackgroundHandle,
-    shared::{physics::*, METRE},
+    enemy::{Diver, Turret},
+    loading::{Atlases, BackgroundHandle},
+    rng::RunRng,
+    shared::{physics::*, Formation, METRE},
+    wave::{self, WaveSpec, Waves},
+    ui::{self, create_health_bar, Link, ObjectType},
 };
 use bevy::prelude::*;
 use bevy::sprite::ColorMesh2dBundle;
+use bevy::window::PrimaryWindow;
+use rand::Rng;
 
 // Level border
 #[derive(Component)]
@@ -22,7 +36,9 @@ pub enum Wall {
     Bottom,
 }
 
-#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Hash, States)]
+#[derive(
+    Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Hash, States, Serialize, Deserialize,
+)]
 pub enum CurrentLevel {
     #[default]
     None,
@@ -32,51 +48,496 @@ pub enum CurrentLevel {
     Endless,
 }
 
+impl CurrentLevel {
+    // Level which follows this one in story mode. None after the last level.
+    pub fn next_stage(&self) -> Option<CurrentLevel> {
+        match self {
+            CurrentLevel::One => Some(CurrentLevel::Two),
+            CurrentLevel::Two => Some(CurrentLevel::Three),
+            CurrentLevel::Three | CurrentLevel::None | CurrentLevel::Endless => None,
+        }
+    }
+
+    // Target clear time of the level, if it has one
+    pub fn par_time(&self) -> Option<Duration> {
+        match self {
+            CurrentLevel::One => Some(level1::PAR_TIME),
+            CurrentLevel::Two => Some(level2::PAR_TIME),
+            CurrentLevel::Three => Some(level3::PAR_TIME),
+            CurrentLevel::None | CurrentLevel::Endless => None,
+        }
+    }
+
+    // What happens over the course of the level, before its boss is beaten
+    pub fn timeline(&self) -> &'static [StageEvent] {
+        match self {
+            CurrentLevel::One => level1::TIMELINE,
+            CurrentLevel::Two => level2::TIMELINE,
+            CurrentLevel::Three => level3::TIMELINE,
+            CurrentLevel::None | CurrentLevel::Endless => &[],
+        }
+    }
+}
+
+// Smaller boss which turns up partway through a level. Normal enemies stop spawning while
+// it is alive, and it always drops a good amount of power.
+#[derive(Component)]
+pub struct MidBoss;
+
+// Health bar of the mid-boss, shown under the boss' health bar
+#[derive(Component)]
+pub struct MidBossHealthBar;
+impl ui::ProgressBar for MidBossHealthBar {}
+
+const MID_BOSS_DROPS: Drops = Drops { score: 10, power: 10 };
+// Mid-bosses glide down this far to where they hover, slowing to a stop
+const MID_BOSS_ENTRY_DROP: f32 = 120.0;
+const MID_BOSS_ENTRY_SECS: f32 = 1.5;
+
+// Step of a level's timeline. The steps are gone through one after the other, so a level is
+// scripted as a list of them, e.g. drones for 20 seconds, then a break once they are all gone,
+// then the mid-boss.
+#[derive(Debug, Clone, Copy)]
+pub enum StageEvent {
+    // Normal enemies spawn for this long
+    Spawn(Duration),
+    // Nothing new spawns for this long
+    Wait(Duration),
+    // Nothing new spawns until every enemy other than the boss is gone
+    WaitForClear,
+    // Bring in the mid-boss
+    MidBoss,
+    // Send in a group of enemies in formation
+    Wave(&'static WaveSpec),
+}
+
+// Where the current level is in its timeline. Set up again whenever the level changes. Once
+// the timeline runs out, normal enemies keep spawning until the level is over.
+#[derive(Resource, Debug, Default)]
+pub struct StageTimeline {
+    events: &'static [StageEvent],
+    step: usize,
+    // Time spent on the current step
+    elapsed: Duration,
+    // Set by a MidBoss step, until the level has spawned it
+    mid_boss_due: bool,
+    // Waves whose step has come up, until they are spawned
+    waves_due: Vec<&'static WaveSpec>,
+}
+
+impl StageTimeline {
+    fn new(events: &'static [StageEvent]) -> Self {
+        Self {
+            events,
+            ..default()
+        }
+    }
+
+    fn current(&self) -> Option<StageEvent> {
+        self.events.get(self.step).copied()
+    }
+
+    // Whether normal enemies should be spawning at the moment
+    pub fn spawning(&self) -> bool {
+        matches!(self.current(), None | Some(StageEvent::Spawn(_)))
+    }
+
+    // Returns true once, on the frame that the mid-boss should be spawned
+    pub fn take_mid_boss(&mut self) -> bool {
+        std::mem::take(&mut self.mid_boss_due)
+    }
+}
+
+pub fn start_stage_timeline(
+    current_level: Res<State<CurrentLevel>>,
+    mut timeline: ResMut<StageTimeline>,
+) {
+    *timeline = StageTimeline::new(current_level.get().timeline());
+}
+
+// Go through the steps of the current level's timeline. The levels' spawn systems run after
+// this and only act on what it has decided, so every level is run the same way.
+pub fn run_stage_timeline(
+    time: Res<GameTime>,
+    mut timeline: ResMut<StageTimeline>,
+    enemies: Query<(), (With<Enemy>, Without<Boss>)>,
+) {
+    timeline.elapsed += time.delta();
+    // Several steps can be over on the same frame, e.g. a mid-boss and the wait after it
+    while let Some(event) = timeline.current() {
+        let done = match event {
+            StageEvent::Spawn(duration) | StageEvent::Wait(duration) => {
+                timeline.elapsed >= duration
+            }
+            // A mid-boss that hasn't been spawned yet counts as well
+            StageEvent::WaitForClear => enemies.is_empty() && !timeline.mid_boss_due,
+            StageEvent::MidBoss => {
+                timeline.mid_boss_due = true;
+                true
+            }
+            StageEvent::Wave(spec) => {
+                timeline.waves_due.push(spec);
+                true
+            }
+        };
+        if !done {
+            break;
+        }
+        timeline.step += 1;
+        timeline.elapsed = Duration::ZERO;
+    }
+}
+
+// Spawn the waves the timeline has brought up. Waves are the same in every level, so they are
+// spawned here rather than by the levels' own spawn systems.
+pub fn spawn_stage_waves(
+    mut commands: Commands,
+    mut timeline: ResMut<StageTimeline>,
+    mut waves: ResMut<Waves>,
+    playfield: Res<Playfield>,
+    atlases: Res<Atlases<'static>>,
+    mut rng: ResMut<RunRng>,
+) {
+    for spec in std::mem::take(&mut timeline.waves_due) {
+        wave::spawn_wave(&mut commands, spec, &mut waves, &playfield, &atlases, &mut rng);
+    }
+}
+
+// Run condition for the normal enemy spawns of the story levels
+pub fn stage_spawning(timeline: Res<StageTimeline>) -> bool {
+    timeline.spawning()
+}
+
+// Share of the drones in stages two and three which are volatile instead
+const VOLATILE_CHANCE: f64 = 0.2;
+
+// Kind of a new drone in the stages where some of them blow up when destroyed
+pub fn roll_drone_kind(rng: &mut RunRng) -> EnemyKind {
+    if rng.gen_bool(VOLATILE_CHANCE) {
+        EnemyKind::Volatile
+    } else {
+        EnemyKind::Drone
+    }
+}
+
+// Share of the enemies in a stage which are one of its archetypes instead of a drone
+const ARCHETYPE_CHANCE: f64 = 0.25;
+// Seconds a turret stays in place before it leaves
+const TURRET_STAY_SECS: f32 = 8.0;
+
+// Kind of a new enemy in a stage which has some of the archetypes in with its drones, or None
+// for a drone
+pub fn roll_archetype(rng: &mut RunRng, archetypes: &[EnemyKind]) -> Option<EnemyKind> {
+    if archetypes.is_empty() || !rng.gen_bool(ARCHETYPE_CHANCE) {
+        return None;
+    }
+    Some(archetypes[rng.gen_range(0..archetypes.len())])
+}
+
+// Spawn a diver, turret or carrier along with the components that make up its behaviour
+pub fn spawn_archetype(
+    commands: &mut Commands,
+    atlases: &Atlases<'static>,
+    playfield: &Playfield,
+    rng: &mut RunRng,
+    kind: EnemyKind,
+    spawn_point: Transform,
+) -> Entity {
+    match kind {
+        EnemyKind::Turret => {
+            let sprite = enemy::kind_sprite(atlases, kind, 28.0);
+            let enemy = enemy::spawn_enemy(commands, kind, spawn_point, turret_attacks(), sprite);
+            let park_y = playfield.max.y - rng.gen_range(120.0..220.0);
+            commands.entity(enemy).insert(Turret::new(park_y, TURRET_STAY_SECS));
+            enemy
+        }
+        EnemyKind::Carrier => {
+            let sprite = enemy::kind_sprite(atlases, kind, 34.0);
+            let enemy = enemy::spawn_enemy(commands, kind, spawn_point, carrier_attacks(), sprite);
+            commands.entity(enemy).insert((
+                enemy::CARRIER,
+                Movement::relative(Vec2::new(0.0, -1.5), Vec2::ZERO),
+            ));
+            enemy
+        }
+        _ => {
+            let sprite = enemy::kind_sprite(atlases, EnemyKind::Diver, 18.0);
+            let enemy = enemy::spawn_enemy(commands, kind, spawn_point, Attacks::none(), sprite);
+            commands.entity(enemy).insert(Diver::new(1.0, 12.0, 14.0));
+            enemy
+        }
+    }
+}
+
+// Short streams fired once the turret has stopped. They lead the player, so that they can't be
+// dodged just by sliding along.
+fn turret_attacks() -> Attacks {
+    Attacks::new(
+        vec![AttackPattern::new(
+            BulletGroup {
+                formation: Formation::predictive(0.0),
+                number: 5,
+                collider_type: ColliderType::EnemyBullet,
+                bullet: Bullet::new(5.0, 5.0),
+                ..default()
+            },
+            Movement::new(Vec2::ZERO, Vec2::ZERO, true, Vec2::new(0.0, 12.0), Vec2::ZERO),
+            Timer::new(Duration::from_millis(1600), TimerMode::Once),
+            Some(Timer::new(Duration::from_millis(80), TimerMode::Once)),
+        )],
+        Timer::new(Duration::from_secs(10), TimerMode::Once),
+    )
+}
+
+// Single aimed shots, for enemies flying in formation. A whole formation firing at once is
+// already a lot to dodge.
+pub fn formation_attacks() -> Attacks {
+    Attacks::new(
+        vec![AttackPattern::new(
+            BulletGroup {
+                formation: Formation::aimed(0.0),
+                number: 1,
+                collider_type: ColliderType::EnemyBullet,
+                bullet: Bullet::new(5.0, 5.0),
+                ..default()
+            },
+            Movement::new(Vec2::ZERO, Vec2::ZERO, true, Vec2::new(0.0, 8.0), Vec2::ZERO),
+            Timer::new(Duration::from_millis(2500), TimerMode::Once),
+            None,
+        )],
+        Timer::new(Duration::from_secs(10), TimerMode::Once),
+    )
+}
+
+// Slow rings, to keep the player from sitting under the carrier
+fn carrier_attacks() -> Attacks {
+    Attacks::new(
+        vec![AttackPattern::new(
+            BulletGroup {
+                formation: Formation::circular(false, 20.0),
+                number: 12,
+                collider_type: ColliderType::EnemyBullet,
+                bullet: Bullet::new(5.0, 5.0),
+                ..default()
+            },
+            Movement::new(Vec2::ZERO, Vec2::ZERO, true, Vec2::new(0.0, 3.0), Vec2::ZERO),
+            Timer::new(Duration::from_millis(2000), TimerMode::Once),
+            None,
+        )],
+        Timer::new(Duration::from_secs(10), TimerMode::Once),
+    )
+}
+
+// Spawn a mid-boss along with its health bar. Used by the levels once their schedule is due.
+pub fn spawn_mid_boss<T: ExtraSpriteInfo>(
+    commands: &mut Commands,
+    fonts: &Fonts,
+    kind: EnemyKind,
+    spawn_point: Transform,
+    attacks: Attacks,
+    sprite: T,
+) {
+    let health_bar = create_health_bar::<MidBossHealthBar>(
+        commands,
+        fonts,
+        Name::from(kind.name()),
+        ObjectType::MidBoss,
+        MidBossHealthBar,
+    );
+    let mut entry_point = spawn_point;
+    entry_point.translation.y += MID_BOSS_ENTRY_DROP;
+    let entry = vec![Vec2::ZERO, Vec2::new(0.0, -MID_BOSS_ENTRY_DROP)];
+    let mid_boss = enemy::spawn_enemy(commands, kind, entry_point, attacks, sprite);
+    commands.entity(mid_boss).insert((
+        MidBoss,
+        // Hovers in place at the spawn point once it has come in, rather than flying down the
+        // screen like other enemies
+        Movement::along(MovePath::waypoints(entry, MID_BOSS_ENTRY_SECS, Easing::Out)),
+        MID_BOSS_DROPS,
+        Link(health_bar),
+    ));
+}
+
+// Which way of playing was picked from the main menu
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+    // Levels One to Three, each ending with a boss
+    #[default]
+    Story,
+    // A single level which keeps getting harder until the player dies
+    Endless,
+    // A single stage of the story, set up on the practice menu
+    Practice,
+}
+
+// Points awarded for every second that a level is cleared under its par time
+const TIME_BONUS_PER_SECOND: u64 = 100;
+// Points awarded for clearing a level without taking damage
+const NO_MISS_BONUS: u64 = 5000;
+// Points awarded for clearing a level without using a special
+const NO_BOMB_BONUS: u64 = 3000;
+// Points awarded for every bullet grazed during a level
+const GRAZE_BONUS: u64 = 10;
+// Points awarded for every life the player has left when clearing a level
+const LIFE_BONUS: u64 = 2000;
+
+// Whether the player has taken damage or used a special during the current level.
+// Reset whenever a new level starts.
+#[derive(Resource, Debug, Default)]
+pub struct LevelConduct {
+    pub missed: bool,
+    pub bombed: bool,
+    // Graze count when the level started
+    pub graze_at_start: u32,
+}
+
+// Bonus awarded for clearing a level, kept for the results screen.
+#[derive(Debug, Clone)]
+pub struct StageBonus {
+    pub level: CurrentLevel,
+    pub time: Duration,
+    pub par_time: Duration,
+    pub time_bonus: u64,
+    pub no_miss_bonus: u64,
+    pub no_bomb_bonus: u64,
+    pub graze_bonus: u64,
+    pub lives_bonus: u64,
+}
+
+impl StageBonus {
+    pub fn total(&self) -> u64 {
+        self.time_bonus
+            + self.no_miss_bonus
+            + self.no_bomb_bonus
+            + self.graze_bonus
+            + self.lives_bonus
+    }
+}
+
+// All stage bonuses awarded during the current run
+#[derive(Resource, Debug, Default, Deref, DerefMut)]
+pub struct StageBonuses(pub Vec<StageBonus>);
+
 pub struct LevelsPlugin;
 
 impl Plugin for LevelsPlugin {
     fn build(&self, app: &mut App) {
         app.add_state::<CurrentLevel>()
+            .init_resource::<GameMode>()
+            .init_resource::<palette::Palette>()
+            .init_resource::<practice::Practice>()
+            .init_resource::<StageTimeline>()
             .add_systems(OnEnter(CurrentLevel::One),
                 (
                     level1::spawn_boss,
                     reset_enemies_killed,
+                    reset_level_conduct,
                 )
             )
             .add_systems(Update,
                 (
                     level1::enemy_movement,
-                    level1::spawn_enemies,
+                    level1::spawn_enemies.run_if(stage_spawning).run_if(practice::normal_enemies),
+                    level1::spawn_mid_boss.run_if(practice::normal_enemies),
                     level1::boss_movement,
-                ).run_if(in_state(CurrentLevel::One))
+                )
+                    .after(run_stage_timeline)
+                    .run_if(in_state(CurrentLevel::One))
             )
             .add_systems(OnExit(CurrentLevel::One), convert_leftover_bullets)
             .add_systems(OnEnter(CurrentLevel::Two),
-                (level2::spawn_boss, level2::setup_level, reset_enemies_killed, magnetise_all)
+                (level2::spawn_boss, level2::setup_level, reset_enemies_killed, reset_level_conduct, magnetise_all)
             )
             .add_systems(Update,
                 (
-                    level2::spawn_enemies,
+                    level2::spawn_enemies.run_if(stage_spawning).run_if(practice::normal_enemies),
+                    level2::spawn_mid_boss.run_if(practice::normal_enemies),
                     level2::enemy_movement,
                     level2::boss_movement,
-                ).run_if(in_state(CurrentLevel::Two))
+                )
+                    .after(run_stage_timeline)
+                    .run_if(in_state(CurrentLevel::Two))
             )
             .add_systems(OnExit(CurrentLevel::Two), convert_leftover_bullets)
             .add_systems(OnEnter(CurrentLevel::Three),
-                (level3::spawn_boss, level3::setup_level, reset_enemies_killed, magnetise_all)
+                (level3::spawn_boss, level3::setup_level, reset_enemies_killed, reset_level_conduct, magnetise_all)
             )
             .add_systems(Update,
                 (
-                    level3::spawn_enemies,
+                    level3::spawn_enemies.run_if(stage_spawning).run_if(practice::normal_enemies),
+                    level3::spawn_mid_boss.run_if(practice::normal_enemies),
                     level3::enemy_movement,
                     level3::boss_movement,
-                ).run_if(in_state(CurrentLevel::Three))
-            );
+                )
+                    .after(run_stage_timeline)
+                    .run_if(in_state(CurrentLevel::Three))
+            )
+            .add_systems(OnEnter(CurrentLevel::Endless),
+                (endless::setup_level, reset_enemies_killed, reset_level_conduct)
+            )
+            .add_systems(Update,
+                (
+                    endless::update_difficulty,
+                    endless::advance_wave,
+                    endless::expire_wave_banners,
+                    endless::spawn_enemies,
+                    endless::spawn_mini_boss,
+                    level1::enemy_movement,
+                    level1::boss_movement,
+                )
+                    .chain()
+                    .run_if(in_state(CurrentLevel::Endless))
+            )
+            .add_systems(Update,
+                (
+                    ambient::change_ambience.run_if(state_changed::<CurrentLevel>()),
+                    start_stage_timeline.run_if(state_changed::<CurrentLevel>()),
+                    run_stage_timeline,
+                    ambient::emit_ambient_particles,
+                    ambient::move_ambient_particles,
+                )
+                    .chain()
+                    .run_if(in_state(GameplayState::Playing))
+            )
+            .add_systems(Update,
+                spawn_stage_waves
+                    .after(run_stage_timeline)
+                    .run_if(practice::normal_enemies)
+                    .run_if(in_state(GameplayState::Playing))
+            )
+            .add_systems(Update,
+                (
+                    clear_bullets_on_phase_change,
+                    ui::update_health_bar::<MidBossHealthBar, MidBoss>,
+                )
+                    .run_if(in_state(GameplayState::Playing))
+            )
+            .add_systems(Update,
+                (palette::tint_background, palette::tint_enemies)
+                    .run_if(in_state(GameplayState::Playing))
+            )
+            .despawn_on_exit::<ambient::AmbientLayer>(GameplayState::Playing)
+            .despawn_on_exit::<ambient::AmbientParticle>(GameplayState::Playing);
     }
 }
 
-pub fn check_won(bosses: Query<&Boss>, enemies_killed: Query<&EnemiesKilled, With<Player>>) -> bool {
+pub fn check_won(
+    current_level: Res<State<CurrentLevel>>,
+    mode: Res<GameMode>,
+    practice: Res<practice::Practice>,
+    bosses: Query<&Boss>,
+    enemies_killed: Query<&EnemiesKilled, With<Player>>,
+) -> bool {
+    // Endless mode only ends when the player dies, and there is nothing to win before the
+    // first level has started
+    if matches!(current_level.get(), CurrentLevel::Endless | CurrentLevel::None) {
+        return false;
+    }
     if bosses.iter().len() == 0 {
+        // Boss only practice runs have no other enemies to kill
+        if *mode == GameMode::Practice && practice.boss_only {
+            return true;
+        }
         for enemies_killed_instance in enemies_killed.iter() {
             if enemies_killed_instance.get_current_level() >= 15 {
                 return true;
@@ -87,19 +548,48 @@ pub fn check_won(bosses: Query<&Boss>, enemies_killed: Query<&EnemiesKilled, Wit
 }
 
 pub fn convert_leftover_bullets(bullets: Query<(Entity, &ColliderType, &Transform), With<Bullet>>, mut commands: Commands, asset_server: Res<AssetServer>) {
+    convert_enemy_bullets(&bullets, &mut commands, &asset_server);
+}
+
+// Give the player a clean start on every new boss phase
+pub fn clear_bullets_on_phase_change(
+    mut phase_ev: EventReader<PhaseChangeEvent>,
+    bullets: Query<(Entity, &ColliderType, &Transform), With<Bullet>>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    if phase_ev.iter().count() == 0 {
+        return;
+    }
+    convert_enemy_bullets(&bullets, &mut commands, &asset_server);
+}
+
+// Turn every enemy bullet into a score collectable
+fn convert_enemy_bullets(bullets: &Query<(Entity, &ColliderType, &Transform), With<Bullet>>, commands: &mut Commands, asset_server: &AssetServer) {
     for (bullet, kind, transform) in bullets.iter() {
         if *kind == ColliderType::EnemyBullet {
             if let Some(entity) = commands.get_entity(bullet) {
                 entity.despawn_recursive();
             }
-            spawn_collectables(&mut commands, 1, 0, transform, &asset_server, Movement::absolute(Vec2::new(0.0, -4.0), Vec2::ZERO));
+            spawn_collectables(commands, 1, 0, transform, asset_server, Movement::absolute(Vec2::new(0.0, -4.0), Vec2::ZERO));
         }
     }
 }
 
-pub fn setup_levels(mut commands: Commands, mut next_state: ResMut<NextState<CurrentLevel>>) {
+pub fn setup_levels(
+    mut commands: Commands,
+    mode: Res<GameMode>,
+    practice: Res<practice::Practice>,
+    mut next_state: ResMut<NextState<CurrentLevel>>,
+) {
     commands.insert_resource(SpawnEnemyTimer::default());
-    next_state.set(CurrentLevel::One);
+    commands.insert_resource(StageBonuses::default());
+    commands.insert_resource(LevelConduct::default());
+    match *mode {
+        GameMode::Story => next_state.set(CurrentLevel::One),
+        GameMode::Endless => next_state.set(CurrentLevel::Endless),
+        GameMode::Practice => next_state.set(practice.level),
+    }
 }
 
 pub fn remove_level(mut next_state: ResMut<NextState<CurrentLevel>>) {
@@ -125,7 +615,7 @@ pub fn setup_background(
     mut commands: Commands,
     bg_handle: Res<BackgroundHandle>,
     images: Res<Assets<Image>>,
-    windows: Query<&Window>,
+    windows: Query<&Window, With<PrimaryWindow>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     mut meshes: ResMut<Assets<Mesh>>,
 ) {
@@ -171,8 +661,8 @@ pub fn setup_background(
 
 pub fn pan_background(
     mut background: Query<(&LevelBackground, &mut Transform)>,
-    windows: Query<&Window>,
-    time: Res<Time>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    time: Res<GameTime>,
 ) {
     // First we get the coordinates of the bottom of the screen.
     // Then, every background part is translated down by pan_speed * dt.
@@ -192,7 +682,36 @@ pub fn pan_background(
     }
 }
 
-pub fn create_playfield(mut commands: Commands, windows: Query<&Window>) {
+// Area of the world inside the walls, centred on the origin
+#[derive(Resource, Debug, Clone, Copy, Deref)]
+pub struct Playfield(pub Rect);
+
+impl Playfield {
+    // Random point near the top of the playfield for an enemy to spawn at. `spread` is the
+    // fraction of the playfield's width to pick from, centred on the middle, and `margin`
+    // keeps the point at least that far away from the side walls.
+    pub fn spawn_point(
+        &self,
+        rng: &mut RunRng,
+        spread: f32,
+        margin: f32,
+        below_top: f32,
+    ) -> Vec2 {
+        let half_width = self.half_size().x;
+        let range = (half_width * spread).min(half_width - margin);
+        let x = if range > 0.0 {
+            rng.gen_range(-range..range)
+        } else {
+            0.0
+        };
+        Vec2::new(self.center().x + x, self.max.y - below_top)
+    }
+}
+
+pub fn create_playfield(
+    mut commands: Commands,
+    windows: Query<&Window, With<PrimaryWindow>>,
+) {
     let window = windows.get_single().unwrap();
 
     // Get coordinates of window edges so that the walls can be spawned there.
@@ -201,6 +720,7 @@ pub fn create_playfield(mut commands: Commands, windows: Query<&Window>) {
     let vertical = window.height() / 2.;
     // horizontal = right coordinates, -horizontal = left coordinates
     let horizontal = window.width() / 2.;
+    commands.insert_resource(Playfield(Rect::new(-horizontal, -vertical, horizontal, vertical)));
 
     // Arbitrary value for the relative "height" of a wall
     let cross_axis = 4.0 * METRE;
@@ -269,19 +789,80 @@ pub fn create_playfield(mut commands: Commands, windows: Query<&Window>) {
     }
 }
 
-pub fn advance_level(current_level: Res<State<CurrentLevel>>, mut next_level: ResMut<NextState<CurrentLevel>>, mut next_gamestate: ResMut<NextState<GameState>>, mut next_gameplaystate: ResMut<NextState<GameplayState>>) {
+// Tally up the stage bonus of the level that was just cleared. Between levels the results are
+// shown in the stage clear screen, which then moves on to the next level. Clearing the last
+// level wins the game straight away.
+#[allow(clippy::too_many_arguments)]
+pub fn advance_level(
+    mut commands: Commands,
+    current_level: Res<State<CurrentLevel>>,
+    mut next_level: ResMut<NextState<CurrentLevel>>,
+    mut next_gamestate: ResMut<NextState<GameState>>,
+    mut next_gameplaystate: ResMut<NextState<GameplayState>>,
+    mut g_time: ResMut<GameplayTime>,
+    mut bonuses: ResMut<StageBonuses>,
+    conduct: Res<LevelConduct>,
+    mut player: Query<(&mut Score, &Graze, &Lives, &Power, &EnemiesKilled), With<Player>>,
+    versus: Option<Res<Versus>>,
+    mode: Res<GameMode>,
+) {
+    // Record how long the level that was just cleared took
+    let level_time = g_time.level_elapsed();
+    g_time.split(*current_level.get());
+
+    // Award points for every second under the par time of the level, as well as for
+    // not getting hit and not using any specials, for grazing and for lives left.
+    if let (Some(par_time), Ok((mut score, graze, lives, ..))) =
+        (current_level.get().par_time(), player.get_single_mut())
+    {
+        let bonus = StageBonus {
+            level: *current_level.get(),
+            time: level_time,
+            par_time,
+            time_bonus: par_time.saturating_sub(level_time).as_secs() * TIME_BONUS_PER_SECOND,
+            no_miss_bonus: if conduct.missed { 0 } else { NO_MISS_BONUS },
+            no_bomb_bonus: if conduct.bombed { 0 } else { NO_BOMB_BONUS },
+            graze_bonus: graze.get().saturating_sub(conduct.graze_at_start) as u64 * GRAZE_BONUS,
+            lives_bonus: lives.get() as u64 * LIFE_BONUS,
+        };
+        score.add(bonus.total());
+        bonuses.push(bonus);
+    }
+
     match current_level.get() {
-        CurrentLevel::One => next_level.set(CurrentLevel::Two),
-        CurrentLevel::Two => next_level.set(CurrentLevel::Three),
-        CurrentLevel::Three => {
+        // A practice run is over once its stage is cleared
+        CurrentLevel::One | CurrentLevel::Two if *mode != GameMode::Practice => {
+            next_gamestate.set(GameState::StageClear)
+        }
+        CurrentLevel::One | CurrentLevel::Two | CurrentLevel::Three => {
             next_level.set(CurrentLevel::None);
             next_gameplaystate.set(GameplayState::None);
-            next_gamestate.set(GameState::GameWon);
+            match player.get_single() {
+                Ok((score, graze, _, power, enemies_killed)) => commands
+                    .insert_resource(RunEnd::new(true, (score, power, enemies_killed, graze))),
+                Err(_) => commands.remove_resource::<RunEnd>(),
+            }
+            // Versus runs end on the comparison between the players instead
+            if versus.is_some() {
+                next_gamestate.set(GameState::VersusResults);
+            } else {
+                next_gamestate.set(GameState::GameWon);
+            }
         },
         CurrentLevel::None | CurrentLevel::Endless => {}
     }
 }
 
+fn reset_level_conduct(
+    mut conduct: ResMut<LevelConduct>,
+    graze: Query<&Graze, With<Player>>,
+) {
+    *conduct = LevelConduct {
+        graze_at_start: graze.get_single().map_or(0, |graze| graze.get()),
+        ..default()
+    };
+}
+
 fn reset_enemies_killed(mut enemies_killed: Query<&mut EnemiesKilled, With<Player>>) {
     for mut e in enemies_killed.iter_mut() {
         e.reset_current();
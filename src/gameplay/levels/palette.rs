@@ -0,0 +1,80 @@
+// Colour variants of the levels. The background and enemies are tinted to give a different
+// time of day, without needing any new art.
+use super::LevelBackground;
+use crate::gameplay::enemy::Enemy;
+use bevy::prelude::*;
+
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Palette {
+    #[default]
+    Day,
+    Dusk,
+    Night,
+}
+
+impl Palette {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Palette::Day => "Day",
+            Palette::Dusk => "Dusk",
+            Palette::Night => "Night",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Palette::Day => Palette::Dusk,
+            Palette::Dusk => Palette::Night,
+            Palette::Night => Palette::Day,
+        }
+    }
+
+    // Colour that the background and enemy sprites are multiplied by
+    fn tint(&self) -> Color {
+        match self {
+            Palette::Day => Color::WHITE,
+            Palette::Dusk => Color::rgb(1.0, 0.72, 0.55),
+            Palette::Night => Color::rgb(0.45, 0.5, 0.85),
+        }
+    }
+}
+
+pub fn tinted(colour: Color, tint: Color) -> Color {
+    Color::rgba(
+        colour.r() * tint.r(),
+        colour.g() * tint.g(),
+        colour.b() * tint.b(),
+        colour.a(),
+    )
+}
+
+// Tint the background whenever it gets a new material, e.g. when the level changes
+#[allow(clippy::type_complexity)]
+pub fn tint_background(
+    palette: Res<Palette>,
+    backgrounds: Query<&Handle<ColorMaterial>, (With<LevelBackground>, Changed<Handle<ColorMaterial>>)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for handle in backgrounds.iter() {
+        if let Some(material) = materials.get_mut(handle) {
+            material.color = palette.tint();
+        }
+    }
+}
+
+pub fn tint_enemies(
+    palette: Res<Palette>,
+    mut enemies: Query<(Option<&mut TextureAtlasSprite>, Option<&mut Sprite>), Added<Enemy>>,
+) {
+    if *palette == Palette::Day {
+        return;
+    }
+    for (atlas_sprite, sprite) in enemies.iter_mut() {
+        if let Some(mut atlas_sprite) = atlas_sprite {
+            atlas_sprite.color = tinted(atlas_sprite.color, palette.tint());
+        }
+        if let Some(mut sprite) = sprite {
+            sprite.color = tinted(sprite.color, palette.tint());
+        }
+    }
+}
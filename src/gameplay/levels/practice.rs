@@ -0,0 +1,94 @@
+// Practice runs, for playing a single stage of the story, or only its boss, with whatever
+// power and specials the player wants to try it with. A practice run ends once its stage is
+// cleared, and nothing from it is kept in the high scores or the personal best.
+use super::{CurrentLevel, GameMode};
+use crate::gameplay::{
+    player::{Player, Power, Specials},
+    run_reset::{MAX_POWER, STARTING_POWER, STARTING_SPECIALS},
+    shared::Counter,
+};
+use bevy::prelude::*;
+
+// Power added with every press of the power button, going back to none past the maximum
+const POWER_STEP: u16 = 100;
+// Most specials a practice run can be started with
+const MAX_SPECIALS: u8 = 9;
+
+// How the next practice run is played, picked on the practice menu
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct Practice {
+    pub level: CurrentLevel,
+    // Only the boss, without the normal enemies and the mid-boss before it
+    pub boss_only: bool,
+    pub power: u16,
+    pub specials: u8,
+}
+
+impl Default for Practice {
+    fn default() -> Self {
+        Self {
+            level: CurrentLevel::One,
+            boss_only: false,
+            power: STARTING_POWER,
+            specials: STARTING_SPECIALS,
+        }
+    }
+}
+
+impl Practice {
+    pub fn next_level(&mut self) {
+        self.level = match self.level {
+            CurrentLevel::One => CurrentLevel::Two,
+            CurrentLevel::Two => CurrentLevel::Three,
+            _ => CurrentLevel::One,
+        };
+    }
+
+    pub fn toggle_boss_only(&mut self) {
+        self.boss_only = !self.boss_only;
+    }
+
+    pub fn next_power(&mut self) {
+        self.power = if self.power >= MAX_POWER {
+            0
+        } else {
+            (self.power + POWER_STEP).min(MAX_POWER)
+        };
+    }
+
+    pub fn next_specials(&mut self) {
+        self.specials = (self.specials + 1) % (MAX_SPECIALS + 1);
+    }
+}
+
+// Run condition for the normal enemies and mid-bosses, which are left out of boss only
+// practice runs
+pub fn normal_enemies(mode: Res<GameMode>, practice: Res<Practice>) -> bool {
+    !(*mode == GameMode::Practice && practice.boss_only)
+}
+
+// Run condition for anything which is only for practice runs
+pub fn practice(mode: Res<GameMode>) -> bool {
+    *mode == GameMode::Practice
+}
+
+// Run condition for anything which is only for full runs, like moving on to the next stage
+pub fn not_practice(mode: Res<GameMode>) -> bool {
+    *mode != GameMode::Practice
+}
+
+// Give the player the power and specials picked for the practice run as they are spawned.
+// Runs before the player's loadout is saved, so that it is kept through lost lives.
+pub fn apply_practice_start(
+    mode: Res<GameMode>,
+    practice: Res<Practice>,
+    mut player: Query<(&mut Power, &mut Specials), Added<Player>>,
+) {
+    if *mode != GameMode::Practice {
+        return;
+    }
+    for (mut power, mut specials) in player.iter_mut() {
+        power.set(practice.power);
+        specials.set(practice.specials);
+    }
+}
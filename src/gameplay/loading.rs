@@ -13,22 +13,23 @@ pub struct Atlases<'a>(HashMap<&'a str, Handle<TextureAtlas>>);
 // 'static lifetime is required for all the hash tables, as the strings
 // must be available in memory for the entire duration of the game running.
 
+// Define list of real images to be loaded as texture atlases by
+// giving their path relative to the game directory, the size of one texture
+// in (x, y) pixels, the number of columns and number of rows in the texture atlas.
+pub const SPRITE_SHEETS: [(&str, Vec2, usize, usize); 5] = [
+    ("sprites/white-plane3.png", Vec2::new(60.0, 90.0), 8, 1),
+    ("sprites/enemy-projectile.png", Vec2::new(128.0, 128.0), 4, 1),
+    ("sprites/enemy-small.png", Vec2::new(64.0, 64.0), 2, 1),
+    ("sprites/enemy-medium.png", Vec2::new(128.0, 64.0), 2, 1),
+    ("sprites/enemy-big.png", Vec2::new(120.0, 128.0), 2, 1),
+];
+
 pub fn load_texture_atlases(
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
     mut atlas_handles: ResMut<Atlases<'static>>,
     asset_server: Res<AssetServer>,
 ) {
-    // Define list of real images to be loaded as texture atlases by
-    // giving their path relative to the game directory, the size of one texture
-    // in (x, y) pixels, the number of columns and number of rows in the texture atlas.
-    let assets = [
-        ("sprites/white-plane3.png", Vec2::new(60.0, 90.0), 8, 1),
-        ("sprites/enemy-projectile.png",Vec2::new(128.0, 128.0),4,1),
-        ("sprites/enemy-small.png", Vec2::new(64.0, 64.0), 2, 1),
-        ("sprites/enemy-medium.png", Vec2::new(128.0, 64.0), 2, 1),
-        ("sprites/enemy-big.png", Vec2::new(120.0, 128.0), 2, 1),
-    ];
-    for (path, size, columns, rows) in assets {
+    for (path, size, columns, rows) in SPRITE_SHEETS {
         // Load atlas
         let texture_atlas =
             TextureAtlas::from_grid(asset_server.load(path), size, columns, rows, None, None);
@@ -46,16 +47,18 @@ pub fn load_particle_effects(
     mut effects: ResMut<Assets<EffectAsset>>,
     mut effect_handles: ResMut<ParticleEffects<'static>>,
 ) {
-    // Define and add the particle effect for the player rocket booster
+    // Define and add the particle effect for the player rocket booster.
+    // The width and length of the flame are properties, so that they can follow what the
+    // player is doing every frame.
     let mut module = Module::default();
 
-    let init_lifetime = SetAttributeModifier::new(Attribute::LIFETIME, module.lit(1.0));
+    let init_lifetime = SetAttributeModifier::new(Attribute::LIFETIME, module.prop("lifetime"));
     let update_accel = AccelModifier::new(module.lit(Vec3::Y * -8.0));
-    let pos_b_r = module.lit(40.0);
+    let pos_b_r = module.prop("radius");
     let pos_t_r = module.lit(0.0);
     let pos_h = module.lit(50.0);
     let vel_c = module.lit(Vec3::ZERO);
-    let vel_s = module.lit(1.0);
+    let vel_s = module.prop("speed");
     let vel_a = module.lit(Vec3::Z);
 
     let player_booster_effect = effects.add(
@@ -65,6 +68,9 @@ pub fn load_particle_effects(
             "acceleration",
             graph::Value::Vector(Vec3::new(0.0, -3.0, 0.0).into()),
         )
+        .with_property("radius", graph::Value::Scalar(40.0.into()))
+        .with_property("lifetime", graph::Value::Scalar(1.0.into()))
+        .with_property("speed", graph::Value::Scalar(1.0.into()))
         .init(SetPositionCone3dModifier {
             base_radius: pos_b_r,
             top_radius: pos_t_r,
@@ -100,7 +106,49 @@ pub fn load_particle_effects(
             screen_space_size: false,
         }),
     );
+
+    // Small puffs out of the side of the player while strafing. The direction they are blown
+    // in is a property, as the same effect is used on both sides.
+    let mut module = Module::default();
+
+    let init_lifetime = SetAttributeModifier::new(Attribute::LIFETIME, module.lit(0.25));
+    let init_velocity = SetAttributeModifier::new(Attribute::VELOCITY, module.prop("velocity"));
+    let pos_c = module.lit(Vec3::ZERO);
+    let pos_r = module.lit(3.0);
+
+    let side_thruster_effect = effects.add(
+        EffectAsset::new(1024, Spawner::rate(CpuValue::Single(80.0)), module)
+        .with_name("player_side_thruster")
+        .with_property("velocity", graph::Value::Vector(Vec3::ZERO.into()))
+        .init(SetPositionSphereModifier {
+            center: pos_c,
+            radius: pos_r,
+            dimension: ShapeDimension::Volume,
+        })
+        .init(init_velocity)
+        .init(init_lifetime)
+        .render(ColorOverLifetimeModifier {
+            gradient: {
+                let mut gradient = Gradient::new();
+                gradient.add_key(0.0, Vec4::splat(1.0));
+                gradient.add_key(0.5, Vec4::new(0.6, 0.8, 1.0, 0.8));
+                gradient.add_key(1.0, Vec4::splat(0.0));
+                gradient
+            },
+        })
+        .render(SizeOverLifetimeModifier {
+            gradient: {
+                let mut gradient = Gradient::new();
+                gradient.add_key(0.0, Vec2::splat(4.0));
+                gradient.add_key(1.0, Vec2::splat(1.0));
+                gradient
+            },
+            screen_space_size: false,
+        }),
+    );
+
     effect_handles.insert("player_booster", player_booster_effect);
+    effect_handles.insert("player_side_thruster", side_thruster_effect);
 }
 
 // Resource holding a single handle for the loaded background image.
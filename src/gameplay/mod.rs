@@ -1,22 +1,58 @@
+mod afterimage;
+// Public for the bestiary screen in the main menu
+pub mod bestiary;
 mod bullet;
+mod bullet_grid;
+mod chain;
 mod collectables;
 mod collisions;
+mod death_sequence;
+#[cfg(all(feature = "inspector", not(target_arch = "wasm32")))]
+mod debug_window;
+// Public for picking the difficulty in the main menu and showing it on the win screen
+pub mod difficulty;
+// Public for the practice results on the game won screen
+pub mod dps;
 mod enemy;
 mod event;
-mod levels;
+mod explosive;
+mod hit_stop;
+#[cfg(feature = "inspector")]
+mod inspector;
+// Public for the controls menu and the pause key
+pub mod input;
 mod loading;
 // Public for access in the game won screen
+pub mod levels;
 pub mod player;
 pub mod shared;
+mod rank;
+// Public for locking the seed of versus runs
+pub mod rng;
+// Public for retrying a run from the game over screen
+pub mod run_reset;
+mod schedule_dump;
+// Public for picking the scoring mode in the main menu
+pub mod scrape;
+mod shake;
+mod shockwave;
+mod spark;
+mod speedrun;
+pub mod timeline;
+// Public for the shop in the main menu
+pub mod tokens;
+mod tuning;
 mod ui;
+mod wave;
 
-use crate::{despawn_component, gameplay::player::Player};
+use crate::{gameplay::player::Player, DespawnOnExitExt};
 use crate::GameState;
 use bevy::prelude::*;
 use bevy::time::Stopwatch;
+use std::time::Duration;
 use bevy_rapier2d::{
     plugin::{NoUserData, RapierPhysicsPlugin},
-    prelude::{RapierConfiguration, RapierDebugRenderPlugin},
+    prelude::{RapierConfiguration, RapierDebugRenderPlugin, TimestepMode},
 };
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Hash, States)]
@@ -39,28 +75,72 @@ pub struct GameplayPlugin;
 impl Plugin for GameplayPlugin {
     fn build(&self, app: &mut App) {
         if cfg!(debug_assertions) {
-            app.add_plugins(RapierDebugRenderPlugin::default());
+            app.add_plugins(RapierDebugRenderPlugin::default())
+                .add_systems(OnEnter(GameplayState::Playing), ui::spawn_debug_overlay)
+                .add_systems(Update, ui::update_debug_overlay.run_if(in_state(GameplayState::Playing)))
+                // Not in Update, as a schedule is taken out of Schedules while it runs
+                .add_systems(Last, schedule_dump::dump_schedule);
+            // Only built with the `inspector` feature, as it pulls in egui
+            #[cfg(feature = "inspector")]
+            app.add_plugins(inspector::InspectorPlugin);
+            #[cfg(all(feature = "inspector", not(target_arch = "wasm32")))]
+            app.add_plugins(debug_window::DebugWindowPlugin);
         }
 
         app.add_state::<GameplayState>()
             .add_event::<event::TakeDamageEvent>()
             .add_event::<event::DespawnEvent>()
             .add_event::<event::GameOverEvent>()
+            .add_event::<player::LifeLostEvent>()
+            .add_event::<spark::HitSparkEvent>()
+            .add_event::<enemy::PhaseChangeEvent>()
+            .add_event::<event::EnemyKilledEvent>()
+            .add_event::<chain::ChainBroken>()
+            .add_event::<bullet::RetargetBullets>()
             .insert_resource::<loading::Atlases>(Default::default())
             .insert_resource::<loading::ParticleEffects>(Default::default())
             .insert_resource::<loading::BackgroundHandle>(Default::default())
             .insert_resource::<collisions::Collisions>(collisions::Collisions::default())
+            .insert_resource(speedrun::PersonalBest::load())
+            .init_resource::<shake::ScreenShake>()
+            .init_resource::<hit_stop::HitStop>()
+            .init_resource::<bullet::BulletPool>()
+            .init_resource::<bullet_grid::BulletGrid>()
+            .init_resource::<GameTime>()
+            .init_resource::<scrape::ScoringMode>()
+            .init_resource::<difficulty::Difficulty>()
+            .init_resource::<rank::ScoringConfig>()
+            .init_resource::<dps::DpsMeter>()
+            .init_resource::<rng::RunSeed>()
+            .insert_resource(bestiary::Bestiary::load())
+            .insert_resource(tokens::Tokens::load())
+            .add_systems(
+                PreUpdate,
+                (
+                    (hit_stop::update_hit_stop, update_game_time).chain(),
+                    bullet::settle_bullet_pool,
+                ),
+            )
             .add_plugins(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(
                 shared::METRE,
             ))
             .add_plugins(levels::LevelsPlugin)
+            .add_plugins(input::PlayerInputPlugin)
+            .add_plugins(tuning::TuningPlugin)
+            .add_systems(Startup, collisions::validate_collision_matrix)
             // Enter Gameplay
             .add_systems(OnEnter(GameState::Gameplay), setup)
+            // Stop the gameplay clock while the game is paused or between levels
+            .add_systems(OnEnter(GameState::Paused), pause_gameplay_time)
+            .add_systems(OnExit(GameState::Paused), unpause_gameplay_time)
+            .add_systems(OnEnter(GameState::StageClear), pause_gameplay_time)
+            .add_systems(OnExit(GameState::StageClear), unpause_gameplay_time)
             // Begin Loading / Early Load
             .add_systems(OnEnter(GameplayState::Loading),
                 (
                     loading::load_background,
                     loading::load_particle_effects,
+                    levels::ambient::load_ambient_effects,
                     loading::load_texture_atlases,
                     ui::create_stats_list,
                 )
@@ -69,8 +149,10 @@ impl Plugin for GameplayPlugin {
             .add_systems(Update,
                 loading::finish_loading
                     .run_if(
-                        loading::check_background_loaded, //.and_then(loading::check_particles_loaded)
-                                                          //.and_then(loading::check_atlases_loaded)
+                        loading::check_background_loaded
+                            .and_then(crate::ui::fonts::fonts_ready)
+                            //.and_then(loading::check_particles_loaded)
+                            //.and_then(loading::check_atlases_loaded)
                     )
                     .run_if(in_state(GameplayState::Loading))
             )
@@ -82,17 +164,23 @@ impl Plugin for GameplayPlugin {
                     levels::create_playfield,
                     levels::setup_levels,
                     player::spawn_player,
+                    speedrun::spawn_speedrun_timer,
+                    spark::setup_spark_pool,
+                    difficulty::spawn_difficulty_text,
+                    dps::spawn_dps_text,
+                    enemy::reset_attacks,
                 )
             )
             // OnUpdate
             .add_systems(Update,
                 (
-                    back_to_menu,
                     tick_gameplay,
                     collisions::handle_collisions,
                     collectables::manage_lifetimes,
                     collectables::magnetise_to_player,
                     player::spawn_player_bullet,
+                    player::update_option_pods,
+                    player::fire_option_pods,
                     // when the player hits X on the keyboard,
                     // `uses_special` is true, therefore `special_attack` runs
                     // when `special_attack` changes `Specials`, `used_special` is true
@@ -105,33 +193,175 @@ impl Plugin for GameplayPlugin {
                     collectables::magnetise_all.run_if(player::used_special),
                     player::special_attack.run_if(player::uses_special).after(collectables::magnetise_all),
                     player::move_player,
+                    player::update_booster.after(player::move_player),
+                    afterimage::spawn_afterimages.after(player::move_player),
+                    afterimage::fade_afterimages,
+                    player::tick_auto_special,
                     enemy::enemy_attack,
                 )
-                    .run_if(in_state(GameplayState::Playing)),
+                    .run_if(in_state(GameplayState::Playing))
+                    // Ignore input while paused
+                    .run_if(in_state(GameState::Gameplay)),
             )
             .add_systems(Update,
                 (
+                    bullet::steer_homing.before(shared::move_object::<bullet::Bullet>),
                     shared::move_object::<bullet::Bullet>,
                     shared::move_object::<enemy::Enemy>,
                     shared::move_object::<collectables::Collectable>,
                     levels::pan_background,
-                    levels::advance_level.run_if(levels::check_won),
+                    // Not while the stage clear screen is up, which happens with the boss
+                    // already beaten
+                    levels::advance_level
+                        .run_if(levels::check_won)
+                        .run_if(in_state(GameState::Gameplay)),
+                    speedrun::update_speedrun_timer,
+                    timeline::record_graze_milestones,
+                    bullet::cull_player_bullets,
+                    wave::track_waves,
+                    enemy::animate_boss_aura,
+                    enemy::hover_bosses,
+                    enemy::advance_boss_phases,
+                    enemy::tick_boss_timers,
+                    shockwave::expand_shockwaves,
+                    spark::update_sparks,
+                    death_sequence::run_death_sequences,
+                    death_sequence::update_explosions,
+                    shake::shake_camera,
                 ).run_if(in_state(GameplayState::Playing))
             )
+            .add_systems(Update,
+                bullet::retarget_bullets
+                    .after(enemy::advance_boss_phases)
+                    .before(shared::move_object::<bullet::Bullet>)
+                    .run_if(in_state(GameplayState::Playing))
+            )
+            .add_systems(Update,
+                shared::run_movement_scripts::<bullet::Bullet>
+                    .before(shared::move_object::<bullet::Bullet>)
+                    .run_if(in_state(GameplayState::Playing))
+            )
+            // Enemy bullets are moved and checked for collisions outside of the physics
+            // simulation, ahead of the collisions being handled
+            .add_systems(Update,
+                (bullet_grid::move_bullets, bullet_grid::detect_bullet_collisions)
+                    .chain()
+                    .after(shared::move_object::<bullet::Bullet>)
+                    .before(collisions::handle_collisions)
+                    .run_if(in_state(GameplayState::Playing))
+            )
+            .add_systems(Update,
+                (
+                    levels::practice::apply_practice_start,
+                    run_reset::apply_retry,
+                    tokens::apply_unlocked_specials,
+                    run_reset::snapshot_loadout,
+                )
+                    .chain()
+                    .after(CustomSet::UpdateStats)
+                    .run_if(in_state(GameplayState::Playing))
+            )
+            .add_systems(Update,
+                enemy::tag_enemies.run_if(in_state(GameplayState::Playing))
+            )
+            .add_systems(Update,
+                (enemy::dive_at_player, enemy::park_turrets, wave::track_entry)
+                    .before(shared::move_object::<enemy::Enemy>)
+                    .run_if(in_state(GameplayState::Playing))
+            )
+            .add_systems(Update,
+                hit_stop::hit_stop_on_life_lost.run_if(in_state(GameplayState::Playing))
+            )
+            .add_systems(Update,
+                shake::shake_on_kills
+                    .before(shake::shake_camera)
+                    .run_if(in_state(GameplayState::Playing))
+            )
+            .add_systems(Update,
+                (bestiary::record_encounters, bestiary::record_kills)
+                    .run_if(in_state(GameplayState::Playing))
+            )
+            .add_systems(Update, bestiary::save_bestiary)
+            .add_systems(Update,
+                tokens::drop_tokens
+                    .run_if(levels::practice::not_practice)
+                    .run_if(in_state(GameplayState::Playing))
+            )
+            .add_systems(Update, tokens::save_tokens)
+            .add_systems(Update,
+                (
+                    chain::chain_kills.after(CustomSet::UpdateStats),
+                    chain::drain_chain.after(chain::chain_kills),
+                    chain::update_chain_gauge.after(chain::drain_chain),
+                    chain::record_broken_chains.after(chain::drain_chain),
+                    ui::update_counter_ui::<chain::ChainText>,
+                )
+                    .run_if(in_state(GameplayState::Playing))
+            )
+            .add_systems(Update,
+                difficulty::scale_enemy_health
+                    .before(enemy::enemy_attack)
+                    .run_if(in_state(GameplayState::Playing))
+            )
+            .add_systems(Update,
+                (dps::track_boss_damage, dps::update_dps_text)
+                    .chain()
+                    .after(CustomSet::UpdateStats)
+                    .run_if(levels::practice::practice)
+                    .run_if(in_state(GameplayState::Playing))
+            )
+            .add_systems(Update,
+                rank::update_rank
+                    .after(CustomSet::UpdateStats)
+                    .run_if(in_state(GameplayState::Playing))
+            )
+            .add_systems(Update,
+                scrape::score_scrapes
+                    .run_if(scrape::scrape_scoring)
+                    .run_if(in_state(GameplayState::Playing))
+            )
+            .add_systems(OnEnter(GameState::GameWon),
+                speedrun::save_personal_best.run_if(levels::practice::not_practice)
+            )
+            .add_systems(OnEnter(levels::CurrentLevel::One), timeline::record_level_start)
+            .add_systems(
+                OnEnter(levels::CurrentLevel::Two),
+                (
+                    timeline::record_level_start,
+                    run_reset::carry_over_to_next_stage.run_if(levels::practice::not_practice),
+                ),
+            )
+            .add_systems(
+                OnEnter(levels::CurrentLevel::Three),
+                (
+                    timeline::record_level_start,
+                    run_reset::carry_over_to_next_stage.run_if(levels::practice::not_practice),
+                ),
+            )
+            .add_systems(OnEnter(levels::CurrentLevel::Endless), timeline::record_level_start)
             // OnExit -- Despawn all game objects
-            .add_systems(OnExit(GameplayState::Playing),
+            .add_systems(
+                OnExit(GameplayState::Playing),
                 (
                     remove_player,
-                    despawn_component::<bullet::Bullet>,
-                    despawn_component::<enemy::Enemy>,
-                    despawn_component::<levels::Wall>,
-                    despawn_component::<ui::GameplayUI>,
-                    despawn_component::<player::PlayerBooster>,
-                    despawn_component::<levels::LevelBackground>,
-                    despawn_component::<collectables::Collectable>,
                     levels::remove_level,
-                )
+                    shake::reset_camera,
+                    hit_stop::reset_hit_stop,
+                    bullet::clear_bullet_pool,
+                    bullet_grid::clear_bullet_grid,
+                ),
             )
+            .despawn_on_exit::<bullet::Bullet>(GameplayState::Playing)
+            .despawn_on_exit::<enemy::Enemy>(GameplayState::Playing)
+            .despawn_on_exit::<levels::Wall>(GameplayState::Playing)
+            .despawn_on_exit::<ui::GameplayUI>(GameplayState::Playing)
+            .despawn_on_exit::<player::PlayerBooster>(GameplayState::Playing)
+            .despawn_on_exit::<levels::LevelBackground>(GameplayState::Playing)
+            .despawn_on_exit::<collectables::Collectable>(GameplayState::Playing)
+            .despawn_on_exit::<shockwave::Shockwave>(GameplayState::Playing)
+            .despawn_on_exit::<spark::Spark>(GameplayState::Playing)
+            .despawn_on_exit::<afterimage::Afterimage>(GameplayState::Playing)
+            .despawn_on_exit::<death_sequence::Explosion>(GameplayState::Playing)
             // Configure custom sets
             // Collisions update stage is after the normal Update stage
             .configure_set(PostUpdate,
@@ -149,11 +379,19 @@ impl Plugin for GameplayPlugin {
                 (
                     collisions::handle_bullet_col,
                     collisions::handle_player_col,
+                    collisions::handle_graze_col,
                     collisions::handle_enemy_col,
                     collisions::handle_collectable_col,
+                    collisions::handle_shockwave_col,
                 )
                 .in_set(CustomSet::Collisions)
             )
+            // Blasts of explosive enemies and minions of carriers killed this frame
+            .add_systems(Update,
+                (explosive::detonate, enemy::release_minions)
+                    .after(event::take_damage)
+                    .in_set(CustomSet::UpdateStats)
+            )
             // UpdateStats
             .add_systems(Update,
                 (
@@ -161,14 +399,20 @@ impl Plugin for GameplayPlugin {
                     event::score_on_enemy_damage,
                     event::despawn_entity,
                     event::create_collectables_on_despawn,
-                    event::game_over,
+                    event::game_over.after(event::take_damage),
+                    player::respawn_player.after(event::take_damage),
+                    player::tick_invulnerability,
                     ui::update_health_bar::<enemy::BossHealthBar, enemy::Boss>,
+                    ui::spawn_boss_timer_text,
+                    ui::update_boss_timer_text,
                     ui::update_health_bar::<player::PlayerHealthBar, player::Player>,
+                    ui::update_counter_ui::<player::LivesText>,
                     ui::update_counter_ui::<player::ScoreText>,
                     ui::update_counter_ui::<player::GrazeText>,
                     ui::update_counter_ui::<player::PowerText>,
                     ui::update_counter_ui::<player::SpecialsText>,
                     ui::update_counter_ui::<player::EnemiesKilledText>,
+                    spark::emit_sparks,
                     collisions::cleanup_collisions,
                 )
                 .in_set(CustomSet::UpdateStats)
@@ -176,40 +420,179 @@ impl Plugin for GameplayPlugin {
     }
 }
 
-fn setup(mut next_state: ResMut<NextState<GameplayState>>) {
+fn setup(
+    gameplay_state: Res<State<GameplayState>>,
+    mut next_state: ResMut<NextState<GameplayState>>,
+) {
+    // Resuming from the pause menu carries on with the run that is already loaded
+    if *gameplay_state.get() == GameplayState::Playing {
+        return;
+    }
     // Start loading the game when entering GameState::Gameplay
     next_state.set(GameplayState::Loading);
 }
 
+// Clock for everything that happens during gameplay. It stops while the game is paused and
+// can be slowed down or sped up, so gameplay timers are ticked with its delta rather than
+// the real time's.
+#[derive(Resource, Debug)]
+pub struct GameTime {
+    delta: Duration,
+    // How fast gameplay runs compared to real time
+    scale: f32,
+    paused: bool,
+}
+
+impl Default for GameTime {
+    fn default() -> Self {
+        Self {
+            delta: Duration::ZERO,
+            scale: 1.0,
+            paused: false,
+        }
+    }
+}
+
+impl GameTime {
+    // Gameplay time that passed during the last frame
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale.max(0.0);
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn unpause(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+}
+
+// Work out the gameplay delta for this frame from the real one
+fn update_game_time(mut game_time: ResMut<GameTime>, time: Res<Time>) {
+    game_time.delta = if game_time.paused {
+        Duration::ZERO
+    } else {
+        time.delta().mul_f32(game_time.scale)
+    };
+}
+
 // A resource to keep track of time since started playing the game
-// (excluding time spent in menus and resets when retry-ing)
-#[derive(Resource, Default, Debug, Deref, DerefMut)]
-pub struct GameplayTime(Stopwatch);
+// (excluding time spent in menus and resets when retry-ing).
+// The run time is also split into segments, one for each level, so that
+// the time spent on every level can be shown once the level is over.
+#[derive(Resource, Default, Debug)]
+pub struct GameplayTime {
+    // Time since the run started
+    run: Stopwatch,
+    // Time since the current level started
+    level: Stopwatch,
+    // Time taken by every finished level, in the order they were played
+    splits: Vec<(levels::CurrentLevel, Duration)>,
+}
 
-fn setup_gameplay(mut commands: Commands, mut physics: ResMut<RapierConfiguration>) {
+impl GameplayTime {
+    pub fn tick(&mut self, delta: Duration) {
+        self.run.tick(delta);
+        self.level.tick(delta);
+    }
+
+    pub fn pause(&mut self) {
+        self.run.pause();
+        self.level.pause();
+    }
+
+    pub fn unpause(&mut self) {
+        self.run.unpause();
+        self.level.unpause();
+    }
+
+    // Time since the start of the run
+    pub fn elapsed(&self) -> Duration {
+        self.run.elapsed()
+    }
+
+    // Time since the start of the current level
+    pub fn level_elapsed(&self) -> Duration {
+        self.level.elapsed()
+    }
+
+    pub fn level_elapsed_secs(&self) -> f32 {
+        self.level.elapsed_secs()
+    }
+
+    // Finish the segment for the given level and start timing the next one.
+    pub fn split(&mut self, level: levels::CurrentLevel) {
+        self.splits.push((level, self.level.elapsed()));
+        self.level.reset();
+    }
+
+    pub fn splits(&self) -> &[(levels::CurrentLevel, Duration)] {
+        &self.splits
+    }
+}
+
+fn setup_gameplay(
+    mut commands: Commands,
+    mut physics: ResMut<RapierConfiguration>,
+    seed: Res<rng::RunSeed>,
+) {
     // No automatic gravity required from the physics simulation
     physics.gravity = Vec2::ZERO;
+    // Every physics step covers the whole frame, up to the frame time of the lowest frame cap,
+    // so that the physics bodies keep up with the bullets moved by GameTime at 30 FPS too
+    physics.timestep_mode = TimestepMode::Variable {
+        max_dt: 1.0 / 20.0,
+        time_scale: 1.0,
+        substeps: 1,
+    };
 
     // Insert any resources needed for the Playing state.
     commands.insert_resource(GameplayTime::default());
     commands.insert_resource::<collisions::Collisions>(collisions::Collisions::default());
     commands.insert_resource(player::PlayerAttackCD::default());
+    commands.insert_resource(player::AutoSpecialCD::default());
+    commands.insert_resource(player::OptionPodCD::default());
+    commands.insert_resource(timeline::RunTimeline::default());
+    commands.insert_resource(wave::Waves::default());
+    commands.insert_resource(rng::RunRng::new(&seed));
+    commands.insert_resource(run_reset::PlayerLoadout::default());
+    commands.insert_resource(rank::Rank::default());
+    commands.insert_resource(dps::DpsMeter::default());
 }
 
 // Update the GameplayTime timer
-fn tick_gameplay(mut g_time: ResMut<GameplayTime>, r_time: Res<Time>) {
-    g_time.tick(r_time.delta());
+fn tick_gameplay(mut g_time: ResMut<GameplayTime>, time: Res<GameTime>) {
+    g_time.tick(time.delta());
 }
 
-// Intercept the Escape key on the keyvboard to return the player back to the main menu
-fn back_to_menu(
-    input: Res<Input<KeyCode>>,
-    mut game_state: ResMut<NextState<GameState>>,
-    mut gameplay_state: ResMut<NextState<GameplayState>>,
-) {
-    if input.pressed(KeyCode::Escape) {
-        game_state.set(GameState::Menu);
-        gameplay_state.set(GameplayState::None);
+fn pause_gameplay_time(mut game_time: ResMut<GameTime>, g_time: Option<ResMut<GameplayTime>>) {
+    game_time.pause();
+    if let Some(mut g_time) = g_time {
+        g_time.pause();
+    }
+}
+
+fn unpause_gameplay_time(mut game_time: ResMut<GameTime>, g_time: Option<ResMut<GameplayTime>>) {
+    game_time.unpause();
+    if let Some(mut g_time) = g_time {
+        g_time.unpause();
     }
 }
 
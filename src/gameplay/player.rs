@@ -1,20 +1,50 @@
-use crate::gameplay::event::DespawnEvent;
-
 use super::{
-    bullet::{Bullet, BulletGroup},
+    bullet::{Bullet, Homing},
+    chain::{spawn_chain_gauge, Chain, ChainText, BASE_MULTIPLIER},
+    input::PlayerInput,
     collisions::{ColliderType, PLAYER_BULLET_COL},
+    levels::LevelConduct,
+    run_reset::{PlayerLoadout, MAX_POWER, STARTING_LIVES, STARTING_POWER, STARTING_SPECIALS},
+    shockwave::spawn_shockwave,
     loading::{Atlases, ParticleEffects},
-    shared::{physics::*, Counter, Formation, Health, MetaSprite, Movement, METRE, METRE_SQUARED},
+    shared::{physics::*, Counter, Health, Movement, METRE, METRE_SQUARED},
+    shake::{ScreenShake, SPECIAL_SHAKE},
+    timeline::{RunTimeline, TimelineEventKind},
+    tuning::Tuning,
     ui::{
         create_counter, create_health_bar, Link, ObjectType, ProgressBar, StatsList, UpdatingText,
     },
+    GameTime,
+    GameplayTime,
 };
+use crate::ControlScheme;
+use crate::ui::fonts::Fonts;
 use bevy::prelude::*;
 use bevy_hanabi::prelude::*;
 
 #[derive(Component)]
 pub struct Player;
 
+// Sensor around the player which counts enemy bullets that come close without hitting
+#[derive(Component)]
+pub struct PlayerGraze;
+
+// Radius of the player's hitbox
+const PLAYER_RADIUS: f32 = 5.0;
+// Enemy bullets passing within this distance of the player's centre count as a graze.
+// The extra METRE / 1.3 was found through experimentation and looks ok to me
+pub const GRAZE_RADIUS: f32 = PLAYER_RADIUS + METRE / 1.3;
+
+// Where the player starts, and is sent back to after losing a life
+const PLAYER_SPAWN: Vec3 = Vec3::new(0.0, -300.0, 0.1);
+// How long the player can't be hurt for after losing a life
+const INVULNERABILITY_SECS: f32 = 3.0;
+// How long the player can't be hurt for after any other hit, so that overlapping bullets
+// can't drain all of their health over a few frames
+pub const HIT_INVULNERABILITY_SECS: f32 = 1.0;
+// How long the player is tinted red for after being hit
+const HIT_FLASH_SECS: f32 = 0.15;
+
 #[derive(Component, Debug, Clone, Copy)]
 pub struct Specials {
     remaining: u8
@@ -47,6 +77,93 @@ impl Counter for Specials {
     }
 }
 
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Lives {
+    remaining: u8,
+}
+impl Lives {
+    pub fn new(number: u8) -> Self {
+        Self { remaining: number }
+    }
+}
+
+impl Counter for Lives {
+    type Data = u8;
+
+    fn set(&mut self, remaining_lives: Self::Data) {
+        self.remaining = remaining_lives;
+    }
+
+    fn get(&self) -> Self::Data {
+        self.remaining
+    }
+
+    fn add(&mut self, number: Self::Data) {
+        self.remaining += number;
+    }
+
+    fn subtract(&mut self, number: Self::Data) {
+        self.remaining = self.remaining.saturating_sub(number);
+    }
+}
+
+#[derive(Component)]
+pub struct LivesText {
+    entity: Entity,
+}
+
+impl UpdatingText for LivesText {
+    type DataHolder = Lives;
+
+    fn original(&self) -> String {
+        String::from("Lives:")
+    }
+
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+}
+
+// The player can't take damage while this is on them. Added whenever the player is hit,
+// and for longer when a life is lost.
+#[derive(Component, Debug, Deref, DerefMut)]
+pub struct Invulnerable(Timer);
+impl Invulnerable {
+    pub fn new(secs: f32) -> Self {
+        Self(Timer::from_seconds(secs, TimerMode::Once))
+    }
+}
+
+// Sent when the player runs out of health but still has lives left
+#[derive(Debug, Default, Event)]
+pub struct LifeLostEvent;
+
+// How the last run ended and what the player had at the time. Kept by the systems which end
+// the run, as the player is despawned on a game over before the screens after it are shown.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct RunEnd {
+    pub won: bool,
+    pub score: u64,
+    pub power: u16,
+    pub enemies_killed: u16,
+    pub graze: u32,
+}
+
+impl RunEnd {
+    pub fn new(
+        won: bool,
+        (score, power, enemies_killed, graze): (&Score, &Power, &EnemiesKilled, &Graze),
+    ) -> Self {
+        Self {
+            won,
+            score: score.get(),
+            power: power.get(),
+            enemies_killed: enemies_killed.get(),
+            graze: graze.get(),
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct SpecialsText {
     entity: Entity,
@@ -139,7 +256,7 @@ impl UpdatingText for PowerText {
 #[derive(Debug, Clone, Component)]
 pub struct Score {
     score: u64,
-    pre_multiplier_score: u64,
+    // Applied to score as it is earned. Follows the kill and graze chain, see chain.rs.
     multiplier: f32,
 }
 
@@ -147,8 +264,7 @@ impl Default for Score {
     fn default() -> Self {
         Self {
             score: 0,
-            pre_multiplier_score: 0,
-            multiplier: 1.5,
+            multiplier: BASE_MULTIPLIER,
         }
     }
 }
@@ -158,10 +274,6 @@ impl Score {
         self.multiplier
     }
 
-    pub fn increase_multiplier_by(&mut self, value: f32) {
-        self.multiplier += value;
-    }
-
     pub fn set_multiplier(&mut self, value: f32) {
         self.multiplier = value;
     }
@@ -173,7 +285,6 @@ impl Counter for Score {
     // Bypasses multiplier
     fn set(&mut self, score: Self::Data) {
         self.score = score;
-        self.pre_multiplier_score = score;
     }
 
     fn get(&self) -> Self::Data {
@@ -181,8 +292,7 @@ impl Counter for Score {
     }
 
     fn add(&mut self, score: Self::Data) {
-        self.pre_multiplier_score += score;
-        self.score = (self.pre_multiplier_score as f32 * self.multiplier) as u64;
+        self.score += (score as f32 * self.multiplier) as u64;
     }
 
     // Bypasses multiplier
@@ -330,21 +440,70 @@ impl Default for PlayerAttackCD {
     }
 }
 
+// Distance from the player at which enemy bullets make the simplified controls focus.
+const AUTO_FOCUS_DISTANCE: f32 = 3.0 * METRE;
+// Distance from the player at which enemy bullets make the simplified controls use a special.
+const AUTO_SPECIAL_DISTANCE: f32 = 0.9 * METRE;
+
+// Cooldown between specials used automatically by the simplified controls, so that a single
+// dangerous bullet can not use up more than one special.
+#[derive(Resource, Debug, Deref, DerefMut)]
+pub struct AutoSpecialCD(Timer);
+impl Default for AutoSpecialCD {
+    fn default() -> Self {
+        use std::time::Duration;
+        Self(Timer::new(Duration::from_millis(2000), TimerMode::Once))
+    }
+}
+
+pub fn tick_auto_special(mut cooldown: ResMut<AutoSpecialCD>, dt: Res<GameTime>) {
+    cooldown.tick(dt.delta());
+}
+
+// Check if any enemy bullet is within `distance` of the player.
+fn enemy_bullet_within(
+    player: &Transform,
+    bullets: &Query<(&Transform, &ColliderType), With<Bullet>>,
+    distance: f32,
+) -> bool {
+    bullets.iter().any(|(transform, kind)| {
+        *kind == ColliderType::EnemyBullet
+            && transform
+                .translation
+                .truncate()
+                .distance(player.translation.truncate())
+                <= distance
+    })
+}
+
 #[derive(Component)]
 pub struct PlayerBooster;
 
+// Thruster on one side of the player, only firing while strafing away from that side.
+// The value is -1 for the left side and 1 for the right.
+#[derive(Component)]
+pub struct SideThruster(f32);
+
+// Whether the player is focusing, as decided by move_player
+#[derive(Component, Debug, Default)]
+pub struct Focused(pub bool);
+
+// Speed of the side thruster particles
+const SIDE_THRUSTER_SPEED: f32 = 120.0;
+
 pub fn spawn_player(
     mut commands: Commands,
     mut ui_list: Query<(Entity, &mut StatsList)>,
     atlases: Res<Atlases<'static>>,
     effects: Res<ParticleEffects<'static>>,
-    assets: Res<AssetServer>,
+    fonts: Res<Fonts>,
+    tuning: Res<Tuning>,
 ) {
     let player_name = super::shared::Name::from("Player 1");
     let health_bar = PlayerHealthBar;
     let health_bar = create_health_bar::<PlayerHealthBar>(
         &mut commands,
-        &assets,
+        &fonts,
         player_name.clone(),
         ObjectType::Player,
         health_bar,
@@ -354,17 +513,17 @@ pub fn spawn_player(
         .spawn((
             Player,
             Score::default(),
-            Power::new(0, 500),
+            Power::new(STARTING_POWER, MAX_POWER),
             Health::new(30.0, None),
             RigidBody::Dynamic,
             Velocity::zero(),
-            Collider::ball(5.0),
+            Collider::ball(PLAYER_RADIUS),
             ColliderType::Player,
             ColliderType::Player.collision_group(),
             ActiveEvents::COLLISION_EVENTS,
             LockedAxes::ROTATION_LOCKED,
             Movement::new(
-                Vec2::new(240.0, 240.0),
+                Vec2::splat(tuning.player_speed),
                 Vec2::ZERO,
                 false,
                 Vec2::ZERO,
@@ -374,7 +533,7 @@ pub fn spawn_player(
             Link(health_bar),
             SpriteSheetBundle {
                 texture_atlas: atlases.get("sprites/white-plane3.png").unwrap().clone(),
-                transform: Transform::from_translation(Vec3::new(0.0, -300.0, 0.1)),
+                transform: Transform::from_translation(PLAYER_SPAWN),
                 sprite: TextureAtlasSprite {
                     index: 5,
                     custom_size: Some(Vec2::new(48.0, 68.0)),
@@ -383,10 +542,26 @@ pub fn spawn_player(
                 ..default()
             },
         ));
-    binding.insert((EnemiesKilled::default(), Specials::new(5), Graze::default()));
+    binding.insert((
+        EnemiesKilled::default(),
+        Specials::new(STARTING_SPECIALS),
+        Graze::default(),
+        Chain::default(),
+        Focused::default(),
+        Lives::new(STARTING_LIVES),
+    ));
 
     let player_entity = binding
         .with_children(|parent| {
+            parent.spawn((
+                PlayerGraze,
+                TransformBundle::default(),
+                Collider::ball(GRAZE_RADIUS),
+                ColliderType::Graze,
+                ColliderType::Graze.collision_group(),
+                ActiveEvents::COLLISION_EVENTS,
+                Sensor,
+            ));
             parent.spawn((
                 PlayerBooster,
                 ParticleEffectBundle {
@@ -396,46 +571,89 @@ pub fn spawn_player(
                     ..default()
                 },
             ));
+            for side in [-1.0, 1.0] {
+                parent.spawn((
+                    PlayerBooster,
+                    SideThruster(side),
+                    ParticleEffectBundle {
+                        effect: ParticleEffect::new(
+                            effects.get("player_side_thruster").unwrap().clone(),
+                        )
+                        .with_z_layer_2d(Some(0.0)),
+                        transform: Transform::from_translation(Vec3::new(side * 20.0, -10.0, 0.0)),
+                        ..default()
+                    },
+                ));
+            }
         }).id();
 
+    create_counter::<LivesText>(
+        &mut commands,
+        &mut ui_list,
+        &fonts,
+        LivesText { entity: player_entity }
+    );
+
     create_counter::<ScoreText>(
         &mut commands,
         &mut ui_list,
-        &assets,
+        &fonts,
         ScoreText { entity: player_entity }
     );
 
     create_counter::<GrazeText>(
         &mut commands,
         &mut ui_list,
-        &assets,
+        &fonts,
         GrazeText { entity: player_entity }
     );
 
     create_counter::<PowerText>(
         &mut commands,
         &mut ui_list,
-        &assets,
+        &fonts,
         PowerText { entity: player_entity }
     );
 
     create_counter::<SpecialsText>(
         &mut commands,
         &mut ui_list,
-        &assets,
+        &fonts,
         SpecialsText { entity: player_entity }
     );
 
     create_counter::<EnemiesKilledText>(
         &mut commands,
         &mut ui_list,
-        &assets,
+        &fonts,
         EnemiesKilledText { entity: player_entity }
     );
+
+    create_counter::<ChainText>(
+        &mut commands,
+        &mut ui_list,
+        &fonts,
+        ChainText { entity: player_entity }
+    );
+    spawn_chain_gauge(&mut commands, &mut ui_list);
 }
 
-pub fn uses_special(input: Res<Input<KeyCode>>) -> bool {
-    input.just_pressed(KeyCode::X)
+// With the simplified controls, specials are used automatically when an enemy bullet
+// gets too close to the player instead of with a key press.
+pub fn uses_special(
+    input: Res<PlayerInput>,
+    game_options: Res<crate::GameOptions>,
+    cooldown: Res<AutoSpecialCD>,
+    player: Query<&Transform, With<Player>>,
+    bullets: Query<(&Transform, &ColliderType), With<Bullet>>,
+) -> bool {
+    match game_options.get_control_scheme() {
+        ControlScheme::Standard => input.special,
+        ControlScheme::Simplified => {
+            let Ok(player) = player.get_single() else { return false; };
+            cooldown.finished() && enemy_bullet_within(player, &bullets, AUTO_SPECIAL_DISTANCE)
+        }
+    }
 }
 
 pub fn used_special(specials: Query<Ref<Specials>>) -> bool {
@@ -447,12 +665,17 @@ pub fn used_special(specials: Query<Ref<Specials>>) -> bool {
     false
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn special_attack(
     mut commands: Commands,
     mut player: Query<(&Transform, &mut Specials), With<Player>>,
-    mut despawn_ev: EventWriter<DespawnEvent>,
-    bullets: Query<(Entity, &ColliderType), With<Bullet>>,
-    assets: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut auto_special_cd: ResMut<AutoSpecialCD>,
+    mut conduct: ResMut<LevelConduct>,
+    mut timeline: ResMut<RunTimeline>,
+    mut shake: ResMut<ScreenShake>,
+    g_time: Res<GameplayTime>,
 ) {
     let Ok((&player, mut specials)) = player.get_single_mut() else {
         return;
@@ -463,46 +686,61 @@ pub fn special_attack(
     };
 
     specials.subtract(1);
+    auto_special_cd.reset();
+    conduct.bombed = true;
+    timeline.record(&g_time, TimelineEventKind::Bomb);
+    shake.start(SPECIAL_SHAKE.0, SPECIAL_SHAKE.1);
 
-    for (bullet, kind) in bullets.iter() {
-        if *kind == ColliderType::EnemyBullet {
-            despawn_ev.send(DespawnEvent::new(bullet, true).with_score(1));
-        }
-    }
-
-    let sprite = MetaSprite {
-        sprite: Sprite {
-            custom_size: Some(1.5 * METRE_SQUARED),
-            ..default()
-        },
-        texture: Some(assets.load("debug/sprites/up-arrow.png")),
-        collider: Collider::cuboid(METRE * 0.5, METRE * 0.5),
-        ..default()
-    };
-
-    let movement = Movement::new(
-        Vec2::ZERO,
-        Vec2::ZERO,
-        true,
-        Vec2::new(0.0, 30.0),
-        Vec2::ZERO,
+    // The shockwave clears enemy bullets as it reaches them, rather than all at once
+    spawn_shockwave(
+        &mut commands,
+        player.translation.truncate(),
+        &mut meshes,
+        &mut materials,
     );
+}
 
-    BulletGroup {
-        formation: Formation::circular(false, 20.0),
-        origin: player,
-        number: 35,
-        collider_type: ColliderType::PlayerBullet,
-        bullet: Bullet::new(50.0, 50.0),
-    }
-    .spawn_all(&mut commands, movement, sprite);
+// Spawn a single player bullet
+fn spawn_bullet(
+    commands: &mut Commands,
+    translation: Vec3,
+    movement: Movement,
+    colour: Color,
+    texture: Handle<Image>,
+) -> Entity {
+    commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: colour,
+                    custom_size: Some(2. * METRE_SQUARED),
+                    ..default()
+                },
+                texture,
+                transform: Transform {
+                    translation,
+                    ..default()
+                },
+                ..default()
+            },
+            Bullet::new(5.0, 20.0),
+            RigidBody::Dynamic,
+            Velocity::zero(),
+            movement,
+            Collider::cuboid(METRE / 2., METRE / 2.),
+            ColliderType::PlayerBullet,
+            ColliderType::PlayerBullet.collision_group(),
+            SolverGroups::new(PLAYER_BULLET_COL, Group::NONE),
+            Sensor,
+        ))
+        .id()
 }
 
 pub fn spawn_player_bullet(
     mut commands: Commands,
     player: Query<&Transform, With<Player>>,
-    input: Res<Input<KeyCode>>,
-    dt: Res<Time>,
+    input: Res<PlayerInput>,
+    dt: Res<GameTime>,
     mut cooldown: ResMut<PlayerAttackCD>,
     assets: Res<AssetServer>,
 ) {
@@ -510,7 +748,7 @@ pub fn spawn_player_bullet(
     if !cooldown.finished() {
         return;
     }
-    if !input.pressed(KeyCode::Z) {
+    if !input.fire {
         return;
     }
 
@@ -534,92 +772,252 @@ pub fn spawn_player_bullet(
     ];
 
     for (colour, velocity) in attributes {
-        commands.spawn((
-            SpriteBundle {
-                sprite: Sprite {
-                    color: colour,
-                    custom_size: Some(2. * METRE_SQUARED),
-                    ..default()
-                },
-                texture: bullet_texture.clone(),
-                transform: Transform {
-                    translation: player.translation + Vec3::new(0.0, 10.0, 0.0),
-                    ..default()
-                },
-                ..default()
-            },
-            Bullet::new(5.0, 20.0),
-            RigidBody::Dynamic,
-            Velocity::zero(),
+        spawn_bullet(
+            &mut commands,
+            player.translation + Vec3::new(0.0, 10.0, 0.0),
             Movement::new(velocity, Vec2::ZERO, true, Vec2::ZERO, Vec2::new(0.0, 10.0)),
-            Collider::cuboid(METRE / 2., METRE / 2.),
-            ColliderType::PlayerBullet,
-            ColliderType::PlayerBullet.collision_group(),
-            SolverGroups::new(PLAYER_BULLET_COL, Group::NONE),
-            Sensor,
-        ));
+            colour,
+            bullet_texture.clone(),
+        );
+    }
+    cooldown.reset();
+}
+
+// Side pod which follows the player and fires homing bullets. Pods are gained with power,
+// and `slot` is the position of the pod, counting outwards from the player.
+#[derive(Component, Debug)]
+pub struct OptionPod {
+    slot: usize,
+}
+
+// Power needed for every pair of pods
+const OPTION_POD_POWER: [u16; 2] = [200, 400];
+// Where each pair of pods sits, relative to the player. The other pod of the pair is mirrored.
+const OPTION_POD_OFFSETS: [Vec2; 2] = [Vec2::new(30.0, -5.0), Vec2::new(50.0, -20.0)];
+
+// Cooldown between volleys from the option pods
+#[derive(Resource, Debug, Deref, DerefMut)]
+pub struct OptionPodCD(Timer);
+impl Default for OptionPodCD {
+    fn default() -> Self {
+        Self(Timer::from_seconds(0.45, TimerMode::Once))
+    }
+}
+
+// Add or remove pods whenever the player's power changes
+pub fn update_option_pods(
+    mut commands: Commands,
+    player: Query<(Entity, &Power), (With<Player>, Changed<Power>)>,
+    pods: Query<(Entity, &OptionPod)>,
+) {
+    let Ok((player, power)) = player.get_single() else { return; };
+    let pairs = OPTION_POD_POWER
+        .iter()
+        .filter(|&&needed| power.get() >= needed)
+        .count();
+
+    let mut existing = vec![false; OPTION_POD_OFFSETS.len()];
+    for (entity, pod) in pods.iter() {
+        if pod.slot < pairs {
+            existing[pod.slot] = true;
+        } else {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    for (slot, offset) in OPTION_POD_OFFSETS.iter().enumerate().take(pairs) {
+        if existing[slot] {
+            continue;
+        }
+        commands.entity(player).with_children(|parent| {
+            for side in [-1.0, 1.0] {
+                parent.spawn((
+                    OptionPod { slot },
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: Color::rgb(0.4, 0.9, 1.0),
+                            custom_size: Some(Vec2::splat(10.0)),
+                            ..default()
+                        },
+                        transform: Transform::from_xyz(side * offset.x, offset.y, 0.0),
+                        ..default()
+                    },
+                ));
+            }
+        });
+    }
+}
+
+pub fn fire_option_pods(
+    mut commands: Commands,
+    pods: Query<&GlobalTransform, With<OptionPod>>,
+    input: Res<PlayerInput>,
+    dt: Res<GameTime>,
+    mut cooldown: ResMut<OptionPodCD>,
+    assets: Res<AssetServer>,
+) {
+    cooldown.tick(dt.delta());
+    if !cooldown.finished() || !input.fire || pods.is_empty() {
+        return;
+    }
+
+    let bullet_texture = assets.load("debug/sprites/up-arrow.png");
+    for pod in pods.iter() {
+        let bullet = spawn_bullet(
+            &mut commands,
+            pod.translation(),
+            Movement::absolute(Vec2::new(0.0, 8.0), Vec2::ZERO),
+            Color::rgb(0.4, 0.9, 1.0),
+            bullet_texture.clone(),
+        );
+        commands.entity(bullet).insert(Homing {
+            turn_rate: 5.0,
+            time_left: 1.5,
+        });
     }
     cooldown.reset();
 }
 
+#[allow(clippy::type_complexity)]
 pub fn move_player(
-    mut player: Query<(&mut Velocity, &Movement, &mut TextureAtlasSprite), With<Player>>,
-    input: Res<Input<KeyCode>>,
+    mut player: Query<
+        (&mut Velocity, &Movement, &mut TextureAtlasSprite, &Transform, &mut Focused),
+        With<Player>,
+    >,
+    bullets: Query<(&Transform, &ColliderType), With<Bullet>>,
+    input: Res<PlayerInput>,
     game_options: Res<crate::GameOptions>,
+    tuning: Res<Tuning>,
 ) {
-    let Ok((mut rapier_vel, movement, mut sprite)) = player.get_single_mut() else { return; };
-
-    // input.pressed() returns a boolean value, which can be converted into an integer,
-    // as false = 0 and true = 1
-    // Therefore, the Right arrow can be set as +1 when true and the Left arrow
-    // as -1 when true (by adding a coefficient of -1). This can be represented on a number line
-    // as -1 = left, 0 = none, 1 = right. By adding the two values we get the overall
-    // movement desired. Holding down only the right key gives +1, holding the left key gives
-    // -1, holding both gives 0 and not pressing either gives 0.
-    // The same thing is implemented for vertical movement with the Up and Down arrows.
-    let x = input.pressed(KeyCode::Right) as i8 - (input.pressed(KeyCode::Left) as i8);
-    let y = input.pressed(KeyCode::Up) as i8 - (input.pressed(KeyCode::Down) as i8);
+    let Ok((mut rapier_vel, movement, mut sprite, transform, mut focused)) = player.get_single_mut() else { return; };
 
     // The focus value is the value to divide the velocity by if the player wants to slow down (focus)
     // Unlike the other inputs, this is dependent on a game setting, whether or not the player
     // wants to be focused by default or wants to hold down a key to become focused.
-    // focus is calculated by adding 1 to the value of input.pressed(left_shift) if the
-    // focus setting is left to normal. if the setting has been inverted, the value is calculated
-    // by adding 1 to the negation of the same key. This is so that the focus is alway 1 or 2, and
-    // never 0 to avoid divide-by-zero errors.
-    let focus = if game_options.get_focus() {
-        !input.pressed(KeyCode::ShiftLeft)
-    } else {
-        input.pressed(KeyCode::ShiftLeft)
+    // With the simplified controls, the player focuses automatically when bullets are close.
+    let focus = match game_options.get_control_scheme() {
+        ControlScheme::Simplified => enemy_bullet_within(transform, &bullets, AUTO_FOCUS_DISTANCE),
+        ControlScheme::Standard if game_options.get_focus() => !input.focus,
+        ControlScheme::Standard => input.focus,
     };
 
+    if focused.0 != focus {
+        focused.0 = focus;
+    }
+
     let divisor: f32 = match focus {
-        true => 1.8,
+        true => tuning.focus_divisor,
         false => 1.0,
     };
 
     // Update the player sprite depending on the direction they are moving.
     // The numbers 3, 4, and 5 correspond to indices of the texture atlas
     // for the player sprite, where 3 is moving left, 4 is moving right, and 5
-    // is neither. The x value is checked to achieve this.
-    sprite.index = if x == 1 {
+    // is neither. The x value is checked to achieve this, leaving some room so that
+    // a stick which is pushed almost straight up doesn't count as moving sideways.
+    let move_delta = input.movement;
+    sprite.index = if move_delta.x > 0.3 {
         4
-    } else if x == -1 {
+    } else if move_delta.x < -0.3 {
         3
     } else {
         5
     };
 
-    // Construct a 2D Vector from the x and y deltas
-    let mut move_delta = Vec2::new(x as f32, y as f32);
-    if move_delta != Vec2::ZERO {
-        // Ensure that the vector is normalised to unit length
-        move_delta /= move_delta.length();
-    }
-
     // Update the physics simulation's velocity. This is done by multiplying
-    // the movement delta above (i.e. direction vector) by the player speed
+    // the movement delta above (i.e. direction vector, up to unit length) by the player speed
     // and dividing by the focus in order to slow down by a half when
     // the player is focusing.
     rapier_vel.linvel = move_delta * movement.velocity / divisor;
 }
+
+// Send the player back to the start with full health, and keep them safe for a few seconds
+// so that they aren't hit again straight away.
+#[allow(clippy::type_complexity)]
+pub fn respawn_player(
+    mut commands: Commands,
+    mut life_lost_ev: EventReader<LifeLostEvent>,
+    mut player: Query<
+        (Entity, &mut Transform, &mut Velocity, &mut Health, &mut Power),
+        With<Player>,
+    >,
+    loadout: Res<PlayerLoadout>,
+) {
+    if life_lost_ev.iter().next().is_none() {
+        return;
+    }
+    let Ok((entity, mut transform, mut velocity, mut health, mut power)) = player.get_single_mut()
+    else {
+        return;
+    };
+    transform.translation = PLAYER_SPAWN;
+    *velocity = Velocity::zero();
+    health.current = health.total;
+    loadout.restore(&mut power);
+    // Replaces the shorter invulnerability from the hit that took the life
+    commands.entity(entity).insert(Invulnerable::new(INVULNERABILITY_SECS));
+}
+
+// Flash the player red when hit, then make them blink while invulnerable, and make them
+// vulnerable again once it runs out
+pub fn tick_invulnerability(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    mut player: Query<(Entity, &mut Invulnerable, &mut TextureAtlasSprite), With<Player>>,
+) {
+    for (entity, mut invulnerable, mut sprite) in player.iter_mut() {
+        invulnerable.tick(time.delta());
+        if invulnerable.finished() {
+            sprite.color = Color::WHITE;
+            commands.entity(entity).remove::<Invulnerable>();
+        } else if invulnerable.elapsed_secs() < HIT_FLASH_SECS {
+            sprite.color = Color::rgb(1.0, 0.3, 0.3);
+        } else {
+            // Five blinks a second
+            let visible = (invulnerable.elapsed_secs() * 10.0) as u32 % 2 == 0;
+            sprite.color = Color::rgba(1.0, 1.0, 1.0, if visible { 1.0 } else { 0.3 });
+        }
+    }
+}
+
+// Make the booster flame follow what the player is doing. It burns longer when flying
+// forwards and shorter when backing off, and turns into a smaller, tighter flame when
+// focusing. The side thrusters only fire while strafing away from their side.
+#[allow(clippy::type_complexity)]
+pub fn update_booster(
+    input: Res<PlayerInput>,
+    player: Query<&Focused, With<Player>>,
+    mut boosters: Query<
+        (&mut CompiledParticleEffect, &mut EffectSpawner, Option<&SideThruster>),
+        With<PlayerBooster>,
+    >,
+) {
+    let Ok(focused) = player.get_single() else { return; };
+    let movement = input.movement;
+
+    let (radius, focus_length) = match focused.0 {
+        true => (15.0, 0.6),
+        false => (40.0, 1.0),
+    };
+    // 1.3 at full speed forwards, 0.7 at full speed backwards
+    let lifetime = (1.0 + movement.y * 0.3) * focus_length;
+
+    for (mut effect, mut spawner, thruster) in boosters.iter_mut() {
+        match thruster {
+            None => {
+                effect.set_property("radius", graph::Value::Scalar(radius.into()));
+                effect.set_property("lifetime", graph::Value::Scalar(lifetime.into()));
+                effect.set_property("speed", graph::Value::Scalar(focus_length.into()));
+            }
+            Some(SideThruster(side)) => {
+                // Strafing right pushes out of the left side, and the other way around
+                let firing = movement.x * side < -0.3;
+                if spawner.is_active() != firing {
+                    spawner.set_active(firing);
+                }
+                let velocity = Vec3::new(side * SIDE_THRUSTER_SPEED, -SIDE_THRUSTER_SPEED / 4.0, 0.0);
+                effect.set_property("velocity", graph::Value::Vector(velocity.into()));
+            }
+        }
+    }
+}
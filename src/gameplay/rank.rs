@@ -0,0 +1,140 @@
+// Rank follows how well the run is going. It rises as enemies are killed and falls whenever a
+// life is lost, and together with the difficulty decides how much killed enemies drop. The
+// numbers for both are kept in ScoringConfig, so the economy of each difficulty can be tuned
+// in one place.
+use super::{
+    collectables::Drops, difficulty::Difficulty, event::EnemyKilledEvent, player::LifeLostEvent,
+};
+use bevy::prelude::*;
+
+// Rank gained for every enemy killed, and for every boss on top of that
+const RANK_PER_KILL: f32 = 0.002;
+const RANK_PER_BOSS: f32 = 0.05;
+// Rank taken away by losing a life
+const RANK_PER_LIFE: f32 = 0.25;
+
+// From 0 at the start of a run to 1 at the most
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq)]
+pub struct Rank(f32);
+
+impl Rank {
+    pub fn get(&self) -> f32 {
+        self.0
+    }
+
+    pub fn change(&mut self, by: f32) {
+        self.0 = (self.0 + by).clamp(0.0, 1.0);
+    }
+}
+
+// Multiplies the score and power dropped by enemies
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DropScale {
+    pub score: f32,
+    pub power: f32,
+}
+
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct ScoringConfig {
+    pub easy: DropScale,
+    pub normal: DropScale,
+    pub hard: DropScale,
+    pub lunatic: DropScale,
+    // Applied on top of the difficulty's at full rank, and scaled down with the rank below it
+    pub full_rank: DropScale,
+}
+
+// Harder difficulties are already worth more through the extra bullets to graze, so they hand
+// out less, power most of all. Playing well gives more score and less power.
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        Self {
+            easy: DropScale { score: 1.25, power: 1.5 },
+            normal: DropScale { score: 1.0, power: 1.0 },
+            hard: DropScale { score: 0.9, power: 0.75 },
+            lunatic: DropScale { score: 0.75, power: 0.5 },
+            full_rank: DropScale { score: 1.5, power: 0.5 },
+        }
+    }
+}
+
+impl ScoringConfig {
+    pub fn difficulty_scale(&self, difficulty: Difficulty) -> DropScale {
+        match difficulty {
+            Difficulty::Easy => self.easy,
+            Difficulty::Normal => self.normal,
+            Difficulty::Hard => self.hard,
+            Difficulty::Lunatic => self.lunatic,
+        }
+    }
+
+    // What an enemy drops once the difficulty and rank are taken into account
+    pub fn scale(&self, drops: Drops, difficulty: Difficulty, rank: Rank) -> Drops {
+        let base = self.difficulty_scale(difficulty);
+        let with_rank = |scale: f32, full: f32| scale * (1.0 + (full - 1.0) * rank.get());
+        let score = with_rank(base.score, self.full_rank.score);
+        let power = with_rank(base.power, self.full_rank.power);
+        let apply =
+            |amount: u8, scale: f32| (amount as f32 * scale).round().min(u8::MAX as f32) as u8;
+        Drops {
+            score: apply(drops.score, score),
+            power: apply(drops.power, power),
+        }
+    }
+}
+
+pub fn update_rank(
+    mut rank: ResMut<Rank>,
+    mut killed_ev: EventReader<EnemyKilledEvent>,
+    mut life_lost_ev: EventReader<LifeLostEvent>,
+) {
+    for event in killed_ev.iter() {
+        let boss = event.kind.map_or(false, |kind| kind.tags().contains(&"boss"));
+        rank.change(if boss { RANK_PER_KILL + RANK_PER_BOSS } else { RANK_PER_KILL });
+    }
+    for _ in life_lost_ev.iter() {
+        rank.change(-RANK_PER_LIFE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DROPS: Drops = Drops { score: 10, power: 10 };
+
+    #[test]
+    fn easier_difficulties_drop_more_power() {
+        let config = ScoringConfig::default();
+        let power = |difficulty| config.scale(DROPS, difficulty, Rank::default()).power;
+        assert!(power(Difficulty::Easy) > power(Difficulty::Normal));
+        assert!(power(Difficulty::Normal) > power(Difficulty::Hard));
+        assert!(power(Difficulty::Hard) > power(Difficulty::Lunatic));
+    }
+
+    #[test]
+    fn normal_at_no_rank_drops_as_is() {
+        let drops = ScoringConfig::default().scale(DROPS, Difficulty::Normal, Rank::default());
+        assert_eq!((drops.score, drops.power), (10, 10));
+    }
+
+    #[test]
+    fn rank_trades_power_for_score() {
+        let config = ScoringConfig::default();
+        let mut rank = Rank::default();
+        rank.change(1.0);
+        let low = config.scale(DROPS, Difficulty::Normal, Rank::default());
+        let high = config.scale(DROPS, Difficulty::Normal, rank);
+        assert!(high.score > low.score);
+        assert!(high.power < low.power);
+    }
+
+    #[test]
+    fn rank_stays_in_range() {
+        let mut rank = Rank::default();
+        rank.change(-1.0);
+        assert_eq!(rank.get(), 0.0);
+        rank.change(5.0);
+        assert_eq!(rank.get(), 1.0);
+    }
+}
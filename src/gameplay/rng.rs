@@ -0,0 +1,22 @@
+// Randomness that decides what the player has to face in a run: where enemies spawn and how
+// randomised bullet formations are laid out. Drawing all of it from one seeded generator means
+// two runs on the same seed get the same enemies and patterns, as long as the players do the
+// same things. Cosmetic effects keep using the thread local generator, so that they don't use
+// up numbers and throw the run out of step.
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+// Seed for the next runs. None picks a new random seed for every run.
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct RunSeed(pub Option<u64>);
+
+#[derive(Resource, Debug, Deref, DerefMut)]
+pub struct RunRng(StdRng);
+
+impl RunRng {
+    pub fn new(seed: &RunSeed) -> Self {
+        let seed = seed.0.unwrap_or_else(|| rand::thread_rng().gen());
+        Self(StdRng::seed_from_u64(seed))
+    }
+}
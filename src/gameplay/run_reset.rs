@@ -0,0 +1,230 @@
+// Rules for what the player keeps when a run moves on or starts over, kept in one place
+// rather than relying on which components happen to survive a state change.
+//
+// - A new run starts from scratch: the player is spawned again with the starting values below.
+// - A retry from the game over screen starts from scratch as well, except for half the power
+//   the player had when they lost.
+// - Moving on to the next stage keeps the score, power and lives, and tops the specials up.
+// - The player's loadout is saved in PlayerLoadout as they are spawned and when each stage
+//   starts, and is put back on them when they respawn. Losing a life takes them back to what
+//   they started the stage with, rather than to whatever they had when they were hit.
+use super::{
+    levels::GameMode,
+    player::{Lives, Player, Power, RunEnd, Score, Specials},
+    shared::Counter,
+};
+use bevy::prelude::*;
+
+// What the player is equipped with. Option pods follow power, so power is all that has to be
+// kept for them to come back as well.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct PlayerLoadout {
+    power: u16,
+}
+
+impl Default for PlayerLoadout {
+    fn default() -> Self {
+        Self {
+            power: STARTING_POWER,
+        }
+    }
+}
+
+impl PlayerLoadout {
+    // Put the loadout back on the player
+    pub fn restore(&self, power: &mut Power) {
+        if power.get() != self.power {
+            power.set(self.power);
+        }
+    }
+
+    fn snapshot(&mut self, power: &Power) {
+        self.power = power.get();
+    }
+}
+
+// Save the loadout the player is spawned with, once the practice, retry and shop changes have
+// been made to it
+pub fn snapshot_loadout(
+    player: Query<&Power, Added<Player>>,
+    mut loadout: ResMut<PlayerLoadout>,
+) {
+    for power in player.iter() {
+        loadout.snapshot(power);
+    }
+}
+
+// Values at the start of a run
+pub const STARTING_LIVES: u8 = 3;
+pub const STARTING_SPECIALS: u8 = 5;
+pub const STARTING_POWER: u16 = 0;
+pub const MAX_POWER: u16 = 500;
+
+// How a counter is carried over
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Carry {
+    // Kept as it is
+    Keep,
+    // Only this fraction is kept, rounded down
+    Fraction(f32),
+    // Raised to at least this amount
+    AtLeast(u32),
+    // Reset to the starting value
+    Reset,
+}
+
+impl Carry {
+    fn apply(&self, value: u64, start: u64) -> u64 {
+        match *self {
+            Carry::Keep => value,
+            Carry::Fraction(fraction) => (value as f32 * fraction) as u64,
+            Carry::AtLeast(least) => value.max(least as u64),
+            Carry::Reset => start,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CarryOver {
+    pub score: Carry,
+    pub power: Carry,
+    pub specials: Carry,
+    pub lives: Carry,
+}
+
+// Between the stages of a story run
+pub const NEXT_STAGE: CarryOver = CarryOver {
+    score: Carry::Keep,
+    power: Carry::Keep,
+    specials: Carry::AtLeast(3),
+    lives: Carry::Keep,
+};
+
+// Retrying from the game over screen
+pub const RETRY: CarryOver = CarryOver {
+    score: Carry::Reset,
+    power: Carry::Fraction(0.5),
+    specials: Carry::Reset,
+    lives: Carry::Reset,
+};
+
+impl CarryOver {
+    pub fn apply(
+        &self,
+        score: &mut Score,
+        power: &mut Power,
+        specials: &mut Specials,
+        lives: &mut Lives,
+    ) {
+        match self.score {
+            Carry::Keep => {}
+            Carry::Reset => *score = Score::default(),
+            rule => score.set(rule.apply(score.get(), 0)),
+        }
+        let carried = self.power.apply(power.get() as u64, STARTING_POWER as u64);
+        power.set(carried.min(MAX_POWER as u64) as u16);
+        let carried = self.specials.apply(specials.get() as u64, STARTING_SPECIALS as u64);
+        specials.set(carried.min(u8::MAX as u64) as u8);
+        let carried = self.lives.apply(lives.get() as u64, STARTING_LIVES as u64);
+        lives.set(carried.min(u8::MAX as u64) as u8);
+    }
+}
+
+// Apply the stage rules whenever a story run moves on to its next stage, and save the loadout
+// the player starts it with
+pub fn carry_over_to_next_stage(
+    mut player: Query<(&mut Score, &mut Power, &mut Specials, &mut Lives), With<Player>>,
+    mut loadout: ResMut<PlayerLoadout>,
+) {
+    for (mut score, mut power, mut specials, mut lives) in player.iter_mut() {
+        NEXT_STAGE.apply(&mut score, &mut power, &mut specials, &mut lives);
+        loadout.snapshot(&power);
+    }
+}
+
+// Inserted by the game over screen when the player picks retry
+#[derive(Resource, Debug, Default)]
+pub struct RetryRun;
+
+// Apply the retry rules to the player the retried run spawns, starting from where the last run
+// ended. Not done in practice, which has its own start.
+pub fn apply_retry(
+    mut commands: Commands,
+    mode: Res<GameMode>,
+    retry: Option<Res<RetryRun>>,
+    run_end: Option<Res<RunEnd>>,
+    mut player: Query<(&mut Score, &mut Power, &mut Specials, &mut Lives), Added<Player>>,
+) {
+    if retry.is_none() || player.is_empty() {
+        return;
+    }
+    commands.remove_resource::<RetryRun>();
+    let Some(run_end) = run_end else { return; };
+    if *mode == GameMode::Practice {
+        return;
+    }
+    for (mut score, mut power, mut specials, mut lives) in player.iter_mut() {
+        score.set(run_end.score);
+        power.set(run_end.power);
+        RETRY.apply(&mut score, &mut power, &mut specials, &mut lives);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(score: u64, power: u16, specials: u8, lives: u8) -> (Score, Power, Specials, Lives) {
+        let mut counters = (
+            Score::default(),
+            Power::new(STARTING_POWER, MAX_POWER),
+            Specials::new(STARTING_SPECIALS),
+            Lives::new(STARTING_LIVES),
+        );
+        counters.0.set(score);
+        counters.1.set(power);
+        counters.2.set(specials);
+        counters.3.set(lives);
+        counters
+    }
+
+    fn carry(rule: &CarryOver, counters: (u64, u16, u8, u8)) -> (u64, u16, u8, u8) {
+        let (mut score, mut power, mut specials, mut lives) =
+            player(counters.0, counters.1, counters.2, counters.3);
+        rule.apply(&mut score, &mut power, &mut specials, &mut lives);
+        (score.get(), power.get(), specials.get(), lives.get())
+    }
+
+    #[test]
+    fn next_stage_keeps_score_power_and_lives() {
+        assert_eq!(carry(&NEXT_STAGE, (12_000, 320, 1, 2)), (12_000, 320, 3, 2));
+    }
+
+    #[test]
+    fn next_stage_keeps_specials_above_the_top_up() {
+        assert_eq!(carry(&NEXT_STAGE, (0, 0, 6, 1)), (0, 0, 6, 1));
+    }
+
+    #[test]
+    fn retry_keeps_only_half_the_power() {
+        assert_eq!(
+            carry(&RETRY, (12_000, 321, 0, 0)),
+            (0, 160, STARTING_SPECIALS, STARTING_LIVES)
+        );
+    }
+
+    #[test]
+    fn carry_rules() {
+        assert_eq!(Carry::Keep.apply(7, 3), 7);
+        assert_eq!(Carry::Fraction(0.5).apply(7, 3), 3);
+        assert_eq!(Carry::AtLeast(5).apply(2, 3), 5);
+        assert_eq!(Carry::AtLeast(5).apply(9, 3), 9);
+        assert_eq!(Carry::Reset.apply(7, 3), 3);
+    }
+
+    #[test]
+    fn power_is_capped() {
+        let rule = CarryOver { power: Carry::AtLeast(1000), ..NEXT_STAGE };
+        assert_eq!(carry(&rule, (0, 0, 3, 3)).1, MAX_POWER);
+    }
+}
@@ -0,0 +1,135 @@
+// Debug tool for finding ordering bugs between gameplay systems. Pressing F9 writes the
+// resolved Update and PostUpdate schedules to a graphviz file in the exports folder, keeping
+// only the gameplay systems and the sets they are in. Render it with
+// `dot -Tsvg gameplay_schedule.dot -o gameplay_schedule.svg`.
+//
+// Solid edges go from a system or set to whatever has to run after it, dashed edges from a set
+// to what is in it. Run conditions are listed under the name of the system or set they are on.
+use crate::storage::{self, Storage};
+use bevy::{
+    ecs::schedule::{BoxedCondition, NodeId, ScheduleGraph, ScheduleLabel, Schedules},
+    prelude::*,
+    utils::{get_short_name, HashMap, HashSet},
+};
+use std::fmt::Write;
+
+const DUMP_KEY: KeyCode = KeyCode::F9;
+const FILE_NAME: &str = "gameplay_schedule.dot";
+// Systems from the gameplay plugin have this in their name
+const GAMEPLAY_PATH: &str = "::gameplay::";
+
+pub fn dump_schedule(world: &mut World) {
+    if !world.resource::<Input<KeyCode>>().just_pressed(DUMP_KEY) {
+        return;
+    }
+
+    let schedules = world.resource::<Schedules>();
+    let mut dot = String::from("digraph gameplay {\n    rankdir=LR;\n    node [shape=box];\n");
+    let labels: [(&str, &dyn ScheduleLabel); 2] =
+        [("Update", &Update), ("PostUpdate", &PostUpdate)];
+    for (name, label) in labels {
+        let Some(schedule) = schedules.get(label) else { continue; };
+        write_schedule(&mut dot, name, schedule.graph());
+    }
+    dot.push_str("}\n");
+
+    match storage::write(Storage::Exports, FILE_NAME, dot.as_bytes()) {
+        Ok(()) => info!("Wrote the gameplay schedule to {}", FILE_NAME),
+        Err(error) => warn!("Could not write the gameplay schedule: {}", error),
+    }
+}
+
+fn write_schedule(dot: &mut String, name: &str, graph: &ScheduleGraph) {
+    // Every system gets a set of its own type, which is what `.before(system)` and
+    // `.after(system)` are ordered against. Those sets are swapped for their systems, so that
+    // the ordering shows up between the systems themselves.
+    let mut type_sets: HashMap<NodeId, Vec<NodeId>> = HashMap::default();
+    let mut children: HashMap<NodeId, Vec<NodeId>> = HashMap::default();
+    for (parent, child, _) in graph.hierarchy().graph().all_edges() {
+        children.entry(parent).or_default().push(child);
+    }
+
+    let mut kept = HashSet::new();
+    let _ = writeln!(dot, "    subgraph cluster_{} {{\n        label=\"{}\";", name, name);
+    for (id, system, conditions) in graph.systems() {
+        if !system.name().contains(GAMEPLAY_PATH) {
+            continue;
+        }
+        kept.insert(id);
+        write_node(dot, name, id, &get_short_name(&system.name()), conditions, "box");
+    }
+    for (id, set, conditions) in graph.system_sets() {
+        let members = children.get(&id).map(Vec::as_slice).unwrap_or_default();
+        if set.system_type().is_some() {
+            type_sets.insert(id, members.to_vec());
+            continue;
+        }
+        // Only sets with gameplay systems in them
+        if !members.iter().any(|member| kept.contains(member)) {
+            continue;
+        }
+        kept.insert(id);
+        write_node(dot, name, id, &format!("{:?}", set), conditions, "ellipse");
+    }
+
+    let resolve = |id: NodeId| type_sets.get(&id).cloned().unwrap_or_else(|| vec![id]);
+    for (parent, members) in children.iter() {
+        if !kept.contains(parent) {
+            continue;
+        }
+        for member in members.iter().filter(|member| kept.contains(member)) {
+            write_edge(dot, name, *parent, *member, "dashed");
+        }
+    }
+    for (before, after, _) in graph.dependency().graph().all_edges() {
+        for from in resolve(before).into_iter().filter(|id| kept.contains(id)) {
+            for to in resolve(after).into_iter().filter(|id| kept.contains(id)) {
+                write_edge(dot, name, from, to, "solid");
+            }
+        }
+    }
+    dot.push_str("    }\n");
+}
+
+fn write_node(
+    dot: &mut String,
+    schedule: &str,
+    id: NodeId,
+    name: &str,
+    conditions: &[BoxedCondition],
+    shape: &str,
+) {
+    let mut label = escape(name);
+    for condition in conditions {
+        let _ = write!(label, "\\nif {}", escape(&get_short_name(&condition.name())));
+    }
+    let _ = writeln!(
+        dot,
+        "        {} [label=\"{}\", shape={}];",
+        node_name(schedule, id),
+        label,
+        shape
+    );
+}
+
+fn write_edge(dot: &mut String, schedule: &str, from: NodeId, to: NodeId, style: &str) {
+    let _ = writeln!(
+        dot,
+        "        {} -> {} [style={}];",
+        node_name(schedule, from),
+        node_name(schedule, to),
+        style
+    );
+}
+
+// Node ids are only unique within a schedule
+fn node_name(schedule: &str, id: NodeId) -> String {
+    match id {
+        NodeId::System(index) => format!("{}_system_{}", schedule, index),
+        NodeId::Set(index) => format!("{}_set_{}", schedule, index),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,77 @@
+// Alternative way of scoring grazes. Every bullet still only counts once towards the graze
+// counter, but instead of adding to the chain which raises the score multiplier, staying close
+// to bullets earns score for as long as the player dares to stay there.
+use super::{
+    bullet::Bullet,
+    collisions::ColliderType,
+    player::{Player, Score, GRAZE_RADIUS},
+    shared::Counter,
+    GameTime,
+};
+use bevy::prelude::*;
+
+// Score earned every second with a single bullet right on top of the player
+const SCRAPE_RATE: f32 = 60.0;
+
+// How grazing bullets is rewarded, picked from the main menu
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ScoringMode {
+    // Every graze adds to the kill and graze chain
+    #[default]
+    Graze,
+    // Score trickles in while bullets are close
+    Scrape,
+}
+
+impl ScoringMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScoringMode::Graze => "Graze",
+            ScoringMode::Scrape => "Scrape",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            ScoringMode::Graze => ScoringMode::Scrape,
+            ScoringMode::Scrape => ScoringMode::Graze,
+        }
+    }
+}
+
+pub fn scrape_scoring(mode: Res<ScoringMode>) -> bool {
+    *mode == ScoringMode::Scrape
+}
+
+// Add score for every enemy bullet within graze range, more the closer it is. The amount
+// grows with the square root of the bullets' closeness, so that sitting in a thick cloud of
+// bullets pays off less per bullet than weaving between a few.
+pub fn score_scrapes(
+    time: Res<GameTime>,
+    mut player: Query<(&Transform, &mut Score), With<Player>>,
+    bullets: Query<(&Transform, &ColliderType), With<Bullet>>,
+    // Score earned so far which doesn't add up to a whole point yet
+    mut leftover: Local<f32>,
+) {
+    let Ok((player, mut score)) = player.get_single_mut() else { return; };
+    let position = player.translation.truncate();
+
+    let closeness: f32 = bullets
+        .iter()
+        .filter(|(_, kind)| **kind == ColliderType::EnemyBullet)
+        .map(|(transform, _)| {
+            let distance = transform.translation.truncate().distance(position);
+            (1.0 - distance / GRAZE_RADIUS).max(0.0)
+        })
+        .sum();
+    if closeness <= 0.0 {
+        return;
+    }
+
+    *leftover += SCRAPE_RATE * closeness.sqrt() * time.delta_seconds();
+    let whole = leftover.floor();
+    if whole >= 1.0 {
+        *leftover -= whole;
+        score.add(whole as u64);
+    }
+}
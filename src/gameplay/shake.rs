@@ -0,0 +1,96 @@
+// Shaking the camera for big hits and explosions
+use super::{event::EnemyKilledEvent, GameTime};
+use crate::GameOptions;
+use bevy::prelude::*;
+use rand::Rng;
+
+// Camera shake currently playing. The shake gets weaker as the timer runs out.
+#[derive(Resource, Debug)]
+pub struct ScreenShake {
+    timer: Timer,
+    // Furthest the camera is moved from its resting place, in pixels
+    strength: f32,
+}
+
+impl Default for ScreenShake {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(0.0, TimerMode::Once),
+            strength: 0.0,
+        }
+    }
+}
+
+impl ScreenShake {
+    // Start shaking the camera. A weaker shake doesn't cut a stronger one short.
+    pub fn start(&mut self, strength: f32, secs: f32) {
+        if self.current_strength() > strength {
+            return;
+        }
+        self.strength = strength;
+        self.timer = Timer::from_seconds(secs, TimerMode::Once);
+    }
+
+    fn current_strength(&self) -> f32 {
+        if self.timer.finished() {
+            return 0.0;
+        }
+        self.strength * self.timer.percent_left()
+    }
+}
+
+// Shake from an enemy going down. Kept small, as there can be many in a row.
+const KILL_SHAKE: (f32, f32) = (3.0, 0.15);
+// Shake from the player using a special
+pub const SPECIAL_SHAKE: (f32, f32) = (9.0, 0.4);
+
+// Give every enemy kill a small jolt. Bosses shake the screen much harder later on, as their
+// death sequence ends in an explosion.
+pub fn shake_on_kills(
+    mut killed_ev: EventReader<EnemyKilledEvent>,
+    mut shake: ResMut<ScreenShake>,
+) {
+    if killed_ev.iter().count() > 0 {
+        shake.start(KILL_SHAKE.0, KILL_SHAKE.1);
+    }
+}
+
+// The shake option scales every shake, and turns them off at 0
+pub fn shake_camera(
+    time: Res<GameTime>,
+    options: Res<GameOptions>,
+    mut shake: ResMut<ScreenShake>,
+    mut cameras: Query<&mut Transform, With<Camera2d>>,
+) {
+    if shake.strength == 0.0 {
+        return;
+    }
+    shake.timer.tick(time.delta());
+
+    let offset = if shake.timer.finished() {
+        // Put the camera back where it was once the shake is over
+        shake.strength = 0.0;
+        Vec2::ZERO
+    } else {
+        let mut rng = rand::thread_rng();
+        let direction = Vec2::from_angle(rng.gen_range(0.0..std::f32::consts::TAU));
+        direction * shake.current_strength() * options.get_screen_shake()
+    };
+
+    for mut transform in cameras.iter_mut() {
+        transform.translation.x = offset.x;
+        transform.translation.y = offset.y;
+    }
+}
+
+// Stop any shake when leaving the game, so that the menus aren't drawn off centre
+pub fn reset_camera(
+    mut shake: ResMut<ScreenShake>,
+    mut cameras: Query<&mut Transform, With<Camera2d>>,
+) {
+    *shake = ScreenShake::default();
+    for mut transform in cameras.iter_mut() {
+        transform.translation.x = 0.0;
+        transform.translation.y = 0.0;
+    }
+}
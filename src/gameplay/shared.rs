@@ -1,6 +1,8 @@
+use super::{player::Player, GameTime};
 use bevy::asset::Asset;
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
+use rand::Rng;
 use std::fmt::{Debug, Display};
 
 pub const METRE: f32 = 20.0;
@@ -9,7 +11,7 @@ pub const METRE_SQUARED: Vec2 = Vec2::new(METRE, METRE);
 // Quick way of importing all of the physics-related items.
 pub mod physics {
     pub use bevy_rapier2d::prelude::{
-        ActiveEvents, Collider, CollisionEvent, CollisionGroups, Group, LockedAxes, RigidBody,
+        ActiveEvents, Collider, ColliderDisabled, CollisionEvent, CollisionGroups, Group, LockedAxes, RigidBody,
         Sensor, SolverGroups, Velocity,
     };
 }
@@ -28,6 +30,26 @@ impl<'s> From<&'s str> for Name<'s> {
     }
 }
 
+// Names given to bullets and enemies as they are spawned, so that a group of them can be picked
+// out later on without adding a marker component for every group. There are only ever a few
+// per entity, so they are kept in a plain list.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Component)]
+pub struct Tags(Vec<&'static str>);
+
+impl Tags {
+    pub fn new(tags: &[&'static str]) -> Self {
+        Self(tags.to_vec())
+    }
+
+    pub fn has(&self, tag: &str) -> bool {
+        self.0.contains(&tag)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
 // Generic counter implementation.
 pub trait Counter {
     type Data: Display + Debug;
@@ -48,6 +70,9 @@ pub struct Movement {
     pub local: bool,
     pub v_local: Vec2,
     pub a_local: Vec2,
+    // Designed path to follow. While it lasts it decides the velocity on its own, and once it
+    // is over the entity carries on with the velocity it had at the end.
+    pub path: Option<MovePath>,
     // Used for movement simulation for only the initial run.
     first_run: bool,
 }
@@ -75,6 +100,7 @@ impl Movement {
             local,
             v_local,
             a_local,
+            path: None,
             first_run: true,
         }
     }
@@ -98,6 +124,14 @@ impl Movement {
         }
     }
 
+    // Only follows a path
+    pub fn along(path: MovePath) -> Self {
+        Self {
+            path: Some(path),
+            ..default()
+        }
+    }
+
     // All-zero movement data, with local set to true
     pub const ZERO: Movement = Movement {
         velocity: Vec2::ZERO,
@@ -105,6 +139,7 @@ impl Movement {
         local: true,
         v_local: Vec2::ZERO,
         a_local: Vec2::ZERO,
+        path: None,
         first_run: true,
     };
 
@@ -115,8 +150,322 @@ impl Movement {
         local: true,
         v_local: Vec2::new(0.0, 1.0),
         a_local: Vec2::ZERO,
+        path: None,
         first_run: true,
     };
+
+    // Advance the movement by `dt` seconds and return the resulting absolute velocity.
+    // This holds the actual movement maths of `move_object` without touching the ECS, so that
+    // the same simulation can be stepped outside of the game.
+    pub fn step(&mut self, rotation: Quat, dt: f32) -> Vec2 {
+        if let Some(path) = &mut self.path {
+            path.advance(dt);
+            self.velocity = path.velocity() / METRE;
+            self.acceleration = Vec2::ZERO;
+            if path.finished() {
+                self.path = None;
+            }
+            return self.velocity;
+        }
+
+        // working value for change in velocity
+        let mut dv = Vec2::ZERO;
+
+        if self.local {
+            // Update velocity due to acceleration
+            self.v_local += self.a_local * dt;
+            // Convert the relative velocity to absolute velocity
+            // by extending it into the 3rd dimension and multiplying by
+            // the rotation quaternion of the entity. Then truncate back into
+            // 2 dimensions and add it to the working value (total change).
+            dv += (rotation * self.v_local.extend(0.0)).truncate();
+        }
+
+        // Accelerate
+        self.velocity += self.acceleration * dt;
+        dv += self.velocity;
+
+        dv
+    }
+}
+
+// How the progress along a path is spread over its duration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    // Starts slow and speeds up
+    In,
+    // Starts fast and slows down to a stop
+    Out,
+}
+
+impl Easing {
+    // Rate of change of the eased progress at `t`
+    fn rate(self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => 1.0,
+            Easing::In => 2.0 * t,
+            Easing::Out => 2.0 * (1.0 - t),
+        }
+    }
+
+    fn apply(self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::In => t * t,
+            Easing::Out => 1.0 - (1.0 - t) * (1.0 - t),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum PathShape {
+    // Straight lines from one point to the next
+    Waypoints(Vec<Vec2>),
+    // Cubic Bézier curve with these control points
+    Bezier([Vec2; 4]),
+}
+
+// Flight path for Movement, in pixels. The path gives the velocity rather than the position,
+// so only the differences between its points matter: an entity follows the shape of the path
+// from wherever it is when the path starts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MovePath {
+    shape: PathShape,
+    secs: f32,
+    easing: Easing,
+    // Start over from the beginning once the end is reached, instead of finishing
+    looping: bool,
+    // How fast the path is gone through compared to its duration
+    speed: f32,
+    elapsed: f32,
+}
+
+impl MovePath {
+    fn new(shape: PathShape, secs: f32, easing: Easing) -> Self {
+        Self {
+            shape,
+            secs,
+            easing,
+            looping: false,
+            speed: 1.0,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn waypoints(points: Vec<Vec2>, secs: f32, easing: Easing) -> Self {
+        Self::new(PathShape::Waypoints(points), secs, easing)
+    }
+
+    pub fn bezier(points: [Vec2; 4], secs: f32, easing: Easing) -> Self {
+        Self::new(PathShape::Bezier(points), secs, easing)
+    }
+
+    pub fn looped(mut self) -> Self {
+        self.looping = true;
+        self
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    fn advance(&mut self, dt: f32) {
+        self.elapsed += dt * self.speed;
+        if self.looping {
+            self.elapsed %= self.secs;
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        !self.looping && self.elapsed >= self.secs
+    }
+
+    // Velocity along the path at the current point, in pixels per second
+    fn velocity(&self) -> Vec2 {
+        let t = (self.elapsed / self.secs).clamp(0.0, 1.0);
+        let u = self.easing.apply(t);
+        let rate = self.easing.rate(t) * self.speed / self.secs;
+        let derivative = match &self.shape {
+            PathShape::Waypoints(points) => {
+                // Progress is spread over the length of the path, so the speed is the same on
+                // every segment
+                let segments = points.windows(2).map(|pair| pair[1] - pair[0]);
+                let length: f32 = segments.clone().map(Vec2::length).sum();
+                let mut distance = u * length;
+                let mut direction = Vec2::ZERO;
+                for segment in segments {
+                    direction = segment.normalize_or_zero();
+                    if distance <= segment.length() {
+                        break;
+                    }
+                    distance -= segment.length();
+                }
+                direction * length
+            }
+            PathShape::Bezier([p0, p1, p2, p3]) => {
+                let v = 1.0 - u;
+                3.0 * v * v * (*p1 - *p0) + 6.0 * v * u * (*p2 - *p1) + 3.0 * u * u * (*p3 - *p2)
+            }
+        };
+        derivative * rate
+    }
+}
+
+// One step of a MovementScript
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScriptStage {
+    // Carry on with the Movement as it is for this many seconds
+    Travel(f32),
+    // Slow down evenly to a stop over this many seconds
+    Brake(f32),
+    // Stand still, flashing, for this many seconds, then carry on as before
+    Stop(f32),
+    // Turn to face the player, or straight down without one, and set off at this speed in
+    // metres a second
+    Aim(f32),
+    // Gain this much speed every second, in metres, in the direction the entity is moving
+    Accelerate(f32),
+}
+
+// How often an entity which is stopped by its script flashes, in flashes a second
+const STOP_FLASH_RATE: f32 = 8.0;
+
+// Stages an entity goes through one after the other, changing its Movement as it goes, for
+// bullets that freeze and fire off again partway through their flight. Aim and Accelerate
+// take no time, so they are gone through on the same frame as the stage before them ends.
+// Once the last stage is over the entity is left with the Movement it ended up with.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct MovementScript {
+    stages: Vec<ScriptStage>,
+    current: usize,
+    // Time spent in the current stage, including whatever was left over from the one before
+    elapsed: f32,
+    // Movement put aside while stopped
+    held: Option<Movement>,
+}
+
+impl MovementScript {
+    pub fn new(stages: Vec<ScriptStage>) -> Self {
+        Self {
+            stages,
+            current: 0,
+            elapsed: 0.0,
+            held: None,
+        }
+    }
+
+    // Slows down to a stop over `brake` seconds, holds there for `hold` seconds, then turns
+    // towards the player and fires off again at `speed` metres a second, gaining
+    // `acceleration` every second after that
+    pub fn delayed_fire(brake: f32, hold: f32, speed: f32, acceleration: f32) -> Self {
+        Self::new(vec![
+            ScriptStage::Brake(brake),
+            ScriptStage::Stop(hold),
+            ScriptStage::Aim(speed),
+            ScriptStage::Accelerate(acceleration),
+        ])
+    }
+
+    // Whether the entity is standing still in a Stop stage
+    fn stopped(&self) -> bool {
+        matches!(self.stages.get(self.current), Some(ScriptStage::Stop(_)))
+    }
+
+    // Go through the stages which are over by now. Changes `movement` and `transform` but
+    // leaves the actual moving to move_object.
+    fn run(
+        &mut self,
+        dt: f32,
+        movement: &mut Movement,
+        transform: &mut Transform,
+        target: Option<Vec2>,
+    ) {
+        self.elapsed += dt;
+        while let Some(&stage) = self.stages.get(self.current) {
+            match stage {
+                ScriptStage::Travel(secs) => {
+                    if self.elapsed < secs {
+                        return;
+                    }
+                    self.elapsed -= secs;
+                }
+                ScriptStage::Brake(secs) => {
+                    // Scaling by the share of braking time left slows the entity down evenly,
+                    // reaching a stop right as the time runs out
+                    let before = (self.elapsed - dt).max(0.0);
+                    let scale = if self.elapsed < secs {
+                        (secs - self.elapsed) / (secs - before)
+                    } else {
+                        0.0
+                    };
+                    movement.velocity *= scale;
+                    movement.v_local *= scale;
+                    movement.acceleration = Vec2::ZERO;
+                    movement.a_local = Vec2::ZERO;
+                    if self.elapsed < secs {
+                        return;
+                    }
+                    self.elapsed -= secs;
+                }
+                ScriptStage::Stop(secs) => {
+                    if self.held.is_none() {
+                        self.held = Some(std::mem::replace(movement, Movement::ZERO));
+                    }
+                    if self.elapsed < secs {
+                        return;
+                    }
+                    if let Some(held) = self.held.take() {
+                        *movement = held;
+                    }
+                    self.elapsed -= secs;
+                }
+                ScriptStage::Aim(speed) => {
+                    let position = transform.translation.truncate();
+                    let direction = target
+                        .map(|target| (target - position).normalize_or_zero())
+                        .filter(|direction| *direction != Vec2::ZERO)
+                        .unwrap_or(Vec2::NEG_Y);
+                    transform.rotation = Quat::from_rotation_z(Vec2::Y.angle_between(direction));
+                    *movement = Movement::absolute(direction * speed, Vec2::ZERO);
+                }
+                ScriptStage::Accelerate(rate) => {
+                    let heading = movement.velocity
+                        + (transform.rotation * movement.v_local.extend(0.0)).truncate();
+                    movement.acceleration += heading.normalize_or_zero() * rate;
+                }
+            }
+            self.current += 1;
+        }
+    }
+}
+
+// Runs before move_object, so that the Movement it leaves is the one used this frame
+#[allow(clippy::type_complexity)]
+pub fn run_movement_scripts<T: Component>(
+    time: Res<GameTime>,
+    mut objects: Query<
+        (
+            &mut MovementScript,
+            &mut Movement,
+            &mut Transform,
+            Option<&mut TextureAtlasSprite>,
+        ),
+        With<T>,
+    >,
+    player: Query<&Transform, (With<Player>, Without<T>)>,
+) {
+    let target = player.get_single().ok().map(|player| player.translation.truncate());
+    for (mut script, mut movement, mut transform, sprite) in objects.iter_mut() {
+        script.run(time.delta_seconds(), &mut movement, &mut transform, target);
+
+        let Some(mut sprite) = sprite else { continue; };
+        let flash_off = script.stopped() && (script.elapsed * STOP_FLASH_RATE) as u32 % 2 == 1;
+        let alpha = if flash_off { 0.4 } else { 1.0 };
+        if sprite.color.a() != alpha {
+            sprite.color.set_a(alpha);
+        }
+    }
 }
 
 #[derive(Component)]
@@ -152,21 +501,45 @@ pub enum FormationShape {
     Linear,
     /// Requires a target.
     Positional,
+    /// Requires the spacing between entities, given as the entity size.
+    VWing,
+    /// Requires radius and span.
+    Arc,
+    /// Aimed straight at the player, fanned out over the span. The target is filled in as the
+    /// bullets are fired.
+    Aimed,
+    /// Like Aimed, but leading the player by where they will be when the bullets get there.
+    Predictive,
+    /// Requires radius and ratio. Every bullet is a ratio of a turn on from the one before it.
+    Spiral,
+    /// Requires radius. Spaced evenly like Circular, turning further with every volley.
+    RotatingRing,
 }
 
+// Fraction of a turn between the seeds of a sunflower. Spirals using it never line up with
+// themselves.
+pub const GOLDEN_ANGLE: f32 = 0.381_966;
+
 // Bullet or enemy formation definition
 #[derive(Debug, Clone)]
 pub struct Formation {
     pub kind: FormationShape,
     // Should the formation be generated randomly or in order
     pub randomised: bool,
-    // TODO: For generating spirals.
+    // Fraction of a turn between successive entities of a spiral
     pub ratio: Option<f32>,
     pub radius: Option<f32>,
     pub amplitude: Option<f32>,
     pub frequency: Option<f32>,
     pub target: Option<Transform>,
     pub entity_size: Option<Vec2>,
+    // Angle covered by an arc, in radians
+    pub span: Option<f32>,
+    // Fraction of a turn the formation is turned by after every volley, so that patterns sweep
+    // around
+    pub sweep: f32,
+    // How far the formation has turned so far, as a fraction of a turn
+    pub offset: f32,
 }
 
 impl Default for Formation {
@@ -180,6 +553,9 @@ impl Default for Formation {
             frequency: None,
             target: None,
             entity_size: None,
+            span: None,
+            sweep: 0.0,
+            offset: 0.0,
         }
     }
 }
@@ -225,6 +601,71 @@ impl Formation {
         }
     }
 
+    // Leader in front, with the rest of the entities alternating between the two wings
+    pub fn v_wing(spacing: Vec2) -> Self {
+        Self {
+            kind: FormationShape::VWing,
+            radius: None,
+            entity_size: Some(spacing),
+            ..default()
+        }
+    }
+
+    // Spread evenly over `span` radians of a circle, centred on the point below the origin
+    pub fn arc(radius: f32, span: f32) -> Self {
+        Self {
+            kind: FormationShape::Arc,
+            radius: Some(radius),
+            span: Some(span),
+            ..default()
+        }
+    }
+
+    // Fired at the player, spread evenly over `span` radians
+    pub fn aimed(span: f32) -> Self {
+        Self {
+            kind: FormationShape::Aimed,
+            radius: None,
+            span: Some(span),
+            ..default()
+        }
+    }
+
+    // Fired where the player is heading, spread evenly over `span` radians
+    pub fn predictive(span: f32) -> Self {
+        Self {
+            kind: FormationShape::Predictive,
+            radius: None,
+            span: Some(span),
+            ..default()
+        }
+    }
+
+    // Bullets spiralling out from the origin, `ratio` of a turn apart
+    pub fn spiral(radius: f32, ratio: f32, sweep: f32) -> Self {
+        Self {
+            kind: FormationShape::Spiral,
+            radius: Some(radius),
+            ratio: Some(ratio),
+            sweep,
+            ..default()
+        }
+    }
+
+    pub fn rotating_ring(radius: f32, sweep: f32) -> Self {
+        Self {
+            kind: FormationShape::RotatingRing,
+            radius: Some(radius),
+            sweep,
+            ..default()
+        }
+    }
+
+    // Turn the formation on for the next volley
+    pub fn advance(&mut self) {
+        self.offset = (self.offset + self.sweep).fract();
+    }
+
     /// Both parameters should be of unit length.
     fn rotation(relative_pos: Vec3, forward_direction: Vec3) -> Quat {
         let angle = forward_direction.angle_between(relative_pos);
@@ -244,11 +685,20 @@ impl Formation {
         Quat::from_axis_angle(axis.normalize_or_zero(), angle)
     }
     /// `n`: n >= 1, n ∈ ℤ
-    /// For circular formation, n is the number of vertices
+    /// For circular and rotating ring formations, n is the number of vertices
     /// For harmonic formation, n is the phase out of 2 PI radians
     /// For linear formation, n is the position on the line
+    /// For arc, aimed and predictive formations, n is the number of entities in the spread
     /// `i`: Current iteration.
-    pub fn transform(&self, i: u16, n: u16, origin: Transform) -> Transform {
+    /// Randomised formations draw from `rng`. Passing a seeded generator makes the output
+    /// deterministic.
+    pub fn transform<R: Rng + ?Sized>(
+        &self,
+        i: u16,
+        n: u16,
+        origin: Transform,
+        rng: &mut R,
+    ) -> Transform {
         use std::f32::consts::TAU;
         match self.kind {
             FormationShape::Circular => {
@@ -260,8 +710,7 @@ impl Formation {
                 let mut theta = (TAU / n as f32) * i as f32;
 
                 if self.randomised {
-                    use rand::Rng;
-                    theta = TAU * rand::thread_rng().gen::<f32>();
+                    theta = TAU * rng.gen::<f32>();
                 }
 
                 let translation = Vec3::new(
@@ -352,6 +801,85 @@ impl Formation {
                     scale: origin.scale,
                 }
             }
+            FormationShape::VWing => {
+                let spacing = self
+                    .entity_size
+                    .expect("No spacing was provided for a VWing formation!");
+
+                // Every pair after the leader is a step further back, one on each wing
+                let rank = ((i + 1) / 2) as f32;
+                let side = if i % 2 == 1 { -1.0 } else { 1.0 };
+                Transform {
+                    translation: origin.translation
+                        + Vec3::new(side * rank * spacing.x, rank * spacing.y, 0.0),
+                    rotation: origin.rotation,
+                    scale: origin.scale,
+                }
+            }
+            FormationShape::Arc => {
+                let radius = self
+                    .radius
+                    .expect("No radius was provided for an Arc formation!");
+                let span = self.span.expect("No span was provided for an Arc formation!");
+
+                let fraction = if n > 1 {
+                    i as f32 / (n - 1) as f32 - 0.5
+                } else {
+                    0.0
+                };
+                let theta = -TAU / 4.0 + span * fraction;
+                let translation =
+                    origin.translation + Vec3::new(radius * theta.cos(), radius * theta.sin(), 0.0);
+
+                let relative_pos = (translation - origin.translation).normalize_or_zero();
+                Transform {
+                    translation,
+                    rotation: Formation::rotation(relative_pos, Vec3::X),
+                    scale: origin.scale,
+                }
+            }
+            FormationShape::Spiral | FormationShape::RotatingRing => {
+                let radius = self
+                    .radius
+                    .expect("No radius was provided for a Spiral or RotatingRing formation!");
+                let step = match self.kind {
+                    FormationShape::Spiral => self
+                        .ratio
+                        .expect("No ratio was provided for a Spiral formation!"),
+                    _ => 1.0 / n as f32,
+                };
+
+                let theta = TAU * (self.offset + step * i as f32);
+                let direction = Vec3::new(theta.cos(), theta.sin(), 0.0);
+                Transform {
+                    translation: origin.translation + radius * direction,
+                    rotation: Formation::rotation(direction, Vec3::X),
+                    scale: origin.scale,
+                }
+            }
+            FormationShape::Aimed | FormationShape::Predictive => {
+                let target = self
+                    .target
+                    .expect("No target was provided for an aimed formation!");
+                let span = self.span.unwrap_or(0.0);
+
+                let fraction = if n > 1 {
+                    i as f32 / (n - 1) as f32 - 0.5
+                } else {
+                    0.0
+                };
+                // Straight down when the target is right on top of the origin
+                let aim = (target.translation - origin.translation)
+                    .truncate()
+                    .try_normalize()
+                    .unwrap_or(Vec2::NEG_Y);
+                let direction = Vec2::from_angle(span * fraction).rotate(aim);
+                Transform {
+                    translation: origin.translation,
+                    rotation: Formation::rotation(direction.extend(0.0), Vec3::Y),
+                    scale: origin.scale,
+                }
+            }
         }
     }
 }
@@ -365,7 +893,6 @@ pub trait ExtraSpriteInfo {
     fn texture(&self) -> Option<Handle<Self::T>>;
     fn sprite(&self) -> Self::S;
     fn collider(&self) -> Collider;
-    fn grazing_collider(&self) -> Option<Collider> { None }
     fn bundle(&self, transform: Transform) -> Self::C;
 }
 
@@ -375,7 +902,6 @@ pub struct MetaSprite {
     pub sprite: Sprite,
     pub texture: Option<Handle<Image>>,
     pub collider: Collider,
-    pub grazing_collider: Option<Collider>,
 }
 
 impl ExtraSpriteInfo for MetaSprite {
@@ -395,10 +921,6 @@ impl ExtraSpriteInfo for MetaSprite {
         self.collider.clone()
     }
 
-    fn grazing_collider(&self) -> Option<Collider> {
-        self.grazing_collider.clone()
-    }
-
     fn bundle(&self, transform: Transform) -> Self::C {
         Self::C {
             sprite: self.sprite(),
@@ -415,7 +937,6 @@ pub struct MetaSpriteAtlas {
     pub sprite: TextureAtlasSprite,
     pub texture_atlas: Option<Handle<TextureAtlas>>,
     pub collider: Collider,
-    pub grazing_collider: Option<Collider>,
 }
 
 impl ExtraSpriteInfo for MetaSpriteAtlas {
@@ -435,10 +956,6 @@ impl ExtraSpriteInfo for MetaSpriteAtlas {
         self.collider.clone()
     }
 
-    fn grazing_collider(&self) -> Option<Collider> {
-        self.grazing_collider.clone()
-    }
-
     fn bundle(&self, transform: Transform) -> Self::C {
         Self::C {
             sprite: self.sprite(),
@@ -455,7 +972,6 @@ impl Default for MetaSprite {
             sprite: Sprite::default(),
             texture: None,
             collider: Collider::default(),
-            grazing_collider: None,
         }
     }
 }
@@ -466,7 +982,6 @@ impl Default for MetaSpriteAtlas {
             sprite: TextureAtlasSprite::default(),
             texture_atlas: None,
             collider: Collider::default(),
-            grazing_collider: None,
         }
     }
 }
@@ -476,36 +991,26 @@ impl Default for MetaSpriteAtlas {
 // the physics simulation's velocity data.
 pub fn move_object<T: Component>(
     mut object: Query<(&mut Velocity, &mut Movement, &Transform), With<T>>,
-    dt: Res<Time>,
+    dt: Res<GameTime>,
 ) {
     for (mut rapier_vel, mut movement, transform) in &mut object {
-        // Reborrow movement because compiler thinks I'm borrowing
-        // movement immutably and mutably simultaneosly.
-        let movement = &mut *movement;
-
-        // working value for change in velocity
-        let mut dv = Vec2::ZERO;
-
-        if movement.local {
-            // Update velocity due to acceleration
-            movement.v_local += movement.a_local * dt.delta_seconds();
-            // Convert the relative velocity to absolute velocity
-            // by extending it into the 3rd dimension and multiplying by
-            // the rotation quaternion of the entity. Then truncate back into
-            // 2 dimensions and add it to the working value (total change).
-            dv += (transform.rotation * movement.v_local.extend(0.0)).truncate();
-        }
-
-        // Accelerate
-        movement.velocity += movement.acceleration * dt.delta_seconds();
-        dv += movement.velocity;
-
-        // Update the physics simulation with the working value of linear
-        // velocity created above. Make all movement operations in metres.
-        rapier_vel.linvel = dv * METRE;
+        // Update the physics simulation with the linear velocity of this
+        // update. Make all movement operations in metres.
+        // The physics simulation steps in real time, so the velocity is scaled by how fast
+        // gameplay is running for slowed down gameplay to move slower too.
+        rapier_vel.linvel =
+            movement.step(transform.rotation, dt.delta_seconds()) * METRE * dt.scale();
     }
 }
 
+// Format a duration as minutes, seconds and hundredths of a second, e.g. 01:23.45
+pub fn format_duration(duration: std::time::Duration) -> String {
+    let total = duration.as_secs_f32();
+    let minutes = (total / 60.0).floor();
+    let seconds = total - minutes * 60.0;
+    format!("{:02}:{:05.2}", minutes as u32, seconds)
+}
+
 /// T is a number between 0 and 1 which indicates how far between the points to go. 0.5 is the
 /// midpoint
 #[allow(unused)]
@@ -0,0 +1,62 @@
+use super::collisions::ColliderType;
+use super::GameTime;
+use bevy::prelude::*;
+use bevy::sprite::MaterialMesh2dBundle;
+use bevy_rapier2d::prelude::*;
+
+// How fast the shockwave of a special grows, and how big it gets before disappearing.
+// Big enough to reach every corner of the playfield from anywhere inside it.
+const SHOCKWAVE_SPEED: f32 = 900.0;
+const SHOCKWAVE_MAX_RADIUS: f32 = 1100.0;
+
+// Expanding ring sent out by a special. It turns the enemy bullets it touches into score
+// collectables and damages enemies. The collider and the mesh both have a radius of 1, and
+// the whole entity is scaled to the current radius.
+#[derive(Component, Debug)]
+pub struct Shockwave {
+    radius: f32,
+}
+
+pub fn spawn_shockwave(
+    commands: &mut Commands,
+    origin: Vec2,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+) {
+    commands.spawn((
+        Shockwave { radius: 1.0 },
+        MaterialMesh2dBundle {
+            mesh: meshes.add(shape::Circle::new(1.0).into()).into(),
+            material: materials.add(ColorMaterial::from(Color::rgba(0.6, 0.9, 1.0, 0.5))),
+            // Above the player and enemies, but under the UI
+            transform: Transform::from_translation(origin.extend(0.5)),
+            ..default()
+        },
+        Collider::ball(1.0),
+        Sensor,
+        ColliderType::Shockwave,
+        ColliderType::Shockwave.collision_group(),
+        ActiveEvents::COLLISION_EVENTS,
+    ));
+}
+
+// Grow every shockwave, fading it out as it gets bigger
+pub fn expand_shockwaves(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    mut shockwaves: Query<(Entity, &mut Shockwave, &mut Transform, &Handle<ColorMaterial>)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (entity, mut shockwave, mut transform, material) in shockwaves.iter_mut() {
+        shockwave.radius += SHOCKWAVE_SPEED * time.delta_seconds();
+        if shockwave.radius >= SHOCKWAVE_MAX_RADIUS {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.scale = Vec3::new(shockwave.radius, shockwave.radius, 1.0);
+        if let Some(material) = materials.get_mut(material) {
+            let faded = 0.5 * (1.0 - shockwave.radius / SHOCKWAVE_MAX_RADIUS);
+            material.color.set_a(faded);
+        }
+    }
+}
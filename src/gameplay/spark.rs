@@ -0,0 +1,123 @@
+use super::GameTime;
+use bevy::prelude::*;
+use rand::Rng;
+
+// Number of spark sprites kept around for reuse. Once they are all in use, the oldest
+// spark is taken for the next one.
+const SPARK_POOL_SIZE: usize = 64;
+// Sparks shown for every hit, and how long they last
+const SPARKS_PER_HIT: usize = 4;
+const SPARK_LIFETIME: f32 = 0.18;
+const SPARK_SPEED: f32 = 220.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SparkKind {
+    // A player bullet hit an enemy
+    Enemy,
+    // The player was hit
+    Player,
+}
+
+impl SparkKind {
+    fn colour(&self) -> Color {
+        match self {
+            SparkKind::Enemy => Color::rgb(1.0, 0.9, 0.4),
+            SparkKind::Player => Color::rgb(1.0, 0.25, 0.2),
+        }
+    }
+}
+
+// Sent with the point where a bullet hit something
+#[derive(Debug, Event)]
+pub struct HitSparkEvent {
+    pub position: Vec2,
+    pub kind: SparkKind,
+}
+
+#[derive(Component, Debug)]
+pub struct Spark {
+    timer: Timer,
+    velocity: Vec2,
+}
+
+// Spark entities, reused in turn so that no entities are spawned while playing
+#[derive(Resource, Debug, Default)]
+pub struct SparkPool {
+    sparks: Vec<Entity>,
+    next: usize,
+}
+
+impl SparkPool {
+    fn take(&mut self) -> Option<Entity> {
+        let spark = *self.sparks.get(self.next)?;
+        self.next = (self.next + 1) % self.sparks.len();
+        Some(spark)
+    }
+}
+
+pub fn setup_spark_pool(mut commands: Commands) {
+    let sparks = (0..SPARK_POOL_SIZE)
+        .map(|_| {
+            commands
+                .spawn((
+                    Spark {
+                        timer: Timer::from_seconds(SPARK_LIFETIME, TimerMode::Once),
+                        velocity: Vec2::ZERO,
+                    },
+                    SpriteBundle {
+                        sprite: Sprite {
+                            custom_size: Some(Vec2::new(2.0, 6.0)),
+                            ..default()
+                        },
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    },
+                ))
+                .id()
+        })
+        .collect();
+    commands.insert_resource(SparkPool { sparks, next: 0 });
+}
+
+pub fn emit_sparks(
+    mut spark_ev: EventReader<HitSparkEvent>,
+    mut pool: ResMut<SparkPool>,
+    mut sparks: Query<(&mut Spark, &mut Sprite, &mut Transform, &mut Visibility)>,
+) {
+    let mut rng = rand::thread_rng();
+    for event in spark_ev.iter() {
+        for _ in 0..SPARKS_PER_HIT {
+            let Some(entity) = pool.take() else { return; };
+            let Ok((mut spark, mut sprite, mut transform, mut visibility)) = sparks.get_mut(entity) else { continue; };
+            let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+            let direction = Vec2::from_angle(angle);
+
+            spark.timer.reset();
+            spark.velocity = direction * SPARK_SPEED * rng.gen_range(0.6..1.0);
+            sprite.color = event.kind.colour();
+            // Above the player and enemies
+            *transform = Transform::from_translation(event.position.extend(0.4))
+                .with_rotation(Quat::from_rotation_z(angle - std::f32::consts::FRAC_PI_2));
+            *visibility = Visibility::Inherited;
+        }
+    }
+}
+
+// Move the sparks outwards, fading them out until they are hidden again
+pub fn update_sparks(
+    time: Res<GameTime>,
+    mut sparks: Query<(&mut Spark, &mut Sprite, &mut Transform, &mut Visibility)>,
+) {
+    for (mut spark, mut sprite, mut transform, mut visibility) in sparks.iter_mut() {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        spark.timer.tick(time.delta());
+        if spark.timer.finished() {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        transform.translation += (spark.velocity * time.delta_seconds()).extend(0.0);
+        sprite.color.set_a(spark.timer.percent_left());
+    }
+}
@@ -0,0 +1,134 @@
+use super::{levels::CurrentLevel, shared::format_duration, ui::GameplayUI, GameplayTime};
+use crate::persistence::{load_or_default, SaveLoad};
+use crate::storage::Storage;
+use crate::GameOptions;
+use crate::ui::fonts::Fonts;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const PERSONAL_BEST_FILE: &str = "personal_best.ron";
+
+// The fastest full run so far. Holds the time of the run at the end of every level, so
+// that the current run can be compared against it at each stage clear. Kept between launches.
+#[derive(Resource, Debug, Default, Clone, Serialize, Deserialize)]
+pub struct PersonalBest {
+    pub splits: Vec<(CurrentLevel, Duration)>,
+}
+
+impl PersonalBest {
+    pub fn load() -> Self {
+        load_or_default(Storage::Saves, PERSONAL_BEST_FILE)
+    }
+
+    pub fn total(&self) -> Option<Duration> {
+        self.splits.last().map(|(_, time)| *time)
+    }
+}
+
+// Marker for the run timer overlay
+#[derive(Component)]
+pub struct SpeedrunTimerText;
+
+// Create the run timer in the bottom left corner of the screen if enabled in the options.
+// The first text section is the run time, the second one lists the finished splits.
+pub fn spawn_speedrun_timer(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    options: Res<GameOptions>,
+) {
+    if !options.get_speedrun_timer() {
+        return;
+    }
+    let font = fonts.main();
+
+    commands.spawn((
+        TextBundle::from_sections([
+            TextSection::new(
+                format_duration(Duration::ZERO),
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 28.0,
+                    color: Color::WHITE,
+                },
+            ),
+            TextSection::new(
+                "",
+                TextStyle {
+                    font,
+                    font_size: 18.0,
+                    color: Color::WHITE,
+                },
+            ),
+        ])
+        .with_text_alignment(TextAlignment::Left)
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(30.0),
+            left: Val::Px(30.0),
+            ..default()
+        }),
+        SpeedrunTimerText,
+        GameplayUI,
+    ));
+}
+
+// Update the run time and list every finished level along with the difference to the
+// personal best at the same point.
+pub fn update_speedrun_timer(
+    mut texts: Query<&mut Text, With<SpeedrunTimerText>>,
+    g_time: Res<GameplayTime>,
+    personal_best: Res<PersonalBest>,
+) {
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = format_duration(g_time.elapsed());
+
+        let mut splits = String::new();
+        for (i, (level, time)) in cumulative_splits(&g_time).into_iter().enumerate() {
+            splits.push_str(&format!("\nLevel {:?}: {}", level, format_duration(time)));
+            if let Some((_, best)) = personal_best.splits.get(i) {
+                splits.push_str(&format!(" ({})", format_delta(time, *best)));
+            }
+        }
+        text.sections[1].value = splits;
+    }
+}
+
+// Save the splits of a finished run if it was faster than the personal best.
+pub fn save_personal_best(
+    g_time: Option<Res<GameplayTime>>,
+    mut personal_best: ResMut<PersonalBest>,
+    mut save_load: ResMut<SaveLoad>,
+) {
+    let Some(g_time) = g_time else { return; };
+    let splits = cumulative_splits(&g_time);
+    let Some((_, total)) = splits.last() else { return; };
+
+    if personal_best.total().map_or(true, |best| *total < best) {
+        personal_best.splits = splits;
+        save_load.save(Storage::Saves, PERSONAL_BEST_FILE, &*personal_best);
+    }
+}
+
+// GameplayTime keeps the time taken by each level, so add them up to get
+// the time of the run at the end of each level.
+fn cumulative_splits(g_time: &GameplayTime) -> Vec<(CurrentLevel, Duration)> {
+    let mut total = Duration::ZERO;
+    g_time
+        .splits()
+        .iter()
+        .map(|(level, time)| {
+            total += *time;
+            (*level, total)
+        })
+        .collect()
+}
+
+// Ahead of the personal best is shown as negative, behind as positive.
+fn format_delta(time: Duration, best: Duration) -> String {
+    if time <= best {
+        format!("-{}", format_duration(best - time))
+    } else {
+        format!("+{}", format_duration(time - best))
+    }
+}
@@ -0,0 +1,153 @@
+use super::{levels::CurrentLevel, player::{Graze, Player}, shared::Counter, GameplayTime};
+use crate::ui::theme::Theme;
+use bevy::prelude::*;
+use std::time::Duration;
+
+// A graze milestone is recorded every time the graze counter passes a multiple of this
+const GRAZE_MILESTONE: u32 = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimelineEventKind {
+    Kill,
+    // Player took damage
+    Hit,
+    Death,
+    Bomb,
+    // Total graze reached
+    Graze(u32),
+    LevelStart(CurrentLevel),
+    // A long kill and graze chain ran out
+    ChainBroken,
+}
+
+impl TimelineEventKind {
+    // Colour of the event's marker in the timeline graph
+    fn colour(&self) -> Color {
+        match self {
+            TimelineEventKind::Kill => Color::rgb(0.6, 0.6, 0.6),
+            TimelineEventKind::Hit => Color::ORANGE,
+            TimelineEventKind::Death => Color::RED,
+            TimelineEventKind::Bomb => Color::CYAN,
+            TimelineEventKind::Graze(_) => Color::YELLOW,
+            TimelineEventKind::LevelStart(_) => Color::WHITE,
+            TimelineEventKind::ChainBroken => Color::GOLD,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimelineEvent {
+    // Time into the run at which the event happened
+    pub time: Duration,
+    pub kind: TimelineEventKind,
+}
+
+// Everything significant that happened during a run, in order, for looking back at after
+// the run is over.
+#[derive(Resource, Debug, Default, Clone)]
+pub struct RunTimeline {
+    events: Vec<TimelineEvent>,
+    next_graze_milestone: u32,
+}
+
+impl RunTimeline {
+    pub fn record(&mut self, time: &GameplayTime, kind: TimelineEventKind) {
+        self.events.push(TimelineEvent {
+            time: time.elapsed(),
+            kind,
+        });
+    }
+    pub fn events(&self) -> &[TimelineEvent] {
+        &self.events
+    }
+    pub fn count(&self, kind: TimelineEventKind) -> usize {
+        self.events.iter().filter(|event| event.kind == kind).count()
+    }
+}
+
+pub fn record_level_start(
+    mut timeline: ResMut<RunTimeline>,
+    g_time: Res<GameplayTime>,
+    level: Res<State<CurrentLevel>>,
+) {
+    timeline.record(&g_time, TimelineEventKind::LevelStart(*level.get()));
+}
+
+pub fn record_graze_milestones(
+    mut timeline: ResMut<RunTimeline>,
+    g_time: Res<GameplayTime>,
+    graze: Query<&Graze, (With<Player>, Changed<Graze>)>,
+) {
+    let Ok(graze) = graze.get_single() else { return; };
+    if timeline.next_graze_milestone == 0 {
+        timeline.next_graze_milestone = GRAZE_MILESTONE;
+    }
+    while graze.get() >= timeline.next_graze_milestone {
+        let milestone = timeline.next_graze_milestone;
+        timeline.record(&g_time, TimelineEventKind::Graze(milestone));
+        timeline.next_graze_milestone += GRAZE_MILESTONE;
+    }
+}
+
+// Add a graph of the run to a results screen. Every event is a thin coloured line placed
+// along a bar which represents the whole run, with a summary of the events below it.
+pub fn spawn_timeline_graph(
+    parent: &mut ChildBuilder,
+    timeline: &RunTimeline,
+    run_time: Duration,
+    theme: &Theme,
+) {
+    let total = run_time.as_secs_f32().max(f32::EPSILON);
+
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Px(400.0),
+                height: Val::Px(24.0),
+                margin: UiRect::top(Val::Px(20.0)),
+                ..default()
+            },
+            background_color: Color::rgb(0.15, 0.15, 0.15).into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            for event in timeline.events() {
+                // Level starts go across the whole bar, other events only the bottom half
+                let (height, top) = match event.kind {
+                    TimelineEventKind::LevelStart(_) => (100.0, 0.0),
+                    _ => (50.0, 50.0),
+                };
+                parent.spawn(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        left: Val::Percent(event.time.as_secs_f32() / total * 100.0),
+                        top: Val::Percent(top),
+                        width: Val::Px(2.0),
+                        height: Val::Percent(height),
+                        ..default()
+                    },
+                    background_color: event.kind.colour().into(),
+                    ..default()
+                });
+            }
+        });
+
+    let summary = [
+        ("Kills", TimelineEventKind::Kill),
+        ("Hits", TimelineEventKind::Hit),
+        ("Bombs", TimelineEventKind::Bomb),
+    ]
+    .map(|(name, kind)| {
+        TextSection::new(
+            format!("{}: {}  ", name, timeline.count(kind)),
+            TextStyle {
+                color: kind.colour(),
+                ..theme.text_style(18.0)
+            },
+        )
+    });
+    parent.spawn(TextBundle::from_sections(summary).with_style(Style {
+        margin: UiRect::top(Val::Px(5.0)),
+        ..default()
+    }));
+}
@@ -0,0 +1,157 @@
+// Tokens dropped by bosses, one for every boss beaten. They are kept between runs, and spent
+// in the shop of the main menu on things which stay unlocked from then on.
+use super::{
+    bestiary::EnemyKind,
+    collectables::{spawn_collectable_around, CollectableType},
+    event::EnemyKilledEvent,
+    levels::{palette::Palette, GameMode},
+    player::{Player, Specials},
+    shared::{Counter, Movement},
+};
+use crate::persistence::{load_or_default, SaveLoad};
+use crate::storage::Storage;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const TOKENS_FILE: &str = "tokens.ron";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Unlock {
+    DuskPalette,
+    NightPalette,
+    // One more special at the start of every run, bought up to three times
+    ExtraSpecial,
+}
+
+impl Unlock {
+    // In the order they are listed in the shop
+    pub const ALL: [Unlock; 3] = [Unlock::DuskPalette, Unlock::NightPalette, Unlock::ExtraSpecial];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Unlock::DuskPalette => "Dusk Palette",
+            Unlock::NightPalette => "Night Palette",
+            Unlock::ExtraSpecial => "Extra Special",
+        }
+    }
+
+    pub fn cost(&self) -> u32 {
+        match self {
+            Unlock::DuskPalette => 1,
+            Unlock::NightPalette => 2,
+            Unlock::ExtraSpecial => 3,
+        }
+    }
+
+    // How many times it can be bought
+    pub fn limit(&self) -> u8 {
+        match self {
+            Unlock::DuskPalette | Unlock::NightPalette => 1,
+            Unlock::ExtraSpecial => 3,
+        }
+    }
+}
+
+// Tokens picked up from each kind of boss, and what has been bought with them
+#[derive(Resource, Debug, Default, Serialize, Deserialize)]
+pub struct Tokens {
+    collected: BTreeMap<EnemyKind, u32>,
+    spent: u32,
+    unlocked: BTreeMap<Unlock, u8>,
+}
+
+impl Tokens {
+    pub fn load() -> Self {
+        load_or_default(Storage::Saves, TOKENS_FILE)
+    }
+
+    pub fn collect(&mut self, kind: EnemyKind) {
+        *self.collected.entry(kind).or_insert(0) += 1;
+    }
+
+    // Tokens which haven't been spent yet
+    pub fn balance(&self) -> u32 {
+        self.collected.values().sum::<u32>().saturating_sub(self.spent)
+    }
+
+    pub fn bought(&self, unlock: Unlock) -> u8 {
+        self.unlocked.get(&unlock).copied().unwrap_or(0)
+    }
+
+    pub fn can_buy(&self, unlock: Unlock) -> bool {
+        self.bought(unlock) < unlock.limit() && self.balance() >= unlock.cost()
+    }
+
+    // Returns false if it is sold out or there aren't enough tokens for it
+    pub fn buy(&mut self, unlock: Unlock) -> bool {
+        if !self.can_buy(unlock) {
+            return false;
+        }
+        self.spent += unlock.cost();
+        *self.unlocked.entry(unlock).or_insert(0) += 1;
+        true
+    }
+
+    // Day is always there, the others have to be bought first
+    pub fn palette_unlocked(&self, palette: Palette) -> bool {
+        match palette {
+            Palette::Day => true,
+            Palette::Dusk => self.bought(Unlock::DuskPalette) > 0,
+            Palette::Night => self.bought(Unlock::NightPalette) > 0,
+        }
+    }
+
+    // Next palette along which has been unlocked
+    pub fn next_palette(&self, palette: Palette) -> Palette {
+        let mut next = palette.next();
+        while !self.palette_unlocked(next) {
+            next = next.next();
+        }
+        next
+    }
+}
+
+// Story bosses leave a token behind as they go down. Not run in practice, where bosses can be
+// fought over and over, and the mini-bosses of endless mode come too often to drop any.
+pub fn drop_tokens(
+    mut commands: Commands,
+    mut killed_ev: EventReader<EnemyKilledEvent>,
+    assets: Res<AssetServer>,
+) {
+    for event in killed_ev.iter() {
+        let Some(kind) = event.kind else { continue; };
+        if !matches!(kind, EnemyKind::BigBoss | EnemyKind::BiggerBoss | EnemyKind::BiggestBoss) {
+            continue;
+        }
+        spawn_collectable_around(
+            &mut commands,
+            &Transform::from_translation(event.position.extend(0.0)),
+            &assets,
+            Movement::absolute(Vec2::new(0.0, -3.0), Vec2::ZERO),
+            CollectableType::Token(kind),
+        );
+    }
+}
+
+// Give the player the extra specials bought in the shop as they are spawned
+pub fn apply_unlocked_specials(
+    mode: Res<GameMode>,
+    tokens: Res<Tokens>,
+    mut player: Query<&mut Specials, Added<Player>>,
+) {
+    if *mode == GameMode::Practice {
+        return;
+    }
+    let extra = tokens.bought(Unlock::ExtraSpecial);
+    for mut specials in player.iter_mut() {
+        let with_extra = specials.get().saturating_add(extra);
+        specials.set(with_extra);
+    }
+}
+
+pub fn save_tokens(tokens: Res<Tokens>, mut save_load: ResMut<SaveLoad>) {
+    if tokens.is_changed() && !tokens.is_added() {
+        save_load.save(Storage::Saves, TOKENS_FILE, &*tokens);
+    }
+}
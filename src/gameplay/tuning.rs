@@ -0,0 +1,112 @@
+// Gameplay numbers which are worth playing around with, kept in assets/gameplay.tuning.ron
+// rather than spread over the code. Debug builds watch the assets folder, so saving the file
+// changes them while the game is running.
+use super::{player::Player, shared::Movement};
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::{TypePath, TypeUuid};
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+
+const TUNING_PATH: &str = "gameplay.tuning.ron";
+
+// Anything left out of the file keeps its default value
+#[derive(Resource, Debug, Clone, PartialEq, Deserialize, TypeUuid, TypePath)]
+#[uuid = "b4829fc1-4285-43e7-a97c-82e483460f5c"]
+#[serde(default)]
+pub struct Tuning {
+    // Pixels per second
+    pub player_speed: f32,
+    // The player's speed is divided by this while focusing
+    pub focus_divisor: f32,
+    // Damage the shockwave of a special deals to every enemy it passes over
+    pub bomb_damage: f32,
+    // Pull of collectables towards the player once they are close enough
+    pub magnet_strength: f32,
+    pub magnet_distance: f32,
+    // Pull of every collectable towards the player at the start of a stage
+    pub magnet_all_strength: f32,
+    // Damage taken by the player and by the enemy when the two run into each other
+    pub player_contact_damage: f32,
+    pub enemy_contact_damage: f32,
+}
+
+// Used until the file has been loaded
+impl Default for Tuning {
+    fn default() -> Self {
+        Self {
+            player_speed: 240.0,
+            focus_divisor: 1.8,
+            bomb_damage: 30.0,
+            magnet_strength: 15.0,
+            magnet_distance: 30.0,
+            magnet_all_strength: 40.0,
+            player_contact_damage: 10.0,
+            enemy_contact_damage: 15.0,
+        }
+    }
+}
+
+#[derive(Default)]
+struct TuningLoader;
+
+impl AssetLoader for TuningLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let tuning: Tuning = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(tuning));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tuning.ron"]
+    }
+}
+
+#[derive(Resource)]
+struct TuningHandle(Handle<Tuning>);
+
+pub struct TuningPlugin;
+
+impl Plugin for TuningPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<Tuning>()
+            .init_asset_loader::<TuningLoader>()
+            .init_resource::<Tuning>()
+            .add_systems(Startup, load_tuning)
+            .add_systems(Update, apply_tuning);
+    }
+}
+
+fn load_tuning(mut commands: Commands, assets: Res<AssetServer>) {
+    commands.insert_resource(TuningHandle(assets.load(TUNING_PATH)));
+}
+
+// Take on the values from the file once it has loaded, and again whenever it is reloaded
+fn apply_tuning(
+    handle: Res<TuningHandle>,
+    assets: Res<Assets<Tuning>>,
+    mut events: EventReader<AssetEvent<Tuning>>,
+    mut tuning: ResMut<Tuning>,
+    mut player: Query<&mut Movement, With<Player>>,
+) {
+    if events.iter().count() == 0 {
+        return;
+    }
+    let Some(asset) = assets.get(&handle.0) else { return; };
+    if *asset == *tuning {
+        return;
+    }
+    *tuning = asset.clone();
+    info!("Applied gameplay tuning from {}", TUNING_PATH);
+
+    // The player's speed is only read from the tuning when they are spawned
+    for mut movement in player.iter_mut() {
+        movement.velocity = Vec2::splat(tuning.player_speed);
+    }
+}
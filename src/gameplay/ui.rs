@@ -1,7 +1,22 @@
+use super::bullet::Bullet;
+use super::collisions::ColliderType;
+use super::enemy::BossTimer;
 use super::shared::Counter;
 use super::shared::Health;
 use super::shared::Name;
+use super::GameTime;
+use crate::graphics::FrameCap;
+use crate::ui::fonts::Fonts;
+use crate::GameOptions;
 use bevy::prelude::*;
+use bevy::utils::Duration;
+
+// How long a counter takes to count up (or down) to a new value
+const COUNT_TIME: f32 = 0.25;
+// How long a counter stays enlarged and tinted after its value changes
+const PULSE_TIME: f32 = 0.3;
+const PULSE_SCALE: f32 = 1.25;
+const PULSE_COLOUR: Color = Color::rgb(1.0, 0.85, 0.3);
 
 // General way of linking a game object to an UI object
 #[derive(Component)]
@@ -11,6 +26,10 @@ pub struct Link(pub Entity);
 #[derive(Component)]
 pub struct GameplayUI;
 
+// Marker for the debug information shown during gameplay in debug builds
+#[derive(Component)]
+pub struct DebugOverlayText;
+
 // Trait bound for progress bars such as a health bar
 pub trait ProgressBar {}
 
@@ -30,9 +49,71 @@ pub trait UpdatingText {
     fn entity(&self) -> Entity;
 }
 
+// Animation state of a counter text, so that changes are noticeable and shown
+// gradually rather than snapping to the new value.
+#[derive(Component, Debug)]
+pub struct CounterAnimation {
+    // Value currently displayed
+    shown: f64,
+    // Real value of the counter. None until the first update.
+    target: Option<u64>,
+    // Units per second to count at, so that every change takes COUNT_TIME
+    rate: f64,
+    pulse: Timer,
+}
+
+impl Default for CounterAnimation {
+    fn default() -> Self {
+        // Start with the pulse already finished
+        let mut pulse = Timer::from_seconds(PULSE_TIME, TimerMode::Once);
+        pulse.tick(pulse.duration());
+        Self {
+            shown: 0.0,
+            target: None,
+            rate: 0.0,
+            pulse,
+        }
+    }
+}
+
+impl CounterAnimation {
+    fn set_target(&mut self, value: u64) {
+        match self.target {
+            // Don't animate the initial value
+            None => self.shown = value as f64,
+            Some(target) if target != value => {
+                self.rate = (value as f64 - self.shown).abs() / COUNT_TIME as f64;
+                self.pulse.reset();
+            }
+            _ => {}
+        }
+        self.target = Some(value);
+    }
+    fn tick(&mut self, delta: Duration) {
+        self.pulse.tick(delta);
+        let Some(target) = self.target else { return; };
+        let target = target as f64;
+        let step = self.rate * delta.as_secs_f64();
+        if (target - self.shown).abs() <= step {
+            self.shown = target;
+        } else {
+            self.shown += step * (target - self.shown).signum();
+        }
+    }
+    fn shown(&self) -> u64 {
+        self.shown.round() as u64
+    }
+    // 1 right after a change, going down to 0 by the end of the pulse
+    fn pulse(&self) -> f32 {
+        1.0 - self.pulse.percent()
+    }
+}
+
 #[derive(Component)]
 pub enum ObjectType {
     Enemy,
+    // Bar shown under the boss' bar, so that both fit on screen at once
+    MidBoss,
     Player,
     Neutral,
 }
@@ -92,7 +173,7 @@ pub fn create_stats_list(mut commands: Commands) {
 pub fn create_counter<T: UpdatingText + Component>(
     commands: &mut Commands,
     list: &mut Query<(Entity, &mut StatsList)>,
-    assets: &AssetServer,
+    fonts: &Fonts,
     text: T,
 ) {
     // Retrieve the stats list from the world
@@ -109,7 +190,7 @@ pub fn create_counter<T: UpdatingText + Component>(
                 TextBundle::from_section(
                     text.original(),
                     TextStyle {
-                        font: assets.load("fonts/FiraSans-Bold.ttf"),
+                        font: fonts.main(),
                         font_size: 22.0,
                         color: Color::WHITE,
                     },
@@ -123,6 +204,7 @@ pub fn create_counter<T: UpdatingText + Component>(
                 }),
             )
             .insert(GameplayUI)
+            .insert(CounterAnimation::default())
             .insert(text);
     });
 }
@@ -130,7 +212,7 @@ pub fn create_counter<T: UpdatingText + Component>(
 // Add a health bar to the screen
 pub fn create_health_bar<T: ProgressBar + Component>(
     commands: &mut Commands,
-    assets: &AssetServer,
+    fonts: &Fonts,
     name: Name,
     kind: ObjectType,
     health_bar_component: T,
@@ -143,6 +225,11 @@ pub fn create_health_bar<T: ProgressBar + Component>(
             left: Val::Px(30.0),
             ..default()
         },
+        ObjectType::MidBoss => Style {
+            top: Val::Px(90.0),
+            left: Val::Px(30.0),
+            ..default()
+        },
         ObjectType::Player => Style {
             bottom: Val::Px(30.0),
             right: Val::Px(30.0),
@@ -160,7 +247,7 @@ pub fn create_health_bar<T: ProgressBar + Component>(
         TextBundle::from_section(
             name.0.unwrap_or("").to_owned(),
             TextStyle {
-                font: assets.load("fonts/FiraSans-Bold.ttf"),
+                font: fonts.main(),
                 font_size: 22.0,
                 color: Color::WHITE,
             },
@@ -214,6 +301,60 @@ pub fn create_health_bar<T: ProgressBar + Component>(
     binding.id()
 }
 
+// Time left to beat a boss, shown to the right of its health bar
+#[derive(Component)]
+pub struct BossTimerText;
+
+// Add the countdown to the health bar of every boss given a time limit
+pub fn spawn_boss_timer_text(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    bosses: Query<&Link, Added<BossTimer>>,
+) {
+    for health_bar in bosses.iter() {
+        let Some(mut health_bar) = commands.get_entity(health_bar.0) else { continue; };
+        health_bar.with_children(|parent| {
+            parent.spawn((
+                BossTimerText,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: fonts.main(),
+                        font_size: 22.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_style(Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(16.0),
+                    left: Val::Px(215.0),
+                    ..default()
+                }),
+            ));
+        });
+    }
+}
+
+pub fn update_boss_timer_text(
+    bosses: Query<(&BossTimer, &Link)>,
+    mut texts: Query<(&mut Text, &Parent), With<BossTimerText>>,
+) {
+    for (mut text, parent) in texts.iter_mut() {
+        let Some((boss_timer, _)) = bosses.iter().find(|(_, link)| link.0 == parent.get()) else {
+            continue;
+        };
+        let section = &mut text.sections[0];
+        match boss_timer.seconds_left() {
+            Some(seconds) => {
+                section.value = format!("{}", seconds);
+                // Warn the player when time is nearly up
+                section.style.color = if seconds <= 10 { Color::RED } else { Color::WHITE };
+            }
+            None => section.value.clear(),
+        }
+    }
+}
+
 // Change the heatlh bar size and colour based on the entity's health
 pub fn update_health_bar<B: Component + ProgressBar, C: Component>(
     mut health_bars: Query<(&mut BackgroundColor, &mut Style), With<B>>,
@@ -225,25 +366,87 @@ pub fn update_health_bar<B: Component + ProgressBar, C: Component>(
         for (mut bar_color, mut bar_style) in &mut health_bars {
             // Update bar size with percentage of total entity health
             bar_style.width = Val::Percent(fraction * 100.0);
-            // Make the bar red when under 25% health
-            if fraction <= 0.25 {
-                bar_color.0 = Color::RED;
-            }
+            // Make the bar red when under 25% health, and green again once healed, e.g. after
+            // the player loses a life
+            bar_color.0 = if fraction <= 0.25 {
+                Color::RED
+            } else {
+                Color::rgb(0.1, 0.8, 0.1)
+            };
         }
     }
 }
 
-// Update the text for a counter with the actual real-time data
+// Update the text for a counter with the actual real-time data. Changes are counted up to
+// over a few frames, while the text briefly grows and changes colour.
 pub fn update_counter_ui<T>(
-    mut texts: Query<(&mut Text, &T)>,
+    time: Res<GameTime>,
+    mut texts: Query<(&mut Text, &mut Transform, &mut CounterAnimation, &T)>,
     counter: Query<&<T as UpdatingText>::DataHolder>,
 ) where
     T: Component + UpdatingText,
+    <<T as UpdatingText>::DataHolder as Counter>::Data: Into<u64>,
 {
-    for (mut real_text, updating_text) in texts.iter_mut() {
-        if let Ok(data) = counter.get(updating_text.entity()) {
-            real_text.sections[updating_text.section()].value =
-                format!("{} {}", updating_text.original().as_str(), data.get());
-        }
+    for (mut real_text, mut transform, mut animation, updating_text) in texts.iter_mut() {
+        let Ok(data) = counter.get(updating_text.entity()) else { continue; };
+        animation.set_target(data.get().into());
+        animation.tick(time.delta());
+
+        let pulse = animation.pulse();
+        let [r, g, b, _] = PULSE_COLOUR.as_rgba_f32();
+        let section = &mut real_text.sections[updating_text.section()];
+        section.value = format!("{} {}", updating_text.original().as_str(), animation.shown());
+        // Fade from the pulse colour back to white
+        section.style.color = Color::rgb(
+            1.0 + (r - 1.0) * pulse,
+            1.0 + (g - 1.0) * pulse,
+            1.0 + (b - 1.0) * pulse,
+        );
+        transform.scale = Vec3::splat(1.0 + (PULSE_SCALE - 1.0) * pulse);
     }
 }
+
+// Show live debug information in the bottom left corner, above the run timer
+pub fn spawn_debug_overlay(mut commands: Commands, fonts: Res<Fonts>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: fonts.main(),
+                font_size: 16.0,
+                color: Color::YELLOW,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(70.0),
+            left: Val::Px(30.0),
+            ..default()
+        }),
+        DebugOverlayText,
+        GameplayUI,
+    ));
+}
+
+pub fn update_debug_overlay(
+    mut texts: Query<&mut Text, With<DebugOverlayText>>,
+    bullets: Query<&ColliderType, With<Bullet>>,
+    options: Res<GameOptions>,
+) {
+    let overlay = debug_overlay_text(&bullets, options.get_frame_cap());
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = overlay.clone();
+    }
+}
+
+// Contents of the debug overlay, also shown in the debug window
+pub fn debug_overlay_text(
+    bullets: &Query<&ColliderType, With<Bullet>>,
+    frame_cap: FrameCap,
+) -> String {
+    let player_bullets = bullets
+        .iter()
+        .filter(|kind| **kind == ColliderType::PlayerBullet)
+        .count();
+    format!("Player bullets: {}\nFrame cap: {}", player_bullets, frame_cap.name())
+}
@@ -0,0 +1,243 @@
+use super::{
+    bestiary::EnemyKind,
+    collectables::spawn_collectables,
+    enemy::{self, Attacks},
+    event::EnemyKilledEvent,
+    levels::Playfield,
+    loading::Atlases,
+    rng::RunRng,
+    shared::{Easing, Formation, Movement, MovePath},
+};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+// Number of normal enemies in a wave
+const WAVE_SIZE: u8 = 5;
+// Score collectables rained down for clearing a whole wave
+const WAVE_BONUS_COLLECTABLES: u8 = 12;
+
+// Wave that an enemy was spawned as part of
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WaveMember(pub u32);
+
+#[derive(Debug)]
+struct WaveStatus {
+    // Enemies in the whole wave
+    size: u8,
+    // Enemies of the wave that have appeared in the world so far
+    arrived: u8,
+    // Enemies of the wave that have been killed. Ones which are despawned any other way, like
+    // by flying off the screen or a boss' time running out, aren't counted.
+    killed: u8,
+    // An enemy of the wave made it out of the playfield
+    escaped: bool,
+}
+
+impl Default for WaveStatus {
+    fn default() -> Self {
+        Self {
+            size: WAVE_SIZE,
+            arrived: 0,
+            killed: 0,
+            escaped: false,
+        }
+    }
+}
+
+// Groups normal enemies into waves, in the order they are spawned. A wave is cleared when
+// every one of its enemies is killed without any of them getting away.
+#[derive(Resource, Debug, Default)]
+pub struct Waves {
+    // Wave that the next spawned enemy joins
+    next: u32,
+    // Enemies given to the next wave so far
+    joined: u8,
+    open: HashMap<u32, WaveStatus>,
+}
+
+impl Waves {
+    // Wave for an enemy that is about to be spawned
+    pub fn join(&mut self) -> WaveMember {
+        if self.joined >= WAVE_SIZE {
+            self.next += 1;
+            self.joined = 0;
+        }
+        self.joined += 1;
+        WaveMember(self.next)
+    }
+
+    // Wave of its own for a group of `size` enemies spawned together. The wave being filled up
+    // by join is cut short at the enemies it already has.
+    pub fn start(&mut self, size: u8) -> WaveMember {
+        if self.joined > 0 {
+            self.open.entry(self.next).or_default().size = self.joined;
+            self.next += 1;
+            self.joined = 0;
+        }
+        let wave = self.next;
+        self.next += 1;
+        self.open.entry(wave).or_default().size = size;
+        WaveMember(wave)
+    }
+}
+
+pub fn track_waves(
+    mut commands: Commands,
+    mut waves: ResMut<Waves>,
+    arrived: Query<&WaveMember, Added<WaveMember>>,
+    mut killed_ev: EventReader<EnemyKilledEvent>,
+    members: Query<(&WaveMember, &Transform, Option<&FlightPath>)>,
+    playfield: Res<Playfield>,
+    assets: Res<AssetServer>,
+) {
+    // Count enemies as they show up, rather than when they are handed a wave, so that a wave
+    // is never seen as empty before its last enemy has been spawned.
+    for member in arrived.iter() {
+        waves.open.entry(member.0).or_default().arrived += 1;
+    }
+    for member in killed_ev.iter().filter_map(|event| event.wave) {
+        if let Some(status) = waves.open.get_mut(&member.0) {
+            status.killed += 1;
+        }
+    }
+
+    let mut alive: HashMap<u32, u8> = HashMap::default();
+    for (member, transform, flight_path) in members.iter() {
+        *alive.entry(member.0).or_default() += 1;
+        let entering = flight_path.is_some_and(FlightPath::entering);
+        if !entering && !playfield.contains(transform.translation.truncate()) {
+            if let Some(status) = waves.open.get_mut(&member.0) {
+                status.escaped = true;
+            }
+        }
+    }
+
+    let mut cleared = 0;
+    waves.open.retain(|wave, status| {
+        let finished = status.arrived >= status.size && !alive.contains_key(wave);
+        if finished && !status.escaped && status.killed >= status.size {
+            cleared += 1;
+        }
+        !finished
+    });
+
+    for _ in 0..cleared {
+        bonus_shower(&mut commands, &playfield, &assets);
+    }
+}
+
+// Rain score collectables down from across the top of the playfield
+fn bonus_shower(commands: &mut Commands, playfield: &Playfield, assets: &AssetServer) {
+    let spacing = playfield.width() / WAVE_BONUS_COLLECTABLES as f32;
+    for i in 0..WAVE_BONUS_COLLECTABLES {
+        // Far enough below the top wall that the collectables don't bounce off it
+        let target = Transform::from_xyz(
+            playfield.min.x + spacing * (i as f32 + 0.5),
+            playfield.max.y - 60.0,
+            0.0,
+        );
+        spawn_collectables(
+            commands,
+            1,
+            0,
+            &target,
+            assets,
+            Movement::new(Vec2::new(0.0, -5.0), Vec2::ZERO, false, Vec2::ZERO, Vec2::ZERO),
+        );
+    }
+}
+
+// Shape a group of enemies flies in
+#[derive(Debug, Clone, Copy)]
+pub enum WaveShape {
+    // Leader in front with the rest trailing behind on either side, `spacing` pixels apart
+    VWing { spacing: f32 },
+    // One behind the other
+    Column { spacing: f32 },
+    // Spread over `span` radians of a circle, the middle enemy in front
+    Arc { radius: f32, span: f32 },
+}
+
+impl WaveShape {
+    fn formation(self) -> Formation {
+        match self {
+            WaveShape::VWing { spacing } => Formation::v_wing(Vec2::splat(spacing)),
+            // Lined up going back up the screen
+            WaveShape::Column { spacing } => {
+                Formation::linear(Transform::from_xyz(0.0, 1.0, 0.0), Vec2::splat(spacing))
+            }
+            WaveShape::Arc { radius, span } => Formation::arc(radius, span),
+        }
+    }
+}
+
+// A group of enemies spawned together in a formation. They come in from the top along a path,
+// sweep across the playfield and leave, keeping their formation the whole way.
+#[derive(Debug, Clone, Copy)]
+pub struct WaveSpec {
+    pub kind: EnemyKind,
+    pub count: u8,
+    pub shape: WaveShape,
+    // Control points of the curve the formation follows, in playfield coordinates: -1 to 1
+    // from the left to the right wall and from the bottom to the top one
+    pub path: [Vec2; 4],
+    // Seconds taken to follow the path, and how they are spread along it
+    pub secs: f32,
+    pub easing: Easing,
+    pub attacks: fn() -> Attacks,
+}
+
+// Size of enemies spawned in formation
+const FORMATION_ENEMY_SIZE: f32 = 20.0;
+
+pub fn spawn_wave(
+    commands: &mut Commands,
+    spec: &WaveSpec,
+    waves: &mut Waves,
+    playfield: &Playfield,
+    atlases: &Atlases<'static>,
+    rng: &mut RunRng,
+) {
+    let path = spec
+        .path
+        .map(|point| playfield.center() + point * playfield.half_size());
+    let formation = spec.shape.formation();
+    let member = waves.start(spec.count);
+    for i in 0..spec.count as u16 {
+        let offset = formation
+            .transform(i, spec.count as u16, Transform::IDENTITY, &mut **rng)
+            .translation
+            .truncate();
+        let spawn_point = Transform::from_translation((path[0] + offset).extend(0.2));
+        let sprite = enemy::kind_sprite(atlases, spec.kind, FORMATION_ENEMY_SIZE);
+        let enemy = enemy::spawn_enemy(commands, spec.kind, spawn_point, (spec.attacks)(), sprite);
+        commands.entity(enemy).insert((
+            member,
+            FlightPath::default(),
+            // Once the path is over, the enemy keeps going the way it was headed
+            Movement::along(MovePath::bezier(path, spec.secs, spec.easing)),
+        ));
+    }
+}
+
+// Enemy flying in along a path. Enemies at the back of a formation start out above the
+// playfield, and don't count as having left it until they have been inside.
+#[derive(Component, Debug, Clone, Default)]
+pub struct FlightPath {
+    entered: bool,
+}
+
+impl FlightPath {
+    // Whether the enemy is still on its way into the playfield
+    pub fn entering(&self) -> bool {
+        !self.entered
+    }
+}
+
+pub fn track_entry(playfield: Res<Playfield>, mut enemies: Query<(&mut FlightPath, &Transform)>) {
+    for (mut path, transform) in enemies.iter_mut() {
+        if path.entering() && playfield.contains(transform.translation.truncate()) {
+            path.entered = true;
+        }
+    }
+}
@@ -0,0 +1,176 @@
+mod post_process;
+
+use crate::GameOptions;
+use bevy::prelude::*;
+use bevy::render::texture::ImageSampler;
+use bevy::sprite::Material2dPlugin;
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::utils::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+
+// How textures are sampled when sprites are drawn at a different size than the image.
+// Nearest keeps the pixel art sharp, linear smooths it out.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Debug, Default, Serialize, Deserialize)]
+pub enum TextureFiltering {
+    #[default]
+    Linear,
+    Nearest,
+}
+
+impl TextureFiltering {
+    pub fn next(self) -> Self {
+        match self {
+            TextureFiltering::Linear => TextureFiltering::Nearest,
+            TextureFiltering::Nearest => TextureFiltering::Linear,
+        }
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            TextureFiltering::Linear => "Smooth",
+            TextureFiltering::Nearest => "Sharp",
+        }
+    }
+    fn sampler(self) -> ImageSampler {
+        match self {
+            TextureFiltering::Linear => ImageSampler::linear(),
+            TextureFiltering::Nearest => ImageSampler::nearest(),
+        }
+    }
+}
+
+// Overall graphics quality. Low switches off all post-processing, whatever the individual
+// options are set to. It is the default on the web, where the GPU is often slower.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Debug, Serialize, Deserialize)]
+pub enum GraphicsQuality {
+    High,
+    Low,
+}
+
+impl Default for GraphicsQuality {
+    fn default() -> Self {
+        if cfg!(target_arch = "wasm32") {
+            GraphicsQuality::Low
+        } else {
+            GraphicsQuality::High
+        }
+    }
+}
+
+impl GraphicsQuality {
+    pub fn next(self) -> Self {
+        match self {
+            GraphicsQuality::High => GraphicsQuality::Low,
+            GraphicsQuality::Low => GraphicsQuality::High,
+        }
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            GraphicsQuality::High => "High",
+            GraphicsQuality::Low => "Low",
+        }
+    }
+}
+
+// Most frames a second drawn. Unlimited leaves it up to vsync.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Debug, Default, Serialize, Deserialize)]
+pub enum FrameCap {
+    Fps30,
+    Fps60,
+    Fps120,
+    #[default]
+    Unlimited,
+}
+
+impl FrameCap {
+    pub fn next(self) -> Self {
+        match self {
+            FrameCap::Fps30 => FrameCap::Fps60,
+            FrameCap::Fps60 => FrameCap::Fps120,
+            FrameCap::Fps120 => FrameCap::Unlimited,
+            FrameCap::Unlimited => FrameCap::Fps30,
+        }
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            FrameCap::Fps30 => "30 FPS",
+            FrameCap::Fps60 => "60 FPS",
+            FrameCap::Fps120 => "120 FPS",
+            FrameCap::Unlimited => "Unlimited",
+        }
+    }
+    // Shortest time a frame can take
+    #[cfg(not(target_arch = "wasm32"))]
+    fn frame_time(self) -> Option<Duration> {
+        let fps = match self {
+            FrameCap::Fps30 => 30,
+            FrameCap::Fps60 => 60,
+            FrameCap::Fps120 => 120,
+            FrameCap::Unlimited => return None,
+        };
+        Some(Duration::from_secs(1) / fps)
+    }
+}
+
+pub struct GraphicsPlugin;
+
+impl Plugin for GraphicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(Material2dPlugin::<post_process::CrtMaterial>::default())
+            .add_systems(Startup, post_process::spawn_crt_overlay)
+            .add_systems(Update, (apply_texture_filtering, post_process::apply_post_processing));
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(Last, limit_frame_rate);
+    }
+}
+
+// Wait out the rest of the frame when running faster than the frame cap. Gameplay is moved
+// by how long each frame took, so it runs at the same speed under every cap. Browsers already
+// pace frames to the display, and the game can't sleep there, so the cap only applies to the
+// native builds.
+#[cfg(not(target_arch = "wasm32"))]
+fn limit_frame_rate(options: Res<GameOptions>, mut last_frame: Local<Option<Instant>>) {
+    let frame_time = options.get_frame_cap().frame_time();
+    if let (Some(frame_time), Some(last_frame)) = (frame_time, *last_frame) {
+        let elapsed = last_frame.elapsed();
+        if elapsed < frame_time {
+            std::thread::sleep(frame_time - elapsed);
+        }
+    }
+    *last_frame = Some(Instant::now());
+}
+
+// All the sampler settings for the game's images are set here, rather than wherever the
+// images are loaded. Only images loaded from files are changed, so that generated
+// images such as font atlases keep their own settings.
+fn apply_texture_filtering(
+    options: Res<GameOptions>,
+    assets: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+    mut image_events: EventReader<AssetEvent<Image>>,
+    mut current: Local<Option<TextureFiltering>>,
+) {
+    let filtering = options.get_texture_filtering();
+
+    // The option was changed, so update every image
+    if *current != Some(filtering) {
+        for (id, image) in images.iter_mut() {
+            if assets.get_handle_path(id).is_some() {
+                image.sampler_descriptor = filtering.sampler();
+            }
+        }
+        *current = Some(filtering);
+        image_events.clear();
+        return;
+    }
+
+    // Otherwise only the images which have just loaded
+    for event in image_events.iter() {
+        let AssetEvent::Created { handle } = event else { continue; };
+        if assets.get_handle_path(handle).is_none() {
+            continue;
+        }
+        if let Some(image) = images.get_mut(handle) {
+            image.sampler_descriptor = filtering.sampler();
+        }
+    }
+}
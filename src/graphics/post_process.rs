@@ -0,0 +1,114 @@
+use super::GraphicsQuality;
+use crate::GameOptions;
+use bevy::core_pipeline::bloom::{BloomPrefilterSettings, BloomSettings};
+use bevy::prelude::*;
+use bevy::reflect::{TypePath, TypeUuid};
+use bevy::render::render_resource::{AsBindGroup, ShaderRef};
+use bevy::sprite::{Material2d, MaterialMesh2dBundle};
+use bevy::window::PrimaryWindow;
+
+// In front of everything in the game world, but still inside the camera's view
+const CRT_OVERLAY_Z: f32 = 900.0;
+// Window pixels per scanline
+const SCANLINE_SPACING: f32 = 3.0;
+
+// Scanlines and vignette drawn over the whole game by assets/shaders/crt.wgsl
+#[derive(AsBindGroup, TypeUuid, TypePath, Debug, Clone)]
+#[uuid = "47bd6043-b0dd-4d3e-b653-458221a4240a"]
+pub struct CrtMaterial {
+    // x: strength of the effect from 0 to 1, y: number of scanlines
+    #[uniform(0)]
+    params: Vec4,
+}
+
+impl Material2d for CrtMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/crt.wgsl".into()
+    }
+}
+
+#[derive(Component)]
+pub struct CrtOverlay;
+
+pub fn spawn_crt_overlay(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<CrtMaterial>>,
+) {
+    commands.spawn((
+        CrtOverlay,
+        MaterialMesh2dBundle {
+            mesh: meshes.add(shape::Quad::new(Vec2::ONE).into()).into(),
+            material: materials.add(CrtMaterial { params: Vec4::ZERO }),
+            transform: Transform::from_xyz(0.0, 0.0, CRT_OVERLAY_Z),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+    ));
+}
+
+// Keep the post-processing in line with the options. Bloom needs an HDR camera, so HDR is
+// only switched on while bloom is.
+#[allow(clippy::type_complexity)]
+pub fn apply_post_processing(
+    mut commands: Commands,
+    options: Res<GameOptions>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cameras: Query<(Entity, &mut Camera, Option<&mut BloomSettings>), With<Camera2d>>,
+    mut overlay: Query<
+        (&mut Transform, &mut Visibility, &Handle<CrtMaterial>),
+        With<CrtOverlay>,
+    >,
+    mut materials: ResMut<Assets<CrtMaterial>>,
+) {
+    let high_quality = options.get_graphics_quality() == GraphicsQuality::High;
+    let bloom = if high_quality { options.get_bloom() } else { 0.0 };
+    let crt = if high_quality { options.get_crt() } else { 0.0 };
+
+    for (entity, mut camera, settings) in cameras.iter_mut() {
+        let hdr = bloom > 0.0;
+        if camera.hdr != hdr {
+            camera.hdr = hdr;
+        }
+        match (settings, hdr) {
+            (Some(mut settings), true) => {
+                let intensity = 0.4 * bloom;
+                if settings.intensity != intensity {
+                    settings.intensity = intensity;
+                }
+            }
+            (None, true) => {
+                commands.entity(entity).insert(BloomSettings {
+                    intensity: 0.4 * bloom,
+                    // Only the brightest things, such as bullets and explosions, glow
+                    prefilter_settings: BloomPrefilterSettings {
+                        threshold: 0.8,
+                        threshold_softness: 0.3,
+                    },
+                    ..BloomSettings::NATURAL
+                });
+            }
+            (Some(_), false) => {
+                commands.entity(entity).remove::<BloomSettings>();
+            }
+            (None, false) => {}
+        }
+    }
+
+    let Ok(window) = windows.get_single() else { return; };
+    let Ok((mut transform, mut visibility, material)) = overlay.get_single_mut() else { return; };
+    let shown = if crt > 0.0 { Visibility::Inherited } else { Visibility::Hidden };
+    if *visibility != shown {
+        *visibility = shown;
+    }
+    let size = Vec3::new(window.width(), window.height(), 1.0);
+    if transform.scale != size {
+        transform.scale = size;
+    }
+    let params = Vec4::new(crt, window.height() / SCANLINE_SPACING, 0.0, 0.0);
+    if materials.get(material).map(|m| m.params) != Some(params) {
+        if let Some(material) = materials.get_mut(material) {
+            material.params = params;
+        }
+    }
+}
@@ -0,0 +1,354 @@
+// The best runs the player has had, kept between launches. When a run ends with a score good
+// enough for the table, the player is asked for their initials on top of the game over or win
+// screen before the score is added.
+#[cfg(not(target_arch = "wasm32"))]
+mod screenshot;
+
+use crate::gameplay::difficulty::Difficulty;
+use crate::gameplay::input::{Control, KeyBindings};
+use crate::gameplay::levels::{endless::EndlessWave, GameMode};
+use crate::gameplay::player::RunEnd;
+use crate::gameplay::GameplayTime;
+use crate::ui::theme::Theme;
+use crate::ui::widgets::spawn_modal;
+use crate::GameState;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Number of runs kept in the table
+const MAX_ENTRIES: usize = 10;
+const INITIALS: usize = 3;
+
+// How far a run got before it ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Reached {
+    Stage(u8),
+    AllClear,
+    // Endless runs recorded before waves were counted
+    Endless,
+    // Wave an endless run got to
+    Wave(u32),
+}
+
+impl Reached {
+    pub fn name(&self) -> String {
+        match self {
+            Reached::Stage(stage) => format!("Stage {}", stage),
+            Reached::AllClear => String::from("All Clear"),
+            Reached::Endless => String::from("Endless"),
+            Reached::Wave(wave) => format!("Endless Wave {}", wave),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighScoreEntry {
+    pub score: u64,
+    pub reached: Reached,
+    pub difficulty: Difficulty,
+    // Seconds since the Unix epoch
+    pub date: u64,
+    pub initials: String,
+}
+
+impl HighScoreEntry {
+    // Date the run was played on, as YYYY-MM-DD
+    pub fn date_text(&self) -> String {
+        // Days to civil date, from Howard Hinnant's date algorithms
+        let days = (self.date / 86400) as i64 + 719468;
+        let era = days.div_euclid(146097);
+        let day_of_era = days.rem_euclid(146097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = year_of_era + era * 400 + (month <= 2) as i64;
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+// Best runs, highest score first, and the furthest wave reached in endless mode
+#[derive(Resource, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HighScoreTable {
+    entries: Vec<HighScoreEntry>,
+    // Kept apart from the entries, as it may come from a run which didn't score enough for them
+    #[serde(default)]
+    best_wave: u32,
+}
+
+impl HighScoreTable {
+    pub fn entries(&self) -> &[HighScoreEntry] {
+        &self.entries
+    }
+
+    pub fn best_wave(&self) -> u32 {
+        self.best_wave
+    }
+
+    pub fn best(&self) -> u64 {
+        self.entries.first().map_or(0, |entry| entry.score)
+    }
+
+    // Whether a score would make it into the table
+    pub fn qualifies(&self, score: u64) -> bool {
+        score > 0
+            && (self.entries.len() < MAX_ENTRIES
+                || self.entries.last().map_or(true, |entry| score > entry.score))
+    }
+
+    // Add a run to the table, dropping the lowest one if it is full. Runs with the same score
+    // as an older one are placed below it.
+    // Table holding only the best score kept by older versions. Everything but the score is
+    // unknown, so the run is taken to have been played on stage 1 on the day it is carried over.
+    pub fn from_old_high_score(score: u64) -> Self {
+        let mut table = Self::default();
+        if score > 0 {
+            table.insert(HighScoreEntry {
+                score,
+                reached: Reached::Stage(1),
+                difficulty: Difficulty::default(),
+                date: unix_time(),
+                initials: String::from("???"),
+            });
+        }
+        table
+    }
+
+    pub fn insert(&mut self, entry: HighScoreEntry) {
+        let position = self.entries.partition_point(|other| other.score >= entry.score);
+        self.entries.insert(position, entry);
+        self.entries.truncate(MAX_ENTRIES);
+    }
+}
+
+// Run waiting for the player to enter their initials
+#[derive(Resource, Debug, Default)]
+pub struct PendingHighScore(Option<HighScoreEntry>);
+
+// Initials being entered, as indices into the alphabet, and the letter being changed
+#[derive(Resource, Debug, Default)]
+struct InitialsEntry {
+    letters: [u8; INITIALS],
+    cursor: usize,
+}
+
+impl InitialsEntry {
+    fn text(&self) -> String {
+        self.letters.iter().map(|letter| (b'A' + letter) as char).collect()
+    }
+}
+
+// Marker for the initials entry dialog
+#[derive(Component)]
+struct InInitialsEntry;
+
+// Marker for the text showing the initials being entered
+#[derive(Component)]
+struct InitialsText;
+
+pub struct HighScorePlugin;
+
+impl Plugin for HighScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PendingHighScore>()
+            .init_resource::<InitialsEntry>()
+            .add_systems(OnEnter(GameState::GameOver), (record_run, spawn_initials_entry).chain())
+            .add_systems(OnEnter(GameState::GameWon), (record_run, spawn_initials_entry).chain())
+            .add_systems(
+                Update,
+                (enter_initials, update_initials_text)
+                    .chain()
+                    .run_if(in_state(GameState::GameOver).or_else(in_state(GameState::GameWon))),
+            )
+            // Leaving the screen before confirming still keeps the score
+            .add_systems(OnExit(GameState::GameOver), finish_initials_entry)
+            .add_systems(OnExit(GameState::GameWon), finish_initials_entry);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        app.init_resource::<screenshot::HighScoreScreenshot>()
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                screenshot::queue_screenshot.after(record_run),
+            )
+            .add_systems(
+                OnEnter(GameState::GameWon),
+                screenshot::queue_screenshot.after(record_run),
+            )
+            .add_systems(
+                Update,
+                screenshot::take_screenshot
+                    .run_if(in_state(GameState::GameOver).or_else(in_state(GameState::GameWon))),
+            );
+    }
+}
+
+// Seconds since the Unix epoch, for dating runs
+#[cfg(not(target_arch = "wasm32"))]
+fn unix_time() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs())
+}
+
+// The standard library has no clock on the web, so the browser's is used instead
+#[cfg(target_arch = "wasm32")]
+fn unix_time() -> u64 {
+    (web_sys::js_sys::Date::now() / 1000.0) as u64
+}
+
+// Keep the result of a run which just ended, if it is good enough for the table. Only runs on
+// the game over and win screens, so runs which are quit from the pause menu don't count.
+fn record_run(
+    run_end: Option<Res<RunEnd>>,
+    mut table: ResMut<HighScoreTable>,
+    difficulty: Res<Difficulty>,
+    game_mode: Res<GameMode>,
+    g_time: Option<Res<GameplayTime>>,
+    endless_wave: Option<Res<EndlessWave>>,
+    mut pending: ResMut<PendingHighScore>,
+) {
+    pending.0 = None;
+    let Some(run_end) = run_end else {
+        return;
+    };
+    let won = run_end.won;
+    let wave = endless_wave.map_or(0, |wave| wave.number());
+    if *game_mode == GameMode::Endless && wave > table.best_wave {
+        table.best_wave = wave;
+    }
+    if !table.qualifies(run_end.score) {
+        return;
+    }
+
+    let cleared = g_time.map_or(0, |g_time| g_time.splits().len());
+    let reached = match (*game_mode, won) {
+        (GameMode::Endless, _) => Reached::Wave(wave),
+        (GameMode::Story, true) => Reached::AllClear,
+        (GameMode::Story, false) => Reached::Stage(cleared as u8 + 1),
+        // Practice runs skip most of the story, so they aren't comparable with other runs
+        (GameMode::Practice, _) => return,
+    };
+    pending.0 = Some(HighScoreEntry {
+        score: run_end.score,
+        reached,
+        difficulty: *difficulty,
+        date: unix_time(),
+        initials: String::new(),
+    });
+}
+
+fn spawn_initials_entry(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    pending: Res<PendingHighScore>,
+    mut initials: ResMut<InitialsEntry>,
+) {
+    let Some(entry) = &pending.0 else {
+        return;
+    };
+    *initials = InitialsEntry::default();
+
+    spawn_modal(&mut commands, InInitialsEntry, |parent| {
+        parent.spawn(TextBundle::from_section("New High Score!", theme.text_style(40.0)));
+        parent.spawn(TextBundle::from_section(
+            entry.score.to_string(),
+            theme.text_style(30.0),
+        ));
+        // Filled in by update_initials_text
+        parent.spawn((
+            TextBundle::from_section("", theme.text_style(60.0)).with_style(Style {
+                margin: UiRect::vertical(Val::Px(20.0)),
+                ..default()
+            }),
+            InitialsText,
+        ));
+        parent.spawn(TextBundle::from_section(
+            "Up/Down: change letter\nLeft/Right: move\nEnter: confirm",
+            theme.text_style(20.0),
+        ));
+    });
+}
+
+// Change the initials with the movement keys, and confirm them with enter. The fire key isn't
+// used, as it is likely to still be mashed as the run ends.
+fn enter_initials(
+    mut commands: Commands,
+    input: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut initials: ResMut<InitialsEntry>,
+    mut pending: ResMut<PendingHighScore>,
+    mut table: ResMut<HighScoreTable>,
+    dialogs: Query<Entity, With<InInitialsEntry>>,
+) {
+    if pending.0.is_none() {
+        return;
+    }
+    let pressed = |control| bindings.just_pressed(&input, control);
+    let cursor = initials.cursor;
+
+    if pressed(Control::Up) {
+        initials.letters[cursor] = (initials.letters[cursor] + 1) % 26;
+    }
+    if pressed(Control::Down) {
+        initials.letters[cursor] = (initials.letters[cursor] + 25) % 26;
+    }
+    if pressed(Control::Left) {
+        initials.cursor = cursor.saturating_sub(1);
+    }
+    if pressed(Control::Right) {
+        initials.cursor = (cursor + 1).min(INITIALS - 1);
+    }
+
+    if input.just_pressed(KeyCode::Return) {
+        if let Some(mut entry) = pending.0.take() {
+            entry.initials = initials.text();
+            table.insert(entry);
+        }
+        for dialog in dialogs.iter() {
+            commands.entity(dialog).despawn_recursive();
+        }
+    }
+}
+
+// Show the initials, with the letter being changed in brackets
+fn update_initials_text(
+    initials: Res<InitialsEntry>,
+    mut texts: Query<&mut Text, With<InitialsText>>,
+) {
+    if !initials.is_changed() {
+        return;
+    }
+    let text = initials
+        .text()
+        .chars()
+        .enumerate()
+        .map(|(i, letter)| {
+            if i == initials.cursor {
+                format!("[{}]", letter)
+            } else {
+                format!(" {} ", letter)
+            }
+        })
+        .collect::<String>();
+    for mut initials_text in texts.iter_mut() {
+        initials_text.sections[0].value = text.clone();
+    }
+}
+
+fn finish_initials_entry(
+    mut commands: Commands,
+    initials: Res<InitialsEntry>,
+    mut pending: ResMut<PendingHighScore>,
+    mut table: ResMut<HighScoreTable>,
+    dialogs: Query<Entity, With<InInitialsEntry>>,
+) {
+    if let Some(mut entry) = pending.0.take() {
+        entry.initials = initials.text();
+        table.insert(entry);
+    }
+    for dialog in dialogs.iter() {
+        commands.entity(dialog).despawn_recursive();
+    }
+}
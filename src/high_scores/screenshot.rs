@@ -0,0 +1,76 @@
+// Screenshot of the results of a run which beat the best score in the high score table, taken
+// on its own while the initials entry is up, so that the score shows on it. Only on native builds, as
+// there is nowhere to save an image to on the web.
+use super::{HighScoreTable, PendingHighScore};
+use crate::storage::{self, Storage};
+use crate::ui::{theme::Theme, widgets::spawn_toast};
+use bevy::{prelude::*, render::view::screenshot::ScreenshotManager, window::PrimaryWindow};
+
+// Time given to the results screen to be laid out before it is captured
+const DELAY_SECS: f32 = 0.5;
+
+#[derive(Debug)]
+enum Step {
+    // Waiting to take the screenshot, with the name it will be saved under
+    Waiting(Timer, String),
+    // Taken this frame. The toast is only shown on the next one, so that it isn't captured too.
+    // The image is written out in the background after that, so the toast can only say that it
+    // is being saved.
+    Taken(String),
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct HighScoreScreenshot(Option<Step>);
+
+// Set up a screenshot for the results screen, if the run is a new top score. Runs before the
+// run is added to the table, so the best score is still the one it has to beat.
+pub fn queue_screenshot(
+    pending: Res<PendingHighScore>,
+    table: Res<HighScoreTable>,
+    mut screenshot: ResMut<HighScoreScreenshot>,
+) {
+    let top_score = pending.0.as_ref().filter(|entry| entry.score > table.best());
+    screenshot.0 = top_score.map(|entry| {
+        Step::Waiting(
+            Timer::from_seconds(DELAY_SECS, TimerMode::Once),
+            format!("high_score_{}_{}.png", entry.date, entry.score),
+        )
+    });
+}
+
+pub fn take_screenshot(
+    mut commands: Commands,
+    time: Res<Time>,
+    theme: Res<Theme>,
+    mut screenshot: ResMut<HighScoreScreenshot>,
+    mut manager: ResMut<ScreenshotManager>,
+    window: Query<Entity, With<PrimaryWindow>>,
+) {
+    match screenshot.0.take() {
+        Some(Step::Waiting(mut timer, name)) => {
+            if !timer.tick(time.delta()).finished() {
+                screenshot.0 = Some(Step::Waiting(timer, name));
+                return;
+            }
+            let Ok(window) = window.get_single() else { return; };
+            match storage::path(Storage::Screenshots, &name) {
+                Ok(path) => match manager.save_screenshot_to_disk(window, &path) {
+                    Ok(()) => screenshot.0 = Some(Step::Taken(path.display().to_string())),
+                    Err(error) => {
+                        error!("Couldn't take screenshot {}: {}", path.display(), error);
+                        let message = format!("Couldn't take screenshot: {}", error);
+                        spawn_toast(&mut commands, &theme, &message);
+                    }
+                },
+                Err(error) => {
+                    let message = format!("Couldn't save screenshot: {}", error);
+                    spawn_toast(&mut commands, &theme, &message);
+                }
+            }
+        }
+        Some(Step::Taken(path)) => {
+            spawn_toast(&mut commands, &theme, &format!("Saving screenshot to {}", path));
+        }
+        None => {}
+    }
+}
@@ -0,0 +1,78 @@
+// Arcade presentation for the title screen. With arcade mode on, the coin key adds credits and
+// starting a game takes one. Coins are free, this only changes how the title screen behaves.
+use super::InMainMenu;
+use crate::gameplay::input::{Control, KeyBindings};
+use crate::ui::theme::Theme;
+use crate::GameOptions;
+use bevy::prelude::*;
+
+// Most credits that can be stored up, as on the single digit counters of arcade cabinets
+const MAX_CREDITS: u32 = 9;
+// Time the "Insert Coin" text is shown and hidden for while blinking
+const BLINK_SECS: f32 = 0.5;
+
+// Credits inserted and not yet used. Not saved, like on a cabinet that was switched off.
+#[derive(Resource, Debug, Default)]
+pub struct Credits(pub u32);
+
+// Marker for the credit counter under the title
+#[derive(Component)]
+pub struct CreditText;
+
+pub fn setup(mut commands: Commands, theme: Res<Theme>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    top: Val::Px(230.0),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            InMainMenu,
+        ))
+        .with_children(|parent| {
+            // The text is filled in by update_credit_text
+            parent.spawn((TextBundle::from_section("", theme.text_style(36.0)), CreditText));
+        });
+}
+
+pub fn insert_coin(
+    input: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    options: Res<GameOptions>,
+    mut credits: ResMut<Credits>,
+) {
+    if options.get_arcade_mode() && bindings.just_pressed(&input, Control::Coin) {
+        credits.0 = (credits.0 + 1).min(MAX_CREDITS);
+    }
+}
+
+// Blink "Insert Coin" until there is a credit, then show how many there are
+pub fn update_credit_text(
+    time: Res<Time>,
+    options: Res<GameOptions>,
+    credits: Res<Credits>,
+    mut texts: Query<(&mut Text, &mut Visibility), With<CreditText>>,
+) {
+    for (mut text, mut visibility) in texts.iter_mut() {
+        let blink_off = (time.elapsed_seconds() / BLINK_SECS) as u32 % 2 == 1;
+        *visibility = if !options.get_arcade_mode() || (credits.0 == 0 && blink_off) {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+
+        let value = if credits.0 == 0 {
+            String::from("INSERT COIN")
+        } else {
+            format!("CREDITS {}", credits.0)
+        };
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use crate::gameplay::bestiary::{Bestiary, EnemyKind};
+
+use super::Action;
+use super::InBestiaryMenu;
+use crate::ui::theme::Theme;
+use crate::ui::widgets::spawn_button;
+use bevy::prelude::*;
+
+// Size of the enemy pictures. Sprites which aren't square are squashed to fit.
+const PICTURE_SIZE: f32 = 48.0;
+
+// List every kind of enemy, with the details of the ones the player has come across
+pub fn setup(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    bestiary: Res<Bestiary>,
+    asset_server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+) {
+    let style = theme.widget_style(175.0, 50.0, 40.0);
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(95.0),
+                    height: Val::Percent(95.0),
+                    top: Val::Px(15.0),
+                    left: Val::Px(15.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::FlexStart,
+                    ..default()
+                },
+                ..default()
+            },
+            InBestiaryMenu,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("Bestiary", theme.text_style(40.0)));
+
+            for kind in EnemyKind::ALL {
+                let row = Style {
+                    align_items: AlignItems::Center,
+                    margin: UiRect::top(Val::Px(10.0)),
+                    ..default()
+                };
+                parent.spawn(NodeBundle { style: row, ..default() }).with_children(|parent| {
+                    let picture = Style {
+                        width: Val::Px(PICTURE_SIZE),
+                        height: Val::Px(PICTURE_SIZE),
+                        margin: UiRect::right(Val::Px(15.0)),
+                        ..default()
+                    };
+                    if !bestiary.encountered(kind) {
+                        // Undiscovered enemies only take up their space in the list
+                        parent.spawn(NodeBundle {
+                            style: picture,
+                            background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                            ..default()
+                        });
+                        parent.spawn(TextBundle::from_section("???", theme.text_style(24.0)));
+                        return;
+                    }
+
+                    let (_, colour) = kind.sprite();
+                    parent.spawn(AtlasImageBundle {
+                        style: picture,
+                        texture_atlas: kind.texture_atlas(&asset_server, &mut texture_atlases),
+                        background_color: colour.into(),
+                        ..default()
+                    });
+                    parent.spawn(TextBundle::from_sections([
+                        TextSection::new(
+                            format!("{}  ", kind.name()),
+                            theme.text_style(24.0),
+                        ),
+                        TextSection::new(
+                            format!(
+                                "Health {}  Defeated {}\n{}",
+                                kind.health(),
+                                bestiary.kills(kind),
+                                kind.attacks(),
+                            ),
+                            theme.text_style(18.0),
+                        ),
+                    ]));
+                });
+            }
+
+            spawn_button(parent, Action::GoToMenu, "Back", &style);
+        });
+}
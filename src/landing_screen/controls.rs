@@ -0,0 +1,112 @@
+use crate::gameplay::input::{Control, KeyBindings};
+
+use super::Action;
+use super::InControlsMenu;
+use super::options::options_widget_style;
+use crate::ui::theme::Theme;
+use crate::ui::widgets::{spawn_button, spawn_labeled_row};
+use bevy::prelude::*;
+
+// Marker for the label of a control, showing the key it is bound to
+#[derive(Debug, Clone, Copy, Component)]
+pub struct ControlText(Control);
+
+#[derive(Debug, Clone, Copy, Component)]
+pub enum ControlAction {
+    Rebind(Control),
+    Reset,
+}
+
+// Control waiting for a new key, after its button was pressed
+#[derive(Resource, Debug, Default)]
+pub struct Rebinding(Option<Control>);
+
+pub fn setup(mut commands: Commands, theme: Res<Theme>) {
+    let style = options_widget_style(&theme);
+    commands.insert_resource(Rebinding::default());
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(95.0),
+                    height: Val::Percent(95.0),
+                    top: Val::Px(15.0),
+                    left: Val::Px(15.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::FlexStart,
+                    ..default()
+                },
+                ..default()
+            },
+            InControlsMenu,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("Controls", theme.text_style(40.0)));
+
+            for control in Control::ALL {
+                // The label text is filled in by update_control_text
+                spawn_labeled_row(parent, "", ControlText(control), &style, |parent| {
+                    spawn_button(parent, ControlAction::Rebind(control), "Change", &style);
+                });
+            }
+            spawn_labeled_row(parent, "Default Keys", (), &style, |parent| {
+                spawn_button(parent, ControlAction::Reset, "Reset", &style);
+            });
+            spawn_labeled_row(parent, "Back", (), &style, |parent| {
+                spawn_button(parent, Action::GoToOptions, "Back", &style);
+            });
+        });
+}
+
+#[allow(clippy::type_complexity)]
+pub fn control_interactions(
+    interaction: Query<(&Interaction, &ControlAction), (Changed<Interaction>, With<Button>)>,
+    mut rebinding: ResMut<Rebinding>,
+    mut key_bindings: ResMut<KeyBindings>,
+) {
+    for (interaction, action) in interaction.iter() {
+        if *interaction == Interaction::Pressed {
+            match action {
+                ControlAction::Rebind(control) => rebinding.0 = Some(*control),
+                ControlAction::Reset => {
+                    *key_bindings = KeyBindings::default();
+                    rebinding.0 = None;
+                }
+            }
+        }
+    }
+}
+
+// Bind the next key pressed to the control waiting for one. Escape cancels instead, so that
+// it can't be bound to anything and is always free for pausing.
+pub fn rebind_key(
+    keys: Res<Input<KeyCode>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut key_bindings: ResMut<KeyBindings>,
+) {
+    let Some(control) = rebinding.0 else {
+        return;
+    };
+    let Some(key) = keys.get_just_pressed().next() else {
+        return;
+    };
+    if *key != KeyCode::Escape {
+        key_bindings.set(control, *key);
+    }
+    rebinding.0 = None;
+}
+
+pub fn update_control_text(
+    mut query: Query<(&mut Text, &ControlText)>,
+    key_bindings: Res<KeyBindings>,
+    rebinding: Res<Rebinding>,
+) {
+    for (mut text, ControlText(control)) in &mut query {
+        text.sections[0].value = if rebinding.0 == Some(*control) {
+            format!("{}: press a key", control.name())
+        } else {
+            format!("{}: {:?}", control.name(), key_bindings.get(*control))
+        };
+    }
+}
@@ -0,0 +1,210 @@
+use crate::gameplay::bestiary::{Bestiary, EnemyKind};
+
+use super::Action;
+use super::InGalleryMenu;
+use crate::ui::theme::Theme;
+use crate::ui::widgets::spawn_button;
+use bevy::prelude::*;
+
+// Art that can be looked at in the gallery, in the order it is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GalleryPiece {
+    Stage(u8),
+    Boss(EnemyKind),
+}
+
+impl GalleryPiece {
+    const ALL: [GalleryPiece; 7] = [
+        GalleryPiece::Stage(1),
+        GalleryPiece::Boss(EnemyKind::BigBoss),
+        GalleryPiece::Stage(2),
+        GalleryPiece::Boss(EnemyKind::BiggerBoss),
+        GalleryPiece::Stage(3),
+        GalleryPiece::Boss(EnemyKind::BiggestBoss),
+        GalleryPiece::Boss(EnemyKind::MiniBoss),
+    ];
+
+    fn title(&self) -> String {
+        match self {
+            GalleryPiece::Stage(stage) => format!("Stage {}", stage),
+            GalleryPiece::Boss(kind) => kind.name().to_string(),
+        }
+    }
+
+    // A stage is unlocked by beating its boss, and a boss by beating it once
+    fn unlocked(&self, bestiary: &Bestiary) -> bool {
+        let boss = match self {
+            GalleryPiece::Stage(1) => EnemyKind::BigBoss,
+            GalleryPiece::Stage(2) => EnemyKind::BiggerBoss,
+            GalleryPiece::Stage(_) => EnemyKind::BiggestBoss,
+            GalleryPiece::Boss(kind) => *kind,
+        };
+        bestiary.kills(boss) > 0
+    }
+
+    // Size the picture is shown at. Whole multiples of the image size, so that the pixel art
+    // stays sharp.
+    fn size(&self) -> Vec2 {
+        match self {
+            GalleryPiece::Stage(1) => Vec2::new(256.0, 320.0),
+            GalleryPiece::Stage(2) => Vec2::new(256.0, 272.0),
+            GalleryPiece::Stage(_) => Vec2::new(384.0, 192.0),
+            GalleryPiece::Boss(_) => Vec2::splat(192.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Component)]
+pub enum GalleryAction {
+    Previous,
+    Next,
+}
+
+// Piece of art currently shown, as an index into GalleryPiece::ALL
+#[derive(Resource, Debug, Default)]
+pub struct GallerySelection(usize);
+
+// Marker for the node the current picture is placed in
+#[derive(Component)]
+pub struct GalleryFrame;
+
+// Marker for the caption under the current picture
+#[derive(Component)]
+pub struct GalleryCaption;
+
+pub fn setup(mut commands: Commands, theme: Res<Theme>) {
+    let style = theme.widget_style(175.0, 50.0, 40.0);
+    commands.insert_resource(GallerySelection::default());
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(95.0),
+                    height: Val::Percent(95.0),
+                    top: Val::Px(15.0),
+                    left: Val::Px(15.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            InGalleryMenu,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("Gallery", theme.text_style(40.0)));
+            // The picture is added by show_gallery_piece
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(400.0),
+                        height: Val::Px(400.0),
+                        margin: UiRect::vertical(Val::Px(15.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                    ..default()
+                },
+                GalleryFrame,
+            ));
+            parent.spawn((TextBundle::from_section("", theme.text_style(24.0)), GalleryCaption));
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        margin: UiRect::top(Val::Px(10.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    spawn_button(parent, GalleryAction::Previous, "<", &style);
+                    spawn_button(parent, GalleryAction::Next, ">", &style);
+                });
+            spawn_button(parent, Action::GoToMenu, "Back", &style);
+        });
+}
+
+#[allow(clippy::type_complexity)]
+pub fn gallery_interactions(
+    interaction: Query<(&Interaction, &GalleryAction), (Changed<Interaction>, With<Button>)>,
+    mut selection: ResMut<GallerySelection>,
+) {
+    let count = GalleryPiece::ALL.len();
+    for (interaction, action) in interaction.iter() {
+        if *interaction == Interaction::Pressed {
+            selection.0 = match action {
+                GalleryAction::Previous => (selection.0 + count - 1) % count,
+                GalleryAction::Next => (selection.0 + 1) % count,
+            };
+        }
+    }
+}
+
+// Put the selected piece in the frame. Images are only loaded once they are shown, and the
+// picture holds the only handle to its image, so replacing the picture or leaving the
+// gallery lets the image be unloaded again.
+#[allow(clippy::too_many_arguments)]
+pub fn show_gallery_piece(
+    mut commands: Commands,
+    selection: Res<GallerySelection>,
+    bestiary: Res<Bestiary>,
+    theme: Res<Theme>,
+    asset_server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    frames: Query<Entity, With<GalleryFrame>>,
+    mut captions: Query<&mut Text, With<GalleryCaption>>,
+) {
+    if !selection.is_changed() {
+        return;
+    }
+    let Ok(frame) = frames.get_single() else {
+        return;
+    };
+    let piece = GalleryPiece::ALL[selection.0];
+    let unlocked = piece.unlocked(&bestiary);
+
+    for mut caption in captions.iter_mut() {
+        caption.sections[0].value = if unlocked {
+            format!("{}  ({}/{})", piece.title(), selection.0 + 1, GalleryPiece::ALL.len())
+        } else {
+            format!("???  ({}/{})", selection.0 + 1, GalleryPiece::ALL.len())
+        };
+    }
+
+    commands.entity(frame).despawn_descendants();
+    if !unlocked {
+        commands.entity(frame).with_children(|parent| {
+            parent.spawn(TextBundle::from_section("Locked", theme.text_style(24.0)));
+        });
+        return;
+    }
+
+    let size = piece.size();
+    let style = Style {
+        width: Val::Px(size.x),
+        height: Val::Px(size.y),
+        ..default()
+    };
+    commands.entity(frame).with_children(|parent| match piece {
+        GalleryPiece::Stage(stage) => {
+            parent.spawn(ImageBundle {
+                style,
+                image: asset_server.load(format!("backgrounds/level_{}.png", stage)).into(),
+                ..default()
+            });
+        }
+        GalleryPiece::Boss(kind) => {
+            let (_, colour) = kind.sprite();
+            parent.spawn(AtlasImageBundle {
+                style,
+                texture_atlas: kind.texture_atlas(&asset_server, &mut texture_atlases),
+                background_color: colour.into(),
+                ..default()
+            });
+        }
+    });
+}
@@ -1,23 +1,16 @@
 use super::Action;
 use super::InMainMenu;
+use crate::gameplay::difficulty::Difficulty;
+use crate::gameplay::levels::palette::Palette;
+use crate::gameplay::scrape::ScoringMode;
+use crate::ui::theme::Theme;
+use crate::ui::widgets::spawn_button;
+use crate::GameOptions;
 use bevy::prelude::*;
 
 // Create the main menu
-pub fn setup(mut commands: Commands, assets: Res<AssetServer>) {
-    let font: Handle<Font> = assets.load("fonts/FiraSans-Bold.ttf");
-    let button_style = Style {
-        width: Val::Px(175.0),
-        height: Val::Px(50.0),
-        margin: UiRect::all(Val::Px(15.0)),
-        justify_content: JustifyContent::Center,
-        align_items: AlignItems::Center,
-        ..default()
-    };
-    let text_style = TextStyle {
-        font: font.clone(),
-        font_size: 40.0,
-        color: crate::ui::TEXT_COLOUR,
-    };
+pub fn setup(mut commands: Commands, theme: Res<Theme>) {
+    let style = theme.widget_style(175.0, 50.0, 40.0);
 
     commands
         .spawn((
@@ -43,11 +36,7 @@ pub fn setup(mut commands: Commands, assets: Res<AssetServer>) {
             parent.spawn(
                 TextBundle::from_section(
                     env!("CARGO_PKG_NAME").to_string(),
-                    TextStyle {
-                        font: font.clone(),
-                        font_size: 60.0,
-                        color: crate::ui::TEXT_COLOUR,
-                    },
+                    theme.text_style(60.0),
                 )
                 .with_style(Style {
                     margin: UiRect::all(Val::Px(50.0)),
@@ -55,7 +44,17 @@ pub fn setup(mut commands: Commands, assets: Res<AssetServer>) {
                 }),
             );
         });
-    // Create buttons list
+    // Create buttons list. There are too many buttons for a single column to fit under the
+    // title, so the records and settings are placed in a second column to the left.
+    let column = || NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::FlexEnd,
+            align_items: AlignItems::FlexStart,
+            ..default()
+        },
+        ..default()
+    };
     commands
         .spawn((
             NodeBundle {
@@ -63,9 +62,8 @@ pub fn setup(mut commands: Commands, assets: Res<AssetServer>) {
                     position_type: PositionType::Absolute,
                     right: Val::Px(20.0),
                     bottom: Val::Px(10.0),
-                    flex_direction: FlexDirection::Column,
-                    justify_content: JustifyContent::FlexStart,
-                    align_items: AlignItems::FlexStart,
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::FlexEnd,
                     ..default()
                 },
                 // Background colour of space around buttons
@@ -75,41 +73,106 @@ pub fn setup(mut commands: Commands, assets: Res<AssetServer>) {
             InMainMenu,
         ))
         .with_children(|parent| {
-            parent
-                .spawn((
-                    ButtonBundle {
-                        style: button_style.clone(),
-                        background_color: crate::ui::BUTTON_BASE.into(),
-                        ..default()
-                    },
-                    Action::StartGameplay,
-                ))
-                .with_children(|parent| {
-                    parent.spawn(TextBundle::from_section("Play", text_style.clone()));
-                });
-            parent
-                .spawn((
-                    ButtonBundle {
-                        style: button_style.clone(),
-                        background_color: crate::ui::BUTTON_BASE.into(),
-                        ..default()
-                    },
-                    Action::GoToOptions,
-                ))
-                .with_children(|parent| {
-                    parent.spawn(TextBundle::from_section("Settings", text_style.clone()));
-                });
-            parent
-                .spawn((
-                    ButtonBundle {
-                        style: button_style.clone(),
-                        background_color: crate::ui::BUTTON_BASE.into(),
-                        ..default()
-                    },
-                    Action::Exit,
-                ))
-                .with_children(|parent| {
-                    parent.spawn(TextBundle::from_section("Quit", text_style.clone()));
-                });
+            parent.spawn(column()).with_children(|parent| {
+                // The label is filled in by update_arcade_text
+                spawn_button(parent, Action::ToggleArcade, "", &style);
+                spawn_button(parent, Action::GoToScores, "Scores", &style);
+                spawn_button(parent, Action::GoToBestiary, "Bestiary", &style);
+                spawn_button(parent, Action::GoToGallery, "Gallery", &style);
+                spawn_button(parent, Action::GoToShop, "Shop", &style);
+                spawn_button(parent, Action::GoToOptions, "Settings", &style);
+                spawn_button(parent, Action::Exit, "Quit", &style);
+            });
+            parent.spawn(column()).with_children(|parent| {
+                spawn_button(parent, Action::StartGameplay, "Play", &style);
+                spawn_button(parent, Action::StartSimplified, "Simple", &style);
+                spawn_button(parent, Action::StartEndless, "Endless", &style);
+                spawn_button(parent, Action::StartVersus, "Versus", &style);
+                spawn_button(parent, Action::GoToPractice, "Practice", &style);
+                // The label is filled in by update_palette_text
+                spawn_button(parent, Action::CyclePalette, "", &style);
+                // The label is filled in by update_scoring_text
+                spawn_button(parent, Action::CycleScoring, "", &style);
+                // The label is filled in by update_difficulty_text
+                spawn_button(parent, Action::CycleDifficulty, "", &style);
+            });
         });
 }
+
+// Show the palette that the levels will be played with on its button
+pub fn update_palette_text(
+    palette: Res<Palette>,
+    buttons: Query<(&Action, &Children)>,
+    texts: Query<&mut Text>,
+) {
+    set_button_label(
+        &buttons,
+        texts,
+        |action| matches!(action, Action::CyclePalette),
+        palette.name(),
+    );
+}
+
+// Show how grazing bullets is scored on its button
+pub fn update_scoring_text(
+    scoring: Res<ScoringMode>,
+    buttons: Query<(&Action, &Children)>,
+    texts: Query<&mut Text>,
+) {
+    let label = format!("Score: {}", scoring.name());
+    set_button_label(
+        &buttons,
+        texts,
+        |action| matches!(action, Action::CycleScoring),
+        &label,
+    );
+}
+
+// Show the difficulty that the levels will be played at on its button
+pub fn update_difficulty_text(
+    difficulty: Res<Difficulty>,
+    buttons: Query<(&Action, &Children)>,
+    texts: Query<&mut Text>,
+) {
+    set_button_label(
+        &buttons,
+        texts,
+        |action| matches!(action, Action::CycleDifficulty),
+        difficulty.name(),
+    );
+}
+
+// Show whether arcade mode is on on its button
+pub fn update_arcade_text(
+    options: Res<GameOptions>,
+    buttons: Query<(&Action, &Children)>,
+    texts: Query<&mut Text>,
+) {
+    let label = if options.get_arcade_mode() { "Arcade: On" } else { "Arcade: Off" };
+    set_button_label(
+        &buttons,
+        texts,
+        |action| matches!(action, Action::ToggleArcade),
+        label,
+    );
+}
+
+// Change the text of the buttons picked out by `is_button`, if it isn't `label` already
+fn set_button_label(
+    buttons: &Query<(&Action, &Children)>,
+    mut texts: Query<&mut Text>,
+    is_button: impl Fn(&Action) -> bool,
+    label: &str,
+) {
+    for (action, children) in buttons.iter() {
+        if !is_button(action) {
+            continue;
+        }
+        for &child in children.iter() {
+            let Ok(mut text) = texts.get_mut(child) else { continue; };
+            if text.sections[0].value != label {
+                text.sections[0].value = label.to_string();
+            }
+        }
+    }
+}
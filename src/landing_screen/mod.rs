@@ -1,10 +1,22 @@
+mod arcade;
+mod bestiary;
+mod controls;
+mod gallery;
 mod main_menu;
-mod options;
+mod practice;
+mod scores;
+mod shop;
+// Public so that the options panel can be reused outside of the main menu
+pub mod options;
 
-use bevy::app::AppExit;
+use crate::gameplay::levels::{palette::Palette, GameMode};
+use crate::gameplay::difficulty::Difficulty;
+use crate::gameplay::scrape::ScoringMode;
+use crate::gameplay::tokens::Tokens;
+use crate::shutdown::ExitRequest;
 use bevy::prelude::*;
 
-use crate::despawn_component;
+use crate::DespawnOnExitExt;
 use crate::GameState;
 
 // Define menu states
@@ -12,6 +24,12 @@ use crate::GameState;
 enum MenuState {
     MainMenu,
     Options,
+    Controls,
+    Bestiary,
+    Gallery,
+    Scores,
+    Practice,
+    Shop,
     #[default]
     None,
 }
@@ -20,13 +38,23 @@ enum MenuState {
 #[derive(Component)]
 enum Action {
     StartGameplay,
+    StartSimplified,
+    StartEndless,
+    StartVersus,
+    StartPractice,
+    CyclePalette,
+    CycleScoring,
+    CycleDifficulty,
+    ToggleArcade,
     GoToOptions,
+    GoToControls,
+    GoToBestiary,
+    GoToGallery,
+    GoToScores,
+    GoToPractice,
+    GoToShop,
     GoToMenu,
     Exit,
-    InvertFocus,
-    Sound,
-    VolumeUp,
-    VolumeDown,
 }
 
 // Marker for UI objects that exist in the main menu
@@ -37,6 +65,30 @@ struct InMainMenu;
 #[derive(Component)]
 struct InOptionsMenu;
 
+// Marker for UI objects that exist in the controls menu
+#[derive(Component)]
+struct InControlsMenu;
+
+// Marker for UI objects that exist in the bestiary
+#[derive(Component)]
+struct InBestiaryMenu;
+
+// Marker for UI objects that exist in the gallery
+#[derive(Component)]
+struct InGalleryMenu;
+
+// Marker for UI objects that exist in the high score table
+#[derive(Component)]
+struct InScoresMenu;
+
+// Marker for UI objects that exist in the practice menu
+#[derive(Component)]
+struct InPracticeMenu;
+
+// Marker for UI objects that exist in the shop
+#[derive(Component)]
+struct InShopMenu;
+
 pub struct LandingScreenPlugin;
 
 impl Plugin for LandingScreenPlugin {
@@ -46,14 +98,69 @@ impl Plugin for LandingScreenPlugin {
         // }
 
         app.add_state::<MenuState>()
+            .init_resource::<arcade::Credits>()
             .add_systems(OnEnter(GameState::Menu), setup)
-            .add_systems(OnEnter(MenuState::MainMenu), main_menu::setup)
-            .add_systems(OnExit(MenuState::MainMenu), despawn_component::<InMainMenu>)
+            .add_systems(OnEnter(MenuState::MainMenu), (main_menu::setup, arcade::setup))
+            .despawn_on_exit::<InMainMenu>(MenuState::MainMenu)
             .add_systems(OnEnter(MenuState::Options), options::setup)
-            .add_systems(Update, options::update_option_text.run_if(in_state(MenuState::Options)))
-            .add_systems(OnExit(MenuState::Options), despawn_component::<InOptionsMenu>)
+            // The options panel can be shown in other states too, so these are not limited
+            // to MenuState::Options. They only act when option elements exist.
+            .add_systems(Update, (options::option_interactions, options::update_option_text))
+            .despawn_on_exit::<InOptionsMenu>(MenuState::Options)
+            .add_systems(OnEnter(MenuState::Controls), controls::setup)
+            .add_systems(
+                Update,
+                (
+                    controls::control_interactions,
+                    controls::rebind_key,
+                    controls::update_control_text,
+                )
+                    .chain()
+                    .run_if(in_state(MenuState::Controls)),
+            )
+            .despawn_on_exit::<InControlsMenu>(MenuState::Controls)
+            .add_systems(OnEnter(MenuState::Bestiary), bestiary::setup)
+            .despawn_on_exit::<InBestiaryMenu>(MenuState::Bestiary)
+            .add_systems(OnEnter(MenuState::Gallery), gallery::setup)
+            .add_systems(
+                Update,
+                (gallery::gallery_interactions, gallery::show_gallery_piece)
+                    .chain()
+                    .run_if(in_state(MenuState::Gallery)),
+            )
+            .despawn_on_exit::<InGalleryMenu>(MenuState::Gallery)
+            .add_systems(OnEnter(MenuState::Scores), scores::setup)
+            .despawn_on_exit::<InScoresMenu>(MenuState::Scores)
+            .add_systems(OnEnter(MenuState::Practice), practice::setup)
+            .add_systems(
+                Update,
+                (practice::practice_interactions, practice::update_practice_text)
+                    .chain()
+                    .run_if(in_state(MenuState::Practice)),
+            )
+            .despawn_on_exit::<InPracticeMenu>(MenuState::Practice)
+            .add_systems(OnEnter(MenuState::Shop), shop::setup)
+            .add_systems(
+                Update,
+                (shop::shop_interactions, shop::update_shop_text)
+                    .chain()
+                    .run_if(in_state(MenuState::Shop)),
+            )
+            .despawn_on_exit::<InShopMenu>(MenuState::Shop)
             .add_systems(Update, (crate::ui::colour_buttons, button_interactions).run_if(in_state(GameState::Menu)))
-            .add_systems(OnExit(GameState::Menu), despawn_component::<InMainMenu>);
+            .add_systems(
+                Update,
+                (
+                    main_menu::update_palette_text,
+                    main_menu::update_scoring_text,
+                    main_menu::update_difficulty_text,
+                    main_menu::update_arcade_text,
+                    arcade::update_credit_text,
+                )
+                    .run_if(in_state(MenuState::MainMenu)),
+            )
+            .add_systems(Update, arcade::insert_coin.run_if(in_state(GameState::Menu)))
+            .despawn_on_exit::<InMainMenu>(GameState::Menu);
     }
 }
 
@@ -62,34 +169,83 @@ fn setup(mut next_state: ResMut<NextState<MenuState>>) {
 }
 
 // Handle all possible button interactions in the menus
-#[allow(clippy::type_complexity)]
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
 fn button_interactions(
+    mut commands: Commands,
     interaction: Query<(&Interaction, &Action), (Changed<Interaction>, With<Button>)>,
-    mut exit: EventWriter<AppExit>,
+    mut exit: EventWriter<ExitRequest>,
     mut game_state: ResMut<NextState<GameState>>,
     mut menu_state: ResMut<NextState<MenuState>>,
     mut game_options: ResMut<crate::GameOptions>,
+    mut game_mode: ResMut<GameMode>,
+    mut palette: ResMut<Palette>,
+    mut scoring: ResMut<ScoringMode>,
+    mut difficulty: ResMut<Difficulty>,
+    mut credits: ResMut<arcade::Credits>,
+    tokens: Res<Tokens>,
 ) {
     for (interaction, action) in interaction.iter() {
         if *interaction == Interaction::Pressed {
+            let starts_game = matches!(
+                action,
+                Action::StartGameplay
+                    | Action::StartSimplified
+                    | Action::StartEndless
+                    | Action::StartVersus
+                    | Action::StartPractice
+            );
+            if starts_game && game_options.get_arcade_mode() {
+                // Nothing happens without a credit, the blinking "Insert Coin" says why
+                if credits.0 == 0 {
+                    continue;
+                }
+                credits.0 -= 1;
+            }
             match action {
                 Action::StartGameplay => {
+                    *game_mode = GameMode::Story;
+                    game_options.set_control_scheme(crate::ControlScheme::Standard);
                     game_state.set(GameState::Gameplay);
                     menu_state.set(MenuState::None);
                 }
-                Action::InvertFocus => game_options.set_invert_focus(),
-                Action::GoToOptions => menu_state.set(MenuState::Options),
-                Action::GoToMenu => menu_state.set(MenuState::MainMenu),
-                Action::Exit => exit.send(AppExit),
-                Action::VolumeUp => {
-                    let current_volume = game_options.get_volume();
-                    game_options.set_volume(current_volume + 0.1);
+                Action::StartSimplified => {
+                    *game_mode = GameMode::Story;
+                    game_options.set_control_scheme(crate::ControlScheme::Simplified);
+                    game_state.set(GameState::Gameplay);
+                    menu_state.set(MenuState::None);
+                }
+                Action::StartEndless => {
+                    *game_mode = GameMode::Endless;
+                    game_options.set_control_scheme(crate::ControlScheme::Standard);
+                    game_state.set(GameState::Gameplay);
+                    menu_state.set(MenuState::None);
                 }
-                Action::VolumeDown => {
-                    let current_volume = game_options.get_volume();
-                    game_options.set_volume(current_volume - 0.1);
+                Action::StartVersus => {
+                    crate::versus::start_versus(&mut commands, &mut game_mode);
+                    game_options.set_control_scheme(crate::ControlScheme::Standard);
+                    game_state.set(GameState::Gameplay);
+                    menu_state.set(MenuState::None);
                 }
-                _ => {}
+                Action::StartPractice => {
+                    *game_mode = GameMode::Practice;
+                    game_options.set_control_scheme(crate::ControlScheme::Standard);
+                    game_state.set(GameState::Gameplay);
+                    menu_state.set(MenuState::None);
+                }
+                // Palettes which haven't been bought in the shop are skipped
+                Action::CyclePalette => *palette = tokens.next_palette(*palette),
+                Action::CycleScoring => *scoring = scoring.next(),
+                Action::CycleDifficulty => *difficulty = difficulty.next(),
+                Action::ToggleArcade => game_options.toggle_arcade_mode(),
+                Action::GoToOptions => menu_state.set(MenuState::Options),
+                Action::GoToControls => menu_state.set(MenuState::Controls),
+                Action::GoToBestiary => menu_state.set(MenuState::Bestiary),
+                Action::GoToGallery => menu_state.set(MenuState::Gallery),
+                Action::GoToScores => menu_state.set(MenuState::Scores),
+                Action::GoToPractice => menu_state.set(MenuState::Practice),
+                Action::GoToShop => menu_state.set(MenuState::Shop),
+                Action::GoToMenu => menu_state.set(MenuState::MainMenu),
+                Action::Exit => exit.send_default(),
             }
         }
     }
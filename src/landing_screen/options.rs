@@ -1,7 +1,13 @@
+use crate::gameplay::input::KeyBindings;
+use crate::settings::{export_settings, import_settings};
 use crate::GameOptions;
 
 use super::Action;
 use super::InOptionsMenu;
+use crate::ui::theme::Theme;
+use crate::ui::widgets::{
+    spawn_button, spawn_labeled_row, spawn_slider, spawn_toast, spawn_toggle, WidgetStyle,
+};
 use bevy::prelude::*;
 
 // Markers for options text elements
@@ -9,24 +15,47 @@ use bevy::prelude::*;
 pub enum OptionText {
     Volume,
     InvertFocus,
+    SpeedrunTimer,
+    Mute,
+    MuteWhenUnfocused,
+    Theme,
+    TextureFiltering,
+    GraphicsQuality,
+    FrameCap,
+    Bloom,
+    Crt,
+    ScreenShake,
+    Afterimages,
 }
 
-pub fn setup(mut commands: Commands, assets: Res<AssetServer>) {
-    let font: Handle<Font> = assets.load("fonts/FiraSans-Bold.ttf");
-    let button_style = Style {
-        width: Val::Px(120.0),
-        height: Val::Px(40.0),
-        margin: UiRect::all(Val::Px(10.0)),
-        justify_content: JustifyContent::Center,
-        align_items: AlignItems::Center,
-        ..default()
-    };
+// Actions of the buttons that change the game options.
+// These are separate from the menu actions so that the options panel
+// can also be shown outside of the main menu, e.g. during gameplay.
+#[derive(Debug, Clone, Copy, Component)]
+pub enum OptionAction {
+    InvertFocus,
+    SpeedrunTimer,
+    Mute,
+    MuteWhenUnfocused,
+    Theme,
+    TextureFiltering,
+    GraphicsQuality,
+    FrameCap,
+    Afterimages,
+    VolumeUp,
+    VolumeDown,
+    BloomUp,
+    BloomDown,
+    CrtUp,
+    CrtDown,
+    ScreenShakeUp,
+    ScreenShakeDown,
+    ExportSettings,
+    ImportSettings,
+}
 
-    let text_style = TextStyle {
-        font: font.clone(),
-        font_size: 36.0,
-        color: crate::ui::TEXT_COLOUR,
-    };
+pub fn setup(mut commands: Commands, theme: Res<Theme>) {
+    let style = options_widget_style(&theme);
 
     commands
         .spawn((
@@ -45,137 +74,161 @@ pub fn setup(mut commands: Commands, assets: Res<AssetServer>) {
             InOptionsMenu,
         ))
         .with_children(|parent| {
-            parent.spawn(TextBundle::from_section(
-                "Options",
-                TextStyle {
-                    font_size: 40.0,
-                    font: font.clone(),
-                    color: crate::ui::TEXT_COLOUR,
-                },
-            ));
+            parent.spawn(TextBundle::from_section("Options", theme.text_style(40.0)));
 
-            for (action, text, component, alternate) in [
-                (
-                    Action::InvertFocus,
-                    "Switch",
-                    Some(OptionText::InvertFocus),
-                    None,
-                ),
-                (
-                    Action::Sound,
-                    "Volume",
-                    Some(OptionText::Volume),
-                    Some(setup_volume_buttons),
-                ),
-                (Action::GoToMenu, "Back", None, None),
-            ] {
-                if let Some(alternative_setup) = alternate {
-                    alternative_setup(parent, &text_style, &button_style);
-                    continue;
-                }
-                parent
-                    .spawn(NodeBundle {
-                        style: Style {
-                            flex_wrap: FlexWrap::NoWrap,
-                            justify_content: JustifyContent::SpaceBetween,
-                            align_items: AlignItems::Center,
-                            width: Val::Percent(98.0),
-                            height: Val::Percent(10.0),
-                            margin: UiRect {
-                                top: Val::Px(25.0),
-                                left: Val::Px(15.0),
-                                ..default()
-                            },
-                            ..default()
-                        },
-                        ..default()
-                    })
-                    .with_children(|parent| {
-                        let mut option_name =
-                            parent.spawn(TextBundle::from_section(text, text_style.clone()));
-                        if let Some(component) = component {
-                            option_name.insert(component);
-                        }
-                        parent
-                            .spawn((
-                                ButtonBundle {
-                                    style: button_style.clone(),
-                                    background_color: crate::ui::BUTTON_BASE.into(),
-                                    ..default()
-                                },
-                                action,
-                            ))
-                            .with_children(|parent| {
-                                parent.spawn(TextBundle::from_section(text, text_style.clone()));
-                            });
-                    });
-            }
+            spawn_options_panel(parent, &style);
+            spawn_labeled_row(parent, "Keyboard", (), &style, |parent| {
+                spawn_button(parent, Action::GoToControls, "Controls", &style);
+            });
+            spawn_labeled_row(parent, "Back", (), &style, |parent| {
+                spawn_button(parent, Action::GoToMenu, "Back", &style);
+            });
         });
 }
 
-fn setup_volume_buttons(parent: &mut ChildBuilder, text_style: &TextStyle, button_style: &Style) {
-    let mut custom_style = button_style.clone();
-    custom_style.width = button_style.width / 2.0;
+// Style of the widgets in the options panel
+pub fn options_widget_style(theme: &Theme) -> WidgetStyle {
+    theme.widget_style(120.0, 40.0, 36.0)
+}
 
-    parent
-        .spawn(NodeBundle {
-            style: Style {
-                flex_wrap: FlexWrap::NoWrap,
-                justify_content: JustifyContent::SpaceBetween,
-                align_items: AlignItems::Center,
-                width: Val::Percent(98.0),
-                height: Val::Percent(10.0),
-                margin: UiRect {
-                    top: Val::Px(25.0),
-                    left: Val::Px(15.0),
+// Add a row for every game option to a parent UI node.
+pub fn spawn_options_panel(parent: &mut ChildBuilder, style: &WidgetStyle) {
+    for (action, component) in [
+        (OptionAction::InvertFocus, OptionText::InvertFocus),
+        (OptionAction::SpeedrunTimer, OptionText::SpeedrunTimer),
+        (OptionAction::Mute, OptionText::Mute),
+        (OptionAction::MuteWhenUnfocused, OptionText::MuteWhenUnfocused),
+        (OptionAction::Theme, OptionText::Theme),
+        (OptionAction::TextureFiltering, OptionText::TextureFiltering),
+        (OptionAction::GraphicsQuality, OptionText::GraphicsQuality),
+        (OptionAction::FrameCap, OptionText::FrameCap),
+        (OptionAction::Afterimages, OptionText::Afterimages),
+    ] {
+        // The label text is filled in by update_option_text
+        spawn_toggle(parent, "", component, action, style);
+    }
+    spawn_slider(
+        parent,
+        "Volume",
+        OptionText::Volume,
+        OptionAction::VolumeUp,
+        OptionAction::VolumeDown,
+        style,
+    );
+    spawn_slider(
+        parent,
+        "Bloom",
+        OptionText::Bloom,
+        OptionAction::BloomUp,
+        OptionAction::BloomDown,
+        style,
+    );
+    spawn_slider(
+        parent,
+        "CRT Filter",
+        OptionText::Crt,
+        OptionAction::CrtUp,
+        OptionAction::CrtDown,
+        style,
+    );
+    spawn_slider(
+        parent,
+        "Screen Shake",
+        OptionText::ScreenShake,
+        OptionAction::ScreenShakeUp,
+        OptionAction::ScreenShakeDown,
+        style,
+    );
+    spawn_labeled_row(parent, "Settings File", (), style, |parent| {
+        parent
+            .spawn(NodeBundle {
+                style: Style {
+                    flex_wrap: FlexWrap::NoWrap,
                     ..default()
                 },
                 ..default()
-            },
-            ..default()
-        })
-        .with_children(|parent| {
-            parent.spawn((
-                TextBundle::from_section("Volume", text_style.clone()),
-                OptionText::Volume,
-            ));
-            parent
-                .spawn(NodeBundle {
-                    style: Style {
-                        flex_wrap: FlexWrap::NoWrap,
-                        justify_content: JustifyContent::Center,
-                        align_items: AlignItems::Center,
-                        ..default()
-                    },
-                    ..default()
-                })
-                .with_children(|parent| {
-                    parent
-                        .spawn((
-                            ButtonBundle {
-                                style: custom_style.clone(),
-                                background_color: crate::ui::BUTTON_BASE.into(),
-                                ..default()
-                            },
-                            Action::VolumeUp,
-                        ))
-                        .with_children(|parent| {
-                            parent.spawn(TextBundle::from_section("+", text_style.clone()));
-                        });
-                    parent
-                        .spawn((
-                            ButtonBundle {
-                                style: custom_style.clone(),
-                                background_color: crate::ui::BUTTON_BASE.into(),
-                                ..default()
-                            },
-                            Action::VolumeDown,
-                        ))
-                        .with_children(|parent| {
-                            parent.spawn(TextBundle::from_section("-", text_style.clone()));
-                        });
-                });
-        });
+            })
+            .with_children(|parent| {
+                spawn_button(parent, OptionAction::ExportSettings, "Export", style);
+                spawn_button(parent, OptionAction::ImportSettings, "Import", style);
+            });
+    });
+}
+
+// Handle the option buttons wherever the options panel is shown
+#[allow(clippy::type_complexity)]
+pub fn option_interactions(
+    mut commands: Commands,
+    interaction: Query<(&Interaction, &OptionAction), (Changed<Interaction>, With<Button>)>,
+    mut game_options: ResMut<GameOptions>,
+    mut key_bindings: ResMut<KeyBindings>,
+    theme: Res<Theme>,
+) {
+    for (interaction, action) in interaction.iter() {
+        if *interaction == Interaction::Pressed {
+            match action {
+                OptionAction::InvertFocus => game_options.set_invert_focus(),
+                OptionAction::SpeedrunTimer => game_options.toggle_speedrun_timer(),
+                OptionAction::Mute => game_options.toggle_muted(),
+                OptionAction::MuteWhenUnfocused => game_options.toggle_mute_when_unfocused(),
+                OptionAction::Theme => game_options.cycle_theme(),
+                OptionAction::TextureFiltering => game_options.cycle_texture_filtering(),
+                OptionAction::GraphicsQuality => game_options.cycle_graphics_quality(),
+                OptionAction::FrameCap => game_options.cycle_frame_cap(),
+                OptionAction::Afterimages => game_options.toggle_afterimages(),
+                OptionAction::VolumeUp => {
+                    let current_volume = game_options.get_volume();
+                    game_options.set_volume(current_volume + 0.1);
+                }
+                OptionAction::VolumeDown => {
+                    let current_volume = game_options.get_volume();
+                    game_options.set_volume(current_volume - 0.1);
+                }
+                OptionAction::BloomUp => {
+                    let current_bloom = game_options.get_bloom();
+                    game_options.set_bloom(current_bloom + 0.2);
+                }
+                OptionAction::BloomDown => {
+                    let current_bloom = game_options.get_bloom();
+                    game_options.set_bloom(current_bloom - 0.2);
+                }
+                OptionAction::CrtUp => {
+                    let current_crt = game_options.get_crt();
+                    game_options.set_crt(current_crt + 0.2);
+                }
+                OptionAction::CrtDown => {
+                    let current_crt = game_options.get_crt();
+                    game_options.set_crt(current_crt - 0.2);
+                }
+                OptionAction::ScreenShakeUp => {
+                    let current_shake = game_options.get_screen_shake();
+                    game_options.set_screen_shake(current_shake + 0.2);
+                }
+                OptionAction::ScreenShakeDown => {
+                    let current_shake = game_options.get_screen_shake();
+                    game_options.set_screen_shake(current_shake - 0.2);
+                }
+                OptionAction::ExportSettings => {
+                    let message = match export_settings(&game_options, &key_bindings) {
+                        Ok(()) => "Settings exported".to_string(),
+                        Err(error) => format!("Couldn't export settings: {}", error),
+                    };
+                    spawn_toast(&mut commands, &theme, &message);
+                }
+                OptionAction::ImportSettings => {
+                    let message = match import_settings() {
+                        Ok((options, bindings)) => {
+                            *game_options = options;
+                            *key_bindings = bindings;
+                            "Settings imported".to_string()
+                        }
+                        Err(error) => format!("Couldn't import settings: {}", error),
+                    };
+                    spawn_toast(&mut commands, &theme, &message);
+                }
+            }
+        }
+    }
 }
 
 pub fn update_option_text(mut query: Query<(&mut Text, &OptionText)>, options: Res<GameOptions>) {
@@ -191,6 +244,66 @@ pub fn update_option_text(mut query: Query<(&mut Text, &OptionText)>, options: R
                     "Focus Mode: Normal".to_string()
                 }
             }
+            OptionText::Mute => {
+                text.sections[0].value = if options.get_muted() {
+                    "Sound: Muted".to_string()
+                } else {
+                    "Sound: On".to_string()
+                }
+            }
+            OptionText::MuteWhenUnfocused => {
+                text.sections[0].value = if options.get_mute_when_unfocused() {
+                    "Background Audio: Off".to_string()
+                } else {
+                    "Background Audio: On".to_string()
+                }
+            }
+            OptionText::Theme => {
+                text.sections[0].value = format!("Theme: {}", options.get_theme().name());
+            }
+            OptionText::TextureFiltering => {
+                text.sections[0].value =
+                    format!("Sprites: {}", options.get_texture_filtering().name());
+            }
+            OptionText::GraphicsQuality => {
+                text.sections[0].value =
+                    format!("Graphics: {}", options.get_graphics_quality().name());
+            }
+            OptionText::FrameCap => {
+                text.sections[0].value = format!("Frame Cap: {}", options.get_frame_cap().name());
+            }
+            OptionText::Bloom => {
+                text.sections[0].value = effect_text("Bloom", options.get_bloom());
+            }
+            OptionText::Crt => {
+                text.sections[0].value = effect_text("CRT Filter", options.get_crt());
+            }
+            OptionText::ScreenShake => {
+                text.sections[0].value = effect_text("Screen Shake", options.get_screen_shake());
+            }
+            OptionText::Afterimages => {
+                text.sections[0].value = if options.get_afterimages() {
+                    "Focus Trail: On".to_string()
+                } else {
+                    "Focus Trail: Off".to_string()
+                }
+            }
+            OptionText::SpeedrunTimer => {
+                text.sections[0].value = if options.get_speedrun_timer() {
+                    "Run Timer: On".to_string()
+                } else {
+                    "Run Timer: Off".to_string()
+                }
+            }
         }
     }
 }
+
+// Text for an effect option, with its strength shown in steps of 0.2
+fn effect_text(name: &str, strength: f32) -> String {
+    if strength <= 0. {
+        format!("{}: Off", name)
+    } else {
+        format!("{}: {:.0}", name, strength * 5.)
+    }
+}
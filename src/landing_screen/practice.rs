@@ -0,0 +1,104 @@
+use crate::gameplay::levels::{practice::Practice, CurrentLevel};
+
+use super::Action;
+use super::InPracticeMenu;
+use crate::ui::theme::Theme;
+use crate::ui::widgets::spawn_button;
+use bevy::prelude::*;
+
+// Buttons which change how the practice run is played. Their labels show the current
+// choice and are filled in by update_practice_text.
+#[derive(Debug, Clone, Copy, Component)]
+pub enum PracticeAction {
+    Level,
+    BossOnly,
+    Power,
+    Specials,
+}
+
+pub fn setup(mut commands: Commands, theme: Res<Theme>) {
+    let style = theme.widget_style(175.0, 50.0, 40.0);
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(95.0),
+                    height: Val::Percent(95.0),
+                    top: Val::Px(15.0),
+                    left: Val::Px(15.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            InPracticeMenu,
+        ))
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section("Practice", theme.text_style(40.0)).with_style(Style {
+                    margin: UiRect::bottom(Val::Px(30.0)),
+                    ..default()
+                }),
+            );
+            for action in [
+                PracticeAction::Level,
+                PracticeAction::BossOnly,
+                PracticeAction::Power,
+                PracticeAction::Specials,
+            ] {
+                spawn_button(parent, action, "", &style);
+            }
+            spawn_button(parent, Action::StartPractice, "Start", &style);
+            spawn_button(parent, Action::GoToMenu, "Back", &style);
+        });
+}
+
+#[allow(clippy::type_complexity)]
+pub fn practice_interactions(
+    interaction: Query<(&Interaction, &PracticeAction), (Changed<Interaction>, With<Button>)>,
+    mut practice: ResMut<Practice>,
+) {
+    for (interaction, action) in interaction.iter() {
+        if *interaction == Interaction::Pressed {
+            match action {
+                PracticeAction::Level => practice.next_level(),
+                PracticeAction::BossOnly => practice.toggle_boss_only(),
+                PracticeAction::Power => practice.next_power(),
+                PracticeAction::Specials => practice.next_specials(),
+            }
+        }
+    }
+}
+
+pub fn update_practice_text(
+    practice: Res<Practice>,
+    buttons: Query<(&PracticeAction, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    for (action, children) in buttons.iter() {
+        let label = match action {
+            PracticeAction::Level => match practice.level {
+                CurrentLevel::Two => "Stage 2".to_string(),
+                CurrentLevel::Three => "Stage 3".to_string(),
+                _ => "Stage 1".to_string(),
+            },
+            PracticeAction::BossOnly => {
+                if practice.boss_only {
+                    "Boss Only".to_string()
+                } else {
+                    "Full Stage".to_string()
+                }
+            }
+            PracticeAction::Power => format!("Power: {}", practice.power),
+            PracticeAction::Specials => format!("Specials: {}", practice.specials),
+        };
+        for &child in children.iter() {
+            let Ok(mut text) = texts.get_mut(child) else { continue; };
+            if text.sections[0].value != label {
+                text.sections[0].value = label.clone();
+            }
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use crate::high_scores::HighScoreTable;
+
+use super::Action;
+use super::InScoresMenu;
+use crate::ui::theme::Theme;
+use crate::ui::widgets::spawn_button;
+use bevy::prelude::*;
+
+// List the best runs, highest score first
+pub fn setup(mut commands: Commands, theme: Res<Theme>, high_scores: Res<HighScoreTable>) {
+    let style = theme.widget_style(175.0, 50.0, 40.0);
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(95.0),
+                    height: Val::Percent(95.0),
+                    top: Val::Px(15.0),
+                    left: Val::Px(15.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::FlexStart,
+                    ..default()
+                },
+                ..default()
+            },
+            InScoresMenu,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("High Scores", theme.text_style(40.0)));
+
+            if high_scores.entries().is_empty() {
+                parent.spawn(
+                    TextBundle::from_section("No scores yet", theme.text_style(24.0))
+                        .with_style(Style {
+                            margin: UiRect::top(Val::Px(10.0)),
+                            ..default()
+                        }),
+                );
+            }
+            for (rank, entry) in high_scores.entries().iter().enumerate() {
+                parent.spawn(
+                    TextBundle::from_sections([
+                        TextSection::new(
+                            format!("{:>2}. {}  {}\n", rank + 1, entry.initials, entry.score),
+                            theme.text_style(24.0),
+                        ),
+                        TextSection::new(
+                            format!(
+                                "{}, {}, {}",
+                                entry.reached.name(),
+                                entry.difficulty.name(),
+                                entry.date_text(),
+                            ),
+                            theme.text_style(16.0),
+                        ),
+                    ])
+                    .with_style(Style {
+                        margin: UiRect::top(Val::Px(8.0)),
+                        ..default()
+                    }),
+                );
+            }
+
+            if high_scores.best_wave() > 0 {
+                parent.spawn(
+                    TextBundle::from_section(
+                        format!("Best endless wave: {}", high_scores.best_wave()),
+                        theme.text_style(24.0),
+                    )
+                    .with_style(Style {
+                        margin: UiRect::top(Val::Px(20.0)),
+                        ..default()
+                    }),
+                );
+            }
+
+            spawn_button(parent, Action::GoToMenu, "Back", &style);
+        });
+}
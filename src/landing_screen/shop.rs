@@ -0,0 +1,96 @@
+use crate::gameplay::tokens::{Tokens, Unlock};
+
+use super::Action;
+use super::InShopMenu;
+use crate::ui::theme::Theme;
+use crate::ui::widgets::spawn_button;
+use bevy::prelude::*;
+
+// Buttons for buying each unlock. Their labels show the cost, or that it has been bought, and
+// are filled in by update_shop_text.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct ShopAction(Unlock);
+
+// Tokens left to spend
+#[derive(Component)]
+pub struct BalanceText;
+
+pub fn setup(mut commands: Commands, theme: Res<Theme>) {
+    let style = theme.widget_style(300.0, 50.0, 30.0);
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(95.0),
+                    height: Val::Percent(95.0),
+                    top: Val::Px(15.0),
+                    left: Val::Px(15.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            InShopMenu,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("Shop", theme.text_style(40.0)));
+            parent.spawn((
+                TextBundle::from_section("", theme.text_style(24.0)).with_style(Style {
+                    margin: UiRect::bottom(Val::Px(30.0)),
+                    ..default()
+                }),
+                BalanceText,
+            ));
+            for unlock in Unlock::ALL {
+                spawn_button(parent, ShopAction(unlock), "", &style);
+            }
+            spawn_button(parent, Action::GoToMenu, "Back", &style);
+        });
+}
+
+#[allow(clippy::type_complexity)]
+pub fn shop_interactions(
+    interaction: Query<(&Interaction, &ShopAction), (Changed<Interaction>, With<Button>)>,
+    mut tokens: ResMut<Tokens>,
+) {
+    for (interaction, action) in interaction.iter() {
+        // Nothing happens when it can't be bought, the label says why. Tokens are only touched
+        // when it can, so that they aren't saved again for nothing.
+        if *interaction == Interaction::Pressed && tokens.can_buy(action.0) {
+            tokens.buy(action.0);
+        }
+    }
+}
+
+pub fn update_shop_text(
+    tokens: Res<Tokens>,
+    buttons: Query<(&ShopAction, &Children)>,
+    mut balance: Query<&mut Text, With<BalanceText>>,
+    mut texts: Query<&mut Text, Without<BalanceText>>,
+) {
+    let label = format!("Boss tokens: {}", tokens.balance());
+    for mut text in balance.iter_mut() {
+        if text.sections[0].value != label {
+            text.sections[0].value = label.clone();
+        }
+    }
+    for (action, children) in buttons.iter() {
+        let unlock = action.0;
+        let bought = tokens.bought(unlock);
+        let label = if bought >= unlock.limit() {
+            format!("{}: Owned", unlock.name())
+        } else if unlock.limit() > 1 {
+            format!("{} {}/{} ({})", unlock.name(), bought, unlock.limit(), unlock.cost())
+        } else {
+            format!("{} ({})", unlock.name(), unlock.cost())
+        };
+        for &child in children.iter() {
+            let Ok(mut text) = texts.get_mut(child) else { continue; };
+            if text.sections[0].value != label {
+                text.sections[0].value = label.clone();
+            }
+        }
+    }
+}
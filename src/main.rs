@@ -1,15 +1,32 @@
+mod audio;
 mod game_over;
 mod gameplay;
+mod graphics;
+mod high_scores;
 mod landing_screen;
+mod pause;
+mod persistence;
+mod settings;
+mod shutdown;
+mod stage_clear;
+mod storage;
 mod ui;
+mod versus;
 mod win_game;
+mod window;
 
+use bevy::asset::ChangeWatcher;
 use bevy::diagnostic::{EntityCountDiagnosticsPlugin, FrameTimeDiagnosticsPlugin};
 use bevy::prelude::*;
-// use bevy_editor_pls::prelude::EditorPlugin;
 use bevy_hanabi::HanabiPlugin;
+use serde::{Deserialize, Serialize};
+use graphics::{FrameCap, GraphicsQuality, TextureFiltering};
+use ui::theme::ThemeKind;
 
 const DEBUG_TIMER_DURATION: f32 = 5.0;
+// The volume option is mapped onto this range of decibels (up to 0 dB) as
+// loudness is perceived logarithmically rather than linearly.
+const MIN_VOLUME_DB: f32 = -40.0;
 
 // Define all game states
 #[derive(Clone, Copy, Eq, PartialEq, Debug, Default, Hash, States)]
@@ -17,19 +34,48 @@ pub enum GameState {
     #[default]
     Menu,
     Paused,
+    // Results of a cleared level, shown before moving on to the next one
+    StageClear,
     GameOver,
     Gameplay,
     GameWon,
+    // Comparison of the players' runs in versus mode, also shown between their turns
+    VersusResults,
 }
 
-#[derive(Clone, Copy, Debug, Default, Deref, DerefMut, PartialEq, Eq, Resource)]
-pub struct HighScore(pub u64);
+// Control presets. Simplified only uses the movement keys and a single fire button,
+// with focusing and specials handled automatically for the player.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Debug, Default, Serialize, Deserialize)]
+pub enum ControlScheme {
+    #[default]
+    Standard,
+    Simplified,
+}
 
-// Collection of global game options
-#[derive(Clone, PartialEq, PartialOrd, Debug, Resource)]
+// Collection of global game options. Missing fields take their default values when
+// loading settings saved by an older version of the game.
+#[derive(Clone, PartialEq, PartialOrd, Debug, Resource, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GameOptions {
     volume: f32,
     invert_focus: bool,
+    speedrun_timer: bool,
+    control_scheme: ControlScheme,
+    muted: bool,
+    mute_when_unfocused: bool,
+    theme: ThemeKind,
+    texture_filtering: TextureFiltering,
+    graphics_quality: GraphicsQuality,
+    frame_cap: FrameCap,
+    // Strength of the post-processing effects, from 0 (off) to 1
+    bloom: f32,
+    crt: f32,
+    // Strength of the camera shake from explosions and specials, from 0 (off) to 1
+    screen_shake: f32,
+    // Trail of fading copies of the player while focusing
+    afterimages: bool,
+    // Title screen asks for coins, and starting a game takes a credit
+    arcade_mode: bool,
 }
 
 impl GameOptions {
@@ -46,12 +92,102 @@ impl GameOptions {
     pub fn get_volume(&self) -> f32 {
         self.volume
     }
+    pub fn toggle_muted(&mut self) {
+        self.muted = !self.muted;
+    }
+    pub fn get_muted(&self) -> bool {
+        self.muted
+    }
+    pub fn toggle_mute_when_unfocused(&mut self) {
+        self.mute_when_unfocused = !self.mute_when_unfocused;
+    }
+    pub fn get_mute_when_unfocused(&self) -> bool {
+        self.mute_when_unfocused
+    }
+    // Volume to actually play audio at, given whether the game window is focused.
+    // The volume option is converted to decibels first so that every step of the
+    // option sounds like the same change in loudness.
+    pub fn output_volume(&self, focused: bool) -> f32 {
+        if self.muted || self.volume <= 0. || (self.mute_when_unfocused && !focused) {
+            return 0.;
+        }
+        let decibels = MIN_VOLUME_DB * (1. - self.volume);
+        10f32.powf(decibels / 20.)
+    }
     pub fn set_invert_focus(&mut self) {
         self.invert_focus = !self.invert_focus;
     }
     pub fn get_focus(&self) -> bool {
         self.invert_focus
     }
+    pub fn toggle_speedrun_timer(&mut self) {
+        self.speedrun_timer = !self.speedrun_timer;
+    }
+    pub fn get_speedrun_timer(&self) -> bool {
+        self.speedrun_timer
+    }
+    pub fn set_control_scheme(&mut self, scheme: ControlScheme) {
+        self.control_scheme = scheme;
+    }
+    pub fn get_control_scheme(&self) -> ControlScheme {
+        self.control_scheme
+    }
+    pub fn cycle_theme(&mut self) {
+        self.theme = self.theme.next();
+    }
+    pub fn get_theme(&self) -> ThemeKind {
+        self.theme
+    }
+    pub fn cycle_texture_filtering(&mut self) {
+        self.texture_filtering = self.texture_filtering.next();
+    }
+    pub fn get_texture_filtering(&self) -> TextureFiltering {
+        self.texture_filtering
+    }
+    pub fn cycle_graphics_quality(&mut self) {
+        self.graphics_quality = self.graphics_quality.next();
+    }
+    pub fn get_graphics_quality(&self) -> GraphicsQuality {
+        self.graphics_quality
+    }
+    pub fn cycle_frame_cap(&mut self) {
+        self.frame_cap = self.frame_cap.next();
+    }
+    pub fn get_frame_cap(&self) -> FrameCap {
+        self.frame_cap
+    }
+    // Effect strengths are kept to steps of 0.2, so that stepping down always reaches
+    // exactly 0 and turns the effect off
+    pub fn set_bloom(&mut self, bloom: f32) {
+        self.bloom = ((bloom * 5.).round() / 5.).clamp(0., 1.);
+    }
+    pub fn get_bloom(&self) -> f32 {
+        self.bloom
+    }
+    pub fn set_crt(&mut self, crt: f32) {
+        self.crt = ((crt * 5.).round() / 5.).clamp(0., 1.);
+    }
+    pub fn get_crt(&self) -> f32 {
+        self.crt
+    }
+    pub fn set_screen_shake(&mut self, screen_shake: f32) {
+        self.screen_shake = ((screen_shake * 5.).round() / 5.).clamp(0., 1.);
+    }
+    pub fn get_screen_shake(&self) -> f32 {
+        self.screen_shake
+    }
+    pub fn toggle_afterimages(&mut self) {
+        self.afterimages = !self.afterimages;
+    }
+    pub fn get_afterimages(&self) -> bool {
+        self.afterimages
+    }
+    pub fn toggle_arcade_mode(&mut self) {
+        self.arcade_mode = !self.arcade_mode;
+    }
+    pub fn get_arcade_mode(&self) -> bool {
+        self.arcade_mode
+    }
 }
 
 impl Default for GameOptions {
@@ -59,6 +195,19 @@ impl Default for GameOptions {
         Self {
             volume: 0.5,
             invert_focus: false,
+            speedrun_timer: false,
+            control_scheme: ControlScheme::Standard,
+            muted: false,
+            mute_when_unfocused: false,
+            theme: ThemeKind::Default,
+            texture_filtering: TextureFiltering::Linear,
+            graphics_quality: GraphicsQuality::default(),
+            frame_cap: FrameCap::default(),
+            bloom: 0.6,
+            crt: 0.,
+            screen_shake: 1.,
+            afterimages: true,
+            arcade_mode: false,
         }
     }
 }
@@ -69,16 +218,23 @@ fn main() {
     if cfg!(debug_assertions) {
         app.add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
-                title: format!(
-                    "{} - {} - debug",
-                    env!("CARGO_PKG_NAME"),
-                    env!("CARGO_PKG_VERSION")
-                ),
+                title: window::base_title(),
                 resolution: (600., 800.).into(),
                 resizable: true,
                 mode: bevy::window::WindowMode::Windowed,
                 ..default()
             }),
+            // Handled by the shutdown plugin, so that a run isn't lost by accident
+            close_when_requested: false,
+            ..default()
+        }).set(AssetPlugin {
+            // Reload assets when they are saved, e.g. for tuning gameplay while playing. There
+            // is no file system to watch on the web.
+            watch_for_changes: if cfg!(target_arch = "wasm32") {
+                None
+            } else {
+                ChangeWatcher::with_delay(std::time::Duration::from_millis(200))
+            },
             ..default()
         }))
         .init_resource::<DebugTimer>()
@@ -86,20 +242,17 @@ fn main() {
         .add_systems(Update, (tick_debug_timer, debug_game_state))
         .add_plugin(FrameTimeDiagnosticsPlugin)
         .add_plugin(EntityCountDiagnosticsPlugin);
-        //.add_plugin(EditorPlugin);
     } else {
         app.add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
-                title: format!(
-                    "{} - {} - release",
-                    env!("CARGO_PKG_NAME"),
-                    env!("CARGO_PKG_VERSION")
-                ),
+                title: window::base_title(),
                 resolution: (600., 800.).into(),
                 resizable: false,
                 mode: bevy::window::WindowMode::Windowed,
                 ..default()
             }),
+            // Handled by the shutdown plugin, so that a run isn't lost by accident
+            close_when_requested: false,
             ..default()
         }));
     }
@@ -107,13 +260,25 @@ fn main() {
     app.add_systems(Startup, spawn_camera)
         // Particle effects creator and renderer
         .add_plugin(HanabiPlugin)
+        .add_plugin(audio::GameAudioPlugin)
+        .add_plugin(ui::UiPlugin)
+        .add_plugin(window::GameWindowPlugin)
+        .add_plugin(shutdown::ShutdownPlugin)
+        // Must come before GameOptions, HighScoreTable and KeyBindings are initialised, so the saved
+        // ones are used
+        .add_plugin(persistence::PersistencePlugin)
+        .add_plugin(graphics::GraphicsPlugin)
         .add_state::<GameState>()
         .init_resource::<GameOptions>()
-        .init_resource::<HighScore>()
+        .init_resource::<high_scores::HighScoreTable>()
+        .add_plugin(high_scores::HighScorePlugin)
         .add_plugin(landing_screen::LandingScreenPlugin)
         .add_plugin(game_over::GameOverPlugin)
         .add_plugin(gameplay::GameplayPlugin)
-        .add_plugin(win_game::WinGamePlugin);
+        .add_plugin(pause::PausePlugin)
+        .add_plugin(stage_clear::StageClearPlugin)
+        .add_plugin(win_game::WinGamePlugin)
+        .add_plugin(versus::VersusPlugin);
 
     app.run();
 }
@@ -147,6 +312,17 @@ fn spawn_camera(mut commands: Commands) {
     commands.spawn(Camera2dBundle::default());
 }
 
+// Registration helper for despawning everything with a component when leaving a state.
+pub trait DespawnOnExitExt {
+    fn despawn_on_exit<T: Component>(&mut self, state: impl States) -> &mut Self;
+}
+
+impl DespawnOnExitExt for App {
+    fn despawn_on_exit<T: Component>(&mut self, state: impl States) -> &mut Self {
+        self.add_systems(OnExit(state), despawn_component::<T>)
+    }
+}
+
 // General despawn everything with a component.
 fn despawn_component<T: Component>(mut commands: Commands, entities: Query<Entity, With<T>>) {
     for entity in entities.iter() {
@@ -0,0 +1,90 @@
+// Demo reset for exhibition and kiosk setups. When nobody has touched the controls for a while
+// during a run, the game is paused, and if nobody comes back to it the run is dropped and the
+// game goes back to the title screen for the next player.
+//
+// Off unless turned on in "idle.ron" in the config folder, for example:
+// (enabled: true, pause_after_secs: 30.0, quit_after_secs: 15.0)
+use crate::gameplay::GameplayState;
+use crate::GameState;
+use bevy::input::mouse::MouseMotion;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub const IDLE_FILE: &str = "idle.ron";
+// Stick movement smaller than this is taken to be drift rather than a player
+const STICK_DEADZONE: f32 = 0.2;
+
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IdleSettings {
+    pub enabled: bool,
+    // Seconds without any input before the run is paused
+    pub pause_after_secs: f32,
+    // Further seconds spent paused without any input before going back to the title screen
+    pub quit_after_secs: f32,
+}
+
+impl Default for IdleSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            pause_after_secs: 60.0,
+            quit_after_secs: 30.0,
+        }
+    }
+}
+
+// Seconds since the last input. Counted on the real clock, which keeps going while paused.
+#[derive(Resource, Debug, Default)]
+pub struct IdleTime(f32);
+
+pub fn reset_idle_time(mut idle: ResMut<IdleTime>) {
+    idle.0 = 0.0;
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn detect_idle(
+    settings: Res<IdleSettings>,
+    time: Res<Time>,
+    keys: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    mut mouse_motion: EventReader<MouseMotion>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut idle: ResMut<IdleTime>,
+    game_state: Res<State<GameState>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut gameplay_state: ResMut<NextState<GameplayState>>,
+) {
+    let moved_mouse = mouse_motion.iter().count() > 0;
+    // Results screens between stages are left alone
+    let in_run = matches!(game_state.get(), GameState::Gameplay | GameState::Paused);
+    if !settings.enabled || !in_run {
+        idle.0 = 0.0;
+        return;
+    }
+
+    // Held controls count as well, so that a player holding down fire isn't seen as idle
+    let active = keys.get_pressed().next().is_some()
+        || mouse_buttons.get_pressed().next().is_some()
+        || moved_mouse
+        || gamepad_buttons.get_pressed().next().is_some()
+        || axes
+            .devices()
+            .any(|axis| axes.get(*axis).is_some_and(|value| value.abs() > STICK_DEADZONE));
+    if active {
+        idle.0 = 0.0;
+        return;
+    }
+    // Real time, as the game clock is stopped while paused
+    idle.0 += time.raw_delta_seconds();
+
+    if idle.0 >= settings.pause_after_secs + settings.quit_after_secs {
+        info!("No input for {:.0} seconds, going back to the title screen", idle.0);
+        idle.0 = 0.0;
+        next_game_state.set(GameState::Menu);
+        gameplay_state.set(GameplayState::None);
+    } else if idle.0 >= settings.pause_after_secs && *game_state.get() == GameState::Gameplay {
+        next_game_state.set(GameState::Paused);
+    }
+}
@@ -0,0 +1,150 @@
+// Pausing in the middle of a run. The run stays loaded while paused: the clock and the physics
+// simulation are stopped, and a menu is shown on top of the game.
+mod idle;
+
+use crate::gameplay::input::{Control, KeyBindings};
+use crate::persistence::load_or_default;
+use crate::storage::Storage;
+use crate::gameplay::GameplayState;
+use crate::landing_screen::options::{options_widget_style, spawn_options_panel};
+use crate::ui::theme::Theme;
+use crate::ui::widgets::{spawn_button, spawn_modal};
+use crate::DespawnOnExitExt;
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::RapierConfiguration;
+
+// Pages of the pause menu
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default, Hash, States)]
+enum PauseMenuState {
+    Main,
+    Options,
+    #[default]
+    None,
+}
+
+#[derive(Component)]
+enum PauseAction {
+    Resume,
+    GoToOptions,
+    GoBack,
+    QuitToMenu,
+}
+
+// Marker for UI objects on the main page of the pause menu
+#[derive(Component)]
+struct InPauseMenu;
+
+// Marker for UI objects on the options page of the pause menu
+#[derive(Component)]
+struct InPauseOptions;
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        let idle_settings = load_or_default::<idle::IdleSettings>(Storage::Config, idle::IDLE_FILE);
+        app.add_state::<PauseMenuState>()
+            .insert_resource(idle_settings)
+            .init_resource::<idle::IdleTime>()
+            .add_systems(
+                Update,
+                (toggle_pause, idle::detect_idle).run_if(in_state(GameplayState::Playing)),
+            )
+            .add_systems(OnEnter(GameState::Gameplay), idle::reset_idle_time)
+            .add_systems(OnEnter(GameState::Paused), pause)
+            .add_systems(OnExit(GameState::Paused), unpause)
+            .add_systems(OnEnter(PauseMenuState::Main), setup_main)
+            .despawn_on_exit::<InPauseMenu>(PauseMenuState::Main)
+            .add_systems(OnEnter(PauseMenuState::Options), setup_options)
+            .despawn_on_exit::<InPauseOptions>(PauseMenuState::Options)
+            .add_systems(
+                Update,
+                (crate::ui::colour_buttons, button_interactions)
+                    .run_if(in_state(GameState::Paused)),
+            );
+    }
+}
+
+// The pause key pauses the game, and pressing it again resumes it. Escape always works as
+// well, so that the game can still be paused after binding pause to an awkward key.
+fn toggle_pause(
+    input: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    game_state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !bindings.just_pressed(&input, Control::Pause) && !input.just_pressed(KeyCode::Escape) {
+        return;
+    }
+    match game_state.get() {
+        GameState::Gameplay => next_state.set(GameState::Paused),
+        GameState::Paused => next_state.set(GameState::Gameplay),
+        _ => {}
+    }
+}
+
+fn pause(
+    mut time: ResMut<Time>,
+    mut physics: ResMut<RapierConfiguration>,
+    mut menu_state: ResMut<NextState<PauseMenuState>>,
+) {
+    // Gameplay timers run on the gameplay clock, which is stopped on entering the paused
+    // state. The real clock is stopped as well so that particle effects freeze too.
+    time.pause();
+    physics.physics_pipeline_active = false;
+    menu_state.set(PauseMenuState::Main);
+}
+
+fn unpause(
+    mut time: ResMut<Time>,
+    mut physics: ResMut<RapierConfiguration>,
+    mut menu_state: ResMut<NextState<PauseMenuState>>,
+) {
+    time.unpause();
+    physics.physics_pipeline_active = true;
+    menu_state.set(PauseMenuState::None);
+}
+
+fn setup_main(mut commands: Commands, theme: Res<Theme>) {
+    let style = theme.widget_style(250.0, 50.0, 40.0);
+
+    spawn_modal(&mut commands, InPauseMenu, |parent| {
+        parent.spawn(TextBundle::from_section("Paused", theme.text_style(60.0)));
+        spawn_button(parent, PauseAction::Resume, "Resume", &style);
+        spawn_button(parent, PauseAction::GoToOptions, "Settings", &style);
+        spawn_button(parent, PauseAction::QuitToMenu, "Quit to Menu", &style);
+    });
+}
+
+fn setup_options(mut commands: Commands, theme: Res<Theme>) {
+    let style = options_widget_style(&theme);
+
+    spawn_modal(&mut commands, InPauseOptions, |parent| {
+        parent.spawn(TextBundle::from_section("Options", theme.text_style(40.0)));
+        spawn_options_panel(parent, &style);
+        spawn_button(parent, PauseAction::GoBack, "Back", &style);
+    });
+}
+
+#[allow(clippy::type_complexity)]
+fn button_interactions(
+    interaction: Query<(&Interaction, &PauseAction), (Changed<Interaction>, With<Button>)>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut gameplay_state: ResMut<NextState<GameplayState>>,
+    mut menu_state: ResMut<NextState<PauseMenuState>>,
+) {
+    for (interaction, action) in interaction.iter() {
+        if *interaction == Interaction::Pressed {
+            match action {
+                PauseAction::Resume => game_state.set(GameState::Gameplay),
+                PauseAction::GoToOptions => menu_state.set(PauseMenuState::Options),
+                PauseAction::GoBack => menu_state.set(PauseMenuState::Main),
+                PauseAction::QuitToMenu => {
+                    game_state.set(GameState::Menu);
+                    gameplay_state.set(GameplayState::None);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,170 @@
+// Keeps the high score table, game options and key bindings between launches of the game. Other
+// systems can keep their own data the same way through the SaveLoad resource.
+use crate::gameplay::input::KeyBindings;
+use crate::shutdown::SaveRequest;
+use crate::storage::{self, Storage};
+use crate::high_scores::HighScoreTable;
+use crate::GameOptions;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{fmt, io};
+
+const OPTIONS_FILE: &str = "options.ron";
+const HIGH_SCORES_FILE: &str = "highscores.ron";
+// Older versions only kept the best score, in "highscore.ron"
+const OLD_HIGH_SCORE_FILE: &str = "highscore.ron";
+const KEY_BINDINGS_FILE: &str = "keybindings.ron";
+
+#[derive(Debug)]
+pub enum PersistenceError {
+    Io(io::Error),
+    Format(String),
+}
+
+impl fmt::Display for PersistenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PersistenceError::Io(error) => write!(f, "{}", error),
+            PersistenceError::Format(error) => write!(f, "invalid save file: {}", error),
+        }
+    }
+}
+
+// Saving and loading of anything that should be kept between launches. Saves are queued and
+// written out at the end of the frame, so a value that changes several times in one frame is
+// only written once.
+#[derive(Resource, Debug, Default)]
+pub struct SaveLoad {
+    pending: HashMap<(Storage, String), String>,
+}
+
+impl SaveLoad {
+    // Read a saved value. Returns None if nothing has been saved under the name yet.
+    pub fn load<T: DeserializeOwned>(
+        storage: Storage,
+        name: &str,
+    ) -> Result<Option<T>, PersistenceError> {
+        let contents = match storage::read_string(storage, name) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(PersistenceError::Io(error)),
+        };
+        ron::from_str(&contents)
+            .map(Some)
+            .map_err(|error| PersistenceError::Format(error.to_string()))
+    }
+
+    // Queue a value to be saved, replacing anything already queued under the same name
+    pub fn save<T: Serialize>(&mut self, storage: Storage, name: &str, value: &T) {
+        match ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => {
+                self.pending.insert((storage, name.to_string()), contents);
+            }
+            Err(error) => error!("Couldn't save {}: {}", name, error),
+        }
+    }
+
+    fn flush(&mut self) {
+        for ((storage, name), contents) in self.pending.drain() {
+            if let Err(error) = storage::write(storage, &name, contents.as_bytes()) {
+                error!("Couldn't save {}: {}", name, error);
+            }
+        }
+    }
+}
+
+// What older versions saved in OLD_HIGH_SCORE_FILE
+#[derive(Deserialize)]
+struct OldHighScore(u64);
+
+// Load the high score table. When there isn't one yet, the best score saved by an older
+// version is carried over into it, and the table is saved straight away.
+fn load_high_scores(save_load: &mut SaveLoad) -> HighScoreTable {
+    match SaveLoad::load::<HighScoreTable>(Storage::Saves, HIGH_SCORES_FILE) {
+        Ok(Some(table)) => table,
+        Ok(None) => {
+            let old = SaveLoad::load::<OldHighScore>(Storage::Saves, OLD_HIGH_SCORE_FILE);
+            let Ok(Some(OldHighScore(score))) = old else { return HighScoreTable::default(); };
+            info!("Carrying the old high score of {} over into the table", score);
+            let table = HighScoreTable::from_old_high_score(score);
+            save_load.save(Storage::Saves, HIGH_SCORES_FILE, &table);
+            table
+        }
+        Err(error) => {
+            warn!("Couldn't load {}, using defaults instead: {}", HIGH_SCORES_FILE, error);
+            HighScoreTable::default()
+        }
+    }
+}
+
+// Load a saved value, or the default one if nothing was saved or the save can't be read
+pub fn load_or_default<T: DeserializeOwned + Default>(storage: Storage, name: &str) -> T {
+    match SaveLoad::load(storage, name) {
+        Ok(value) => value.unwrap_or_default(),
+        Err(error) => {
+            warn!("Couldn't load {}, using defaults instead: {}", name, error);
+            T::default()
+        }
+    }
+}
+
+pub struct PersistencePlugin;
+
+impl Plugin for PersistencePlugin {
+    fn build(&self, app: &mut App) {
+        // Loaded straight away rather than in a startup system, so that everything sees the
+        // saved values from the very first frame.
+        let mut save_load = SaveLoad::default();
+        let high_scores = load_high_scores(&mut save_load);
+        app.insert_resource(load_or_default::<GameOptions>(Storage::Config, OPTIONS_FILE))
+            .insert_resource(high_scores)
+            .insert_resource(load_or_default::<KeyBindings>(Storage::Config, KEY_BINDINGS_FILE))
+            .insert_resource(save_load)
+            .add_systems(
+                Update,
+                (save_options, save_high_scores, save_key_bindings, save_on_request),
+            )
+            .add_systems(PostUpdate, flush_saves);
+    }
+}
+
+fn save_options(options: Res<GameOptions>, mut save_load: ResMut<SaveLoad>) {
+    if options.is_changed() && !options.is_added() {
+        save_load.save(Storage::Config, OPTIONS_FILE, &*options);
+    }
+}
+
+fn save_high_scores(high_scores: Res<HighScoreTable>, mut save_load: ResMut<SaveLoad>) {
+    if high_scores.is_changed() && !high_scores.is_added() {
+        save_load.save(Storage::Saves, HIGH_SCORES_FILE, &*high_scores);
+    }
+}
+
+fn save_key_bindings(bindings: Res<KeyBindings>, mut save_load: ResMut<SaveLoad>) {
+    if bindings.is_changed() && !bindings.is_added() {
+        save_load.save(Storage::Config, KEY_BINDINGS_FILE, &*bindings);
+    }
+}
+
+// Save everything once more before the game exits
+fn save_on_request(
+    mut save_ev: EventReader<SaveRequest>,
+    options: Res<GameOptions>,
+    high_scores: Res<HighScoreTable>,
+    bindings: Res<KeyBindings>,
+    mut save_load: ResMut<SaveLoad>,
+) {
+    if save_ev.iter().count() == 0 {
+        return;
+    }
+    save_load.save(Storage::Config, OPTIONS_FILE, &*options);
+    save_load.save(Storage::Saves, HIGH_SCORES_FILE, &*high_scores);
+    save_load.save(Storage::Config, KEY_BINDINGS_FILE, &*bindings);
+}
+
+fn flush_saves(mut save_load: ResMut<SaveLoad>) {
+    if !save_load.pending.is_empty() {
+        save_load.flush();
+    }
+}
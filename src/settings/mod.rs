@@ -0,0 +1,66 @@
+// Exporting and importing of all the player's settings as a single file, for moving them
+// to another machine or attaching them to a bug report.
+use crate::gameplay::input::KeyBindings;
+use crate::storage::{self, Storage};
+use crate::GameOptions;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const SETTINGS_FILE: &str = "settings.ron";
+// Increased whenever the layout of the file changes in a way that older versions of the
+// game can't read.
+const SETTINGS_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SettingsFile {
+    version: u32,
+    options: GameOptions,
+    // Not in files exported before key bindings existed
+    #[serde(default)]
+    key_bindings: KeyBindings,
+}
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Io(std::io::Error),
+    Format(String),
+    // The file was written by a newer version of the game
+    Version(u32),
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::Io(error) => write!(f, "{}", error),
+            SettingsError::Format(error) => write!(f, "invalid settings file: {}", error),
+            SettingsError::Version(version) => {
+                write!(f, "settings file version {} is not supported", version)
+            }
+        }
+    }
+}
+
+pub fn export_settings(
+    options: &GameOptions,
+    key_bindings: &KeyBindings,
+) -> Result<(), SettingsError> {
+    let file = SettingsFile {
+        version: SETTINGS_VERSION,
+        options: options.clone(),
+        key_bindings: key_bindings.clone(),
+    };
+    let contents = ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default())
+        .map_err(|error| SettingsError::Format(error.to_string()))?;
+    storage::write(Storage::Exports, SETTINGS_FILE, contents.as_bytes()).map_err(SettingsError::Io)
+}
+
+pub fn import_settings() -> Result<(GameOptions, KeyBindings), SettingsError> {
+    let contents =
+        storage::read_string(Storage::Exports, SETTINGS_FILE).map_err(SettingsError::Io)?;
+    let file: SettingsFile =
+        ron::from_str(&contents).map_err(|error| SettingsError::Format(error.to_string()))?;
+    if file.version > SETTINGS_VERSION {
+        return Err(SettingsError::Version(file.version));
+    }
+    Ok((file.options, file.key_bindings))
+}
@@ -0,0 +1,147 @@
+// Everything that quits the game goes through here, so that anything which has to be saved
+// gets the chance to do so before the app actually exits.
+use crate::gameplay::GameplayState;
+use crate::GameState;
+use crate::ui::theme::Theme;
+use crate::ui::widgets::{spawn_button, spawn_modal};
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::window::{PrimaryWindow, WindowCloseRequested};
+use bevy_rapier2d::prelude::RapierConfiguration;
+
+// Ask for the game to be closed. Used instead of sending AppExit directly.
+#[derive(Event, Default)]
+pub struct ExitRequest;
+
+// Sent right before the game exits. Anything that keeps data on disk (high scores,
+// options, stats, replays) should write it out when this is received.
+#[derive(Event, Default)]
+pub struct SaveRequest;
+
+// Number of frames left until the game exits, once an exit has been requested.
+// Savers get a frame to react to the SaveRequest.
+#[derive(Resource, Default)]
+struct Shutdown(Option<u8>);
+
+// Marker for the dialog asking whether to quit in the middle of a run
+#[derive(Component)]
+struct ConfirmQuit;
+
+#[derive(Component)]
+enum ConfirmAction {
+    Quit,
+    Cancel,
+}
+
+pub struct ShutdownPlugin;
+
+impl Plugin for ShutdownPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ExitRequest>()
+            .add_event::<SaveRequest>()
+            .init_resource::<Shutdown>()
+            .add_systems(
+                Update,
+                (
+                    window_close_requested,
+                    confirm_interactions,
+                    crate::ui::colour_buttons.run_if(any_with_component::<ConfirmQuit>()),
+                    exit_requested,
+                    finish_exit,
+                )
+                    .chain(),
+            );
+    }
+}
+
+// Closing the window during a run freezes the game and asks first, as the run would be
+// lost. Otherwise the game exits straight away.
+#[allow(clippy::too_many_arguments)]
+fn window_close_requested(
+    mut commands: Commands,
+    mut close_ev: EventReader<WindowCloseRequested>,
+    primary_window: Query<(), With<PrimaryWindow>>,
+    mut exit_ev: EventWriter<ExitRequest>,
+    gameplay_state: Res<State<GameplayState>>,
+    confirm: Query<(), With<ConfirmQuit>>,
+    theme: Res<Theme>,
+    mut time: ResMut<Time>,
+    mut physics: ResMut<RapierConfiguration>,
+) {
+    // Other windows, like the debug window, close on their own
+    let primary_closed = close_ev.iter().any(|event| primary_window.contains(event.window));
+    if !primary_closed {
+        return;
+    }
+    if *gameplay_state.get() != GameplayState::Playing {
+        exit_ev.send_default();
+        return;
+    }
+    if !confirm.is_empty() {
+        return;
+    }
+
+    time.pause();
+    physics.physics_pipeline_active = false;
+
+    let style = theme.widget_style(175.0, 50.0, 40.0);
+    spawn_modal(&mut commands, ConfirmQuit, |parent| {
+        parent.spawn(TextBundle::from_section(
+            "Quit in the middle of a run?",
+            theme.text_style(36.0),
+        ));
+        spawn_button(parent, ConfirmAction::Quit, "Quit", &style);
+        spawn_button(parent, ConfirmAction::Cancel, "Cancel", &style);
+    });
+}
+
+#[allow(clippy::type_complexity)]
+fn confirm_interactions(
+    mut commands: Commands,
+    interaction: Query<(&Interaction, &ConfirmAction), (Changed<Interaction>, With<Button>)>,
+    confirm: Query<Entity, With<ConfirmQuit>>,
+    mut exit_ev: EventWriter<ExitRequest>,
+    game_state: Res<State<GameState>>,
+    mut time: ResMut<Time>,
+    mut physics: ResMut<RapierConfiguration>,
+) {
+    for (interaction, action) in interaction.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match action {
+            ConfirmAction::Quit => exit_ev.send_default(),
+            ConfirmAction::Cancel => {
+                for entity in confirm.iter() {
+                    commands.entity(entity).despawn_recursive();
+                }
+                // Stay frozen if the game was already paused or between levels before closing
+                // was attempted
+                if !matches!(game_state.get(), GameState::Paused | GameState::StageClear) {
+                    time.unpause();
+                    physics.physics_pipeline_active = true;
+                }
+            }
+        }
+    }
+}
+
+fn exit_requested(
+    mut exit_requests: EventReader<ExitRequest>,
+    mut save_ev: EventWriter<SaveRequest>,
+    mut shutdown: ResMut<Shutdown>,
+) {
+    if exit_requests.iter().count() > 0 && shutdown.0.is_none() {
+        info!("Exit requested, saving before quitting");
+        save_ev.send_default();
+        shutdown.0 = Some(1);
+    }
+}
+
+fn finish_exit(mut shutdown: ResMut<Shutdown>, mut exit: EventWriter<AppExit>) {
+    match shutdown.0 {
+        Some(0) => exit.send(AppExit),
+        Some(frames) => shutdown.0 = Some(frames - 1),
+        None => {}
+    }
+}
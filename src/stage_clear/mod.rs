@@ -0,0 +1,106 @@
+// Results screen shown between levels. The stage bonus has already been added to the score
+// by the time it is shown, this only shows how it was made up. The run stays loaded and frozen
+// behind it, like in the pause menu.
+use crate::gameplay::levels::{CurrentLevel, StageBonuses};
+use crate::gameplay::shared::format_duration;
+use crate::ui::theme::Theme;
+use crate::ui::widgets::spawn_modal;
+use crate::DespawnOnExitExt;
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::RapierConfiguration;
+
+// How long the results stay up if no key is pressed
+const RESULTS_SECS: f32 = 5.0;
+// Keys pressed before this are ignored, so that the results aren't skipped by accident by
+// someone still firing at the boss
+const SKIP_AFTER_SECS: f32 = 1.0;
+
+// Marker for UI objects of the stage clear screen
+#[derive(Component)]
+struct InStageClear;
+
+#[derive(Resource, Debug, Deref, DerefMut)]
+struct ResultsTimer(Timer);
+
+pub struct StageClearPlugin;
+
+impl Plugin for StageClearPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::StageClear), (freeze_physics, show_results))
+            .add_systems(OnExit(GameState::StageClear), unfreeze_physics)
+            .add_systems(Update, next_stage.run_if(in_state(GameState::StageClear)))
+            .despawn_on_exit::<InStageClear>(GameState::StageClear);
+    }
+}
+
+fn freeze_physics(mut physics: ResMut<RapierConfiguration>) {
+    physics.physics_pipeline_active = false;
+}
+
+fn unfreeze_physics(mut physics: ResMut<RapierConfiguration>) {
+    physics.physics_pipeline_active = true;
+}
+
+fn show_results(mut commands: Commands, theme: Res<Theme>, bonuses: Res<StageBonuses>) {
+    commands.insert_resource(ResultsTimer(Timer::from_seconds(RESULTS_SECS, TimerMode::Once)));
+    let Some(bonus) = bonuses.last() else { return; };
+
+    let mut lines = vec![
+        format!(
+            "Time {} (par {})  +{}",
+            format_duration(bonus.time),
+            format_duration(bonus.par_time),
+            bonus.time_bonus,
+        ),
+        format!("Graze  +{}", bonus.graze_bonus),
+        format!("Lives  +{}", bonus.lives_bonus),
+    ];
+    if bonus.no_miss_bonus > 0 {
+        lines.push(format!("No Miss  +{}", bonus.no_miss_bonus));
+    }
+    if bonus.no_bomb_bonus > 0 {
+        lines.push(format!("No Bomb  +{}", bonus.no_bomb_bonus));
+    }
+
+    spawn_modal(&mut commands, InStageClear, |parent| {
+        parent.spawn(TextBundle::from_section(
+            format!("Level {:?} Clear", bonus.level),
+            theme.text_style(50.0),
+        ));
+        for line in lines {
+            parent.spawn(TextBundle::from_section(line, theme.text_style(26.0)));
+        }
+        parent.spawn(TextBundle::from_section(
+            format!("Stage Bonus  {}", bonus.total()),
+            theme.text_style(34.0),
+        ));
+        parent.spawn(TextBundle::from_section(
+            "Press any key to continue",
+            theme.text_style(20.0),
+        ));
+    });
+}
+
+// Move on once the results have been up for long enough, or straight away on a key press
+fn next_stage(
+    time: Res<Time>,
+    mut timer: ResMut<ResultsTimer>,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    current_level: Res<State<CurrentLevel>>,
+    mut next_level: ResMut<NextState<CurrentLevel>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    timer.tick(time.delta());
+    let skipped = timer.elapsed_secs() >= SKIP_AFTER_SECS
+        && (keys.get_just_pressed().next().is_some()
+            || buttons.get_just_pressed().next().is_some());
+    if !timer.finished() && !skipped {
+        return;
+    }
+    if let Some(level) = current_level.get().next_stage() {
+        next_level.set(level);
+    }
+    game_state.set(GameState::Gameplay);
+}
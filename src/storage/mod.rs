@@ -0,0 +1,61 @@
+// Reading and writing of user data. Every feature which stores something on the player's
+// machine goes through here, so that only this module has to care about where and how
+// files are kept on each platform.
+#[cfg(not(target_arch = "wasm32"))]
+mod native;
+#[cfg(target_arch = "wasm32")]
+mod web;
+
+#[cfg(not(target_arch = "wasm32"))]
+use native as platform;
+#[cfg(target_arch = "wasm32")]
+use web as platform;
+
+use std::io;
+
+// Name used for the game's folder (or key prefix on the web)
+const APP_NAME: &str = env!("CARGO_PKG_NAME");
+
+// Kinds of user data, each kept in its own place
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Storage {
+    // Settings, key bindings, etc.
+    Config,
+    // Save data such as high scores
+    Saves,
+    Screenshots,
+    // Run summaries and other files meant to be shared
+    Exports,
+}
+
+impl Storage {
+    fn folder(self) -> &'static str {
+        match self {
+            Storage::Config => "config",
+            Storage::Saves => "saves",
+            Storage::Screenshots => "screenshots",
+            Storage::Exports => "exports",
+        }
+    }
+}
+
+pub fn read(storage: Storage, name: &str) -> io::Result<Vec<u8>> {
+    platform::read(storage, name)
+}
+
+pub fn read_string(storage: Storage, name: &str) -> io::Result<String> {
+    String::from_utf8(read(storage, name)?)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+// Write a file, replacing it if it already exists
+pub fn write(storage: Storage, name: &str, contents: &[u8]) -> io::Result<()> {
+    platform::write(storage, name, contents)
+}
+
+// Where a file is kept on disk, for things which have to write the file themselves. There is
+// no file system on the web.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn path(storage: Storage, name: &str) -> io::Result<std::path::PathBuf> {
+    platform::path(storage, name)
+}
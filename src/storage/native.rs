@@ -0,0 +1,39 @@
+use super::{Storage, APP_NAME};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+// Config goes in the OS config folder, everything else in the data folder,
+// e.g. ~/.config/shmup_game/config and ~/.local/share/shmup_game/saves on Linux.
+fn folder(storage: Storage) -> io::Result<PathBuf> {
+    let base = match storage {
+        Storage::Config => dirs::config_dir(),
+        _ => dirs::data_dir(),
+    };
+    let Some(base) = base else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "Couldn't find a folder for user data on this system.",
+        ));
+    };
+    let folder = base.join(APP_NAME).join(storage.folder());
+    fs::create_dir_all(&folder)?;
+    Ok(folder)
+}
+
+pub fn read(storage: Storage, name: &str) -> io::Result<Vec<u8>> {
+    fs::read(folder(storage)?.join(name))
+}
+
+pub fn write(storage: Storage, name: &str, contents: &[u8]) -> io::Result<()> {
+    // Write to a temporary file first so that a crash part way through
+    // doesn't leave a broken file behind.
+    let path = folder(storage)?.join(name);
+    let temp = path.with_extension("tmp");
+    fs::write(&temp, contents)?;
+    fs::rename(temp, path)
+}
+
+pub fn path(storage: Storage, name: &str) -> io::Result<PathBuf> {
+    Ok(folder(storage)?.join(name))
+}
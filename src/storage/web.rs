@@ -0,0 +1,36 @@
+use super::{Storage, APP_NAME};
+use std::io;
+
+// There is no file system in the browser, so files are kept in local storage under keys
+// like "shmup_game/saves/highscores.ron". Local storage only holds strings, so contents
+// must be valid UTF-8.
+
+fn local_storage() -> io::Result<web_sys::Storage> {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "Local storage is unavailable."))
+}
+
+fn prefix(storage: Storage) -> String {
+    format!("{}/{}/", APP_NAME, storage.folder())
+}
+
+fn js_error(error: web_sys::wasm_bindgen::JsValue) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, format!("{:?}", error))
+}
+
+pub fn read(storage: Storage, name: &str) -> io::Result<Vec<u8>> {
+    local_storage()?
+        .get_item(&(prefix(storage) + name))
+        .map_err(js_error)?
+        .map(String::into_bytes)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, name.to_string()))
+}
+
+pub fn write(storage: Storage, name: &str, contents: &[u8]) -> io::Result<()> {
+    let contents = std::str::from_utf8(contents)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    local_storage()?
+        .set_item(&(prefix(storage) + name), contents)
+        .map_err(js_error)
+}
@@ -0,0 +1,102 @@
+use super::theme::Theme;
+use super::widgets::spawn_toast;
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+// Font used by the HUD and as the default theme font
+pub const MAIN_FONT: &str = "fonts/FiraSans-Bold.ttf";
+
+// Fonts loaded at startup, so that they are ready by the time any screen uses them
+const PRELOADED_FONTS: [&str; 2] = [MAIN_FONT, "fonts/FiraSans-ExtraBold.ttf"];
+
+#[derive(Debug, Clone, PartialEq)]
+enum FontStatus {
+    Loading(Handle<Font>),
+    Loaded(Handle<Font>),
+    Failed,
+}
+
+// Hash table holding handles to fonts, with the path of the font as the key.
+// Fonts which failed to load are replaced with Bevy's built in font, as text
+// drawn with a missing font would otherwise be invisible.
+#[derive(Resource, Debug)]
+pub struct Fonts(HashMap<String, FontStatus>);
+
+impl Fonts {
+    // Get a font, loading it first if it hasn't been loaded yet
+    pub fn load(&mut self, assets: &AssetServer, path: &str) -> Handle<Font> {
+        let status = self
+            .0
+            .entry(path.to_string())
+            .or_insert_with(|| FontStatus::Loading(assets.load(path)));
+        match status {
+            FontStatus::Loading(handle) | FontStatus::Loaded(handle) => handle.clone(),
+            FontStatus::Failed => Handle::default(),
+        }
+    }
+    pub fn main(&self) -> Handle<Font> {
+        match self.0.get(MAIN_FONT) {
+            Some(FontStatus::Loading(handle)) | Some(FontStatus::Loaded(handle)) => handle.clone(),
+            _ => Handle::default(),
+        }
+    }
+    // True once every font has either loaded or failed to load
+    pub fn ready(&self) -> bool {
+        !self.0.values().any(|status| matches!(status, FontStatus::Loading(_)))
+    }
+}
+
+impl FromWorld for Fonts {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self(
+            PRELOADED_FONTS
+                .iter()
+                .map(|path| (path.to_string(), FontStatus::Loading(assets.load(*path))))
+                .collect(),
+        )
+    }
+}
+
+// Run condition for loading stages that should wait for the fonts
+pub fn fonts_ready(fonts: Res<Fonts>) -> bool {
+    fonts.ready()
+}
+
+// Keep track of fonts which are still loading. When one fails, switch everything using it
+// over to the fallback font and let the player know.
+pub fn check_fonts(
+    mut commands: Commands,
+    assets: Res<AssetServer>,
+    mut fonts: ResMut<Fonts>,
+    mut theme: ResMut<Theme>,
+    mut texts: Query<&mut Text>,
+) {
+    if fonts.ready() {
+        return;
+    }
+    for (path, status) in fonts.0.iter_mut() {
+        let FontStatus::Loading(handle) = status.clone() else { continue; };
+        match assets.get_load_state(&handle) {
+            LoadState::Loaded => *status = FontStatus::Loaded(handle),
+            LoadState::Failed => {
+                warn!("Couldn't load font {}, using the default font instead.", path);
+                *status = FontStatus::Failed;
+
+                for mut text in texts.iter_mut() {
+                    for section in text.sections.iter_mut() {
+                        if section.style.font == handle {
+                            section.style.font = Handle::default();
+                        }
+                    }
+                }
+                if theme.font == handle {
+                    theme.font = Handle::default();
+                }
+                spawn_toast(&mut commands, &theme, &format!("Missing font: {}", path));
+            }
+            _ => {}
+        }
+    }
+}
@@ -1,14 +1,25 @@
+pub mod fonts;
+pub mod theme;
+pub mod widgets;
+
 use bevy::prelude::*;
+use theme::Theme;
+
+pub struct UiPlugin;
 
-// Consistent colour scheme for buttons and text throughout the game
-pub const BUTTON_BASE: Color = Color::rgb(0.2, 0.2, 0.2);
-pub const BUTTON_HOVER: Color = Color::rgb(0.45, 0.35, 0.35);
-pub const BUTTON_PRESS: Color = Color::rgb(0.75, 0.55, 0.55);
-pub const TEXT_COLOUR: Color = Color::rgb(0.9, 0.9, 0.9);
+impl Plugin for UiPlugin {
+    fn build(&self, app: &mut App) {
+        // Fonts have to exist before the theme, which starts out with the main font
+        app.init_resource::<fonts::Fonts>()
+            .add_plugin(theme::ThemePlugin)
+            .add_systems(Update, (fonts::check_fonts, widgets::expire_toasts));
+    }
+}
 
 // Change the colour of buttons when hovered over or clicked on
 #[allow(clippy::type_complexity)]
 pub fn colour_buttons(
+    theme: Res<Theme>,
     mut interaction: Query<
         (&Interaction, &mut BackgroundColor),
         (Changed<Interaction>, With<Button>),
@@ -16,9 +27,9 @@ pub fn colour_buttons(
 ) {
     for (interaction, mut colour) in interaction.iter_mut() {
         *colour = match *interaction {
-            Interaction::Pressed => BUTTON_PRESS.into(),
-            Interaction::Hovered => BUTTON_HOVER.into(),
-            Interaction::None => BUTTON_BASE.into(),
+            Interaction::Pressed => theme.button_press.into(),
+            Interaction::Hovered => theme.button_hover.into(),
+            Interaction::None => theme.button_base.into(),
         }
     }
 }
@@ -0,0 +1,220 @@
+use super::fonts::Fonts;
+use super::widgets::WidgetStyle;
+use crate::GameOptions;
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::{TypePath, TypeUuid};
+use bevy::utils::BoxedFuture;
+use serde::{Deserialize, Serialize};
+
+// Themes which can be picked in the options
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Debug, Default, Serialize, Deserialize)]
+pub enum ThemeKind {
+    #[default]
+    Default,
+    HighContrast,
+}
+
+impl ThemeKind {
+    pub fn next(self) -> Self {
+        match self {
+            ThemeKind::Default => ThemeKind::HighContrast,
+            ThemeKind::HighContrast => ThemeKind::Default,
+        }
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            ThemeKind::Default => "Default",
+            ThemeKind::HighContrast => "High Contrast",
+        }
+    }
+    fn path(self) -> &'static str {
+        match self {
+            ThemeKind::Default => "themes/default.theme.ron",
+            ThemeKind::HighContrast => "themes/high_contrast.theme.ron",
+        }
+    }
+}
+
+// Colours, font and spacing used by all the menus
+#[derive(Debug, Clone, PartialEq, Resource)]
+pub struct Theme {
+    pub button_base: Color,
+    pub button_hover: Color,
+    pub button_press: Color,
+    pub text: Color,
+    pub font: Handle<Font>,
+    // Multiplier for every font size
+    pub text_scale: f32,
+    // Space around buttons
+    pub padding: f32,
+}
+
+impl Theme {
+    pub fn text_style(&self, font_size: f32) -> TextStyle {
+        TextStyle {
+            font: self.font.clone(),
+            font_size: font_size * self.text_scale,
+            color: self.text,
+        }
+    }
+    // Style of a button of a given size with its contents centered
+    pub fn button_style(&self, width: f32, height: f32) -> Style {
+        Style {
+            width: Val::Px(width),
+            height: Val::Px(height),
+            margin: UiRect::all(Val::Px(self.padding)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        }
+    }
+    pub fn widget_style(&self, button_width: f32, button_height: f32, font_size: f32) -> WidgetStyle {
+        WidgetStyle {
+            button: self.button_style(button_width, button_height),
+            button_colour: self.button_base,
+            text: self.text_style(font_size),
+        }
+    }
+}
+
+// Used until the selected theme file has been loaded
+impl FromWorld for Theme {
+    fn from_world(world: &mut World) -> Self {
+        let fonts = world.resource::<Fonts>();
+        Self {
+            button_base: Color::rgb(0.2, 0.2, 0.2),
+            button_hover: Color::rgb(0.45, 0.35, 0.35),
+            button_press: Color::rgb(0.75, 0.55, 0.55),
+            text: Color::rgb(0.9, 0.9, 0.9),
+            font: fonts.main(),
+            text_scale: 1.0,
+            padding: 10.0,
+        }
+    }
+}
+
+// Contents of a .theme.ron file
+#[derive(Debug, Deserialize, TypeUuid, TypePath)]
+#[uuid = "6f1b8d2e-3c4a-4e57-9b1a-2d8f5c7e9a13"]
+pub struct ThemeAsset {
+    button_base: [f32; 3],
+    button_hover: [f32; 3],
+    button_press: [f32; 3],
+    text: [f32; 3],
+    font: String,
+    text_scale: f32,
+    padding: f32,
+}
+
+impl ThemeAsset {
+    fn to_theme(&self, fonts: &mut Fonts, assets: &AssetServer) -> Theme {
+        let colour = |c: [f32; 3]| Color::rgb(c[0], c[1], c[2]);
+        Theme {
+            button_base: colour(self.button_base),
+            button_hover: colour(self.button_hover),
+            button_press: colour(self.button_press),
+            text: colour(self.text),
+            font: fonts.load(assets, &self.font),
+            text_scale: self.text_scale,
+            padding: self.padding,
+        }
+    }
+}
+
+#[derive(Default)]
+struct ThemeLoader;
+
+impl AssetLoader for ThemeLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let theme: ThemeAsset = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(theme));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["theme.ron"]
+    }
+}
+
+#[derive(Resource)]
+struct ThemeHandles {
+    default: Handle<ThemeAsset>,
+    high_contrast: Handle<ThemeAsset>,
+}
+
+impl ThemeHandles {
+    fn get(&self, kind: ThemeKind) -> &Handle<ThemeAsset> {
+        match kind {
+            ThemeKind::Default => &self.default,
+            ThemeKind::HighContrast => &self.high_contrast,
+        }
+    }
+}
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<ThemeAsset>()
+            .init_asset_loader::<ThemeLoader>()
+            .init_resource::<Theme>()
+            .add_systems(Startup, load_themes)
+            .add_systems(Update, apply_theme);
+    }
+}
+
+fn load_themes(mut commands: Commands, assets: Res<AssetServer>) {
+    commands.insert_resource(ThemeHandles {
+        default: assets.load(ThemeKind::Default.path()),
+        high_contrast: assets.load(ThemeKind::HighContrast.path()),
+    });
+}
+
+// Switch to the theme selected in the options once it has loaded, and restyle the
+// UI which is already on screen.
+#[allow(clippy::too_many_arguments)]
+fn apply_theme(
+    options: Res<GameOptions>,
+    handles: Res<ThemeHandles>,
+    themes: Res<Assets<ThemeAsset>>,
+    mut theme_events: EventReader<AssetEvent<ThemeAsset>>,
+    assets: Res<AssetServer>,
+    mut fonts: ResMut<Fonts>,
+    mut theme: ResMut<Theme>,
+    mut buttons: Query<&mut BackgroundColor, With<Button>>,
+    mut texts: Query<&mut Text>,
+) {
+    // A theme file can finish loading, or be reloaded, after it has been selected
+    let themes_changed = theme_events.iter().count() > 0;
+    if !options.is_changed() && !themes_changed {
+        return;
+    }
+    let Some(asset) = themes.get(handles.get(options.get_theme())) else { return; };
+    let new_theme = asset.to_theme(&mut fonts, &assets);
+    if new_theme == *theme {
+        return;
+    }
+
+    for mut colour in buttons.iter_mut() {
+        *colour = new_theme.button_base.into();
+    }
+    // Only touch text which was drawn with the old theme, other text (e.g. the HUD)
+    // has its own colours.
+    for mut text in texts.iter_mut() {
+        for section in text.sections.iter_mut() {
+            if section.style.color == theme.text && section.style.font == theme.font {
+                section.style.color = new_theme.text;
+                section.style.font = new_theme.font.clone();
+                section.style.font_size *= new_theme.text_scale / theme.text_scale;
+            }
+        }
+    }
+    *theme = new_theme;
+}
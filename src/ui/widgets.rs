@@ -0,0 +1,235 @@
+use super::theme::Theme;
+use bevy::prelude::*;
+
+// Styles shared by a group of widgets, usually created with Theme::widget_style
+#[derive(Debug, Clone)]
+pub struct WidgetStyle {
+    pub button: Style,
+    pub button_colour: Color,
+    pub text: TextStyle,
+}
+
+// Root element of a menu screen. A column of elements centered horizontally, near the top of
+// the screen. `marker` is added to the root so that the screen can be despawned later.
+pub fn spawn_menu_root(
+    commands: &mut Commands,
+    marker: impl Bundle,
+    content: impl FnOnce(&mut ChildBuilder),
+) -> Entity {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    margin: UiRect {
+                        left: Val::Auto,
+                        right: Val::Auto,
+                        top: Val::Px(40.0),
+                        bottom: Val::Auto,
+                    },
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::NONE.into(),
+                ..default()
+            },
+            marker,
+        ))
+        .with_children(content)
+        .id()
+}
+
+// Column of buttons, placed some distance below the previous element
+pub fn spawn_button_column(parent: &mut ChildBuilder, content: impl FnOnce(&mut ChildBuilder)) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                margin: UiRect::top(Val::Px(150.0)),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            ..default()
+        })
+        .with_children(content);
+}
+
+// Button with a text label. `action` is the component which the screen that spawned the
+// button uses to find out what pressing it should do.
+pub fn spawn_button(
+    parent: &mut ChildBuilder,
+    action: impl Component,
+    label: &str,
+    style: &WidgetStyle,
+) -> Entity {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: style.button.clone(),
+                background_color: style.button_colour.into(),
+                ..default()
+            },
+            action,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(label, style.text.clone()));
+        })
+        .id()
+}
+
+// Row with a text label on the left and any other content on the right.
+// `label_marker` is added to the label, so that its text can be updated later.
+pub fn spawn_labeled_row(
+    parent: &mut ChildBuilder,
+    label: &str,
+    label_marker: impl Bundle,
+    style: &WidgetStyle,
+    content: impl FnOnce(&mut ChildBuilder),
+) -> Entity {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                flex_wrap: FlexWrap::NoWrap,
+                justify_content: JustifyContent::SpaceBetween,
+                align_items: AlignItems::Center,
+                width: Val::Percent(98.0),
+                height: Val::Percent(10.0),
+                margin: UiRect {
+                    top: Val::Px(25.0),
+                    left: Val::Px(15.0),
+                    ..default()
+                },
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(label, style.text.clone()),
+                label_marker,
+            ));
+            content(parent);
+        })
+        .id()
+}
+
+// Labeled row with a button for switching something on or off
+pub fn spawn_toggle(
+    parent: &mut ChildBuilder,
+    label: &str,
+    label_marker: impl Bundle,
+    action: impl Component,
+    style: &WidgetStyle,
+) -> Entity {
+    spawn_labeled_row(parent, label, label_marker, style, |parent| {
+        spawn_button(parent, action, "Switch", style);
+    })
+}
+
+// Labeled row with buttons for increasing and decreasing a value
+pub fn spawn_slider(
+    parent: &mut ChildBuilder,
+    label: &str,
+    label_marker: impl Bundle,
+    increase: impl Component,
+    decrease: impl Component,
+    style: &WidgetStyle,
+) -> Entity {
+    let mut small_button = style.clone();
+    small_button.button.width = style.button.width / 2.0;
+
+    spawn_labeled_row(parent, label, label_marker, style, |parent| {
+        parent
+            .spawn(NodeBundle {
+                style: Style {
+                    flex_wrap: FlexWrap::NoWrap,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|parent| {
+                spawn_button(parent, increase, "+", &small_button);
+                spawn_button(parent, decrease, "-", &small_button);
+            });
+    })
+}
+
+// Dialog shown on top of everything else. The whole screen behind it is dimmed and the
+// content is placed in a column in the middle of the screen.
+pub fn spawn_modal(
+    commands: &mut Commands,
+    marker: impl Bundle,
+    content: impl FnOnce(&mut ChildBuilder),
+) -> Entity {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            marker,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.1, 0.1, 0.1, 0.9).into(),
+                    ..default()
+                })
+                .with_children(content);
+        })
+        .id()
+}
+
+// Short message shown in the corner of the screen for a few seconds
+#[derive(Component, Deref, DerefMut)]
+pub struct Toast(Timer);
+
+pub fn spawn_toast(commands: &mut Commands, theme: &Theme, message: &str) -> Entity {
+    commands
+        .spawn((
+            TextBundle {
+                background_color: theme.button_base.into(),
+                z_index: ZIndex::Global(20),
+                ..TextBundle::from_section(message, theme.text_style(22.0)).with_style(Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(10.0),
+                    left: Val::Px(10.0),
+                    padding: UiRect::all(Val::Px(6.0)),
+                    ..default()
+                })
+            },
+            Toast(Timer::from_seconds(4.0, TimerMode::Once)),
+        ))
+        .id()
+}
+
+pub fn expire_toasts(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut toasts: Query<(Entity, &mut Toast)>,
+) {
+    for (entity, mut toast) in toasts.iter_mut() {
+        if toast.tick(time.delta()).just_finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,229 @@
+// Hot-seat versus mode. Two players take turns playing a full story run each on the same seed,
+// so that both face the same enemies and patterns, and their runs are compared at the end.
+use crate::gameplay::levels::GameMode;
+use crate::gameplay::player::RunEnd;
+use crate::gameplay::rng::RunSeed;
+use crate::gameplay::shared::format_duration;
+use crate::gameplay::GameplayTime;
+use crate::high_scores::Reached;
+use crate::ui::theme::Theme;
+use crate::ui::widgets::{spawn_button, spawn_button_column, spawn_menu_root};
+use crate::DespawnOnExitExt;
+use crate::GameState;
+use bevy::prelude::*;
+use rand::Rng;
+use std::time::Duration;
+
+const PLAYERS: usize = 2;
+// Number of stages in a story run, for telling whether a run was cleared
+const STAGES: usize = 3;
+
+// How one player's run went
+#[derive(Debug, Clone)]
+struct RunSummary {
+    score: u64,
+    reached: Reached,
+    time: Duration,
+    enemies_killed: u16,
+    graze: u32,
+}
+
+// A versus match in progress. Only exists while one is being played.
+#[derive(Resource, Debug)]
+pub struct Versus {
+    seed: u64,
+    results: Vec<RunSummary>,
+}
+
+impl Versus {
+    fn new() -> Self {
+        Self {
+            seed: rand::thread_rng().gen(),
+            results: Vec::new(),
+        }
+    }
+
+    fn finished(&self) -> bool {
+        self.results.len() >= PLAYERS
+    }
+}
+
+#[derive(Component)]
+enum Action {
+    NextPlayer,
+    Rematch,
+    ToMainMenu,
+}
+
+// Marker for UI objects of the versus results screen
+#[derive(Component)]
+struct InVersusResults;
+
+pub struct VersusPlugin;
+
+impl Plugin for VersusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::VersusResults), (record_turn, spawn_ui).chain())
+            .add_systems(
+                Update,
+                (button_interactions, crate::ui::colour_buttons)
+                    .run_if(in_state(GameState::VersusResults)),
+            )
+            .despawn_on_exit::<InVersusResults>(GameState::VersusResults)
+            // Going back to the menu, from the results or by quitting a run, ends the match
+            .add_systems(OnEnter(GameState::Menu), end_versus);
+    }
+}
+
+// Start a versus match. Both players play story runs on the match's seed.
+pub fn start_versus(commands: &mut Commands, game_mode: &mut GameMode) {
+    let versus = Versus::new();
+    commands.insert_resource(RunSeed(Some(versus.seed)));
+    commands.insert_resource(versus);
+    *game_mode = GameMode::Story;
+}
+
+fn end_versus(mut commands: Commands, mut seed: ResMut<RunSeed>) {
+    commands.remove_resource::<Versus>();
+    *seed = RunSeed(None);
+}
+
+// Keep the result of the run which just ended, as kept by the system which ended it
+fn record_turn(
+    run_end: Option<Res<RunEnd>>,
+    g_time: Option<Res<GameplayTime>>,
+    versus: Option<ResMut<Versus>>,
+) {
+    let (Some(mut versus), Some(g_time), Some(run_end)) = (versus, g_time, run_end) else {
+        return;
+    };
+
+    let cleared = g_time.splits().len();
+    versus.results.push(RunSummary {
+        score: run_end.score,
+        reached: if cleared >= STAGES {
+            Reached::AllClear
+        } else {
+            Reached::Stage(cleared as u8 + 1)
+        },
+        time: g_time.elapsed(),
+        enemies_killed: run_end.enemies_killed,
+        graze: run_end.graze,
+    });
+}
+
+fn spawn_ui(mut commands: Commands, theme: Res<Theme>, versus: Option<Res<Versus>>) {
+    let Some(versus) = versus else {
+        return;
+    };
+    let style = theme.widget_style(175.0, 50.0, 40.0);
+
+    spawn_menu_root(&mut commands, InVersusResults, |parent| {
+        if !versus.finished() {
+            let next = versus.results.len() + 1;
+            let last = versus.results.last().map_or(0, |result| result.score);
+            parent.spawn(
+                TextBundle::from_section(
+                    format!("Player {} scored {}\n\nPlayer {}, get ready!", next - 1, last, next),
+                    theme.text_style(40.0),
+                )
+                .with_text_alignment(TextAlignment::Center)
+                .with_style(Style {
+                    margin: UiRect::all(Val::Px(50.0)),
+                    ..default()
+                }),
+            );
+            spawn_button_column(parent, |parent| {
+                spawn_button(parent, Action::NextPlayer, "Start", &style);
+                spawn_button(parent, Action::ToMainMenu, "Main menu", &style);
+            });
+            return;
+        }
+
+        let (first, second) = (&versus.results[0], &versus.results[1]);
+        let verdict = match first.score.cmp(&second.score) {
+            std::cmp::Ordering::Greater => "Player 1 Wins!",
+            std::cmp::Ordering::Less => "Player 2 Wins!",
+            std::cmp::Ordering::Equal => "Draw!",
+        };
+        parent.spawn(
+            TextBundle::from_section(verdict, theme.text_style(46.0)).with_style(Style {
+                margin: UiRect::all(Val::Px(50.0)),
+                ..default()
+            }),
+        );
+
+        // Labels on the left, then a column for each player
+        parent
+            .spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|parent| {
+                let column = |parent: &mut ChildBuilder, lines: Vec<String>| {
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                margin: UiRect::horizontal(Val::Px(20.0)),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for line in lines {
+                                parent.spawn(TextBundle::from_section(line, theme.text_style(23.0)));
+                            }
+                        });
+                };
+                column(
+                    parent,
+                    ["", "Score", "Reached", "Time", "Enemies Killed", "Graze"]
+                        .map(String::from)
+                        .to_vec(),
+                );
+                for (player, result) in versus.results.iter().enumerate() {
+                    column(
+                        parent,
+                        vec![
+                            format!("Player {}", player + 1),
+                            result.score.to_string(),
+                            result.reached.name(),
+                            format_duration(result.time),
+                            result.enemies_killed.to_string(),
+                            result.graze.to_string(),
+                        ],
+                    );
+                }
+            });
+
+        spawn_button_column(parent, |parent| {
+            spawn_button(parent, Action::Rematch, "Rematch", &style);
+            spawn_button(parent, Action::ToMainMenu, "Main menu", &style);
+        });
+    });
+}
+
+#[allow(clippy::type_complexity)]
+fn button_interactions(
+    mut commands: Commands,
+    interaction: Query<(&Interaction, &Action), (Changed<Interaction>, With<Button>)>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut game_mode: ResMut<GameMode>,
+) {
+    for (interaction, action) in interaction.iter() {
+        if *interaction == Interaction::Pressed {
+            match action {
+                Action::NextPlayer => game_state.set(GameState::Gameplay),
+                Action::Rematch => {
+                    start_versus(&mut commands, &mut game_mode);
+                    game_state.set(GameState::Gameplay);
+                }
+                Action::ToMainMenu => game_state.set(GameState::Menu),
+            }
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{gameplay::shared::Counter, despawn_component, HighScore};
+use crate::{gameplay::{difficulty::Difficulty, dps::DpsMeter, levels::{GameMode, StageBonuses}, shared::{format_duration, Counter}, timeline::{spawn_timeline_graph, RunTimeline}, GameplayTime}, high_scores::HighScoreTable, DespawnOnExitExt};
 use crate::GameState;
 use crate::gameplay::player::{
     Player,
@@ -8,7 +8,9 @@ use crate::gameplay::player::{
     EnemiesKilled,
     Specials
 };
-use bevy::app::AppExit;
+use crate::ui::theme::Theme;
+use crate::ui::widgets::{spawn_button, spawn_button_column, spawn_menu_root};
+use crate::shutdown::ExitRequest;
 use bevy::prelude::*;
 
 #[derive(Component)]
@@ -31,62 +33,46 @@ impl Plugin for WinGamePlugin {
                     crate::ui::colour_buttons,
                 ).run_if(in_state(GameState::GameWon))
             )
-            .add_systems(OnExit(GameState::GameWon), (crate::despawn_component::<InWinGameMenu>, despawn_component::<Player>));
+            .despawn_on_exit::<InWinGameMenu>(GameState::GameWon)
+            .despawn_on_exit::<Player>(GameState::GameWon);
     }
 }
 
 #[allow(clippy::type_complexity)]
 fn button_interactions(
     interaction: Query<(&Interaction, &Action), (Changed<Interaction>, With<Button>)>,
-    mut exit: EventWriter<AppExit>,
+    mut exit: EventWriter<ExitRequest>,
     mut game_state: ResMut<NextState<GameState>>,
 ) {
     for (interaction, action) in interaction.iter() {
         if *interaction == Interaction::Pressed {
             match action {
                 Action::ToMainMenu => game_state.set(GameState::Menu),
-                Action::Exit => exit.send(AppExit),
+                Action::Exit => exit.send_default(),
             }
         }
     }
 }
 
 // Create the Game Over menu
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_ui(
     mut commands: Commands,
-    assets: Res<AssetServer>,
+    theme: Res<Theme>,
     player_data: Query<(&Specials, &Power, &Score, &Graze, &EnemiesKilled), With<Player>>,
-    mut highscore: ResMut<HighScore>,
+    high_scores: Res<HighScoreTable>,
+    g_time: Res<GameplayTime>,
+    bonuses: Res<StageBonuses>,
+    timeline: Res<RunTimeline>,
+    difficulty: Res<Difficulty>,
+    mode: Res<GameMode>,
+    dps: Res<DpsMeter>,
 ) {
     let Ok((specials, power, score, graze, enemies_killed)) = player_data.get_single() else { return; };
-    let font: Handle<Font> = assets.load("fonts/FiraSans-Bold.ttf");
 
-    if score.get() > highscore.0 {
-       highscore.0 = score.get();
-    }
-
-    let button_style = Style {
-        width: Val::Px(175.0),
-        height: Val::Px(50.0),
-        margin: UiRect::all(Val::Px(10.0)),
-        justify_content: JustifyContent::Center,
-        align_items: AlignItems::Center,
-        ..default()
-    };
-
-    let button_text_style = TextStyle {
-        font: font.clone(),
-        font_size: 40.0,
-        color: crate::ui::TEXT_COLOUR,
-    };
+    let style = theme.widget_style(175.0, 50.0, 40.0);
 
-    let base_text_bundle = || TextBundle::from_section(
-        "",
-        TextStyle {
-            font: font.clone(),
-            font_size: 23.0,
-            color: crate::ui::TEXT_COLOUR,
-        })
+    let base_text_bundle = || TextBundle::from_section("", theme.text_style(23.0))
         .with_text_alignment(TextAlignment::Left)
         .with_style(Style {
             margin: UiRect::top(Val::Px(10.0)),
@@ -94,102 +80,64 @@ pub fn spawn_ui(
         }
     );
 
-    let formatted_strings = [
+    let mut formatted_strings = vec![
+        format!("Difficulty: {}", difficulty.name()),
         format!("Score: {}", score.get()),
-        format!("Highscore: {}", highscore.0),
+        // The run itself is only added to the table once the player's initials are entered
+        format!("Highscore: {}", high_scores.best().max(score.get())),
         format!("Power: {}", power.get()),
         format!("Specials remaining: {}", specials.get()),
         format!("Graze acquired: {}", graze.get()),
         format!("Enemies Killed: {}", enemies_killed.get()),
+        format!("Time: {}", format_duration(g_time.elapsed())),
     ];
+    if *mode == GameMode::Practice {
+        formatted_strings.push(format!(
+            "Boss DPS: {:.0} peak, {:.0} average",
+            dps.peak(),
+            dps.average()
+        ));
+    }
+    for bonus in bonuses.iter() {
+        let mut line = format!(
+            "Level {:?}: {} (par {}) +{}, Graze +{}, Lives +{}",
+            bonus.level,
+            format_duration(bonus.time),
+            format_duration(bonus.par_time),
+            bonus.time_bonus,
+            bonus.graze_bonus,
+            bonus.lives_bonus,
+        );
+        if bonus.no_miss_bonus > 0 {
+            line.push_str(&format!(", No Miss +{}", bonus.no_miss_bonus));
+        }
+        if bonus.no_bomb_bonus > 0 {
+            line.push_str(&format!(", No Bomb +{}", bonus.no_bomb_bonus));
+        }
+        formatted_strings.push(line);
+    }
 
-    // Root element
-    commands
-        .spawn((
-            NodeBundle {
-                style: Style {
-                    margin: UiRect {
-                        left: Val::Auto,
-                        right: Val::Auto,
-                        top: Val::Px(40.0),
-                        bottom: Val::Auto,
-                    },
-                    flex_direction: FlexDirection::Column,
-                    align_items: AlignItems::Center,
-                    ..default()
-                },
-                background_color: Color::NONE.into(),
-                ..default()
-            },
-            InWinGameMenu,
-        ))
-        .with_children(|parent| {
-            // Game won message
-            parent.spawn((
-                TextBundle::from_section(
-                    "Congratulations, You Won!",
-                    TextStyle {
-                        font: font.clone(),
-                        font_size: 46.0,
-                        color: crate::ui::TEXT_COLOUR,
-                    },
-                )
+    spawn_menu_root(&mut commands, InWinGameMenu, |parent| {
+        // Game won message
+        parent.spawn(
+            TextBundle::from_section("Congratulations, You Won!", theme.text_style(46.0))
                 .with_text_alignment(TextAlignment::Center)
                 .with_style(Style {
                     margin: UiRect::all(Val::Px(50.0)),
                     ..default()
                 }),
-            ));
+        );
 
-            for string in formatted_strings {
-                let mut bundle = base_text_bundle();
-                bundle.text.sections[0].value = string;
-                parent.spawn(bundle);
-            }
+        for string in formatted_strings {
+            let mut bundle = base_text_bundle();
+            bundle.text.sections[0].value = string;
+            parent.spawn(bundle);
+        }
+        spawn_timeline_graph(parent, &timeline, g_time.elapsed(), &theme);
 
-            // Sub-list for the buttons
-            parent
-                .spawn((
-                    NodeBundle {
-                        style: Style {
-                            margin: UiRect::top(Val::Px(150.0)),
-                            flex_direction: FlexDirection::Column,
-                            align_items: AlignItems::Center,
-                            justify_content: JustifyContent::Center,
-                            ..default()
-                        },
-                        background_color: Color::NONE.into(),
-                        ..default()
-                    },
-                    InWinGameMenu,
-                ))
-                .with_children(|parent| {
-                    // Back to main menu button
-                    parent
-                        .spawn((
-                            ButtonBundle {
-                                style: button_style.clone(),
-                                background_color: crate::ui::BUTTON_BASE.into(),
-                                ..default()
-                            },
-                            Action::ToMainMenu,
-                        ))
-                        .with_children(|parent| {
-                            parent.spawn(TextBundle::from_section("Main menu", button_text_style.clone()));
-                        });
-                    // Quit game button
-                    parent
-                        .spawn((
-                            ButtonBundle {
-                                style: button_style.clone(),
-                                background_color: crate::ui::BUTTON_BASE.into(),
-                                ..default()
-                            },
-                            Action::Exit,
-                        ))
-                        .with_children(|parent| {
-                            parent.spawn(TextBundle::from_section("Quit", button_text_style.clone()));
-                        });
-                });
+        spawn_button_column(parent, |parent| {
+            spawn_button(parent, Action::ToMainMenu, "Main menu", &style);
+            spawn_button(parent, Action::Exit, "Quit", &style);
         });
+    });
 }
@@ -0,0 +1,127 @@
+use crate::gameplay::{levels::CurrentLevel, player::{Player, Score}, shared::Counter};
+use crate::{ControlScheme, GameOptions, GameState};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+// The player's plane is used as the window icon. The sprite sheet has 8 frames side by
+// side, of which the one facing straight ahead is used.
+#[cfg(not(target_arch = "wasm32"))]
+const ICON_PATH: &str = "sprites/white-plane3.png";
+#[cfg(not(target_arch = "wasm32"))]
+const ICON_FRAME: u32 = 5;
+#[cfg(not(target_arch = "wasm32"))]
+const ICON_FRAME_SIZE: (u32, u32) = (60, 90);
+
+pub struct GameWindowPlugin;
+
+impl Plugin for GameWindowPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, update_title);
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(Startup, icon::load_icon)
+            .add_systems(Update, icon::set_window_icon);
+    }
+}
+
+// Title of the window when not playing, e.g. "shmup_game - 0.1.0 - debug"
+pub fn base_title() -> String {
+    let build = if cfg!(debug_assertions) { "debug" } else { "release" };
+    format!(
+        "{} - {} - {}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        build
+    )
+}
+
+// Show what is going on in the game in the window title, so that it is visible in the
+// taskbar and window switchers.
+fn update_title(
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    game_state: Res<State<GameState>>,
+    level: Res<State<CurrentLevel>>,
+    options: Res<GameOptions>,
+    score: Query<&Score, With<Player>>,
+) {
+    let Ok(mut window) = windows.get_single_mut() else { return; };
+
+    let mut title = base_title();
+    match game_state.get() {
+        GameState::Menu => {}
+        GameState::Gameplay | GameState::Paused | GameState::StageClear => {
+            if *level.get() != CurrentLevel::None {
+                title.push_str(&format!(" | Level {:?}", level.get()));
+            }
+            if options.get_control_scheme() == ControlScheme::Simplified {
+                title.push_str(" (Simple)");
+            }
+            if let Ok(score) = score.get_single() {
+                title.push_str(&format!(" | Score {}", score.get()));
+            }
+            if *game_state.get() == GameState::Paused {
+                title.push_str(" | Paused");
+            }
+            if *game_state.get() == GameState::StageClear {
+                title.push_str(" | Stage Clear");
+            }
+        }
+        GameState::GameOver => title.push_str(" | Game Over"),
+        GameState::GameWon => title.push_str(" | Cleared"),
+        GameState::VersusResults => title.push_str(" | Versus"),
+    }
+
+    // Only set the title when it actually changes, as that is sent to the OS
+    if window.title != title {
+        window.title = title;
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod icon {
+    use super::{ICON_FRAME, ICON_FRAME_SIZE, ICON_PATH};
+    use bevy::prelude::*;
+    use bevy::render::render_resource::TextureFormat;
+    use bevy::window::PrimaryWindow;
+    use bevy::winit::WinitWindows;
+    use winit::window::Icon;
+
+    #[derive(Resource)]
+    pub struct WindowIcon(Handle<Image>);
+
+    pub fn load_icon(mut commands: Commands, assets: Res<AssetServer>) {
+        commands.insert_resource(WindowIcon(assets.load(ICON_PATH)));
+    }
+
+    // Bevy has no way of setting the window icon, so it is set on the winit window
+    // directly once the image has loaded.
+    pub fn set_window_icon(
+        mut commands: Commands,
+        icon: Option<Res<WindowIcon>>,
+        images: Res<Assets<Image>>,
+        winit_windows: NonSend<WinitWindows>,
+        primary_window: Query<Entity, With<PrimaryWindow>>,
+    ) {
+        let Some(icon) = icon else { return; };
+        let Some(image) = images.get(&icon.0) else { return; };
+        let Ok(entity) = primary_window.get_single() else { return; };
+        let Some(window) = winit_windows.get_window(entity) else { return; };
+        commands.remove_resource::<WindowIcon>();
+
+        if image.texture_descriptor.format != TextureFormat::Rgba8UnormSrgb {
+            warn!("Window icon {} is not an RGBA image.", ICON_PATH);
+            return;
+        }
+        // Copy out the chosen frame, row by row
+        let image_width = image.texture_descriptor.size.width;
+        let (width, height) = ICON_FRAME_SIZE;
+        let mut rgba = Vec::with_capacity((width * height * 4) as usize);
+        for y in 0..height {
+            let start = ((y * image_width + ICON_FRAME * width) * 4) as usize;
+            rgba.extend_from_slice(&image.data[start..start + (width * 4) as usize]);
+        }
+        match Icon::from_rgba(rgba, width, height) {
+            Ok(icon) => window.set_window_icon(Some(icon)),
+            Err(error) => warn!("Couldn't set the window icon: {}", error),
+        }
+    }
+}